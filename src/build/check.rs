@@ -0,0 +1,438 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::settings::toml::{Target, TargetType};
+use crate::upload::Package;
+use crate::wranglerjs::output::Diagnostic;
+use crate::wranglerjs::Bundle;
+
+// the Node modules a script might import, none of which exist in Workers
+const NODE_BUILTINS: &[&str] = &[
+    "assert",
+    "buffer",
+    "child_process",
+    "cluster",
+    "crypto",
+    "dgram",
+    "dns",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "net",
+    "os",
+    "path",
+    "process",
+    "querystring",
+    "readline",
+    "stream",
+    "string_decoder",
+    "tls",
+    "tty",
+    "url",
+    "util",
+    "vm",
+    "worker_threads",
+    "zlib",
+];
+
+// the modules `node_compat = true` polyfills
+const NODE_COMPAT_POLYFILLS: &[&str] = &["buffer", "events", "stream", "util"];
+
+const BASE64_DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+lazy_static! {
+    static ref EVAL: Regex = Regex::new(r"\beval\s*\(").unwrap();
+    static ref NEW_FUNCTION: Regex = Regex::new(r"\bnew\s+Function\s*\(").unwrap();
+    static ref TIMER: Regex = Regex::new(r"\b(setInterval|setTimeout)\s*\(").unwrap();
+    static ref IMPORT: Regex =
+        Regex::new(r#"(?:\brequire\s*\(\s*|\bfrom\s*|\bimport\s*\(?\s*)["']"#).unwrap();
+    static ref SOURCE_MAPPING_URL: Regex = Regex::new(r"//[#@]\s*sourceMappingURL=(\S+)").unwrap();
+}
+
+/// Scans the script the target uploads for code that the Workers runtime rejects, or that
+/// fails when it runs: `eval`, `new Function`, Node's built-in modules that aren't
+/// polyfilled, and timers set in the global scope. Each warning has the byte offset in the script, and is located
+/// in the original source when the script has a source map.
+pub fn check_script(target: &Target) -> Vec<Diagnostic> {
+    let path = match script_path(target) {
+        Some(path) => path,
+        None => return Vec::new(),
+    };
+    let script = match fs::read_to_string(&path) {
+        Ok(script) => script,
+        Err(e) => {
+            log::info!("Could not read {} to check it: {}", path.display(), e);
+            return Vec::new();
+        }
+    };
+    let source_map = SourceMap::load(&path, &script);
+
+    disallowed(&script, target.node_compat)
+        .into_iter()
+        .map(|(offset, message)| {
+            let (line, column) = line_and_column(&script, offset);
+            let message = format!("{} (byte {} of {})", message, offset, path.display());
+            let original = source_map
+                .as_ref()
+                .and_then(|source_map| source_map.original(line, column));
+            match original {
+                Some((file, line, column)) => Diagnostic {
+                    file: Some(file),
+                    line: Some(line as u64 + 1),
+                    column: Some(column as u64 + 1),
+                    message,
+                },
+                None => Diagnostic {
+                    file: Some(path.display().to_string()),
+                    line: Some(line as u64 + 1),
+                    column: Some(column as u64 + 1),
+                    message,
+                },
+            }
+        })
+        .collect()
+}
+
+// Rust targets are left out, their script is wasm-bindgen's glue
+fn script_path(target: &Target) -> Option<PathBuf> {
+    let build_dir = target.build_dir().ok()?;
+    match target.target_type {
+        TargetType::JavaScript => match &target.main {
+            Some(main) => Some(main.clone()),
+            None => {
+                let main = Package::new(&build_dir).ok()?.main(&build_dir).ok()?;
+                Some(build_dir.join(main))
+            }
+        },
+        TargetType::Webpack => Some(Bundle::new(&build_dir).script_path()),
+        TargetType::Rust => None,
+    }
+}
+
+// the byte offset of each problem, and what it is
+fn disallowed(script: &str, node_compat: bool) -> Vec<(usize, String)> {
+    let code = code_only(script);
+    let depths = depths(&code);
+    let mut found = Vec::new();
+
+    for m in EVAL.find_iter(&code) {
+        found.push((
+            m.start(),
+            "eval() is not allowed in Workers and throws at runtime".to_string(),
+        ));
+    }
+    for m in NEW_FUNCTION.find_iter(&code) {
+        found.push((
+            m.start(),
+            "new Function() is not allowed in Workers and throws at runtime".to_string(),
+        ));
+    }
+    for captures in TIMER.captures_iter(&code) {
+        let m = captures.get(0).unwrap();
+        if depths[m.start()] == 0 {
+            found.push((
+                m.start(),
+                format!(
+                    "{}() can't be called in the global scope of a Worker, only while handling an event",
+                    &captures[1]
+                ),
+            ));
+        }
+    }
+    for m in IMPORT.find_iter(&code) {
+        // the module name is inside the quotes, which code_only blanked out
+        let quote = script[m.end() - 1..].chars().next().unwrap();
+        let name = match script[m.end()..].split(quote).next() {
+            Some(name) => name,
+            None => continue,
+        };
+        let module = name.trim_start_matches("node:");
+        let module = module.split('/').next().unwrap_or(module);
+        let polyfilled = NODE_COMPAT_POLYFILLS.contains(&module);
+        if NODE_BUILTINS.contains(&module) && !(node_compat && polyfilled) {
+            found.push((
+                m.start(),
+                format!(
+                    "Node's built-in \"{}\" module is not available in Workers{}",
+                    name,
+                    if polyfilled {
+                        ", unless you set node_compat = true"
+                    } else {
+                        ""
+                    }
+                ),
+            ));
+        }
+    }
+
+    found.sort();
+    found
+}
+
+// The script with the contents of its strings and comments blanked out, byte for byte,
+// so the patterns only match code. Regex literals aren't recognised.
+fn code_only(script: &str) -> String {
+    let bytes = script.as_bytes();
+    let mut code = bytes.to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => bytes[i..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(bytes.len(), |n| i + n),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                find(bytes, i + 2, b"*/").map_or(bytes.len(), |n| n + 2)
+            }
+            quote @ b'"' | quote @ b'\'' | quote @ b'`' => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != quote {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                // keep the quotes
+                for b in &mut code[i + 1..j.min(bytes.len())] {
+                    *b = b' ';
+                }
+                i = j + 1;
+                continue;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        for b in &mut code[i..end] {
+            *b = b' ';
+        }
+        i = end;
+    }
+    // whole characters were blanked, so the offsets are the same as in the script
+    String::from_utf8_lossy(&code).into_owned()
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|n| from + n)
+}
+
+// how deeply nested in brackets each byte of the code is
+fn depths(code: &str) -> Vec<usize> {
+    let mut depth: usize = 0;
+    code.bytes()
+        .map(|b| {
+            match b {
+                b'{' | b'(' | b'[' => depth += 1,
+                b'}' | b')' | b']' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            depth
+        })
+        .collect()
+}
+
+// zero based, with the column in bytes
+fn line_and_column(script: &str, offset: usize) -> (usize, usize) {
+    let before = &script[..offset];
+    let line = before.matches('\n').count();
+    let column = offset - before.rfind('\n').map_or(0, |n| n + 1);
+    (line, column)
+}
+
+#[derive(Deserialize)]
+struct RawSourceMap {
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default, rename = "sourceRoot")]
+    source_root: Option<String>,
+    mappings: String,
+}
+
+/// The parts of a source map needed to find where a generated position came from
+pub struct SourceMap {
+    sources: Vec<String>,
+    // for each generated line, (generated column, source, source line, source column)
+    lines: Vec<Vec<(usize, usize, usize, usize)>>,
+}
+
+impl SourceMap {
+    /// The source map of the script the target uploads, when it has one.
+    pub fn for_target(target: &Target) -> Option<SourceMap> {
+        let path = script_path(target)?;
+        let script = fs::read_to_string(&path).ok()?;
+        SourceMap::load(&path, &script)
+    }
+
+    // From the script's sourceMappingURL, inline or a file next to it, or else from
+    // `<script>.map`. None when there isn't one that can be read.
+    fn load(script_path: &Path, script: &str) -> Option<SourceMap> {
+        let url = SOURCE_MAPPING_URL
+            .captures_iter(script)
+            .last()
+            .map(|captures| captures[1].to_string());
+        let text = match url {
+            Some(url) if url.starts_with("data:") => {
+                let (_, data) = url.split_at(url.find(";base64,")? + ";base64,".len());
+                String::from_utf8(base64::decode(data).ok()?).ok()?
+            }
+            Some(url) => fs::read_to_string(script_path.parent()?.join(url)).ok()?,
+            None => {
+                let mut path = script_path.as_os_str().to_owned();
+                path.push(".map");
+                fs::read_to_string(PathBuf::from(path)).ok()?
+            }
+        };
+        match SourceMap::parse(&text) {
+            Ok(source_map) => Some(source_map),
+            Err(e) => {
+                log::info!("Could not read the source map: {}", e);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn parse(text: &str) -> Result<SourceMap, failure::Error> {
+        let raw: RawSourceMap = serde_json::from_str(text)?;
+        let root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .iter()
+            .map(|source| format!("{}{}", root, source))
+            .collect();
+
+        let mut lines = Vec::new();
+        // everything but the generated column carries on from the previous segment
+        let (mut source, mut source_line, mut source_column) = (0i64, 0i64, 0i64);
+        for line in raw.mappings.split(';') {
+            let mut column = 0i64;
+            let mut segments = Vec::new();
+            for segment in line.split(',').filter(|segment| !segment.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                column += fields[0];
+                if fields.len() >= 4 {
+                    source += fields[1];
+                    source_line += fields[2];
+                    source_column += fields[3];
+                    segments.push((
+                        column as usize,
+                        source as usize,
+                        source_line as usize,
+                        source_column as usize,
+                    ));
+                }
+            }
+            lines.push(segments);
+        }
+        Ok(SourceMap { sources, lines })
+    }
+
+    /// The source file, line and column of a zero based generated position
+    pub fn original(&self, line: usize, column: usize) -> Option<(String, usize, usize)> {
+        let (_, source, source_line, source_column) = self
+            .lines
+            .get(line)?
+            .iter()
+            .take_while(|segment| segment.0 <= column)
+            .last()?;
+        Some((
+            self.sources.get(*source)?.clone(),
+            *source_line,
+            *source_column,
+        ))
+    }
+}
+
+// A segment of a source map's mappings is a series of base64 VLQ numbers.
+fn decode_vlq(segment: &str) -> Result<Vec<i64>, failure::Error> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0);
+    for c in segment.bytes() {
+        let digit = match BASE64_DIGITS.iter().position(|&d| d == c) {
+            Some(digit) => digit as i64,
+            None => failure::bail!("invalid mapping {}", segment),
+        };
+        value += (digit & 31) << shift;
+        if digit & 32 == 0 {
+            // the lowest bit is the sign
+            values.push(if value & 1 == 1 {
+                -(value >> 1)
+            } else {
+                value >> 1
+            });
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 5;
+        }
+    }
+    if values.is_empty() || shift != 0 {
+        failure::bail!("invalid mapping {}", segment)
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_disallowed_code() {
+        let script = r#"const fs = require("fs");
+const path = "eval(x)"; // eval(y)
+setInterval(poll, 1000);
+addEventListener("fetch", (event) => {
+  setTimeout(log, 10);
+  event.respondWith(new Function("return 1")());
+});
+"#;
+        let messages: Vec<String> = disallowed(script, false)
+            .into_iter()
+            .map(|(offset, message)| {
+                let (line, column) = line_and_column(script, offset);
+                format!("{}:{} {}", line, column, message)
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "0:11 Node's built-in \"fs\" module is not available in Workers",
+                "2:0 setInterval() can't be called in the global scope of a Worker, only while handling an event",
+                "5:20 new Function() is not allowed in Workers and throws at runtime",
+            ]
+        );
+
+        let script = r#"const { EventEmitter } = require("node:events");"#;
+        assert!(disallowed(script, false)[0]
+            .1
+            .ends_with("unless you set node_compat = true"));
+        assert!(disallowed(script, true).is_empty());
+    }
+
+    #[test]
+    fn it_maps_positions_through_a_source_map() {
+        assert_eq!(decode_vlq("AAgBC").unwrap(), vec![0, 0, 16, 1]);
+        assert_eq!(decode_vlq("D").unwrap(), vec![-1]);
+
+        // line 0 comes from src/index.ts line 0, and line 1 from columns 0 and 4 of line 2
+        let source_map = SourceMap::parse(
+            r#"{"version":3,"sources":["index.ts"],"sourceRoot":"src/","mappings":"AAAA;AAEA,IAAI"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            source_map.original(1, 6),
+            Some(("src/index.ts".to_string(), 2, 4))
+        );
+        assert_eq!(
+            source_map.original(0, 3),
+            Some(("src/index.ts".to_string(), 0, 0))
+        );
+        assert_eq!(source_map.original(5, 0), None);
+    }
+}
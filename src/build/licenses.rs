@@ -0,0 +1,150 @@
+use super::{packages, BuildOutput};
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::table::Table;
+
+/// Prints the license of every package bundled into the worker by the build that
+/// returned `output`, and fails when one needs a license in `[build.licenses] deny`.
+pub fn report(target: &Target, output: &BuildOutput) -> Result<(), failure::Error> {
+    let packages = packages(target, output)?;
+    let deny = target.build.denied_licenses();
+
+    let mut table = Table::new(&["Package", "Version", "License"]);
+    let mut unknown = Vec::new();
+    let mut denied = Vec::new();
+    for package in &packages {
+        let license = match &package.license {
+            Some(license) => license.as_str(),
+            None => {
+                unknown.push(package.name.as_str());
+                "unknown"
+            }
+        };
+        if package.license.is_some() && is_denied(license, deny) {
+            denied.push(format!(
+                "{} {} ({})",
+                package.name, package.version, license
+            ));
+        }
+        table.add_row(vec![
+            package.name.as_str(),
+            package.version.as_str(),
+            license,
+        ]);
+    }
+    table.print();
+
+    if !unknown.is_empty() {
+        StdErr::warn(&format!(
+            "These packages don't declare a license: {}",
+            unknown.join(", ")
+        ));
+    }
+    if !denied.is_empty() {
+        failure::bail!(
+            "These packages need a license denied in [build.licenses]:\n{}",
+            denied.join("\n")
+        )
+    }
+    StdOut::success(&format!(
+        "Checked the licenses of {} packages.",
+        packages.len()
+    ));
+    Ok(())
+}
+
+// Whether a license expression like "(MIT OR Apache-2.0) AND BSD-3-Clause" can't be met
+// without a denied license. Cargo's older "MIT/Apache-2.0" means the same as OR.
+fn is_denied(expression: &str, deny: &[String]) -> bool {
+    if deny.is_empty() {
+        return false;
+    }
+    let expression = expression
+        .replace('/', " OR ")
+        .replace('(', " ( ")
+        .replace(')', " ) ");
+    let tokens: Vec<&str> = expression.split_whitespace().collect();
+    let mut at = 0;
+    !allowed_or(&tokens, &mut at, deny)
+}
+
+fn allowed_or(tokens: &[&str], at: &mut usize, deny: &[String]) -> bool {
+    let mut allowed = allowed_and(tokens, at, deny);
+    while tokens
+        .get(*at)
+        .map_or(false, |t| t.eq_ignore_ascii_case("OR"))
+    {
+        *at += 1;
+        // evaluated first, so the rest of the expression is always consumed
+        allowed = allowed_and(tokens, at, deny) || allowed;
+    }
+    allowed
+}
+
+fn allowed_and(tokens: &[&str], at: &mut usize, deny: &[String]) -> bool {
+    let mut allowed = allowed_license(tokens, at, deny);
+    while tokens
+        .get(*at)
+        .map_or(false, |t| t.eq_ignore_ascii_case("AND"))
+    {
+        *at += 1;
+        allowed = allowed_license(tokens, at, deny) && allowed;
+    }
+    allowed
+}
+
+fn allowed_license(tokens: &[&str], at: &mut usize, deny: &[String]) -> bool {
+    let token = match tokens.get(*at) {
+        Some(token) => *token,
+        None => return true,
+    };
+    *at += 1;
+    if token == "(" {
+        let allowed = allowed_or(tokens, at, deny);
+        if tokens.get(*at) == Some(&")") {
+            *at += 1;
+        }
+        return allowed;
+    }
+    // "GPL-2.0 WITH Classpath-exception-2.0" is still GPL-2.0
+    if tokens
+        .get(*at)
+        .map_or(false, |t| t.eq_ignore_ascii_case("WITH"))
+    {
+        *at += 2;
+    }
+    !deny.iter().any(|pattern| matches(pattern, token))
+}
+
+// "GPL-3.0+" is GPL-3.0 or later, and is denied along with GPL-3.0
+fn matches(pattern: &str, license: &str) -> bool {
+    let license = license.trim_end_matches('+').to_ascii_lowercase();
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => license.starts_with(prefix),
+        None => license == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_denies_licenses_only_when_there_is_no_other_choice() {
+        let deny = vec!["GPL-3.0-only".to_string(), "AGPL-*".to_string()];
+        assert!(!is_denied("MIT", &deny));
+        assert!(is_denied("gpl-3.0-only", &deny));
+        assert!(is_denied("AGPL-3.0-or-later", &deny));
+        assert!(!is_denied("MIT OR GPL-3.0-only", &deny));
+        assert!(!is_denied("MIT/GPL-3.0-only", &deny));
+        assert!(is_denied("MIT AND GPL-3.0-only", &deny));
+        assert!(is_denied("(MIT OR Apache-2.0) AND AGPL-3.0", &deny));
+        assert!(!is_denied("(GPL-3.0-only OR Apache-2.0) AND MIT", &deny));
+        assert!(is_denied(
+            "GPL-3.0-only WITH Classpath-exception-2.0",
+            &deny
+        ));
+        assert!(!is_denied("GPL-3.0-only", &[]));
+    }
+}
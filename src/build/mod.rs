@@ -1,20 +1,151 @@
+mod check;
+pub mod licenses;
+mod sbom;
+
+pub use check::SourceMap;
+
+use crate::error::WranglerError;
 use crate::settings::toml::{Target, TargetType};
 use crate::terminal::message::{Message, StdErr};
 use crate::terminal::styles;
+use crate::upload::form::artifact::Artifact;
 use crate::wranglerjs;
-use crate::{commands, install};
+use crate::wranglerjs::output::Diagnostic;
+use crate::{install, interrupt};
 
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What a successful build reports back to the command that ran it
+#[derive(Debug, Default)]
+pub struct BuildOutput {
+    pub message: String,
+    /// webpack warnings and the problems found in the script, which were already printed
+    pub warnings: Vec<Diagnostic>,
+    /// The `[build.hooks]` commands that ran, in order, and how long each took
+    pub hooks: Vec<(String, Duration)>,
+    /// The files webpack bundled into the script
+    pub modules: Vec<String>,
+    /// The SBOM written for `[build.sbom]`
+    pub sbom: Option<PathBuf>,
+}
+
+impl From<String> for BuildOutput {
+    fn from(message: String) -> Self {
+        BuildOutput {
+            message,
+            ..Default::default()
+        }
+    }
+}
 
 // Internal build logic, called by both `build` and `publish`
-// TODO: return a struct containing optional build info and construct output at command layer
-pub fn build_target(target: &Target) -> Result<String, failure::Error> {
+pub fn build_target(target: &Target) -> Result<BuildOutput, failure::Error> {
+    for warning in &target.warnings {
+        StdErr::warn(warning);
+    }
+    if let Some(dir) = &target.artifact {
+        let msg = format!(
+            "Using the artifact in {}. Skipping the build!",
+            dir.display()
+        );
+        let mut output = BuildOutput::from(msg);
+        output.sbom = Artifact::read(dir)?.sbom_path();
+        return Ok(output);
+    }
+    let build = || -> Result<BuildOutput, failure::Error> {
+        let mut hooks = run_hooks("pre-build", target.build.pre_hooks(), target)?;
+        let mut output = run_build(target)?;
+        if let Some(config) = &target.build.sbom {
+            let packages = packages(target, &output)?;
+            let path = sbom::write(target, config.format, &packages)?;
+            output.message.push_str(&format!(
+                "\n Wrote an SBOM of {} packages to {}",
+                packages.len(),
+                path.display()
+            ));
+            output.sbom = Some(path);
+        }
+        hooks.extend(run_hooks("post-build", target.build.post_hooks(), target)?);
+        for (hook, duration) in &hooks {
+            output
+                .message
+                .push_str(&format!("\n {} ({:.1}s)", hook, duration.as_secs_f64()));
+        }
+        output.hooks = hooks;
+        Ok(output)
+    };
+    let mut output = build().map_err(WranglerError::Build)?;
+    for warning in check::check_script(target) {
+        StdErr::warn_at(warning.github_location().as_ref(), &warning.render());
+        output.warnings.push(warning);
+    }
+    Ok(output)
+}
+
+// The packages bundled into the worker by the build that returned `output`
+fn packages(target: &Target, output: &BuildOutput) -> Result<Vec<sbom::Package>, failure::Error> {
+    match target.target_type {
+        TargetType::Webpack => Ok(sbom::npm_packages(&output.modules)),
+        TargetType::Rust => sbom::cargo_packages(),
+        TargetType::JavaScript => {
+            StdErr::info(
+                "wrangler doesn't bundle JavaScript projects, so it can't list the packages in your script.",
+            );
+            Ok(Vec::new())
+        }
+    }
+}
+
+// Runs each hook through the shell in turn, stopping at the first that fails. Every
+// hook gets the whole build timeout to itself.
+fn run_hooks(
+    stage: &str,
+    hooks: &[String],
+    target: &Target,
+) -> Result<Vec<(String, Duration)>, failure::Error> {
+    let mut timings = Vec::new();
+    for hook in hooks {
+        StdErr::working(&format!("Running {} hook `{}`", stage, hook));
+        let start = Instant::now();
+        let status = run_command(&mut shell(hook), target.build.timeout())?;
+        if !status.success() {
+            failure::bail!(
+                "the {} hook `{}` in [build.hooks] exited with {}",
+                stage,
+                hook,
+                status
+            )
+        }
+        timings.push((format!("{} hook `{}`", stage, hook), start.elapsed()));
+    }
+    Ok(timings)
+}
+
+fn shell(script: &str) -> Command {
+    let mut c = if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    c.arg(script);
+    c
+}
+
+fn run_build(target: &Target) -> Result<BuildOutput, failure::Error> {
     let target_type = &target.target_type;
     match target_type {
         TargetType::JavaScript => {
             let msg = "JavaScript project found. Skipping unnecessary build!".to_string();
-            Ok(msg)
+            Ok(msg.into())
         }
         TargetType::Rust => {
             let _ = which::which("rustc").map_err(|e| {
@@ -25,23 +156,38 @@ pub fn build_target(target: &Target) -> Result<String, failure::Error> {
                 )
             })?;
 
-            let binary_path = install::install_wasm_pack()?;
+            let binary_path = install::install_wasm_pack(target.tools.wasm_pack_version()?)?;
             let args = ["build", "--target", "no-modules"];
 
-            let command = command(&args, &binary_path);
+            let mut command = command(&args, &binary_path);
             let command_name = format!("{:?}", command);
 
-            commands::run(command, &command_name)?;
+            let status = run_command(&mut command, target.build.timeout())?;
+            if !status.success() {
+                failure::bail!(
+                    "tried running command:\n{}\nexited with {}",
+                    command_name.replace("\"", ""),
+                    status
+                )
+            }
             let msg = "Build succeeded".to_string();
-            Ok(msg)
+            Ok(msg.into())
         }
         TargetType::Webpack => match wranglerjs::run_build(target) {
             Ok(output) => {
-                let msg = format!(
+                let mut message = format!(
                     "Built successfully, built project size is {}",
                     output.project_size()
                 );
-                Ok(msg)
+                if let Some(minified) = output.minified_size() {
+                    message.push_str(&format!(" {}.", minified));
+                }
+                Ok(BuildOutput {
+                    message,
+                    warnings: output.warnings,
+                    modules: output.modules,
+                    ..Default::default()
+                })
             }
             Err(e) => Err(e),
         },
@@ -63,3 +209,73 @@ pub fn command(args: &[&str], binary_path: &PathBuf) -> Command {
     c.args(args);
     c
 }
+
+/// Runs an external build command to completion, printing a heartbeat every 30 seconds
+/// and killing it once `timeout` has passed, so a wedged build can't hang CI forever.
+pub fn run_command(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> Result<ExitStatus, failure::Error> {
+    log::info!("Running {:?}", command);
+
+    let child = interrupt::spawn(command)?;
+    let start = Instant::now();
+    let mut next_heartbeat = HEARTBEAT_INTERVAL;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+
+        let elapsed = start.elapsed();
+        if let Some(timeout) = timeout {
+            if elapsed >= timeout {
+                // dropping the child kills it
+                drop(child);
+                failure::bail!(
+                    "The build did not finish within {} and was stopped. Raise `timeout` in the [build] section of your wrangler.toml if it needs more time.",
+                    format_duration(timeout)
+                )
+            }
+        }
+        if elapsed >= next_heartbeat {
+            StdErr::info(&format!("Still building ({})", format_duration(elapsed)));
+            next_heartbeat += HEARTBEAT_INTERVAL;
+        }
+
+        thread::sleep(interrupt::POLL_INTERVAL);
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 60, secs % 60) {
+        (0, secs) => format!("{}s", secs),
+        (mins, 0) => format!("{}m", mins),
+        (mins, secs) => format!("{}m{}s", mins, secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_formats_build_durations() {
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
+        assert_eq!(format_duration(Duration::from_secs(150)), "2m30s");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn it_stops_at_the_first_failing_hook() {
+        let target = Target::default();
+        let hooks = vec!["true".to_string(), "exit 3".to_string(), "true".to_string()];
+        let error = run_hooks("pre-build", &hooks, &target).unwrap_err();
+        assert!(error.to_string().contains("`exit 3`"));
+
+        let timings = run_hooks("post-build", &hooks[..1], &target).unwrap();
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].0, "post-build hook `true`");
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::settings::toml::{SbomFormat, Target};
+
+/// A package bundled into the worker
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Package {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    /// The SPDX license expression the package declares
+    pub license: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Ecosystem {
+    Npm,
+    Cargo,
+}
+
+impl Package {
+    /// The package URL that identifies the package in both formats
+    pub fn purl(&self) -> String {
+        match self.ecosystem {
+            // the @ of a scope is encoded, as it would be mistaken for the version's
+            Ecosystem::Npm => format!(
+                "pkg:npm/{}@{}",
+                self.name.replacen('@', "%40", 1),
+                self.version
+            ),
+            Ecosystem::Cargo => format!("pkg:cargo/{}@{}", self.name, self.version),
+        }
+    }
+}
+
+/// Writes the SBOM for `packages` next to the project, and returns where.
+pub fn write(
+    target: &Target,
+    format: SbomFormat,
+    packages: &[Package],
+) -> Result<PathBuf, failure::Error> {
+    let (file, sbom) = match format {
+        SbomFormat::CycloneDx => ("sbom.cdx.json", cyclonedx(&target.name, packages)),
+        SbomFormat::Spdx => ("sbom.spdx.json", spdx(&target.name, packages)),
+    };
+    let path = target.build_dir()?.join(file);
+    fs::write(&path, serde_json::to_string_pretty(&sbom)?)?;
+    Ok(path)
+}
+
+/// The npm packages that the files webpack bundled come from, with the versions in
+/// their package.json.
+pub fn npm_packages(modules: &[String]) -> Vec<Package> {
+    let mut versions: HashMap<PathBuf, Option<Package>> = HashMap::new();
+    for module in modules {
+        let dir = match package_dir(Path::new(module)) {
+            Some(dir) => dir,
+            None => continue,
+        };
+        versions
+            .entry(dir.clone())
+            .or_insert_with(|| read_package(&dir));
+    }
+    let packages: BTreeSet<Package> = versions.values().flatten().cloned().collect();
+    packages.into_iter().collect()
+}
+
+// node_modules/<name> or node_modules/@<scope>/<name>, the innermost when packages nest
+fn package_dir(module: &Path) -> Option<PathBuf> {
+    let components: Vec<_> = module.components().collect();
+    let at = components
+        .iter()
+        .rposition(|component| component.as_os_str() == "node_modules")?;
+    let name = components.get(at + 1)?;
+    let end = if name.as_os_str().to_string_lossy().starts_with('@') {
+        at + 3
+    } else {
+        at + 2
+    };
+    if end > components.len() {
+        return None;
+    }
+    Some(components[..end].iter().collect())
+}
+
+fn read_package(dir: &Path) -> Option<Package> {
+    let manifest: Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("package.json")).ok()?).ok()?;
+    Some(Package {
+        ecosystem: Ecosystem::Npm,
+        name: manifest["name"].as_str()?.to_string(),
+        version: manifest["version"].as_str()?.to_string(),
+        license: npm_license(&manifest),
+    })
+}
+
+// `"license": "MIT"`, or the deprecated `"license": { "type": "MIT" }` and
+// `"licenses": [{ "type": "MIT" }, ...]` of older packages
+fn npm_license(manifest: &Value) -> Option<String> {
+    match &manifest["license"] {
+        Value::String(license) => return Some(license.clone()),
+        Value::Object(license) => return license.get("type")?.as_str().map(String::from),
+        _ => (),
+    }
+    let licenses: Vec<&str> = manifest["licenses"]
+        .as_array()?
+        .iter()
+        .filter_map(|license| license["type"].as_str())
+        .collect();
+    match licenses.len() {
+        0 => None,
+        1 => Some(licenses[0].to_string()),
+        _ => Some(format!("({})", licenses.join(" OR "))),
+    }
+}
+
+/// The crates compiled into the worker, from `cargo metadata`: the normal dependencies
+/// of the project's crate, and theirs, but not build or dev dependencies.
+pub fn cargo_packages() -> Result<Vec<Package>, failure::Error> {
+    let output = Command::new("cargo")
+        .args(&["metadata", "--format-version", "1"])
+        .output()?;
+    if !output.status.success() {
+        failure::bail!(
+            "`cargo metadata` failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    }
+    cargo_dependencies(&serde_json::from_slice(&output.stdout)?)
+}
+
+fn cargo_dependencies(metadata: &Value) -> Result<Vec<Package>, failure::Error> {
+    let root = match metadata["resolve"]["root"].as_str() {
+        Some(root) => root,
+        None => failure::bail!("`cargo metadata` did not say which crate is the project's"),
+    };
+    let nodes: HashMap<&str, &Value> = metadata["resolve"]["nodes"]
+        .as_array()
+        .map(|nodes| {
+            nodes
+                .iter()
+                .filter_map(|node| Some((node["id"].as_str()?, node)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut seen = BTreeSet::new();
+    let mut queue = vec![root];
+    while let Some(id) = queue.pop() {
+        let deps = match nodes.get(id).and_then(|node| node["deps"].as_array()) {
+            Some(deps) => deps,
+            None => continue,
+        };
+        for dep in deps {
+            let is_normal = dep["dep_kinds"].as_array().map_or(false, |kinds| {
+                kinds.iter().any(|kind| kind["kind"].is_null())
+            });
+            if let Some(pkg) = dep["pkg"].as_str() {
+                if is_normal && seen.insert(pkg) {
+                    queue.push(pkg);
+                }
+            }
+        }
+    }
+
+    let mut packages: Vec<Package> = metadata["packages"]
+        .as_array()
+        .map(|packages| packages.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|package| package["id"].as_str().map_or(false, |id| seen.contains(id)))
+        .filter_map(|package| {
+            Some(Package {
+                ecosystem: Ecosystem::Cargo,
+                name: package["name"].as_str()?.to_string(),
+                version: package["version"].as_str()?.to_string(),
+                license: package["license"].as_str().map(String::from),
+            })
+        })
+        .collect();
+    packages.sort();
+    Ok(packages)
+}
+
+fn cyclonedx(worker: &str, packages: &[Package]) -> Value {
+    let components: Vec<Value> = packages
+        .iter()
+        .map(|package| {
+            let mut component = json!({
+                "type": "library",
+                "name": package.name,
+                "version": package.version,
+                "purl": package.purl(),
+            });
+            if let Some(license) = &package.license {
+                component["licenses"] = json!([{ "expression": license }]);
+            }
+            component
+        })
+        .collect();
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.4",
+        "serialNumber": format!("urn:uuid:{}", Uuid::new_v4()),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp(),
+            "tools": [{
+                "vendor": "Cloudflare",
+                "name": "wrangler",
+                "version": env!("CARGO_PKG_VERSION"),
+            }],
+            "component": { "type": "application", "name": worker },
+        },
+        "components": components,
+    })
+}
+
+fn spdx(worker: &str, packages: &[Package]) -> Value {
+    let mut spdx_packages = vec![json!({
+        "name": worker,
+        "SPDXID": "SPDXRef-Worker",
+        "downloadLocation": "NOASSERTION",
+    })];
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": "SPDXRef-Worker",
+    })];
+    for (i, package) in packages.iter().enumerate() {
+        // names can hold characters an SPDX id can't
+        let id = format!("SPDXRef-Package-{}", i + 1);
+        spdx_packages.push(json!({
+            "name": package.name,
+            "SPDXID": id,
+            "versionInfo": package.version,
+            "downloadLocation": "NOASSERTION",
+            "licenseDeclared": package.license.as_deref().unwrap_or("NOASSERTION"),
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": package.purl(),
+            }],
+        }));
+        relationships.push(json!({
+            "spdxElementId": "SPDXRef-Worker",
+            "relationshipType": "CONTAINS",
+            "relatedSpdxElement": id,
+        }));
+    }
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": worker,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{}-{}", worker, Uuid::new_v4()),
+        "creationInfo": {
+            "created": timestamp(),
+            "creators": [format!("Tool: wrangler-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": spdx_packages,
+        "relationships": relationships,
+    })
+}
+
+fn timestamp() -> String {
+    Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_npm_packages_webpack_bundled() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for (package, name, version, license) in &[
+            ("node_modules/lodash", "lodash", "4.17.21", "MIT"),
+            (
+                "node_modules/@cloudflare/kv-asset-handler",
+                "@cloudflare/kv-asset-handler",
+                "0.1.3",
+                "MIT OR Apache-2.0",
+            ),
+        ] {
+            fs::create_dir_all(root.join(package)).unwrap();
+            fs::write(
+                root.join(package).join("package.json"),
+                json!({ "name": name, "version": version, "license": license }).to_string(),
+            )
+            .unwrap();
+        }
+        let modules: Vec<String> = vec![
+            "src/index.js",
+            "node_modules/lodash/lodash.js",
+            "node_modules/lodash/fp.js",
+            "node_modules/@cloudflare/kv-asset-handler/dist/index.js",
+        ]
+        .into_iter()
+        .map(|module| root.join(module).to_string_lossy().to_string())
+        .collect();
+
+        let packages = npm_packages(&modules);
+        let purls: Vec<String> = packages.iter().map(Package::purl).collect();
+        assert_eq!(
+            purls,
+            vec![
+                "pkg:npm/%40cloudflare/kv-asset-handler@0.1.3",
+                "pkg:npm/lodash@4.17.21"
+            ]
+        );
+
+        assert_eq!(packages[0].license.as_deref(), Some("MIT OR Apache-2.0"));
+
+        let sbom = cyclonedx("worker", &packages);
+        assert_eq!(sbom["components"][1]["name"], "lodash");
+        assert_eq!(sbom["components"][1]["licenses"][0]["expression"], "MIT");
+        let sbom = spdx("worker", &packages);
+        assert_eq!(sbom["relationships"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn it_leaves_out_cargo_build_and_dev_dependencies() {
+        let metadata = json!({
+            "packages": [
+                { "id": "worker 0.1.0", "name": "worker", "version": "0.1.0" },
+                { "id": "serde 1.0.0", "name": "serde", "version": "1.0.0" },
+                { "id": "cc 1.0.0", "name": "cc", "version": "1.0.0" },
+                { "id": "itoa 1.0.0", "name": "itoa", "version": "1.0.0" },
+            ],
+            "resolve": {
+                "root": "worker 0.1.0",
+                "nodes": [
+                    { "id": "worker 0.1.0", "deps": [
+                        { "pkg": "serde 1.0.0", "dep_kinds": [{ "kind": null }] },
+                        { "pkg": "cc 1.0.0", "dep_kinds": [{ "kind": "build" }] },
+                    ]},
+                    { "id": "serde 1.0.0", "deps": [
+                        { "pkg": "itoa 1.0.0", "dep_kinds": [{ "kind": null }] },
+                    ]},
+                ],
+            },
+        });
+        let names: Vec<String> = cargo_dependencies(&metadata)
+            .unwrap()
+            .into_iter()
+            .map(|package| package.name)
+            .collect();
+        assert_eq!(names, vec!["itoa", "serde"]);
+    }
+}
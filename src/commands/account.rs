@@ -0,0 +1,167 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::WranglerError;
+use crate::http::{self, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{ConfigWriter, Manifest, Target};
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::{interactive, styles};
+
+#[derive(Deserialize)]
+struct Memberships {
+    result: Vec<Membership>,
+}
+
+#[derive(Deserialize)]
+struct Membership {
+    account: MembershipAccount,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MembershipAccount {
+    pub id: String,
+    pub name: String,
+}
+
+/// Makes sure the manifest has an account_id for `env`. If it is missing, the user picks
+/// one of the accounts they are a member of, and can save it to the configuration file.
+pub fn ensure_account_id(
+    user: &GlobalUser,
+    manifest: &mut Manifest,
+    config_path: &Path,
+    env: Option<&str>,
+) -> Result<(), failure::Error> {
+    if manifest.get_account_id(env).is_ok() {
+        return Ok(());
+    }
+
+    let account = choose_account(
+        user,
+        "Your configuration file is missing an account_id field",
+        "Add one of the following accounts",
+    )?;
+    let is_top_level = set_account_id(manifest, env, &account.id);
+
+    if is_top_level
+        && atty::is(atty::Stream::Stdin)
+        && interactive::confirm(&format!(
+            "Save account_id = \"{}\" to {}?",
+            account.id,
+            config_path.display()
+        ))?
+    {
+        let mut config = ConfigWriter::open(config_path)?;
+        config.set_string(None, "account_id", &account.id);
+        config.save(config_path)?;
+        StdOut::success(&format!("Saved account_id to {}", config_path.display()));
+    }
+
+    Ok(())
+}
+
+/// Fails when `target` has no account_id, which is required to manage `what`.
+pub fn check_account_id(target: &Target, what: &str) -> Result<(), failure::Error> {
+    if target.account_id.is_empty() {
+        failure::bail!(
+            "Your configuration file is missing an account_id, which is required to manage {}.",
+            what
+        )
+    }
+    Ok(())
+}
+
+/// The account id from the configuration file at `config_path` if it has one, or else one
+/// of the accounts the user is a member of. For commands that work without a project.
+pub fn account_id(user: &GlobalUser, config_path: &Path) -> Result<String, failure::Error> {
+    if config_path.exists() {
+        let manifest = Manifest::new(config_path)?;
+        if !manifest.account_id.is_empty() {
+            return Ok(manifest.account_id);
+        }
+    }
+    let account = choose_account(
+        user,
+        "No account_id was given",
+        "Pass one of the following accounts with --account-id",
+    )?;
+    Ok(account.id)
+}
+
+// Uses the only account the user is a member of, or asks which one to use. `missing`
+// explains why an account is needed and `fix` how to avoid the question next time.
+fn choose_account(
+    user: &GlobalUser,
+    missing: &str,
+    fix: &str,
+) -> Result<MembershipAccount, failure::Error> {
+    let accounts = memberships(user)?;
+    let account = match accounts.len() {
+        0 => failure::bail!("{}, and you are not a member of any accounts", missing),
+        1 => accounts[0].clone(),
+        _ if interactive::is_ci() || !atty::is(atty::Stream::Stdin) => {
+            let mut msg = format!("{}. {}:", missing, fix);
+            for account in &accounts {
+                msg.push_str(&format!("\n  {} ({})", account.id, account.name));
+            }
+            failure::bail!("{}", msg)
+        }
+        _ => pick_account(&accounts, missing)?,
+    };
+
+    StdOut::info(&format!(
+        "Using account {} ({})",
+        styles::highlight(&account.name),
+        account.id
+    ));
+    Ok(account)
+}
+
+fn pick_account(
+    accounts: &[MembershipAccount],
+    missing: &str,
+) -> Result<MembershipAccount, failure::Error> {
+    StdOut::message(&format!("{}. Which account should be used?", missing));
+    for (i, account) in accounts.iter().enumerate() {
+        StdOut::message(&format!("  {}) {} ({})", i + 1, account.name, account.id));
+    }
+
+    let choice = interactive::get_user_input("Enter a number:")?;
+    match choice.trim().parse::<usize>() {
+        Ok(n) if n >= 1 && n <= accounts.len() => Ok(accounts[n - 1].clone()),
+        _ => failure::bail!("\"{}\" is not one of the listed accounts", choice),
+    }
+}
+
+// An environment that sets `account_id = ""` overrides the top level, so fill in whichever
+// one is actually used for `env`. Returns whether the top level account_id was set.
+fn set_account_id(manifest: &mut Manifest, env: Option<&str>, account_id: &str) -> bool {
+    if let (Some(env), Some(envs)) = (env, manifest.env.as_mut()) {
+        if let Some(environment) = envs.get_mut(env) {
+            if environment.account_id.is_some() {
+                environment.account_id = Some(account_id.to_string());
+                return false;
+            }
+        }
+    }
+    manifest.account_id = account_id.to_string();
+    true
+}
+
+fn memberships(user: &GlobalUser) -> Result<Vec<MembershipAccount>, failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let res = client.get(&http::endpoints::memberships()).send_traced()?;
+
+    if !res.status().is_success() {
+        return Err(WranglerError::Api(format!(
+            "Could not list your accounts. Status: {}, Details {}",
+            res.status(),
+            res.text()?
+        ))
+        .into());
+    }
+
+    let memberships: Memberships = serde_json::from_str(&res.text()?)?;
+    Ok(memberships.result.into_iter().map(|m| m.account).collect())
+}
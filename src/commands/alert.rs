@@ -0,0 +1,197 @@
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::commands::account;
+use crate::commands::metrics::parse_period;
+use crate::http::{self, read_result, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::Table;
+
+// the notification the API sends when a worker's share of failed requests is too high
+const ALERT_TYPE: &str = "workers_error_rate";
+
+#[derive(Deserialize)]
+struct Webhook {
+    id: String,
+    url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Policy {
+    id: String,
+    name: String,
+    alert_type: String,
+    enabled: bool,
+    #[serde(default)]
+    filters: Value,
+}
+
+/// Creates a notification policy that calls `webhook` when more than `error_rate` (e.g.
+/// "5%") of the worker's requests fail over `window` (e.g. "5m"). The webhook is added
+/// to the account as a destination the first time it's used.
+pub fn add(
+    target: &Target,
+    user: &GlobalUser,
+    error_rate: &str,
+    window: &str,
+    webhook: &str,
+) -> Result<(), failure::Error> {
+    account::check_account_id(target, "alerts")?;
+    let error_rate = parse_error_rate(error_rate)?;
+    let window = parse_period(window).map_err(|_| {
+        failure::format_err!(
+            "--window expects a number followed by s, m, h or d (e.g. 5m), not {:?}",
+            window
+        )
+    })?;
+    let client = http::legacy_auth_client(user);
+
+    let webhook_id = find_or_add_webhook(&client, target, webhook)?;
+    let body = json!({
+        "name": format!(
+            "{}: error rate above {}% over {}s",
+            target.name,
+            error_rate,
+            window.num_seconds()
+        ),
+        "description": "Added with wrangler alert add",
+        "enabled": true,
+        "alert_type": ALERT_TYPE,
+        "mechanisms": { "webhooks": [{ "id": webhook_id }] },
+        // the API takes every filter as a list of strings
+        "filters": {
+            "services": [target.name],
+            "error_rate": [error_rate.to_string()],
+            "window_seconds": [window.num_seconds().to_string()],
+        },
+    });
+    StdOut::working(&format!("Adding an error rate alert for {}", target.name));
+    let addr = http::endpoints::alerting_policies(&target.account_id);
+    let res = client.post(&addr).json(&body).send_traced()?;
+    let policy: Value = read_result(res)?.unwrap_or_default();
+
+    StdOut::success(&format!(
+        "Added alert {}. {} will be notified when more than {}% of requests to {} fail.",
+        policy["id"].as_str().unwrap_or_default(),
+        webhook,
+        error_rate,
+        target.name
+    ));
+    Ok(())
+}
+
+/// Lists the error rate alerts of the worker.
+pub fn list(target: &Target, user: &GlobalUser) -> Result<(), failure::Error> {
+    account::check_account_id(target, "alerts")?;
+    let client = http::legacy_auth_client(user);
+    let addr = http::endpoints::alerting_policies(&target.account_id);
+    let res = client.get(&addr).send_traced()?;
+    let policies: Vec<Policy> = read_result(res)?.unwrap_or_default();
+    let policies: Vec<Policy> = policies
+        .into_iter()
+        .filter(|policy| policy.alert_type == ALERT_TYPE && is_for_worker(policy, &target.name))
+        .collect();
+
+    if policies.is_empty() {
+        StdOut::info(&format!("There are no alerts for {}.", target.name));
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Id", "Name", "Enabled"]);
+    for policy in &policies {
+        table.add_row(vec![
+            policy.id.as_str(),
+            policy.name.as_str(),
+            if policy.enabled { "yes" } else { "no" },
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// Deletes an alert after asking for confirmation.
+pub fn delete(target: &Target, user: &GlobalUser, id: &str) -> Result<(), failure::Error> {
+    account::check_account_id(target, "alerts")?;
+    match interactive::confirm(&format!("Are you sure you want to delete alert {}?", id)) {
+        Ok(true) => (),
+        Ok(false) => {
+            StdOut::info(&format!("Not deleting alert {}", id));
+            return Ok(());
+        }
+        Err(e) => failure::bail!(e),
+    }
+
+    StdOut::working(&format!("Deleting alert {}", id));
+    let client = http::legacy_auth_client(user);
+    let addr = format!(
+        "{}/{}",
+        http::endpoints::alerting_policies(&target.account_id),
+        id
+    );
+    let res = client.delete(&addr).send_traced()?;
+    read_result::<Value>(res)?;
+
+    StdOut::success("Success");
+    Ok(())
+}
+
+fn find_or_add_webhook(
+    client: &Client,
+    target: &Target,
+    url: &str,
+) -> Result<String, failure::Error> {
+    let addr = http::endpoints::alerting_webhooks(&target.account_id);
+    let res = client.get(&addr).send_traced()?;
+    let webhooks: Vec<Webhook> = read_result(res)?.unwrap_or_default();
+    if let Some(webhook) = webhooks
+        .into_iter()
+        .find(|webhook| webhook.url.as_deref() == Some(url))
+    {
+        return Ok(webhook.id);
+    }
+
+    let body = json!({ "name": format!("wrangler: {}", target.name), "url": url });
+    let res = client.post(&addr).json(&body).send_traced()?;
+    match read_result::<Webhook>(res)? {
+        Some(webhook) => Ok(webhook.id),
+        None => failure::bail!("The API did not return the webhook it added"),
+    }
+}
+
+fn is_for_worker(policy: &Policy, name: &str) -> bool {
+    policy.filters["services"]
+        .as_array()
+        .map_or(false, |services| {
+            services.iter().any(|service| service == name)
+        })
+}
+
+// "5%", or just "5"
+fn parse_error_rate(error_rate: &str) -> Result<f64, failure::Error> {
+    let percent = error_rate.trim().trim_end_matches('%');
+    match percent.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate <= 100.0 => Ok(rate),
+        _ => failure::bail!(
+            "--error-rate expects a percentage above 0 and up to 100 (e.g. 5%), not {:?}",
+            error_rate
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_error_rates() {
+        assert_eq!(parse_error_rate("5%").ok(), Some(5.0));
+        assert_eq!(parse_error_rate("2.5").ok(), Some(2.5));
+        assert!(parse_error_rate("0%").is_err());
+        assert!(parse_error_rate("150%").is_err());
+        assert!(parse_error_rate("five").is_err());
+    }
+}
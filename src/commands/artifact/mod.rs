@@ -0,0 +1,358 @@
+//! `wrangler artifact push` and `pull` keep artifacts written by `wrangler build --out-dir`
+//! in an OCI registry (`oci://<registry>/<repository>`) or an S3-compatible bucket
+//! (`s3://<bucket>/<prefix>`), so a worker built and tested once can be promoted from
+//! staging to production without being rebuilt.
+//!
+//! An artifact is stored the way ORAS stores files in a registry: an OCI image manifest
+//! whose config is `artifact.json`, with one layer per file, titled with the file's path
+//! in the artifact. Every blob is addressed by its SHA-256 digest, and so is the artifact,
+//! by the digest of its manifest. A bucket holds the same manifests and blobs under
+//! `<prefix>/manifests/` and `<prefix>/blobs/`.
+
+mod oci;
+mod s3;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::install::checksum::sha256_hex;
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::upload::form::artifact::{self, Artifact};
+
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const CONFIG_MEDIA_TYPE: &str = "application/vnd.cloudflare.wrangler.artifact.v1+json";
+const FILE_MEDIA_TYPE: &str = "application/octet-stream";
+// the annotation ORAS names files with
+const TITLE: &str = "org.opencontainers.image.title";
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    schema_version: u32,
+    media_type: String,
+    config: Descriptor,
+    layers: Vec<Descriptor>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    media_type: String,
+    digest: String,
+    size: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, String>,
+}
+
+/// A registry or bucket that holds artifacts. Manifests are stored under both their
+/// digest and any tag they are pushed with.
+trait Store {
+    fn has_blob(&mut self, digest: &str) -> Result<bool, failure::Error>;
+    fn put_blob(&mut self, digest: &str, blob: Vec<u8>) -> Result<(), failure::Error>;
+    fn get_blob(&mut self, digest: &str) -> Result<Vec<u8>, failure::Error>;
+    fn put_manifest(&mut self, reference: &str, manifest: Vec<u8>) -> Result<(), failure::Error>;
+    fn get_manifest(&mut self, reference: &str) -> Result<Vec<u8>, failure::Error>;
+}
+
+#[derive(Debug, PartialEq)]
+enum Scheme {
+    Oci,
+    S3,
+}
+
+/// `oci://<registry>/<repository>` or `s3://<bucket>/<prefix>`, followed by `:<tag>` or
+/// `@sha256:<digest>` to pick one artifact.
+#[derive(Debug, PartialEq)]
+struct Location {
+    scheme: Scheme,
+    /// the registry or bucket
+    host: String,
+    /// the repository or prefix
+    path: String,
+    reference: Option<String>,
+}
+
+impl Location {
+    fn parse(location: &str) -> Result<Self, failure::Error> {
+        let (scheme, rest) = match location.find("://") {
+            Some(i) => (&location[..i], &location[i + 3..]),
+            None => failure::bail!(
+                "{} should start with oci:// for a registry or s3:// for a bucket",
+                location
+            ),
+        };
+        let scheme = match scheme {
+            "oci" => Scheme::Oci,
+            "s3" => Scheme::S3,
+            _ => failure::bail!(
+                "{}:// is not supported. Use oci:// for a registry or s3:// for a bucket",
+                scheme
+            ),
+        };
+
+        // a tag follows the last `/`, as a registry's host can have a port
+        let (rest, reference) = match rest.rfind('@') {
+            Some(at) => (&rest[..at], Some(&rest[at + 1..])),
+            None => {
+                let last = rest.rfind('/').map_or(0, |i| i + 1);
+                match rest[last..].rfind(':') {
+                    Some(i) => (&rest[..last + i], Some(&rest[last + i + 1..])),
+                    None => (rest, None),
+                }
+            }
+        };
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], rest[i + 1..].trim_matches('/')),
+            None => (rest, ""),
+        };
+        if host.is_empty() || (scheme == Scheme::Oci && path.is_empty()) {
+            failure::bail!(
+                "{} should look like oci://<registry>/<repository> or s3://<bucket>/<prefix>",
+                location
+            )
+        }
+        if let Some(reference) = reference {
+            if reference.is_empty() {
+                failure::bail!("{} ends without a tag or digest", location)
+            }
+        }
+
+        Ok(Location {
+            scheme,
+            host: host.to_string(),
+            path: path.to_string(),
+            reference: reference.map(String::from),
+        })
+    }
+
+    fn open(&self, push: bool) -> Result<Box<dyn Store>, failure::Error> {
+        Ok(match self.scheme {
+            Scheme::Oci => Box::new(oci::Registry::new(&self.host, &self.path, push)),
+            Scheme::S3 => Box::new(s3::Bucket::new(&self.host, &self.path)?),
+        })
+    }
+
+    // the location without its tag or digest
+    fn repository(&self) -> String {
+        let scheme = match self.scheme {
+            Scheme::Oci => "oci",
+            Scheme::S3 => "s3",
+        };
+        match self.path.as_str() {
+            "" => format!("{}://{}", scheme, self.host),
+            path => format!("{}://{}/{}", scheme, self.host, path),
+        }
+    }
+}
+
+/// Pushes the artifact in `dir` to `destination`, tagging it when the destination ends
+/// in `:<tag>`, and prints its digest.
+pub fn push(dir: &Path, destination: &str) -> Result<(), failure::Error> {
+    let location = Location::parse(destination)?;
+    if let Some(reference) = &location.reference {
+        if reference.starts_with("sha256:") {
+            failure::bail!("An artifact's digest comes from its contents, so push to a repository, optionally with a :<tag>")
+        }
+    }
+    Artifact::read(dir)?;
+
+    let mut blobs = Vec::new();
+    let config = fs::read(dir.join(artifact::MANIFEST))?;
+    let config_descriptor = descriptor(CONFIG_MEDIA_TYPE, &config, None);
+    blobs.push((config_descriptor.digest.clone(), config));
+    let mut layers = Vec::new();
+    for file in files(dir)? {
+        let blob = fs::read(dir.join(&file))?;
+        let layer = descriptor(FILE_MEDIA_TYPE, &blob, Some(file));
+        blobs.push((layer.digest.clone(), blob));
+        layers.push(layer);
+    }
+    let manifest = serde_json::to_vec(&Manifest {
+        schema_version: 2,
+        media_type: MANIFEST_MEDIA_TYPE.to_string(),
+        config: config_descriptor,
+        layers,
+    })?;
+    let digest = format!("sha256:{}", sha256_hex(&manifest));
+
+    let mut store = location.open(true)?;
+    StdErr::working(&format!("Pushing {} to {}", dir.display(), destination));
+    for (digest, blob) in blobs {
+        // blobs are addressed by their contents, so one that is there already is the same
+        if !store.has_blob(&digest)? {
+            store.put_blob(&digest, blob)?;
+        }
+    }
+    store.put_manifest(&digest, manifest.clone())?;
+    if let Some(tag) = &location.reference {
+        store.put_manifest(tag, manifest)?;
+    }
+
+    let pushed = format!("{}@{}", location.repository(), digest);
+    StdOut::success(&format!("Pushed the artifact as {}", pushed));
+    StdOut::info(&format!(
+        "Pull it with `wrangler artifact pull {} --out-dir <DIR>`",
+        pushed
+    ));
+    Ok(())
+}
+
+/// Pulls the artifact at `source`, named by a tag or digest, into `dir`, checking every
+/// file against its digest.
+pub fn pull(source: &str, dir: &Path) -> Result<(), failure::Error> {
+    let location = Location::parse(source)?;
+    let reference = match &location.reference {
+        Some(reference) => reference,
+        None => failure::bail!(
+            "Name the artifact to pull with :<tag> or @sha256:<digest> after {}",
+            source
+        ),
+    };
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+        failure::bail!(
+            "{} is not empty. Pull into an empty or new directory.",
+            dir.display()
+        )
+    }
+
+    let mut store = location.open(false)?;
+    let manifest = store.get_manifest(reference)?;
+    let digest = format!("sha256:{}", sha256_hex(&manifest));
+    if reference.starts_with("sha256:") && reference != &digest {
+        failure::bail!(
+            "The manifest of {} has the digest {}, so it was changed after it was pushed.",
+            source,
+            digest
+        )
+    }
+    let manifest: Manifest = serde_json::from_slice(&manifest)?;
+    if manifest.config.media_type != CONFIG_MEDIA_TYPE {
+        failure::bail!("{} is not a wrangler artifact", source)
+    }
+
+    StdErr::working(&format!("Pulling {} into {}", source, dir.display()));
+    fs::create_dir_all(dir)?;
+    let config = get_verified(store.as_mut(), &manifest.config)?;
+    fs::write(dir.join(artifact::MANIFEST), config)?;
+    for layer in &manifest.layers {
+        let file = match layer.annotations.get(TITLE) {
+            Some(file) => artifact::safe_path(file)?,
+            None => failure::bail!("{} in the manifest has no file name", layer.digest),
+        };
+        let blob = get_verified(store.as_mut(), layer)?;
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, blob)?;
+    }
+    Artifact::read(dir)?;
+
+    StdOut::success(&format!(
+        "Pulled {}@{} into {}",
+        location.repository(),
+        digest,
+        dir.display()
+    ));
+    StdOut::info(&format!(
+        "Publish it with `wrangler publish --from-artifact {}`",
+        dir.display()
+    ));
+    Ok(())
+}
+
+fn descriptor(media_type: &str, blob: &[u8], file: Option<String>) -> Descriptor {
+    let mut annotations = BTreeMap::new();
+    if let Some(file) = file {
+        annotations.insert(TITLE.to_string(), file);
+    }
+    Descriptor {
+        media_type: media_type.to_string(),
+        digest: format!("sha256:{}", sha256_hex(blob)),
+        size: blob.len() as u64,
+        annotations,
+    }
+}
+
+fn get_verified(store: &mut dyn Store, descriptor: &Descriptor) -> Result<Vec<u8>, failure::Error> {
+    let blob = store.get_blob(&descriptor.digest)?;
+    let digest = format!("sha256:{}", sha256_hex(&blob));
+    if digest != descriptor.digest {
+        failure::bail!(
+            "{} was downloaded with the digest {}, so it was changed after it was pushed.",
+            descriptor.digest,
+            digest
+        )
+    }
+    Ok(blob)
+}
+
+// The files in the artifact besides artifact.json, as sorted `/` separated paths, so the
+// same files always make the same manifest.
+fn files(dir: &Path) -> Result<Vec<String>, failure::Error> {
+    fn walk(dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<(), failure::Error> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if entry.file_type()?.is_dir() {
+                walk(&entry.path(), &format!("{}/", name), files)?;
+            } else if name != artifact::MANIFEST {
+                files.push(name);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(dir, "", &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_artifact_locations() {
+        assert_eq!(
+            Location::parse("oci://localhost:5000/acme/worker:staging").unwrap(),
+            Location {
+                scheme: Scheme::Oci,
+                host: "localhost:5000".to_string(),
+                path: "acme/worker".to_string(),
+                reference: Some("staging".to_string()),
+            }
+        );
+        let location = Location::parse("s3://releases/workers/api@sha256:abc").unwrap();
+        assert_eq!(location.scheme, Scheme::S3);
+        assert_eq!(location.reference, Some("sha256:abc".to_string()));
+        assert_eq!(location.repository(), "s3://releases/workers/api");
+        assert_eq!(Location::parse("s3://releases").unwrap().path, "");
+
+        assert!(Location::parse("oci://ghcr.io").is_err());
+        assert!(Location::parse("ghcr.io/acme/worker").is_err());
+        assert!(Location::parse("gs://bucket/prefix").is_err());
+    }
+
+    #[test]
+    fn it_lists_artifact_files_in_a_stable_order() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("wasm")).unwrap();
+        for file in &[
+            "worker.js",
+            "wasm/module.wasm",
+            "artifact.json",
+            "metadata.json",
+        ] {
+            fs::write(dir.path().join(file), "x").unwrap();
+        }
+        assert_eq!(
+            files(dir.path()).unwrap(),
+            vec!["metadata.json", "wasm/module.wasm", "worker.js"]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::env;
+
+use regex::Regex;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, LOCATION, WWW_AUTHENTICATE};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use url::Url;
+
+use super::{Store, FILE_MEDIA_TYPE, MANIFEST_MEDIA_TYPE};
+use crate::http;
+
+/// A repository in an OCI registry, spoken to with the distribution API. Credentials come
+/// from `$WRANGLER_REGISTRY_USERNAME` and `$WRANGLER_REGISTRY_PASSWORD`, and are only sent
+/// once the registry asks for them.
+pub struct Registry {
+    client: Client,
+    registry: String,
+    repository: String,
+    // what the token asked for has to allow, "pull" or "pull,push"
+    actions: &'static str,
+    authorization: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Token {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+impl Registry {
+    pub fn new(registry: &str, repository: &str, push: bool) -> Self {
+        // a registry on this machine, like `docker run registry`, is served over http
+        let scheme = if registry.starts_with("localhost") || registry.starts_with("127.0.0.1") {
+            "http"
+        } else {
+            "https"
+        };
+        Registry {
+            client: http::client(),
+            registry: format!("{}://{}", scheme, registry),
+            repository: repository.to_string(),
+            actions: if push { "pull,push" } else { "pull" },
+            authorization: None,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/v2/{}/{}", self.registry, self.repository, path)
+    }
+
+    // Sends the request, logging in and sending it again when the registry asks to.
+    fn send(
+        &mut self,
+        request: impl Fn(&Client) -> RequestBuilder,
+    ) -> Result<Response, failure::Error> {
+        let res = self.with_authorization(request(&self.client)).send()?;
+        if res.status() != StatusCode::UNAUTHORIZED || self.authorization.is_some() {
+            return Ok(res);
+        }
+        let challenge = res
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|challenge| challenge.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        self.authorization = Some(self.authorize(&challenge)?);
+        Ok(self.with_authorization(request(&self.client)).send()?)
+    }
+
+    fn with_authorization(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.authorization {
+            Some(authorization) => request.header(AUTHORIZATION, authorization.as_str()),
+            None => request,
+        }
+    }
+
+    fn authorize(&self, challenge: &str) -> Result<String, failure::Error> {
+        let credentials = match (
+            env::var("WRANGLER_REGISTRY_USERNAME"),
+            env::var("WRANGLER_REGISTRY_PASSWORD"),
+        ) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            _ => None,
+        };
+        let scheme = challenge.split_whitespace().next().unwrap_or_default();
+
+        if scheme.eq_ignore_ascii_case("basic") {
+            return match credentials {
+                Some((username, password)) => Ok(format!(
+                    "Basic {}",
+                    base64::encode(format!("{}:{}", username, password))
+                )),
+                None => failure::bail!(
+                    "{} needs credentials. Set $WRANGLER_REGISTRY_USERNAME and $WRANGLER_REGISTRY_PASSWORD.",
+                    self.registry
+                ),
+            };
+        }
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            failure::bail!(
+                "{} asked to log in with \"{}\", which wrangler can't do",
+                self.registry,
+                challenge
+            )
+        }
+
+        // anonymous tokens are enough to pull public repositories
+        let params = challenge_params(challenge);
+        let realm = match params.iter().find(|(name, _)| name == "realm") {
+            Some((_, realm)) => realm,
+            None => failure::bail!("{} asked to log in without saying where", self.registry),
+        };
+        let mut query = vec![(
+            "scope".to_string(),
+            format!("repository:{}:{}", self.repository, self.actions),
+        )];
+        if let Some((_, service)) = params.iter().find(|(name, _)| name == "service") {
+            query.push(("service".to_string(), service.clone()));
+        }
+        let mut request = self.client.get(realm).query(&query);
+        if let Some((username, password)) = credentials {
+            request = request.basic_auth(username, Some(password));
+        }
+        let res = check(request.send()?, "log in to the registry")?;
+        let token: Token = res.json()?;
+        match token.token.or(token.access_token) {
+            Some(token) => Ok(format!("Bearer {}", token)),
+            None => failure::bail!("{} did not return a token", realm),
+        }
+    }
+}
+
+impl Store for Registry {
+    fn has_blob(&mut self, digest: &str) -> Result<bool, failure::Error> {
+        let url = self.url(&format!("blobs/{}", digest));
+        let res = self.send(|client| client.head(&url))?;
+        match res.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            _ => check(res, "check for a blob").map(|_| false),
+        }
+    }
+
+    fn put_blob(&mut self, digest: &str, blob: Vec<u8>) -> Result<(), failure::Error> {
+        let uploads = self.url("blobs/uploads/");
+        let res = check(
+            self.send(|client| client.post(&uploads))?,
+            "start an upload",
+        )?;
+        let location = match res
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+        {
+            Some(location) if location.starts_with('/') => format!("{}{}", self.registry, location),
+            Some(location) => location.to_string(),
+            None => failure::bail!("{} did not say where to upload to", self.registry),
+        };
+        let mut upload = Url::parse(&location)?;
+        upload.query_pairs_mut().append_pair("digest", digest);
+
+        let res = self.send(|client| {
+            client
+                .put(upload.as_str())
+                .header(CONTENT_TYPE, FILE_MEDIA_TYPE)
+                .body(blob.clone())
+        })?;
+        check(res, "upload a blob")?;
+        Ok(())
+    }
+
+    fn get_blob(&mut self, digest: &str) -> Result<Vec<u8>, failure::Error> {
+        let url = self.url(&format!("blobs/{}", digest));
+        let res = check(self.send(|client| client.get(&url))?, "download a blob")?;
+        Ok(res.bytes()?.to_vec())
+    }
+
+    fn put_manifest(&mut self, reference: &str, manifest: Vec<u8>) -> Result<(), failure::Error> {
+        let url = self.url(&format!("manifests/{}", reference));
+        let res = self.send(|client| {
+            client
+                .put(&url)
+                .header(CONTENT_TYPE, MANIFEST_MEDIA_TYPE)
+                .body(manifest.clone())
+        })?;
+        check(res, "upload the manifest")?;
+        Ok(())
+    }
+
+    fn get_manifest(&mut self, reference: &str) -> Result<Vec<u8>, failure::Error> {
+        let url = self.url(&format!("manifests/{}", reference));
+        let res = self.send(|client| client.get(&url).header(ACCEPT, MANIFEST_MEDIA_TYPE))?;
+        if res.status() == StatusCode::NOT_FOUND {
+            failure::bail!("There is no artifact {} in {}", reference, self.repository)
+        }
+        Ok(check(res, "download the manifest")?.bytes()?.to_vec())
+    }
+}
+
+fn check(res: Response, action: &str) -> Result<Response, failure::Error> {
+    if res.status().is_success() {
+        return Ok(res);
+    }
+    failure::bail!(
+        "Could not {}. Status: {}, Details {}",
+        action,
+        res.status(),
+        res.text()?
+    )
+}
+
+// the `name="value"` pairs of a `WWW-Authenticate: Bearer ...` challenge
+fn challenge_params(challenge: &str) -> Vec<(String, String)> {
+    let param = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+    param
+        .captures_iter(challenge)
+        .map(|captures| (captures[1].to_string(), captures[2].to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_bearer_challenges() {
+        let params = challenge_params(
+            r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:acme/worker:pull""#,
+        );
+        assert_eq!(
+            params,
+            vec![
+                ("realm".to_string(), "https://ghcr.io/token".to_string()),
+                ("service".to_string(), "ghcr.io".to_string()),
+                (
+                    "scope".to_string(),
+                    "repository:acme/worker:pull".to_string()
+                ),
+            ]
+        );
+    }
+}
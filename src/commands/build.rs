@@ -3,9 +3,10 @@ use std::path::Path;
 use clap::ArgMatches;
 
 use super::DEFAULT_CONFIG_PATH;
-use crate::build::build_target;
+use crate::build::{build_target, licenses};
 use crate::settings::toml::Manifest;
 use crate::terminal::message::{Message, StdOut};
+use crate::upload;
 
 pub fn build(matches: &ArgMatches) -> Result<(), failure::Error> {
     log::info!("Getting project settings");
@@ -16,8 +17,18 @@ pub fn build(matches: &ArgMatches) -> Result<(), failure::Error> {
     let target = &manifest.get_target(env, false)?;
     let build_result = build_target(&target);
     match build_result {
-        Ok(msg) => {
-            StdOut::success(&msg);
+        Ok(output) => {
+            StdOut::success(&output.message);
+            if matches.is_present("license-report") {
+                licenses::report(&target, &output)?;
+            }
+            if let Some(dir) = matches.value_of("out-dir") {
+                upload::form::write_artifact(&target, Path::new(dir), output.sbom.as_deref())?;
+                StdOut::success(&format!(
+                    "Wrote the artifact to {}. Publish it with `wrangler publish --from-artifact {}`",
+                    dir, dir
+                ));
+            }
             Ok(())
         }
         Err(e) => Err(e),
@@ -6,9 +6,9 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 
 use cloudflare::endpoints::user::{GetUserDetails, GetUserTokenStatus};
-use cloudflare::framework::apiclient::ApiClient;
 
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, TracedClient};
 use crate::settings::{get_global_config_path, global_user::GlobalUser};
 use crate::terminal::message::{Message, StdOut};
 use crate::terminal::styles;
@@ -49,7 +49,7 @@ pub fn validate_credentials(user: &GlobalUser) -> Result<(), failure::Error> {
     let client = http::cf_v4_client(user)?;
 
     match user {
-        GlobalUser::TokenAuth { .. } => match client.request(&GetUserTokenStatus {}) {
+        GlobalUser::TokenAuth { .. } => match client.request_traced(&GetUserTokenStatus {}) {
             Ok(success) => {
                 if success.result.status == "active" {
                     Ok(())
@@ -57,18 +57,19 @@ pub fn validate_credentials(user: &GlobalUser) -> Result<(), failure::Error> {
                     failure::bail!("Authentication check failed. Your token has status \"{}\", not \"active\".\nTry rolling your token on the Cloudflare dashboard.")
                 }
             }
-            Err(e) => failure::bail!(
+            Err(e) => Err(WranglerError::Auth(format!(
                 "Authentication check failed. Please make sure your API token is correct.\n{}",
                 http::format_error(e, None)
-            ),
+            ))
+            .into()),
         },
-        GlobalUser::GlobalKeyAuth { .. } => match client.request(&GetUserDetails {}) {
+        GlobalUser::GlobalKeyAuth { .. } => match client.request_traced(&GetUserDetails {}) {
             Ok(_) => Ok(()),
             Err(_) => {
                 let api_docs_url = styles::url(
                     "https://developers.cloudflare.com/workers/quickstart/#global-api-key",
                 );
-                failure::bail!("Authentication check failed. Please make sure your email and global API key pair are correct.\nSee {}", api_docs_url)
+                Err(WranglerError::Auth(format!("Authentication check failed. Please make sure your email and global API key pair are correct.\nSee {}", api_docs_url)).into())
             }
         },
     }
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+
+use difference::{Changeset, Difference};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+use crate::commands::diff::{diff_maps, LiveBinding};
+use crate::http::{self, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::metadata::Annotations;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+use crate::terminal::table::Table;
+
+// unchanged lines shown around each change in a code diff
+const CONTEXT_LINES: usize = 3;
+
+/// A version of a script, created by each upload.
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Version {
+    pub id: String,
+    #[serde(default)]
+    pub metadata: VersionMetadata,
+    #[serde(default)]
+    pub annotations: Annotations,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct VersionMetadata {
+    pub created_on: Option<String>,
+    pub author_email: Option<String>,
+    pub source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionsResponse {
+    result: Option<VersionList>,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    result: VersionDetail,
+}
+
+// what a version was uploaded with, besides its code
+#[derive(Deserialize)]
+struct VersionDetail {
+    #[serde(flatten)]
+    version: Version,
+    #[serde(default)]
+    resources: Resources,
+}
+
+#[derive(Default, Deserialize)]
+struct Resources {
+    #[serde(default)]
+    bindings: Vec<LiveBinding>,
+    #[serde(default)]
+    script_runtime: ScriptRuntime,
+}
+
+#[derive(Default, Deserialize)]
+struct ScriptRuntime {
+    compatibility_date: Option<String>,
+    #[serde(default)]
+    compatibility_flags: Vec<String>,
+    usage_model: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionList {
+    #[serde(default)]
+    items: Vec<Version>,
+}
+
+/// Lists the uploaded versions of the target's script, newest first, with the message
+/// and tag they were published with.
+pub fn list(target: &Target, user: &GlobalUser) -> Result<(), failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let script_addr = http::endpoints::script(&target.account_id, &target.name);
+    let text = get(&client, &format!("{}/versions", script_addr))?;
+    let versions = parse_versions(&text)?;
+
+    if versions.is_empty() {
+        StdOut::info(&format!("{} has not been published yet", target.name));
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Created", "Version", "Author", "Tag", "Commit", "Message"]);
+    for version in &versions {
+        let metadata = &version.metadata;
+        table.add_row(vec![
+            metadata.created_on.as_deref().unwrap_or("-").to_string(),
+            version.id.clone(),
+            metadata
+                .author_email
+                .as_deref()
+                .or_else(|| metadata.source.as_deref())
+                .unwrap_or("-")
+                .to_string(),
+            version.annotations.tag.clone().unwrap_or_default(),
+            commit(&version.annotations),
+            version.annotations.message.clone().unwrap_or_default(),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// The id of the newest version of the target's script, which is the one running after
+/// a publish.
+pub fn latest_version(
+    target: &Target,
+    user: &GlobalUser,
+) -> Result<Option<String>, failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let script_addr = http::endpoints::script(&target.account_id, &target.name);
+    let text = get(&client, &format!("{}/versions", script_addr))?;
+    Ok(parse_versions(&text)?
+        .into_iter()
+        .next()
+        .map(|version| version.id))
+}
+
+/// Prints what changed between two versions of the target's script: a unified diff of
+/// the code, then the bindings and runtime settings that were added, removed or changed.
+pub fn diff(
+    target: &Target,
+    user: &GlobalUser,
+    from: &str,
+    to: &str,
+) -> Result<(), failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let script_addr = http::endpoints::script(&target.account_id, &target.name);
+    let fetch = |id: &str| -> Result<(VersionDetail, String), failure::Error> {
+        let text = get(&client, &format!("{}/versions/{}", script_addr, id))?;
+        let response: VersionResponse = serde_json::from_str(&text)?;
+        let code = get(
+            &client,
+            &format!("{}/content/v2?version={}", script_addr, id),
+        )?;
+        Ok((response.result, code))
+    };
+    let (old, old_code) = fetch(from)?;
+    let (new, new_code) = fetch(to)?;
+
+    StdOut::message(&format!(
+        "{}",
+        styles::deletion(format!("--- {}", describe(&old.version)))
+    ));
+    StdOut::message(&format!(
+        "{}",
+        styles::addition(format!("+++ {}", describe(&new.version)))
+    ));
+    let code_diff = unified_diff(&old_code, &new_code);
+    if code_diff.is_empty() {
+        StdOut::message("The code is the same");
+    }
+    for line in code_diff {
+        match line.chars().next() {
+            Some('+') => StdOut::message(&styles::addition(line).to_string()),
+            Some('-') => StdOut::message(&styles::deletion(line).to_string()),
+            Some('@') => StdOut::message(&styles::highlight(line).to_string()),
+            _ => StdOut::message(line),
+        }
+    }
+
+    let sections = vec![
+        (
+            "bindings",
+            bindings(&old.resources),
+            bindings(&new.resources),
+        ),
+        (
+            "settings",
+            settings(&old.resources),
+            settings(&new.resources),
+        ),
+    ];
+    for (title, old, new) in sections {
+        let changes = diff_maps(&new, &old);
+        if changes.is_empty() {
+            continue;
+        }
+        StdOut::message(&styles::highlight(title).to_string());
+        for change in changes {
+            StdOut::message(&format!("  {}", change));
+        }
+    }
+    Ok(())
+}
+
+fn bindings(resources: &Resources) -> BTreeMap<String, String> {
+    resources
+        .bindings
+        .iter()
+        .map(|binding| (binding.name.clone(), binding.describe()))
+        .collect()
+}
+
+fn settings(resources: &Resources) -> BTreeMap<String, String> {
+    let runtime = &resources.script_runtime;
+    let mut settings = BTreeMap::new();
+    if let Some(date) = &runtime.compatibility_date {
+        settings.insert("compatibility_date".to_string(), date.clone());
+    }
+    for flag in &runtime.compatibility_flags {
+        settings.insert(format!("compatibility_flags {}", flag), String::new());
+    }
+    if let Some(usage_model) = &runtime.usage_model {
+        settings.insert("usage_model".to_string(), usage_model.clone());
+    }
+    settings
+}
+
+// "3f1e9a52 (2022-11-08T17:19:29Z, v1.4.2)"
+fn describe(version: &Version) -> String {
+    let details: Vec<&str> = vec![
+        version.metadata.created_on.as_deref(),
+        version.annotations.tag.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if details.is_empty() {
+        version.id.clone()
+    } else {
+        format!("{} ({})", version.id, details.join(", "))
+    }
+}
+
+// The lines of a unified diff from `old` to `new`, without the file headers. Empty when
+// they are the same.
+fn unified_diff(old: &str, new: &str) -> Vec<String> {
+    let mut lines: Vec<(char, &str)> = Vec::new();
+    let changeset = Changeset::new(old, new, "\n");
+    for difference in &changeset.diffs {
+        let (tag, text) = match difference {
+            Difference::Same(text) => (' ', text),
+            Difference::Add(text) => ('+', text),
+            Difference::Rem(text) => ('-', text),
+        };
+        lines.extend(text.split('\n').map(|line| (tag, line)));
+    }
+
+    let changed: Vec<usize> = (0..lines.len()).filter(|&i| lines[i].0 != ' ').collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + CONTEXT_LINES + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(hunk) if start <= hunk.1 => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut diff = Vec::new();
+    for (start, end) in hunks {
+        let before = &lines[..start];
+        let hunk = &lines[start..end];
+        let old_start = before.iter().filter(|(tag, _)| *tag != '+').count() + 1;
+        let new_start = before.iter().filter(|(tag, _)| *tag != '-').count() + 1;
+        let old_len = hunk.iter().filter(|(tag, _)| *tag != '+').count();
+        let new_len = hunk.iter().filter(|(tag, _)| *tag != '-').count();
+        diff.push(format!(
+            "@@ -{},{} +{},{} @@",
+            old_start, old_len, new_start, new_len
+        ));
+        for (tag, line) in hunk {
+            diff.push(format!("{}{}", tag, line));
+        }
+    }
+    diff
+}
+
+fn get(client: &Client, url: &str) -> Result<String, failure::Error> {
+    let res = client.get(url).send_traced()?;
+    let status = res.status();
+    let text = res.text()?;
+    if !status.is_success() {
+        return Err(http::status_error(status, &text));
+    }
+    Ok(text)
+}
+
+// "3f1e9a5 main*", with a star when there were uncommitted changes
+fn commit(annotations: &Annotations) -> String {
+    let mut commit: String = match &annotations.git_commit {
+        Some(sha) => sha.chars().take(7).collect(),
+        None => return String::new(),
+    };
+    if let Some(branch) = &annotations.git_branch {
+        commit.push(' ');
+        commit.push_str(branch);
+    }
+    if annotations.git_dirty == Some(true) {
+        commit.push('*');
+    }
+    commit
+}
+
+fn parse_versions(text: &str) -> Result<Vec<Version>, failure::Error> {
+    let response: VersionsResponse = serde_json::from_str(text)?;
+    Ok(response.result.map(|list| list.items).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_annotated_versions() {
+        let text = r#"{
+  "result": {
+    "items": [
+      {
+        "id": "3f1e9a52",
+        "number": 2,
+        "metadata": { "created_on": "2022-11-08T17:19:29Z", "author_email": "dev@example.com", "source": "wrangler" },
+        "annotations": { "workers/message": "Fix the cache key", "workers/tag": "v1.4.2", "workers/triggered_by": "upload" }
+      },
+      { "id": "0c8d11b7", "number": 1 }
+    ]
+  },
+  "success": true
+}"#;
+        let versions = parse_versions(text).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(
+            versions[0].annotations,
+            Annotations {
+                message: Some("Fix the cache key".to_string()),
+                tag: Some("v1.4.2".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            versions[0].metadata.author_email.as_deref(),
+            Some("dev@example.com")
+        );
+        assert_eq!(versions[1].annotations, Annotations::default());
+    }
+
+    #[test]
+    fn it_diffs_code_in_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm";
+        assert_eq!(
+            unified_diff(old, new),
+            vec![
+                "@@ -1,5 +1,5 @@",
+                " a",
+                "-b",
+                "+B",
+                " c",
+                " d",
+                " e",
+                "@@ -10,3 +10,4 @@",
+                " j",
+                " k",
+                " l",
+                "+m",
+            ]
+        );
+        assert!(unified_diff(old, old).is_empty());
+    }
+
+    #[test]
+    fn it_shortens_commits() {
+        let annotations = Annotations {
+            git_commit: Some("3f1e9a52c0d6b1e2f4a7c9d8e0b1a2c3d4e5f607".to_string()),
+            git_branch: Some("main".to_string()),
+            git_dirty: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(commit(&annotations), "3f1e9a5 main*");
+        assert_eq!(commit(&Annotations::default()), "");
+    }
+}
@@ -0,0 +1,416 @@
+use std::fmt;
+use std::fs;
+
+use cloudflare::endpoints::workers::DeleteRoute;
+use cloudflare::endpoints::workerskv::WorkersKvNamespace;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::deploy::{self, state, DeployState};
+use crate::http::{self, Traced, TracedClient};
+use crate::kv;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::{interactive, styles};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DestroyOpt {
+    /// Also delete the production KV namespaces wrangler created
+    pub kv_namespaces: bool,
+    /// Also delete Durable Object namespaces that belong to the script
+    pub durable_objects: bool,
+    /// Print what would be deleted without deleting anything
+    pub dry_run: bool,
+    /// Skip the interactive confirmation
+    pub yes: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    DeleteRoute {
+        zone_id: String,
+        id: String,
+        pattern: String,
+    },
+    ClearSchedules(Vec<String>),
+    DeleteScript,
+    DeleteKvNamespace {
+        id: String,
+        title: String,
+    },
+    DeleteDurableObjectNamespace {
+        id: String,
+        name: String,
+    },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::DeleteRoute { pattern, .. } => write!(f, "route {}", pattern),
+            Action::ClearSchedules(crons) => write!(f, "schedules {}", crons.join(", ")),
+            Action::DeleteScript => write!(f, "script"),
+            Action::DeleteKvNamespace { id, title } => {
+                write!(f, "KV namespace {} ({})", title, id)
+            }
+            Action::DeleteDurableObjectNamespace { id, name } => {
+                write!(f, "Durable Object namespace {} ({})", name, id)
+            }
+        }
+    }
+}
+
+/// Tears down everything wrangler deployed for an environment, using the
+/// deploy state recorded by `wrangler publish`.
+pub fn destroy(
+    target: &Target,
+    user: &GlobalUser,
+    env: Option<&str>,
+    opt: DestroyOpt,
+) -> Result<(), failure::Error> {
+    let state = DeployState::load(env)?;
+    let actions = plan(target, user, &state, opt)?;
+
+    StdOut::info(&format!(
+        "The following will be deleted from the worker {}:",
+        target.name
+    ));
+    for action in &actions {
+        StdOut::message(&format!("  {}", styles::deletion(format!("- {}", action))));
+    }
+
+    if opt.dry_run {
+        StdOut::info("Dry run, nothing was deleted.");
+        return Ok(());
+    }
+
+    if !opt.yes {
+        match interactive::confirm(&format!(
+            "Are you sure you want to permanently delete {} and its resources?",
+            target.name
+        )) {
+            Ok(true) => (),
+            Ok(false) => {
+                StdOut::info(&format!("Not deleting {}.", target.name));
+                return Ok(());
+            }
+            Err(e) => failure::bail!(e),
+        }
+    }
+
+    for action in &actions {
+        StdOut::working(&format!("Deleting {}", action));
+        run(target, user, action)?;
+    }
+
+    let state_path = state::state_path(env)?;
+    if state_path.exists() {
+        fs::remove_file(state_path)?;
+    }
+
+    StdOut::success(&format!("Successfully destroyed {}", target.name));
+    Ok(())
+}
+
+fn plan(
+    target: &Target,
+    user: &GlobalUser,
+    state: &DeployState,
+    opt: DestroyOpt,
+) -> Result<Vec<Action>, failure::Error> {
+    let kv_namespaces = kv::namespace::list(user, target)?;
+    let mut actions = plan_deployed(target, state, opt, kv_namespaces);
+
+    if opt.durable_objects {
+        for namespace in durable_object_namespaces(target, user)? {
+            if namespace.script.as_deref() == Some(target.name.as_str()) {
+                actions.push(Action::DeleteDurableObjectNamespace {
+                    id: namespace.id,
+                    name: namespace.name,
+                });
+            }
+        }
+    }
+
+    Ok(actions)
+}
+
+// What `wrangler publish` deployed, given the KV namespaces of the account. Anything
+// recorded in a deploy state that belongs to another worker is left alone.
+fn plan_deployed(
+    target: &Target,
+    state: &DeployState,
+    opt: DestroyOpt,
+    kv_namespaces: Vec<WorkersKvNamespace>,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let is_for_target = state.is_for(target);
+
+    if is_for_target {
+        for route in &state.routes {
+            match &route.id {
+                Some(id) => actions.push(Action::DeleteRoute {
+                    zone_id: route.zone_id.clone(),
+                    id: id.clone(),
+                    pattern: route.pattern.clone(),
+                }),
+                None => StdOut::warn(&format!(
+                    "Skipping route {} because its id was not recorded; delete it with `wrangler route delete`",
+                    route.pattern
+                )),
+            }
+        }
+        if !state.schedules.is_empty() {
+            actions.push(Action::ClearSchedules(state.schedules.clone()));
+        }
+    } else {
+        StdOut::warn(&format!(
+            "No deploy state was found for {}, so its routes, schedules and KV namespaces will not be deleted.",
+            target.name
+        ));
+    }
+
+    actions.push(Action::DeleteScript);
+
+    // Workers Sites namespaces follow a naming convention, so they can be
+    // found even if they were created before wrangler kept deploy state
+    let mut sites_titles = vec![format!("__{}-workers_sites_assets_preview", target.name)];
+    if opt.kv_namespaces {
+        sites_titles.push(format!("__{}-workers_sites_assets", target.name));
+    }
+
+    for namespace in kv_namespaces {
+        let managed = state
+            .kv_namespaces
+            .iter()
+            .find(|ns| is_for_target && ns.id == namespace.id);
+        let is_managed = match managed {
+            Some(ns) => ns.preview || opt.kv_namespaces,
+            None => false,
+        };
+        if is_managed || sites_titles.contains(&namespace.title) {
+            actions.push(Action::DeleteKvNamespace {
+                id: namespace.id,
+                title: namespace.title,
+            });
+        }
+    }
+
+    actions
+}
+
+fn run(target: &Target, user: &GlobalUser, action: &Action) -> Result<(), failure::Error> {
+    let script_addr = http::endpoints::script(&target.account_id, &target.name);
+
+    match action {
+        Action::DeleteRoute { zone_id, id, .. } => {
+            let client = http::cf_v4_client(user)?;
+            if let Err(e) = client.request_traced(&DeleteRoute {
+                zone_identifier: zone_id,
+                identifier: id,
+            }) {
+                // the route may already have been deleted by hand
+                StdOut::warn(&http::format_error(e, None));
+            }
+            http::cache::invalidate(&http::endpoints::routes(zone_id));
+            Ok(())
+        }
+        Action::ClearSchedules(_) => {
+            let client = http::legacy_auth_client(user);
+            let res = client
+                .put(&format!("{}/schedules", script_addr))
+                .header("Content-Type", "application/json")
+                .body("[]")
+                .send_traced()?;
+            check_status(res)
+        }
+        Action::DeleteScript => {
+            let client = http::legacy_auth_client(user);
+            let res = client.delete(&script_addr).send_traced()?;
+            check_status(res)
+        }
+        Action::DeleteKvNamespace { id, .. } => {
+            let client = http::cf_v4_client(user)?;
+            match kv::namespace::delete(client, target, id) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(http::api_error(e, None)),
+            }
+        }
+        Action::DeleteDurableObjectNamespace { id, .. } => {
+            let client = http::legacy_auth_client(user);
+            let res = client
+                .delete(&http::endpoints::durable_object_namespace(
+                    &target.account_id,
+                    id,
+                ))
+                .send_traced()?;
+            check_status(res)
+        }
+    }
+}
+
+// A 404 means the resource is already gone, which is what we wanted anyway.
+fn check_status(res: reqwest::blocking::Response) -> Result<(), failure::Error> {
+    let status = res.status();
+    if status.is_success() || status == StatusCode::NOT_FOUND {
+        Ok(())
+    } else {
+        Err(http::status_error(status, &res.text()?))
+    }
+}
+
+#[derive(Deserialize)]
+struct DurableObjectNamespaces {
+    result: Vec<DurableObjectNamespace>,
+}
+
+#[derive(Deserialize)]
+struct DurableObjectNamespace {
+    id: String,
+    name: String,
+    script: Option<String>,
+}
+
+fn durable_object_namespaces(
+    target: &Target,
+    user: &GlobalUser,
+) -> Result<Vec<DurableObjectNamespace>, failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let res = client
+        .get(&http::endpoints::durable_object_namespaces(
+            &target.account_id,
+        ))
+        .send_traced()?;
+
+    if !res.status().is_success() {
+        return Err(http::status_error(res.status(), &res.text()?));
+    }
+
+    let namespaces: DurableObjectNamespaces = serde_json::from_str(&res.text()?)?;
+    Ok(namespaces.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::deploy::state::{ManagedKvNamespace, ManagedRoute};
+
+    fn target() -> Target {
+        Target {
+            account_id: "account".to_string(),
+            name: "worker".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn state(name: &str) -> DeployState {
+        DeployState {
+            name: name.to_string(),
+            account_id: "account".to_string(),
+            routes: vec![ManagedRoute {
+                zone_id: "zone".to_string(),
+                id: Some("1".to_string()),
+                pattern: "example.com/*".to_string(),
+            }],
+            kv_namespaces: vec![
+                managed_kv_namespace("production", false),
+                managed_kv_namespace("preview", true),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn managed_kv_namespace(id: &str, preview: bool) -> ManagedKvNamespace {
+        ManagedKvNamespace {
+            binding: "CACHE".to_string(),
+            id: id.to_string(),
+            preview,
+            created_at: None,
+        }
+    }
+
+    fn kv_namespaces() -> Vec<WorkersKvNamespace> {
+        serde_json::from_value(json!([
+            { "id": "production", "title": "worker-CACHE" },
+            { "id": "preview", "title": "worker-CACHE_preview" },
+            { "id": "sites", "title": "__worker-workers_sites_assets" },
+            { "id": "sites_preview", "title": "__worker-workers_sites_assets_preview" },
+            { "id": "other", "title": "other-worker-CACHE" },
+        ]))
+        .unwrap()
+    }
+
+    fn deleted_kv_namespaces(actions: &[Action]) -> Vec<&str> {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::DeleteKvNamespace { id, .. } => Some(id.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_deletes_the_preview_namespaces_by_default() {
+        let actions = plan_deployed(
+            &target(),
+            &state("worker"),
+            DestroyOpt::default(),
+            kv_namespaces(),
+        );
+
+        assert_eq!(
+            actions[..3],
+            [
+                Action::DeleteRoute {
+                    zone_id: "zone".to_string(),
+                    id: "1".to_string(),
+                    pattern: "example.com/*".to_string(),
+                },
+                Action::DeleteScript,
+                Action::DeleteKvNamespace {
+                    id: "preview".to_string(),
+                    title: "worker-CACHE_preview".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            deleted_kv_namespaces(&actions),
+            vec!["preview", "sites_preview"]
+        );
+    }
+
+    #[test]
+    fn it_deletes_the_production_namespaces_with_kv_namespaces() {
+        let opt = DestroyOpt {
+            kv_namespaces: true,
+            ..Default::default()
+        };
+        let actions = plan_deployed(&target(), &state("worker"), opt, kv_namespaces());
+
+        assert_eq!(
+            deleted_kv_namespaces(&actions),
+            vec!["production", "preview", "sites", "sites_preview"]
+        );
+    }
+
+    #[test]
+    fn it_leaves_the_resources_of_another_worker_alone() {
+        let opt = DestroyOpt {
+            kv_namespaces: true,
+            ..Default::default()
+        };
+        let actions = plan_deployed(&target(), &state("other-worker"), opt, kv_namespaces());
+
+        // only the Workers Sites namespaces, found by their titles, are deleted
+        assert_eq!(actions[0], Action::DeleteScript);
+        assert_eq!(
+            deleted_kv_namespaces(&actions),
+            vec!["sites", "sites_preview"]
+        );
+    }
+}
@@ -5,7 +5,7 @@ mod watch;
 use setup::{upload, Session};
 use watch::watch_for_changes;
 
-use crate::commands::dev::{socket, Protocol, ServerConfig};
+use crate::commands::dev::{inspector, socket, Protocol, ServerConfig};
 use crate::deploy::DeployTarget;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
@@ -53,19 +53,31 @@ pub fn dev(
         });
     }
 
+    // with --local-upstream, requests go to that origin instead of the zone's, so
+    // requests the worker passes through reach it just like in production
+    let (host, upstream_protocol) = match &server_config.local_upstream {
+        Some(upstream) if upstream.is_https() => (upstream.to_string(), Protocol::Https),
+        Some(upstream) => (upstream.to_string(), Protocol::Http),
+        None => (session.host.clone(), upstream_protocol),
+    };
+
     let mut runtime = TokioRuntime::new()?;
     runtime.block_on(async {
+        tokio::spawn(inspector::serve(
+            server_config.inspector_address(),
+            session.websocket_url.clone(),
+        ));
         let devtools_listener = tokio::spawn(socket::listen(session.websocket_url));
         let server = match local_protocol {
             Protocol::Https => tokio::spawn(server::https(
                 server_config.clone(),
                 Arc::clone(&preview_token),
-                session.host.clone(),
+                host.clone(),
             )),
             Protocol::Http => tokio::spawn(server::http(
                 server_config,
                 Arc::clone(&preview_token),
-                session.host,
+                host,
                 upstream_protocol,
             )),
         };
@@ -1,9 +1,12 @@
 use super::preview_request;
-use crate::commands::dev::utils::{get_path_as_str, rewrite_redirect};
+use crate::commands::dev::utils::{
+    format_timings, get_path_as_str, is_websocket_upgrade, proxy_websocket, rewrite_redirect,
+};
 use crate::commands::dev::{tls, Protocol, ServerConfig};
 use crate::terminal::emoji;
-use crate::terminal::message::{Message, StdOut};
+use crate::terminal::message::{Message, StdErr, StdOut};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use chrono::prelude::*;
 use futures_util::stream::StreamExt;
@@ -37,8 +40,11 @@ pub async fn https(
             Ok::<_, failure::Error>(service_fn(move |req| {
                 let client = client.to_owned();
                 let preview_token = preview_token.lock().unwrap().to_owned();
+                let recorder = server_config.recorder.clone();
                 let host = host.to_owned();
                 let version = req.version();
+                let timings = server_config.timings;
+                let start = Instant::now();
                 let (parts, body) = req.into_parts();
                 let local_host = format!(
                     "{}:{}",
@@ -49,26 +55,50 @@ pub async fn https(
                 let now: DateTime<Local> = Local::now();
                 let path = get_path_as_str(&parts.uri);
                 async move {
+                    let mut req = Request::from_parts(parts, body);
+                    let recording = match &recorder {
+                        Some(recorder) => recorder.start(&mut req).await?,
+                        None => None,
+                    };
+                    // the local side of a WebSocket is only available once the
+                    // request's body has been taken from it
+                    let downstream = if is_websocket_upgrade(req.headers()) {
+                        Some(std::mem::replace(req.body_mut(), Body::empty()).on_upgrade())
+                    } else {
+                        None
+                    };
                     let mut resp = preview_request(
-                        Request::from_parts(parts, body),
+                        req,
                         client,
                         preview_token.to_owned(),
                         host.clone(),
                         Protocol::Https,
                     )
                     .await?;
+                    if let Some(downstream) = downstream {
+                        resp = proxy_websocket(downstream, resp);
+                    }
+                    if let Some(recording) = recording {
+                        recording.finish(resp.status());
+                    }
 
                     rewrite_redirect(&mut resp, &host, &local_host, true);
 
-                    println!(
-                        "[{}] {} {}{} {:?} {}",
+                    let timings = if timings {
+                        format!(" ({})", format_timings(start.elapsed(), resp.headers()))
+                    } else {
+                        String::new()
+                    };
+                    StdOut::message(&format!(
+                        "[{}] {} {}{} {:?} {}{}",
                         now.format("%Y-%m-%d %H:%M:%S"),
                         req_method,
                         host,
                         path,
                         version,
-                        resp.status()
-                    );
+                        resp.status(),
+                        timings
+                    ));
                     Ok::<_, failure::Error>(resp)
                 }
             }))
@@ -83,14 +113,14 @@ pub async fn https(
             let client = match s {
                 Ok(x) => x,
                 Err(e) => {
-                    eprintln!("Failed to accept client {}", e);
+                    StdErr::message(&format!("Failed to accept client {}", e));
                     return None;
                 }
             };
             match tls_acceptor.accept(client).await {
                 Ok(x) => Some(Ok(x)),
                 Err(e) => {
-                    eprintln!("Client connection error {}", e);
+                    StdErr::message(&format!("Client connection error {}", e));
                     StdOut::info("Make sure to use https and `--insecure` with curl");
                     None
                 }
@@ -102,11 +132,15 @@ pub async fn https(
     })
     .serve(service);
 
-    println!("{} Listening on https://{}", emoji::EAR, listening_address);
+    StdOut::message(&format!(
+        "{} Listening on https://{}",
+        emoji::EAR,
+        listening_address
+    ));
     StdOut::info("Generated certificate is not verified, browsers will give a warning and curl will require `--insecure`");
 
     if let Err(e) = server.await {
-        eprintln!("{}", e);
+        StdErr::message(&e.to_string());
     }
 
     Ok(())
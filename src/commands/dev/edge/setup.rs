@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use crate::deploy::DeployTarget;
+use crate::http::{endpoints, Traced};
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
@@ -48,7 +49,7 @@ pub(super) fn upload(
         .post(&address)
         .header("cf-preview-upload-config-token", session_token)
         .multipart(script_upload_form)
-        .send()?
+        .send_traced()?
         .error_for_status()?;
 
     if !to_delete.is_empty() {
@@ -98,7 +99,7 @@ impl Session {
         };
 
         let client = crate::http::legacy_auth_client(&user);
-        let response = client.get(exchange_url).send()?.error_for_status()?;
+        let response = client.get(exchange_url).send_traced()?.error_for_status()?;
         let text = &response.text()?;
         let response: InspectorV4ApiResponse = serde_json::from_str(text)?;
         let full_url = format!(
@@ -132,24 +133,15 @@ fn get_session_config(target: &DeployTarget) -> serde_json::Value {
 
 fn get_session_address(target: &DeployTarget) -> String {
     match target {
-        DeployTarget::Zoned(config) => format!(
-            "https://api.cloudflare.com/client/v4/zones/{}/workers/edge-preview",
-            config.zone_id
-        ),
+        DeployTarget::Zoned(config) => endpoints::zone_edge_preview(&config.zone_id),
         // TODO: zoneless is probably wrong
-        DeployTarget::Zoneless(config) => format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/workers/subdomain/edge-preview",
-            config.account_id
-        ),
+        DeployTarget::Zoneless(config) => endpoints::subdomain_edge_preview(&config.account_id),
         _ => unreachable!(),
     }
 }
 
 fn get_upload_address(target: &mut Target) -> String {
-    format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/edge-preview",
-        target.account_id, target.name
-    )
+    endpoints::script_edge_preview(&target.account_id, &target.name)
 }
 
 fn get_exchange_url(
@@ -159,7 +151,7 @@ fn get_exchange_url(
     let client = crate::http::legacy_auth_client(&user);
     let address = get_session_address(deploy_target);
     let url = Url::parse(&address)?;
-    let response = client.get(url).send()?.error_for_status()?;
+    let response = client.get(url).send_traced()?.error_for_status()?;
     let text = &response.text()?;
     let response: SessionV4ApiResponse = serde_json::from_str(text)?;
     let url = Url::parse(&response.result.exchange_url)?;
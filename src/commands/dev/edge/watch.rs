@@ -4,6 +4,7 @@ use crate::commands::dev::edge::setup;
 use crate::deploy::DeployTarget;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
 use crate::watch::watch_and_build;
 
 pub fn watch_for_changes(
@@ -32,7 +33,16 @@ pub fn watch_for_changes(
         //
         // this allows the server to route subsequent requests
         // to the proper script
-        *preview_token = setup::upload(&mut target, &deploy_target, &user, session_token, verbose)?;
+        //
+        // if the preview service rejects the new script, keep serving the
+        // previous one and wait for the next change instead of giving up
+        match setup::upload(&mut target, &deploy_target, &user, session_token, verbose) {
+            Ok(token) => *preview_token = token,
+            Err(e) => StdErr::warn(&format!(
+                "Could not upload your changes, still serving the previous version: {}",
+                e
+            )),
+        }
     }
 
     Ok(())
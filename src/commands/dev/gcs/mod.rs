@@ -6,8 +6,9 @@ mod watch;
 use setup::{get_preview_id, get_session_id};
 use watch::watch_for_changes;
 
-use crate::commands::dev::{socket, Protocol, ServerConfig};
+use crate::commands::dev::{inspector, socket, Protocol, ServerConfig};
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
 
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -22,7 +23,7 @@ pub fn dev(
     local_protocol: Protocol,
     verbose: bool,
 ) -> Result<(), failure::Error> {
-    println!("unauthenticated");
+    StdOut::message("unauthenticated");
 
     // setup the session
     let session_id = get_session_id()?;
@@ -73,6 +74,10 @@ pub fn dev(
     // and we must block the main thread on the completion of
     // said futures
     runtime.block_on(async {
+        tokio::spawn(inspector::serve(
+            server_config.inspector_address(),
+            socket_url.clone(),
+        ));
         let devtools_listener = tokio::spawn(socket::listen(socket_url.clone()));
 
         let server = match local_protocol {
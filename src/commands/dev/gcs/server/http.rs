@@ -1,10 +1,12 @@
 use super::preview_request;
 use crate::commands::dev::gcs::headers::destructure_response;
 use crate::commands::dev::server_config::ServerConfig;
-use crate::commands::dev::utils::{get_path_as_str, rewrite_redirect};
+use crate::commands::dev::utils::{format_timings, get_path_as_str, rewrite_redirect};
 use crate::terminal::emoji;
+use crate::terminal::message::{Message, StdErr, StdOut};
 
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use chrono::prelude::*;
 use hyper::service::{make_service_fn, service_fn};
@@ -36,6 +38,8 @@ pub async fn http(
                 let server_config = server_config.to_owned();
                 let preview_id = preview_id.lock().unwrap().to_owned();
                 let version = req.version();
+                let timings = server_config.timings;
+                let start = Instant::now();
 
                 // record the time of the request
                 let now: DateTime<Local> = Local::now();
@@ -56,13 +60,14 @@ pub async fn http(
                 let path = get_path_as_str(&parts.uri);
 
                 async move {
+                    let mut req = Request::from_parts(parts, body);
+                    let recording = match &server_config.recorder {
+                        Some(recorder) => recorder.start(&mut req).await?,
+                        None => None,
+                    };
+
                     // send the request to the preview service
-                    let resp = preview_request(
-                        Request::from_parts(parts, body),
-                        client,
-                        preview_id.to_owned(),
-                    )
-                    .await?;
+                    let resp = preview_request(req, client, preview_id.to_owned()).await?;
                     let (mut parts, body) = resp.into_parts();
 
                     // format the response for the user
@@ -75,17 +80,27 @@ pub async fn http(
                         false,
                     );
 
+                    if let Some(recording) = recording {
+                        recording.finish(resp.status());
+                    }
+
                     // print information about the response
                     // [2020-04-20 15:25:54] GET example.com/ HTTP/1.1 200 OK
-                    println!(
-                        "[{}] {} {}{} {:?} {}",
+                    let timings = if timings {
+                        format!(" ({})", format_timings(start.elapsed(), resp.headers()))
+                    } else {
+                        String::new()
+                    };
+                    StdOut::message(&format!(
+                        "[{}] {} {}{} {:?} {}{}",
                         now.format("%Y-%m-%d %H:%M:%S"),
                         req_method,
                         server_config.host,
                         path,
                         version,
-                        resp.status()
-                    );
+                        resp.status(),
+                        timings
+                    ));
                     Ok::<_, failure::Error>(resp)
                 }
             }))
@@ -93,13 +108,13 @@ pub async fn http(
     });
 
     let server = Server::bind(&listening_address).serve(make_service);
-    println!(
+    StdOut::message(&format!(
         "{} Listening on http://{}",
         emoji::EAR,
         listening_address.to_string()
-    );
+    ));
     if let Err(e) = server.await {
-        eprintln!("server error: {}", e);
+        StdErr::message(&format!("server error: {}", e));
     }
     Ok(())
 }
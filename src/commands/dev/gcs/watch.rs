@@ -4,6 +4,7 @@ use crate::commands::dev::gcs::setup::get_preview_id;
 use crate::commands::dev::server_config::ServerConfig;
 
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
 use crate::watch::watch_and_build;
 
 pub fn watch_for_changes(
@@ -27,7 +28,16 @@ pub fn watch_for_changes(
         //
         // this allows the server to route subsequent requests
         // to the proper script
-        *preview_id = get_preview_id(target, None, server_config, session_id, verbose)?;
+        //
+        // if the preview service rejects the new script, keep serving the
+        // previous one and wait for the next change instead of giving up
+        match get_preview_id(target, None, server_config, session_id, verbose) {
+            Ok(id) => *preview_id = id,
+            Err(e) => StdErr::warn(&format!(
+                "Could not upload your changes, still serving the previous version: {}",
+                e
+            )),
+        }
     }
 
     Ok(())
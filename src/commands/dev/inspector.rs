@@ -0,0 +1,80 @@
+use std::net::SocketAddr;
+
+use futures_util::stream::StreamExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{accept_async, connect_async};
+use url::Url;
+
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::styles;
+
+/// Serves the Chrome Devtools Protocol of the preview worker at `address`, so Chrome
+/// DevTools can set breakpoints in and profile the worker. Each DevTools window gets its
+/// own connection to the runtime.
+pub async fn serve(address: SocketAddr, socket_url: Url) {
+    let mut listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            StdErr::warn(&format!(
+                "Could not start the inspector on {}, use --inspector-port to pick another port: {}",
+                address, e
+            ));
+            return;
+        }
+    };
+    StdOut::info(&format!(
+        "Open {} in Chrome to debug your worker",
+        styles::url(devtools_url(&address))
+    ));
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                StdErr::warn(&format!("Failed to accept inspector client {}", e));
+                continue;
+            }
+        };
+        let socket_url = socket_url.clone();
+        tokio::spawn(async move {
+            if let Err(e) = proxy(stream, socket_url).await {
+                StdErr::warn(&format!("Inspector connection closed: {}", e));
+            }
+        });
+    }
+}
+
+// copies messages between a DevTools window and the runtime until either side closes
+async fn proxy(stream: TcpStream, socket_url: Url) -> Result<(), failure::Error> {
+    let devtools = accept_async(stream).await?;
+    let (runtime, _) = connect_async(&socket_url).await?;
+
+    let (devtools_write, devtools_read) = devtools.split();
+    let (runtime_write, runtime_read) = runtime.split();
+    tokio::select! {
+        res = devtools_read.forward(runtime_write) => res?,
+        res = runtime_read.forward(devtools_write) => res?,
+    }
+    Ok(())
+}
+
+fn devtools_url(address: &SocketAddr) -> String {
+    format!(
+        "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws={}",
+        address
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_devtools_urls() {
+        let address: SocketAddr = "[::1]:9229".parse().unwrap();
+        assert_eq!(
+            devtools_url(&address),
+            "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws=[::1]:9229"
+        );
+    }
+}
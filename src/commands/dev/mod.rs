@@ -1,10 +1,13 @@
 mod edge;
 mod gcs;
+mod inspector;
+mod record;
 mod server_config;
 mod socket;
 mod tls;
 mod utils;
 
+pub use record::{replay, Recorder};
 pub use server_config::Protocol;
 pub use server_config::ServerConfig;
 
@@ -64,6 +67,28 @@ pub fn dev(
         failure::bail!("{} cannot be https if {} is http", local_str, upstream_str)
     }
 
+    if let Some(local_upstream) = &server_config.local_upstream {
+        let local_upstream_str = styles::highlight("--local-upstream");
+        if user.is_none() {
+            failure::bail!(
+                "{} runs your worker on your zone, so you must be logged in. Run `wrangler login`, or use {} instead",
+                local_upstream_str,
+                host_str
+            )
+        } else if matches!(deploy_target, DeployTarget::Zoneless(_)) {
+            failure::bail!(
+                "{} needs routes on a zone, workers.dev has no origin to fall through to",
+                local_upstream_str
+            )
+        } else if local_protocol.is_https() && !local_upstream.is_https() {
+            failure::bail!(
+                "{} cannot be https if {} is http",
+                local_str,
+                local_upstream_str
+            )
+        }
+    }
+
     if let Some(user) = user {
         if server_config.host.is_default() {
             // Authenticated and no host provided, run on edge with user's zone
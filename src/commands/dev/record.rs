@@ -0,0 +1,189 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use hyper::{Body, Request, StatusCode};
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use serde::{Deserialize, Serialize};
+
+use super::utils::{get_path_as_str, is_websocket_upgrade};
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+// set by the client for every request, so they are not replayed
+const SKIPPED_HEADERS: &[&str] = &["host", "content-length", "connection", "transfer-encoding"];
+
+/// A request that came in to the dev server, and the status the worker responded with.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    /// base64 encoded
+    pub body: String,
+    pub status: u16,
+}
+
+/// Appends the requests handled by `wrangler dev --record` to a file, one JSON object per
+/// line.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path) -> Result<Self, failure::Error> {
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(e) => failure::bail!("Could not create {}: {}", path.display(), e),
+        };
+        Ok(Recorder {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// Reads the body of `req` so it can be recorded, leaving an identical body in its
+    /// place. WebSocket upgrades are not recorded.
+    pub(super) async fn start(
+        &self,
+        req: &mut Request<Body>,
+    ) -> Result<Option<Recording>, failure::Error> {
+        if is_websocket_upgrade(req.headers()) {
+            return Ok(None);
+        }
+        let body = std::mem::replace(req.body_mut(), Body::empty());
+        let bytes = hyper::body::to_bytes(body).await?;
+        *req.body_mut() = Body::from(bytes.clone());
+
+        let headers = req
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect();
+        Ok(Some(Recording {
+            recorder: self.clone(),
+            request: RecordedRequest {
+                method: req.method().to_string(),
+                path: get_path_as_str(req.uri()),
+                headers,
+                body: base64::encode(&bytes),
+                status: 0,
+            },
+        }))
+    }
+}
+
+/// A request that is waiting for the worker's response before it's written.
+pub(super) struct Recording {
+    recorder: Recorder,
+    request: RecordedRequest,
+}
+
+impl Recording {
+    pub(super) fn finish(mut self, status: StatusCode) {
+        self.request.status = status.as_u16();
+        let written = serde_json::to_string(&self.request)
+            .map_err(failure::Error::from)
+            .and_then(|line| {
+                let mut file = self.recorder.file.lock().unwrap();
+                writeln!(file, "{}", line)?;
+                Ok(())
+            });
+        if let Err(e) = written {
+            StdErr::warn(&format!("Could not record request: {}", e));
+        }
+    }
+}
+
+/// Sends the requests recorded by `wrangler dev --record` to the dev server at `url`, and
+/// reports the ones that get a different status than when they were recorded.
+pub fn replay(file: &Path, url: &str) -> Result<(), failure::Error> {
+    let requests = read_recording(&fs::read_to_string(file)?)?;
+    if requests.is_empty() {
+        failure::bail!("There are no requests recorded in {}", file.display())
+    }
+
+    // the recorded status is the one before any redirect was followed
+    let client = Client::builder().redirect(Policy::none()).build()?;
+    let url = url.trim_end_matches('/');
+    let mut changed = 0;
+    for recorded in &requests {
+        let method = reqwest::Method::from_bytes(recorded.method.as_bytes())?;
+        let mut req = client.request(method, &format!("{}{}", url, recorded.path));
+        for (name, value) in &recorded.headers {
+            if !SKIPPED_HEADERS.contains(&name.to_lowercase().as_str()) {
+                req = req.header(name.as_str(), value.as_str());
+            }
+        }
+        let res = req.body(base64::decode(&recorded.body)?).send()?;
+
+        let status = res.status().as_u16();
+        if status == recorded.status {
+            StdOut::message(&format!("{} {} {}", recorded.method, recorded.path, status));
+        } else {
+            changed += 1;
+            StdOut::warn(&format!(
+                "{} {} {} (recorded {})",
+                recorded.method, recorded.path, status, recorded.status
+            ));
+        }
+    }
+
+    if changed > 0 {
+        failure::bail!(
+            "{} of {} requests got a different status than when they were recorded",
+            changed,
+            requests.len()
+        )
+    }
+    StdOut::success(&format!(
+        "All {} requests got the same status as when they were recorded",
+        requests.len()
+    ));
+    Ok(())
+}
+
+fn read_recording(contents: &str) -> Result<Vec<RecordedRequest>, failure::Error> {
+    let mut requests = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(request) => requests.push(request),
+            Err(e) => failure::bail!("Line {} is not a recorded request: {}", i + 1, e),
+        }
+    }
+    Ok(requests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_recordings() {
+        let contents = r#"{"method":"POST","path":"/api?x=1","headers":[["content-type","application/json"]],"body":"e30=","status":201}
+
+{"method":"GET","path":"/","headers":[],"body":"","status":200}
+"#;
+        let requests = read_recording(contents).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0],
+            RecordedRequest {
+                method: "POST".to_string(),
+                path: "/api?x=1".to_string(),
+                headers: vec![("content-type".to_string(), "application/json".to_string())],
+                body: "e30=".to_string(),
+                status: 201,
+            }
+        );
+
+        assert!(read_recording("{\"method\":\"GET\"}").is_err());
+    }
+}
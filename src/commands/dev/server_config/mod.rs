@@ -5,12 +5,22 @@ pub use protocol::Protocol;
 
 use host::Host;
 
-use std::net::{SocketAddr, TcpListener};
+use super::Recorder;
+
+use std::net::{IpAddr, SocketAddr, TcpListener};
 
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     pub host: Host,
     pub listening_address: SocketAddr,
+    /// where to record incoming requests, for `wrangler dev --record`
+    pub recorder: Option<Recorder>,
+    /// the port that Chrome DevTools connects to
+    pub inspector_port: u16,
+    /// the origin that requests the worker passes through go to, for `--local-upstream`
+    pub local_upstream: Option<Host>,
+    /// whether to log how long each request took, for `--timings`
+    pub timings: bool,
 }
 
 impl ServerConfig {
@@ -22,7 +32,13 @@ impl ServerConfig {
     ) -> Result<Self, failure::Error> {
         let ip = ip.unwrap_or("127.0.0.1");
         let port = port.unwrap_or(8787);
-        let addr = format!("{}:{}", ip, port);
+        let addr = match ip.parse::<IpAddr>() {
+            Ok(ip) => SocketAddr::new(ip, port),
+            Err(_) => failure::bail!(
+                "{} is not an IP address. Pass an IPv4 or IPv6 address with --ip, like 127.0.0.1 or ::1",
+                ip
+            ),
+        };
         let listening_address = match TcpListener::bind(&addr) {
             Ok(socket) => socket.local_addr(),
             Err(_) => failure::bail!("{} is unavailable, try binding to another address with the --port and --ip flags, or stop other `wrangler dev` processes.", &addr)
@@ -43,6 +59,20 @@ impl ServerConfig {
         Ok(ServerConfig {
             host,
             listening_address,
+            recorder: None,
+            inspector_port: 9229,
+            local_upstream: None,
+            timings: false,
         })
     }
+
+    pub fn set_local_upstream(&mut self, upstream: &str) -> Result<(), failure::Error> {
+        self.local_upstream = Some(Host::new(upstream, false)?);
+        Ok(())
+    }
+
+    /// The inspector listens on the same interface as the server.
+    pub fn inspector_address(&self) -> SocketAddr {
+        SocketAddr::new(self.listening_address.ip(), self.inspector_port)
+    }
 }
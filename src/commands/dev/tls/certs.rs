@@ -4,7 +4,7 @@ use openssl::hash::MessageDigest;
 use openssl::pkey::{PKey, Private};
 use openssl::rsa::Rsa;
 use openssl::x509::extension::{
-    AuthorityKeyIdentifier, BasicConstraints, KeyUsage, SubjectAlternativeName,
+    AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage, SubjectAlternativeName,
     SubjectKeyIdentifier,
 };
 use openssl::x509::{X509NameBuilder, X509Req, X509ReqBuilder, X509};
@@ -13,23 +13,40 @@ use std::path::PathBuf;
 
 use crate::settings::get_wrangler_home_dir;
 use crate::terminal::message::{Message, StdOut};
-/// Create files for cert and private key
-fn create_output_files() -> Result<Option<(PathBuf, PathBuf)>, failure::Error> {
+use crate::terminal::styles;
+
+/// Create files for cert, private key and the certificate authority that signs them.
+/// Certificates from older versions of wrangler were made without a saved authority
+/// (and for the wrong host names), so they are replaced too.
+fn create_output_files() -> Result<Option<(PathBuf, PathBuf, PathBuf)>, failure::Error> {
     let home = get_wrangler_home_dir()?.join("config");
     let cert = home.join("dev-cert.pem");
     let privkey = home.join("dev-privkey.rsa");
+    let ca = home.join("dev-ca.pem");
 
-    if cert.exists() && privkey.exists() {
+    if cert.exists() && privkey.exists() && ca.exists() {
+        Ok(None)
+    } else if cert.exists() && privkey.exists() && !is_generated(&cert)? {
+        // the user replaced the certificate with their own
         Ok(None)
     } else {
         fs::create_dir_all(&home)?;
 
         StdOut::info(format!("Generating certificate and private key for https server, if you would like to use your own you can replace `dev-cert.pem` and `dev-privkey.rsa` at {}", home.to_str().unwrap()).as_str());
 
-        Ok(Some((cert, privkey)))
+        Ok(Some((cert, privkey, ca)))
     }
 }
 
+// whether a certificate was issued by a wrangler generated authority
+fn is_generated(cert: &PathBuf) -> Result<bool, failure::Error> {
+    let cert = X509::from_pem(&fs::read(cert)?)?;
+    Ok(cert
+        .issuer_name()
+        .entries()
+        .any(|entry| entry.data().as_slice() == b"Wrangler"))
+}
+
 /// Generate certificate authority to sign cert
 fn create_ca() -> Result<(X509, PKey<Private>), failure::Error> {
     let rsa = Rsa::generate(2048)?;
@@ -84,8 +101,8 @@ fn create_req(privkey: &PKey<Private>) -> Result<X509Req, failure::Error> {
     let mut x509_name = X509NameBuilder::new()?;
     x509_name.append_entry_by_text("C", "US")?;
     x509_name.append_entry_by_text("ST", "TX")?;
-    x509_name.append_entry_by_text("O", "Some organization")?;
-    x509_name.append_entry_by_text("CN", "www.example.com")?;
+    x509_name.append_entry_by_text("O", "Wrangler")?;
+    x509_name.append_entry_by_text("CN", "localhost")?;
     let x509_name = x509_name.build();
     req_builder.set_subject_name(&x509_name)?;
 
@@ -101,7 +118,7 @@ pub fn generate_cert() -> Result<(), failure::Error> {
         return Ok(());
     }
 
-    let (cert_file, priv_file) = files.unwrap();
+    let (cert_file, priv_file, ca_file) = files.unwrap();
 
     let (ca, ca_key) = create_ca()?;
 
@@ -140,6 +157,7 @@ pub fn generate_cert() -> Result<(), failure::Error> {
             .key_encipherment()
             .build()?,
     )?;
+    cert_builder.append_extension(ExtendedKeyUsage::new().server_auth().build()?)?;
 
     let subject_key_identifier =
         SubjectKeyIdentifier::new().build(&cert_builder.x509v3_context(Some(&ca), None))?;
@@ -152,8 +170,9 @@ pub fn generate_cert() -> Result<(), failure::Error> {
     cert_builder.append_extension(auth_key_identifier)?;
 
     let subject_alt_name = SubjectAlternativeName::new()
-        .dns("*.example.com")
-        .dns("hello.com")
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .ip("::1")
         .build(&cert_builder.x509v3_context(Some(&ca), None))?;
     cert_builder.append_extension(subject_alt_name)?;
 
@@ -164,6 +183,24 @@ pub fn generate_cert() -> Result<(), failure::Error> {
 
     fs::write(cert_file, cert_str)?;
     fs::write(priv_file, priv_str)?;
+    fs::write(&ca_file, ca.to_pem()?)?;
+
+    StdOut::info(&format!(
+        "To make browsers trust https://localhost without a warning, add {} to your trusted certificates:\n{}",
+        styles::highlight(ca_file.display().to_string()),
+        trust_instructions(&ca_file)
+    ));
 
     Ok(())
 }
+
+fn trust_instructions(ca_file: &PathBuf) -> String {
+    let ca_file = ca_file.display();
+    if cfg!(target_os = "macos") {
+        format!("sudo security add-trusted-cert -d -r trustRoot -k /Library/Keychains/System.keychain {}", ca_file)
+    } else if cfg!(target_os = "windows") {
+        format!("certutil -addstore -user Root {}", ca_file)
+    } else {
+        format!("sudo cp {} /usr/local/share/ca-certificates/wrangler-dev.crt && sudo update-ca-certificates", ca_file)
+    }
+}
@@ -1,4 +1,10 @@
-use http::{HeaderValue, Response};
+use std::time::Duration;
+
+use crate::terminal::message::{Message, StdErr};
+
+use futures_util::future::try_join;
+use http::{HeaderMap, HeaderValue, Response, StatusCode};
+use hyper::upgrade::OnUpgrade;
 use hyper::{Body, Uri};
 use url::Url;
 
@@ -45,3 +51,92 @@ pub(super) fn rewrite_redirect(
         }
     }
 }
+
+/// Whether the request asks to upgrade the connection to a WebSocket
+pub(super) fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    let has_token = |name: &str, token: &str| {
+        headers.get_all(name).iter().any(|value| {
+            value
+                .to_str()
+                .map(|value| {
+                    value
+                        .split(',')
+                        .any(|v| v.trim().eq_ignore_ascii_case(token))
+                })
+                .unwrap_or(false)
+        })
+    };
+    has_token("connection", "upgrade") && has_token("upgrade", "websocket")
+}
+
+/// Once the preview service has switched protocols, connects the local WebSocket to the
+/// upstream one and copies data between them until either side closes. Returns the
+/// response to send back to the local client.
+pub(super) fn proxy_websocket(downstream: OnUpgrade, resp: Response<Body>) -> Response<Body> {
+    if resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return resp;
+    }
+    let (parts, body) = resp.into_parts();
+    let upstream = body.on_upgrade();
+
+    tokio::spawn(async move {
+        let (downstream, upstream) = match try_join(downstream, upstream).await {
+            Ok(connections) => connections,
+            Err(e) => {
+                StdErr::warn(&format!("Could not open the WebSocket connection: {}", e));
+                return;
+            }
+        };
+        let (mut down_read, mut down_write) = tokio::io::split(downstream);
+        let (mut up_read, mut up_write) = tokio::io::split(upstream);
+        tokio::select! {
+            _ = tokio::io::copy(&mut down_read, &mut up_write) => {},
+            _ = tokio::io::copy(&mut up_read, &mut down_write) => {},
+        }
+    });
+
+    Response::from_parts(parts, Body::empty())
+}
+
+/// Describes how long a request took, followed by the metrics from any `Server-Timing`
+/// headers on the response, e.g. "total 182ms, kv 41ms, origin 120.5ms"
+pub(super) fn format_timings(elapsed: Duration, headers: &HeaderMap) -> String {
+    let mut timings = vec![format!("total {}ms", elapsed.as_millis())];
+    for value in headers.get_all("server-timing") {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        for metric in value.split(',') {
+            let mut params = metric.split(';').map(str::trim);
+            let name = match params.next() {
+                Some(name) if !name.is_empty() => name,
+                _ => continue,
+            };
+            match params.find_map(|param| param.strip_prefix("dur=")) {
+                Some(duration) => timings.push(format!("{} {}ms", name, duration)),
+                None => timings.push(name.to_string()),
+            }
+        }
+    }
+    timings.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_formats_server_timings() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "server-timing",
+            HeaderValue::from_static("kv;dur=41, cache;desc=\"Cache Read\";dur=2.5"),
+        );
+        headers.append("server-timing", HeaderValue::from_static("miss"));
+        assert_eq!(
+            format_timings(Duration::from_millis(182), &headers),
+            "total 182ms, kv 41ms, cache 2.5ms, miss"
+        );
+    }
+}
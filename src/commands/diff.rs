@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use cloudflare::endpoints::workers::ListSecrets;
+use cloudflare::framework::response::ApiFailure;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::deploy::{self, DeployTarget, DeploymentSet};
+use crate::http::{self, Traced, TracedClient};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+/// A single difference between the configuration file and what is deployed.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// Configured, but not deployed
+    Add(String),
+    /// Deployed, but not configured
+    Delete(String),
+    /// Deployed with a different value than configured
+    Modify {
+        key: String,
+        live: String,
+        desired: String,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Add(key) => write!(f, "{}", styles::addition(format!("+ {}", key))),
+            Change::Delete(key) => write!(f, "{}", styles::deletion(format!("- {}", key))),
+            Change::Modify { key, live, desired } => write!(
+                f,
+                "{}",
+                styles::modification(format!("~ {} ({} => {})", key, live, desired))
+            ),
+        }
+    }
+}
+
+/// Compares the resolved configuration for an environment against the live state of the
+/// account and prints what `wrangler publish` would change. Nothing is modified.
+pub fn diff(
+    target: &Target,
+    deployments: &DeploymentSet,
+    user: &GlobalUser,
+) -> Result<(), failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let script_addr = http::endpoints::script(&target.account_id, &target.name);
+
+    let mut sections: Vec<(String, Vec<Change>)> = Vec::new();
+
+    for deployment in deployments {
+        if let DeployTarget::Zoned(zoned) = deployment {
+            let desired: BTreeMap<String, String> = zoned
+                .routes
+                .iter()
+                .map(|r| (r.pattern.clone(), target.name.clone()))
+                .collect();
+            let live = live_routes(user, &zoned.zone_id, &target.name)?;
+            sections.push((
+                format!("routes (zone {})", zoned.zone_id),
+                diff_maps(&desired, &live),
+            ));
+        }
+    }
+
+    let desired_workers_dev = deployments
+        .iter()
+        .any(|d| matches!(d, DeployTarget::Zoneless(_)));
+    let live_workers_dev =
+        get_result::<SubdomainStatus>(&client, &format!("{}/subdomain", script_addr))?
+            .map(|s| s.enabled)
+            .unwrap_or(false);
+    let mut workers_dev_changes = Vec::new();
+    if desired_workers_dev != live_workers_dev {
+        workers_dev_changes.push(Change::Modify {
+            key: "enabled".to_string(),
+            live: live_workers_dev.to_string(),
+            desired: desired_workers_dev.to_string(),
+        });
+    }
+    sections.push(("workers.dev".to_string(), workers_dev_changes));
+
+    let desired_crons: BTreeMap<String, String> = deployments
+        .iter()
+        .filter_map(|d| match d {
+            DeployTarget::Schedule(schedule) => Some(schedule.crons.clone()),
+            _ => None,
+        })
+        .flatten()
+        .map(|cron| (cron, String::new()))
+        .collect();
+    let live_crons: BTreeMap<String, String> =
+        get_result::<Schedules>(&client, &format!("{}/schedules", script_addr))?
+            .map(|s| s.schedules)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| (s.cron, String::new()))
+            .collect();
+    sections.push((
+        "triggers".to_string(),
+        diff_maps(&desired_crons, &live_crons),
+    ));
+
+    let live_bindings =
+        get_result::<Vec<LiveBinding>>(&client, &format!("{}/bindings", script_addr))?
+            .unwrap_or_default();
+    let live: BTreeMap<String, String> = live_bindings
+        .iter()
+        .filter(|b| is_compared_binding(b))
+        .map(|b| (b.name.clone(), b.describe()))
+        .collect();
+    sections.push((
+        "bindings".to_string(),
+        diff_maps(&desired_bindings(target), &live),
+    ));
+
+    let mut has_changes = false;
+    for (title, changes) in &sections {
+        if changes.is_empty() {
+            continue;
+        }
+        has_changes = true;
+        StdOut::message(&styles::highlight(title).to_string());
+        for change in changes {
+            StdOut::message(&format!("  {}", change));
+        }
+    }
+
+    // secrets and Durable Object namespaces can't be declared in the configuration
+    // file, so list what is deployed rather than diffing against nothing
+    let secrets = live_secrets(user, target)?;
+    if !secrets.is_empty() {
+        StdOut::message(&format!(
+            "{} (set with `wrangler secret put`)",
+            styles::highlight("secrets")
+        ));
+        for name in secrets {
+            StdOut::message(&format!("    {}", name));
+        }
+    }
+
+    let durable_objects = get_result::<Vec<DurableObjectNamespace>>(
+        &client,
+        &http::endpoints::durable_object_namespaces(&target.account_id),
+    )?
+    .unwrap_or_default();
+    let durable_objects: Vec<&DurableObjectNamespace> = durable_objects
+        .iter()
+        .filter(|ns| ns.script.as_deref() == Some(target.name.as_str()))
+        .collect();
+    if !durable_objects.is_empty() {
+        StdOut::message(&styles::highlight("durable object namespaces").to_string());
+        for ns in durable_objects {
+            StdOut::message(&format!("    {} (class {})", ns.name, ns.class));
+        }
+    }
+
+    if !has_changes {
+        StdOut::success(&format!(
+            "{} is up to date with your configuration file",
+            target.name
+        ));
+    }
+
+    Ok(())
+}
+
+// Diffs two maps of key => value, where the desired map comes from the
+// configuration file and the live map from the API.
+pub fn diff_maps(
+    desired: &BTreeMap<String, String>,
+    live: &BTreeMap<String, String>,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for (key, desired_value) in desired {
+        match live.get(key) {
+            None => changes.push(Change::Add(key.clone())),
+            Some(live_value) if live_value != desired_value => changes.push(Change::Modify {
+                key: key.clone(),
+                live: live_value.clone(),
+                desired: desired_value.clone(),
+            }),
+            Some(_) => (),
+        }
+    }
+
+    for key in live.keys() {
+        if !desired.contains_key(key) {
+            changes.push(Change::Delete(key.clone()));
+        }
+    }
+
+    changes
+}
+
+fn desired_bindings(target: &Target) -> BTreeMap<String, String> {
+    let mut bindings = BTreeMap::new();
+
+    for kv in &target.kv_namespaces {
+        bindings.insert(kv.binding.clone(), format!("kv_namespace {}", kv.id));
+    }
+    for certificate in &target.mtls_certificates {
+        bindings.insert(
+            certificate.binding.clone(),
+            format!("mtls_certificate {}", certificate.certificate_id),
+        );
+    }
+    if let Some(email) = &target.email {
+        for send_email in &email.send_email {
+            bindings.insert(send_email.name.clone(), "send_email".to_string());
+        }
+    }
+    if let Some(vars) = &target.vars {
+        for (name, value) in vars {
+            let description = match value {
+                serde_json::Value::String(text) => format!("plain_text {:?}", text),
+                json => format!("json {}", json),
+            };
+            bindings.insert(name.clone(), description);
+        }
+    }
+    if let Some(text_blobs) = &target.text_blobs {
+        for name in text_blobs.keys() {
+            bindings.insert(name.clone(), "text_blob".to_string());
+        }
+    }
+
+    bindings
+}
+
+// wasm modules and the Workers Sites bindings are generated at publish time,
+// and secrets are never part of the configuration file, so leave them out.
+fn is_compared_binding(binding: &LiveBinding) -> bool {
+    !matches!(binding.binding_type.as_str(), "wasm_module" | "secret_text")
+        && !matches!(
+            binding.name.as_str(),
+            "__STATIC_CONTENT" | "__STATIC_CONTENT_MANIFEST"
+        )
+}
+
+fn live_routes(
+    user: &GlobalUser,
+    zone_id: &str,
+    script_name: &str,
+) -> Result<BTreeMap<String, String>, failure::Error> {
+    Ok(deploy::list_routes(user, zone_id)?
+        .into_iter()
+        .filter(|route| route.script.as_deref() == Some(script_name))
+        .map(|route| (route.pattern, script_name.to_string()))
+        .collect())
+}
+
+/// The names of the secrets set on the target's script. A script that hasn't been
+/// published has none.
+pub(crate) fn live_secrets(
+    user: &GlobalUser,
+    target: &Target,
+) -> Result<Vec<String>, failure::Error> {
+    let client = http::cf_v4_client(user)?;
+    match client.request_traced(&ListSecrets {
+        account_identifier: &target.account_id,
+        script_name: &target.name,
+    }) {
+        Ok(success) => Ok(success.result.into_iter().map(|s| s.name).collect()),
+        // the script hasn't been published yet, so it has no secrets
+        Err(ApiFailure::Error(status, errors))
+            if status.as_u16() == 404 || errors.errors.iter().any(|error| error.code == 10007) =>
+        {
+            Ok(Vec::new())
+        }
+        Err(e) => Err(http::api_error(e, None)),
+    }
+}
+
+// GETs a v4 API endpoint and unwraps its `result`; a 404 means the script
+// hasn't been published yet, so there is nothing to compare against.
+fn get_result<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+) -> Result<Option<T>, failure::Error> {
+    let res = client.get(url).send_traced()?;
+    if res.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    http::read_result(res)
+}
+
+#[derive(Deserialize)]
+struct SubdomainStatus {
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct Schedules {
+    schedules: Vec<Schedule>,
+}
+
+#[derive(Deserialize)]
+struct Schedule {
+    cron: String,
+}
+
+#[derive(Deserialize)]
+struct DurableObjectNamespace {
+    name: String,
+    script: Option<String>,
+    class: String,
+}
+
+/// A binding as the API reports it, for a script or one of its versions.
+#[derive(Deserialize)]
+pub(crate) struct LiveBinding {
+    pub name: String,
+    #[serde(rename = "type")]
+    binding_type: String,
+    namespace_id: Option<String>,
+    text: Option<String>,
+    json: Option<serde_json::Value>,
+    certificate_id: Option<String>,
+}
+
+impl LiveBinding {
+    /// The type of the binding and what it points at, e.g. "kv_namespace <id>".
+    pub fn describe(&self) -> String {
+        match self.binding_type.as_str() {
+            "kv_namespace" => format!(
+                "kv_namespace {}",
+                self.namespace_id.as_deref().unwrap_or_default()
+            ),
+            "mtls_certificate" => format!(
+                "mtls_certificate {}",
+                self.certificate_id.as_deref().unwrap_or_default()
+            ),
+            "plain_text" => format!("plain_text {:?}", self.text.as_deref().unwrap_or_default()),
+            "json" => format!(
+                "json {}",
+                self.json.as_ref().unwrap_or(&serde_json::Value::Null)
+            ),
+            other => other.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn it_diffs_added_deleted_and_modified_keys() {
+        let desired = map(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let live = map(&[("b", "2"), ("c", "4"), ("d", "5")]);
+
+        let changes = diff_maps(&desired, &live);
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Add("a".to_string()),
+                Change::Modify {
+                    key: "c".to_string(),
+                    live: "4".to_string(),
+                    desired: "3".to_string(),
+                },
+                Change::Delete("d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_has_no_changes_for_identical_maps() {
+        let desired = map(&[("a", "1")]);
+        assert!(diff_maps(&desired, &desired.clone()).is_empty());
+    }
+}
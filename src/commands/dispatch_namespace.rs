@@ -0,0 +1,133 @@
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::commands::{account, publish, validate_namespace_name};
+use crate::deploy::state;
+use crate::http::{self, read_result, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::table::Table;
+
+#[derive(Debug, Deserialize)]
+pub struct DispatchNamespace {
+    pub namespace_id: String,
+    pub namespace_name: String,
+    pub created_on: Option<String>,
+    pub script_count: Option<u64>,
+}
+
+fn namespaces_addr(target: &Target) -> Result<String, failure::Error> {
+    account::check_account_id(target, "dispatch namespaces")?;
+    Ok(http::endpoints::dispatch_namespaces(&target.account_id))
+}
+
+/// Creates a Workers for Platforms dispatch namespace in the account.
+pub fn create(target: &Target, user: &GlobalUser, name: &str) -> Result<(), failure::Error> {
+    validate_namespace_name(name)?;
+    let addr = namespaces_addr(target)?;
+    StdOut::working(&format!("Creating dispatch namespace {}", name));
+
+    let client = http::legacy_auth_client(user);
+    let res = client
+        .post(&addr)
+        .json(&json!({ "name": name }))
+        .send_traced()?;
+    let namespace: DispatchNamespace = match read_result(res)? {
+        Some(namespace) => namespace,
+        None => failure::bail!("The API did not return the new dispatch namespace"),
+    };
+
+    StdOut::success(&format!(
+        "Created dispatch namespace {} with id {}",
+        namespace.namespace_name, namespace.namespace_id
+    ));
+    StdOut::info(&format!(
+        "Publish user scripts into it with `wrangler publish --dispatch-namespace {}`",
+        namespace.namespace_name
+    ));
+    Ok(())
+}
+
+/// Lists the dispatch namespaces in the account.
+pub fn list(target: &Target, user: &GlobalUser) -> Result<(), failure::Error> {
+    let addr = namespaces_addr(target)?;
+    let client = http::legacy_auth_client(user);
+    let res = client.get(&addr).send_traced()?;
+    let namespaces: Vec<DispatchNamespace> = read_result(res)?.unwrap_or_default();
+
+    if namespaces.is_empty() {
+        StdOut::info("There are no dispatch namespaces in this account.");
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Name", "Id", "Scripts", "Created"]);
+    for namespace in namespaces {
+        table.add_row(vec![
+            namespace.namespace_name,
+            namespace.namespace_id,
+            namespace
+                .script_count
+                .map(|count| count.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            namespace.created_on.unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// Deletes a dispatch namespace, and every user script in it, after asking for confirmation.
+pub fn delete(target: &Target, user: &GlobalUser, name: &str) -> Result<(), failure::Error> {
+    let addr = format!("{}/{}", namespaces_addr(target)?, name);
+
+    match interactive::confirm(&format!(
+        "Are you sure you want to delete dispatch namespace {}? All of the scripts in it will be deleted too.",
+        name
+    )) {
+        Ok(true) => (),
+        Ok(false) => {
+            StdOut::info(&format!("Not deleting dispatch namespace {}", name));
+            return Ok(());
+        }
+        Err(e) => failure::bail!(e),
+    }
+
+    StdOut::working(&format!("Deleting dispatch namespace {}", name));
+    let client = http::legacy_auth_client(user);
+    let res = client.delete(&addr).send_traced()?;
+    read_result::<serde_json::Value>(res)?;
+    StdOut::success("Success");
+    Ok(())
+}
+
+/// Uploads the target as a user script in the dispatch namespace `namespace`. User scripts
+/// are only reachable through a dispatch worker, so routes, workers.dev and schedules in
+/// the configuration file are not deployed.
+pub fn publish(
+    user: &GlobalUser,
+    target: &mut Target,
+    namespace: &str,
+    force: bool,
+    out: Output,
+) -> Result<(), failure::Error> {
+    validate_namespace_name(namespace)?;
+    target.dispatch_namespace = Some(namespace.to_string());
+
+    let state_name = state::dispatch_state_name(namespace, &target.name);
+    publish::publish(
+        user,
+        target,
+        Vec::new(),
+        Some(state_name.as_str()),
+        force,
+        out,
+    )?;
+
+    StdErr::success(&format!(
+        "Published {} to dispatch namespace {}",
+        target.name, namespace
+    ));
+    Ok(())
+}
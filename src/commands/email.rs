@@ -0,0 +1,140 @@
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::commands::account;
+use crate::http::{self, read_result, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::Table;
+
+/// An Email Routing destination address. Workers can only send to verified ones.
+#[derive(Debug, Deserialize)]
+pub struct DestinationAddress {
+    pub email: String,
+    pub verified: Option<String>,
+}
+
+fn addresses_addr(target: &Target) -> Result<String, failure::Error> {
+    account::check_account_id(target, "destination addresses")?;
+    Ok(http::endpoints::email_routing_addresses(&target.account_id))
+}
+
+fn fetch_addresses(
+    target: &Target,
+    user: &GlobalUser,
+) -> Result<Vec<DestinationAddress>, failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let res = client.get(&addresses_addr(target)?).send_traced()?;
+    Ok(read_result(res)?.unwrap_or_default())
+}
+
+/// Lists the destination addresses in the account, and whether the `[email]` bindings
+/// of the target use them.
+pub fn list_addresses(target: &Target, user: &GlobalUser) -> Result<(), failure::Error> {
+    let addresses = fetch_addresses(target, user)?;
+    let configured = configured_addresses(target);
+
+    let mut table = Table::new(&["Address", "Verified", "Used by this worker"]);
+    for address in &addresses {
+        table.add_row(vec![
+            address.email.as_str(),
+            address.verified.as_deref().unwrap_or("pending"),
+            if configured.contains(&address.email) {
+                "yes"
+            } else {
+                "no"
+            },
+        ]);
+    }
+    for missing in missing_addresses(&configured, &addresses) {
+        table.add_row(vec![missing, "not added".to_string(), "yes".to_string()]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// Adds `address` as a destination address, which emails it a verification link. Without
+/// an address, does so for every address in `[email]` that isn't in the account yet.
+pub fn verify_address(
+    target: &Target,
+    user: &GlobalUser,
+    address: Option<&str>,
+) -> Result<(), failure::Error> {
+    let to_verify = match address {
+        Some(address) => vec![address.to_string()],
+        None => {
+            let existing = fetch_addresses(target, user)?;
+            let missing = missing_addresses(&configured_addresses(target), &existing);
+            for address in existing.iter().filter(|a| a.verified.is_none()) {
+                StdOut::info(&format!(
+                    "{} is still waiting to be verified. Check its inbox for the verification email.",
+                    address.email
+                ));
+            }
+            if missing.is_empty() {
+                StdOut::info(
+                    "Every destination address in your configuration file has been added.",
+                );
+                return Ok(());
+            }
+            missing
+        }
+    };
+
+    let client = http::legacy_auth_client(user);
+    let addr = addresses_addr(target)?;
+    for address in to_verify {
+        let res = client
+            .post(&addr)
+            .json(&json!({ "email": address }))
+            .send_traced()?;
+        read_result::<serde_json::Value>(res)?;
+        StdOut::success(&format!(
+            "Sent a verification email to {}. The worker can send to it once the link in it is clicked.",
+            address
+        ));
+    }
+    Ok(())
+}
+
+fn configured_addresses(target: &Target) -> Vec<String> {
+    match &target.email {
+        Some(email) => email.destination_addresses(),
+        None => Vec::new(),
+    }
+}
+
+// addresses used in the configuration file that haven't been added to the account
+fn missing_addresses(configured: &[String], existing: &[DestinationAddress]) -> Vec<String> {
+    configured
+        .iter()
+        .filter(|address| {
+            !existing
+                .iter()
+                .any(|e| e.email.eq_ignore_ascii_case(address))
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_addresses_missing_from_the_account() {
+        let configured = vec![
+            "alerts@example.com".to_string(),
+            "ops@example.com".to_string(),
+        ];
+        let existing = vec![DestinationAddress {
+            email: "Alerts@example.com".to_string(),
+            verified: None,
+        }];
+        assert_eq!(
+            missing_addresses(&configured, &existing),
+            vec!["ops@example.com".to_string()]
+        );
+    }
+}
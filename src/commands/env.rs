@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::deploy::DeployTarget;
+use crate::settings::toml::{Environment, Manifest, VarsStrategy};
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+use crate::terminal::table::Table;
+
+/// Lists the environments in the configuration file and the worker each one publishes.
+pub fn list(manifest: &Manifest) -> Result<(), failure::Error> {
+    let mut names: Vec<&String> = match &manifest.env {
+        Some(envs) => envs.keys().collect(),
+        None => Vec::new(),
+    };
+    if names.is_empty() {
+        StdOut::info("There are no environments in your configuration file.");
+        return Ok(());
+    }
+    names.sort();
+
+    let mut table = Table::new(&["Environment", "Worker"]);
+    for name in names {
+        table.add_row(vec![name.clone(), manifest.worker_name(Some(name))]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// Prints the settings `env` resolves to after inheriting from the top level, and
+/// where each one comes from.
+pub fn show(manifest: &Manifest, env: Option<&str>) -> Result<(), failure::Error> {
+    let mut table = Table::new(&["Setting", "Value", "From"]);
+    for (setting, value, from) in resolve(manifest, env)? {
+        table.add_row(vec![setting, value, from]);
+    }
+
+    match env {
+        Some(env) => StdOut::message(&format!("Environment {}", styles::highlight(env))),
+        None => StdOut::message("Top level (no --env)"),
+    }
+    table.print();
+    Ok(())
+}
+
+// (setting, resolved value, where it came from) for every setting of the target
+pub(crate) fn resolve(
+    manifest: &Manifest,
+    env_name: Option<&str>,
+) -> Result<Vec<(String, String, String)>, failure::Error> {
+    let target = manifest.get_target(env_name, false)?;
+    let deployments = manifest.get_deployments(env_name)?;
+    let env = manifest.get_environment(env_name)?;
+
+    let inherited = |set_in_env: fn(&Environment) -> bool| match (env_name, env) {
+        (Some(name), Some(env)) if set_in_env(env) => format!("[env.{}]", name),
+        (Some(_), _) => "top level (inherited)".to_string(),
+        _ => "top level".to_string(),
+    };
+    let not_inherited = |set_in_env: fn(&Environment) -> bool| match (env_name, env) {
+        (Some(name), Some(env)) if set_in_env(env) => format!("[env.{}]", name),
+        (Some(_), _) => "not set (not inherited from the top level)".to_string(),
+        _ => "top level".to_string(),
+    };
+    let top_level_only = || "top level".to_string();
+
+    let name_from = match (env_name, env) {
+        (Some(name), Some(env)) if env.name.is_some() => format!("[env.{}]", name),
+        (Some(name), _) => format!("top level name + \"-{}\"", name),
+        _ => "top level".to_string(),
+    };
+
+    let mut rows = vec![
+        ("name".to_string(), target.name.clone(), name_from),
+        (
+            "type".to_string(),
+            target.target_type.to_string(),
+            top_level_only(),
+        ),
+        (
+            "account_id".to_string(),
+            or_unset(&target.account_id),
+            inherited(|env| env.account_id.is_some()),
+        ),
+        (
+            "webpack_config".to_string(),
+            target.webpack_config.clone().unwrap_or_else(unset),
+            inherited(|env| env.webpack_config.is_some()),
+        ),
+        (
+            "site".to_string(),
+            target
+                .site
+                .as_ref()
+                .map(|site| site.bucket.display().to_string())
+                .unwrap_or_else(unset),
+            inherited(|env| env.site.is_some()),
+        ),
+        (
+            "kv_namespaces".to_string(),
+            or_unset(
+                &target
+                    .kv_namespaces
+                    .iter()
+                    .map(|ns| format!("{} ({})", ns.binding, ns.id))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            not_inherited(|env| env.kv_namespaces.is_some()),
+        ),
+        (
+            "vars".to_string(),
+            format_vars(target.vars.as_ref()),
+            match (env_name, env) {
+                (Some(name), Some(env)) if env.vars_strategy == Some(VarsStrategy::Merge) => {
+                    format!("top level merged with [env.{}]", name)
+                }
+                _ => not_inherited(|env| env.vars.is_some()),
+            },
+        ),
+        (
+            "text_blobs".to_string(),
+            target
+                .text_blobs
+                .as_ref()
+                .map(|blobs| {
+                    let mut names: Vec<&String> = blobs.keys().collect();
+                    names.sort();
+                    names
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_else(unset),
+            top_level_only(),
+        ),
+    ];
+
+    let mut routes = Vec::new();
+    let mut crons = Vec::new();
+    let mut workers_dev = false;
+    for deployment in &deployments {
+        match deployment {
+            DeployTarget::Zoned(zoned) => routes.extend(
+                zoned
+                    .routes
+                    .iter()
+                    .map(|route| format!("{} (zone {})", route.pattern, zoned.zone_id)),
+            ),
+            DeployTarget::Zoneless(_) => workers_dev = true,
+            DeployTarget::Schedule(schedule) => crons.extend(schedule.crons.iter().cloned()),
+        }
+    }
+    rows.push((
+        "workers_dev".to_string(),
+        workers_dev.to_string(),
+        // an environment without routes of its own publishes like the top level does
+        inherited(|env| env.workers_dev.is_some() || env.route.is_some() || env.routes.is_some()),
+    ));
+    rows.push((
+        "routes".to_string(),
+        or_unset(&routes.join("\n")),
+        not_inherited(|env| env.route.is_some() || env.routes.is_some()),
+    ));
+    rows.push((
+        "triggers".to_string(),
+        or_unset(&crons.join("\n")),
+        inherited(|env| env.triggers.is_some()),
+    ));
+
+    Ok(rows)
+}
+
+fn format_vars(vars: Option<&HashMap<String, serde_json::Value>>) -> String {
+    match vars {
+        Some(vars) if !vars.is_empty() => {
+            // strings print quoted, tables and arrays as JSON
+            let mut vars: Vec<String> = vars
+                .iter()
+                .map(|(name, value)| format!("{} = {}", name, value))
+                .collect();
+            vars.sort();
+            vars.join("\n")
+        }
+        _ => unset(),
+    }
+}
+
+fn or_unset(value: &str) -> String {
+    if value.is_empty() {
+        unset()
+    } else {
+        value.to_string()
+    }
+}
+
+fn unset() -> String {
+    "-".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    #[test]
+    fn it_explains_where_settings_come_from() {
+        let manifest = Manifest::from_str(
+            r#"
+            name = "worker"
+            type = "javascript"
+            account_id = "account"
+            workers_dev = true
+            vars = { MODE = "top" }
+
+            [env.staging]
+            workers_dev = true
+        "#,
+        )
+        .unwrap();
+
+        let rows = resolve(&manifest, Some("staging")).unwrap();
+        let row = |setting: &str| rows.iter().find(|row| row.0 == setting).unwrap().clone();
+
+        assert_eq!(
+            row("name"),
+            (
+                "name".to_string(),
+                "worker-staging".to_string(),
+                "top level name + \"-staging\"".to_string()
+            )
+        );
+        assert_eq!(row("account_id").2, "top level (inherited)");
+        assert_eq!(row("vars").1, "-");
+        assert_eq!(row("vars").2, "not set (not inherited from the top level)");
+        assert_eq!(row("workers_dev").2, "[env.staging]");
+        assert_eq!(
+            row("routes").2,
+            "not set (not inherited from the top level)"
+        );
+    }
+}
@@ -6,35 +6,27 @@ use std::path::Path;
 
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
 
-use indicatif::{ProgressBar, ProgressStyle};
-
 use crate::commands::kv;
+use crate::http;
 use crate::kv::bulk::delete;
 use crate::kv::bulk::BATCH_KEY_MAX;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
-use crate::terminal::message::{Message, StdOut};
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::progress::Unit;
+
+/// Deletes the keys in `filename`. With `dry_run` the file is only checked, without the
+/// API, so it works offline.
 pub fn run(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
     filename: &Path,
+    dry_run: bool,
 ) -> Result<(), failure::Error> {
     kv::validate_target(target)?;
 
-    match interactive::confirm(&format!(
-        "Are you sure you want to delete all keys in {}?",
-        filename.display()
-    )) {
-        Ok(true) => (),
-        Ok(false) => {
-            StdOut::info(&format!("Not deleting keys in {}", filename.display()));
-            return Ok(());
-        }
-        Err(e) => failure::bail!(e),
-    }
-
     let keys: Vec<String> = match &metadata(filename) {
         Ok(file_type) if file_type.is_file() => {
             let data = fs::read_to_string(filename)?;
@@ -50,13 +42,37 @@ pub fn run(
     };
 
     let len = keys.len();
+    if dry_run {
+        StdOut::info(&format!(
+            "Dry run: {} would delete {} keys from namespace {}",
+            filename.display(),
+            len,
+            namespace_id
+        ));
+        return Ok(());
+    }
+    http::require_scopes(user, &[http::Scope::WorkersKvStorageWrite])?;
+
+    match interactive::confirm(&format!(
+        "Are you sure you want to delete all keys in {}?",
+        filename.display()
+    )) {
+        Ok(true) => (),
+        Ok(false) => {
+            StdOut::info(&format!("Not deleting keys in {}", filename.display()));
+            return Ok(());
+        }
+        Err(e) => failure::bail!(e),
+    }
 
     StdOut::working(&format!("deleting {} key value pairs", len));
 
     let progress_bar = if len > BATCH_KEY_MAX {
-        let pb = ProgressBar::new(len as u64);
-        pb.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-        Some(pb)
+        Some(StdErr::progress(
+            "Deleting",
+            len as u64,
+            Unit::Items("keys"),
+        ))
     } else {
         None
     };
@@ -64,7 +80,7 @@ pub fn run(
     delete(target, user, namespace_id, keys, &progress_bar)?;
 
     if let Some(pb) = &progress_bar {
-        pb.finish_with_message(&format!("deleted {} key value pairs", len));
+        pb.finish(&format!("deleted {} key value pairs", len));
     }
 
     StdOut::success("Success");
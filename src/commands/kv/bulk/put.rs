@@ -6,19 +6,21 @@ use std::path::Path;
 
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
 
-use indicatif::{ProgressBar, ProgressStyle};
-
 use crate::commands::kv::validate_target;
-use crate::kv::bulk::put;
-use crate::kv::bulk::BATCH_KEY_MAX;
+use crate::http;
+use crate::kv::bulk::{progress_bar, put};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
-use crate::terminal::message::{Message, StdErr};
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+/// Uploads the key-value pairs in `filename`. With `dry_run` the file is only checked,
+/// without the API, so it works offline.
 pub fn run(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
     filename: &Path,
+    dry_run: bool,
 ) -> Result<(), failure::Error> {
     validate_target(target)?;
 
@@ -28,7 +30,7 @@ pub fn run(
             let data_vec = serde_json::from_str(&data);
             match data_vec {
                 Ok(data_vec) => Ok(data_vec),
-                Err(_) => Err(failure::format_err!("Failed to decode JSON. Please make sure to follow the format, [{{\"key\": \"test_key\", \"value\": \"test_value\"}}, ...]"))
+                Err(_) => Err(failure::format_err!("Failed to decode JSON. Please make sure to follow the format, [{{\"key\": \"test_key\", \"value\": \"test_value\"}}, ...], with \"base64\": true for binary values encoded in base64"))
             }
         }
         Ok(_) => Err(failure::format_err!(
@@ -38,23 +40,65 @@ pub fn run(
         Err(e) => Err(failure::format_err!("{}", e)),
     }?;
 
+    check_base64(&pairs)?;
+
     let len = pairs.len();
+    if dry_run {
+        StdOut::info(&format!(
+            "Dry run: {} would upload {} key value pairs to namespace {}",
+            filename.display(),
+            len,
+            namespace_id
+        ));
+        return Ok(());
+    }
+    http::require_scopes(user, &[http::Scope::WorkersKvStorageWrite])?;
 
     StdErr::working(&format!("uploading {} key value pairs", len));
-    let progress_bar = if len > BATCH_KEY_MAX {
-        let pb = ProgressBar::new(len as u64);
-        pb.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-        Some(pb)
-    } else {
-        None
-    };
+    let progress_bar = progress_bar(&pairs);
 
     put(target, &user, namespace_id, pairs, &progress_bar)?;
 
     if let Some(pb) = &progress_bar {
-        pb.finish_with_message(&format!("uploaded {} key value pairs", len));
+        pb.finish(&format!("uploaded {} key value pairs", len));
     }
 
     StdErr::success("Success");
     Ok(())
 }
+
+// The API decodes values marked "base64": true before storing them. One that doesn't
+// decode fails its whole batch, after the batches before it were written, so they are
+// all checked before anything is uploaded.
+fn check_base64(pairs: &[KeyValuePair]) -> Result<(), failure::Error> {
+    for pair in pairs {
+        if pair.base64 == Some(true) && base64::decode(&pair.value).is_err() {
+            failure::bail!(
+                "The value of {} is marked \"base64\": true, but is not valid base64",
+                pair.key
+            )
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(value: &str, base64: Option<bool>) -> KeyValuePair {
+        KeyValuePair {
+            key: "key".to_string(),
+            value: value.to_string(),
+            expiration: None,
+            expiration_ttl: None,
+            base64,
+        }
+    }
+
+    #[test]
+    fn it_checks_base64_values_before_uploading() {
+        assert!(check_base64(&[pair("AAEC/w==", Some(true)), pair("not base64", None)]).is_ok());
+        assert!(check_base64(&[pair("not base64", Some(true))]).is_err());
+    }
+}
@@ -1,8 +1,7 @@
 use cloudflare::endpoints::workerskv::delete_key::DeleteKey;
-use cloudflare::framework::apiclient::ApiClient;
 
-use crate::commands::kv::{format_error, validate_target};
-use crate::http;
+use crate::commands::kv::{api_error, validate_target};
+use crate::http::{self, TracedClient};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
@@ -28,7 +27,7 @@ pub fn delete(
     let msg = format!("Deleting key \"{}\"", key);
     StdOut::working(&msg);
 
-    let response = client.request(&DeleteKey {
+    let response = client.request_traced(&DeleteKey {
         account_identifier: &target.account_id,
         namespace_identifier: id,
         key, // this is url encoded within cloudflare-rs
@@ -36,7 +35,7 @@ pub fn delete(
 
     match response {
         Ok(_) => StdOut::success("Success"),
-        Err(e) => print!("{}", format_error(e)),
+        Err(e) => return Err(api_error(e)),
     }
 
     Ok(())
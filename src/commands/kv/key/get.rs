@@ -3,41 +3,56 @@
 // because the GET key operation doesn't return json on success--just the raw
 // value).
 
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
 use cloudflare::framework::response::ApiFailure;
 
 use crate::commands::kv;
-use crate::http;
+use crate::http::{self, Traced};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
 
-pub fn get(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result<(), failure::Error> {
+/// Writes the value of `key` to `out`, or to stdout, byte for byte, so binary values
+/// come out as they went in.
+pub fn get(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    key: &str,
+    out: Option<&Path>,
+) -> Result<(), failure::Error> {
     kv::validate_target(target)?;
-    let api_endpoint = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
-        target.account_id,
-        id,
-        kv::url_encode_key(key)
-    );
+    let api_endpoint = http::endpoints::kv_value(&target.account_id, id, &kv::url_encode_key(key));
 
     let client = http::legacy_auth_client(&user);
 
-    let res = client.get(&api_endpoint).send()?;
+    let res = client.get(&api_endpoint).send_traced()?;
 
     let response_status = res.status();
     if response_status.is_success() {
-        let body_text = res.text()?;
-        // We don't use message::success because we don't want to include the emoji/formatting
-        // in case someone is piping this to stdin
-        print!("{}", &body_text);
+        let value = res.bytes()?;
+        match out {
+            Some(out) => {
+                fs::write(out, &value)?;
+                StdErr::success(&format!("Wrote {} bytes to {}", value.len(), out.display()));
+            }
+            // We don't use message::success because we don't want to include the emoji/formatting
+            // in case someone is piping this to stdin
+            None => {
+                let mut stdout = io::stdout();
+                stdout.write_all(&value)?;
+                stdout.flush()?;
+            }
+        }
     } else {
         // This is logic pulled from cloudflare-rs for pretty error formatting right now;
         // it will be redundant when we switch to using cloudflare-rs for all API requests.
         let parsed = res.json();
         let errors = parsed.unwrap_or_default();
-        print!(
-            "{}",
-            kv::format_error(ApiFailure::Error(response_status, errors))
-        );
+        return Err(kv::api_error(ApiFailure::Error(response_status, errors)));
     }
 
     Ok(())
@@ -1,44 +1,205 @@
 extern crate serde_json;
 
+use std::io::Write;
+use std::thread;
+
+use cloudflare::endpoints::workerskv::Key;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
 use crate::commands::kv;
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, Traced};
 use crate::kv::key::KeyList;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+// How many values `--show-values` fetches at once
+const VALUE_FETCHES: usize = 8;
+// and how much of each it shows
+const VALUE_PREVIEW_BYTES: usize = 128;
+
+/// How `kv:key list` lists keys
+#[derive(Default)]
+pub struct ListOptions<'a> {
+    pub prefix: Option<&'a str>,
+    /// Stop after this many keys
+    pub limit: Option<usize>,
+    /// Pick up a limited list where it stopped
+    pub cursor: Option<&'a str>,
+    /// Fetch the start of every value along with its key
+    pub show_values: bool,
+}
 
-// Note: this function only prints keys in json form, given that
+// Note: this function only writes keys in json form, given that
 // the number of entries in each json blob is variable (so csv and tsv
-// representation won't make sense)
+// representation won't make sense). The list is written to `out` as the
+// keys come in, so a long one doesn't have to fit in memory.
 pub fn list(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
-    prefix: Option<&str>,
+    options: &ListOptions,
+    out: &mut impl Write,
 ) -> Result<(), failure::Error> {
     kv::validate_target(target)?;
+    if let Some(limit) = options.limit {
+        if limit < 10 {
+            failure::bail!("--limit must be at least 10, the fewest keys the API lists at once")
+        }
+    }
     let client = http::cf_v4_client(&user)?;
-    let key_list = KeyList::new(target, client, namespace_id, prefix)?;
+    let mut key_list = KeyList::new(target, client, namespace_id, options.prefix)?
+        .starting_at(options.cursor)
+        .limit(options.limit);
+    let value_client = http::legacy_auth_client(&user);
 
-    print!("["); // Open json list bracket
+    write!(out, "[")?; // Open json list bracket
 
     let mut first_key = true;
-
-    for key_result in key_list {
+    let mut batch = Vec::new();
+    loop {
+        let key_result = key_list.next();
         match key_result {
-            Ok(key) => {
+            Some(Ok(key)) => batch.push(key),
+            Some(Err(e)) => return Err(kv::api_error(e)),
+            None => (),
+        }
+        // values are fetched a batch at a time, so the keys are printed in batches too
+        let batch_is_full = !options.show_values || batch.len() == VALUE_FETCHES;
+        if batch_is_full || key_result.is_none() {
+            let keys = if options.show_values {
+                with_values(&value_client, target, namespace_id, batch.split_off(0))?
+            } else {
+                batch
+                    .drain(..)
+                    .map(|key| serde_json::to_value(&key))
+                    .collect::<Result<_, _>>()?
+            };
+            for key in keys {
                 if first_key {
                     first_key = false;
                 } else {
-                    print!(",");
+                    write!(out, ",")?;
                 }
-
-                print!("{}", serde_json::to_string(&key)?);
+                write!(out, "{}", key)?;
             }
-            Err(e) => print!("{}", kv::format_error(e)),
+        }
+        if key_result.is_none() {
+            break;
         }
     }
 
-    print!("]"); // Close json list bracket
+    write!(out, "]")?; // Close json list bracket
+    out.flush()?;
+
+    if options.limit.is_some() {
+        if let Some(cursor) = key_list.cursor() {
+            // on stderr, so the listed json stays valid
+            StdErr::info(&format!(
+                "There are more keys. List them with --cursor {}",
+                cursor
+            ));
+        }
+    }
 
     Ok(())
 }
+
+// Fetches the values of `keys` concurrently, and adds the start of each to its key.
+fn with_values(
+    client: &Client,
+    target: &Target,
+    namespace_id: &str,
+    keys: Vec<Key>,
+) -> Result<Vec<Value>, failure::Error> {
+    let handles: Vec<_> = keys
+        .iter()
+        .map(|key| {
+            let client = client.clone();
+            let addr = http::endpoints::kv_value(
+                &target.account_id,
+                namespace_id,
+                &kv::url_encode_key(&key.name),
+            );
+            thread::spawn(move || -> Result<Option<Vec<u8>>, failure::Error> {
+                let res = client.get(&addr).send_traced()?;
+                // deleted since it was listed
+                if res.status() == StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                if !res.status().is_success() {
+                    return Err(WranglerError::Api(format!(
+                        "Could not get the value. Status: {}",
+                        res.status()
+                    ))
+                    .into());
+                }
+                Ok(Some(res.bytes()?.to_vec()))
+            })
+        })
+        .collect();
+
+    let mut listed = Vec::new();
+    for (key, handle) in keys.iter().zip(handles) {
+        let value = match handle.join() {
+            Ok(value) => value?,
+            Err(_) => failure::bail!("Could not get the value of {}", key.name),
+        };
+        let mut listed_key = serde_json::to_value(key)?;
+        if let Some(value) = value {
+            preview(&mut listed_key, &value);
+        }
+        listed.push(listed_key);
+    }
+    Ok(listed)
+}
+
+// Adds the start of `value` to `key`: the text of text values, and base64 for anything
+// else. `value_bytes` is the size of the whole value, so a longer one was cut short.
+fn preview(key: &mut Value, value: &[u8]) {
+    let mut end = value.len().min(VALUE_PREVIEW_BYTES);
+    let text = loop {
+        match std::str::from_utf8(&value[..end]) {
+            Ok(text) => break Some(text),
+            // cut in the middle of a character
+            Err(e) if e.error_len().is_none() && end < value.len() => end = e.valid_up_to(),
+            Err(_) => break None,
+        }
+    };
+    match text {
+        Some(text) => key["value"] = json!(text),
+        None => {
+            key["value"] = json!(base64::encode(&value[..end]));
+            key["value_encoding"] = json!("base64");
+        }
+    }
+    key["value_bytes"] = json!(value.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_previews_the_start_of_values() {
+        let mut key = json!({ "name": "greeting" });
+        preview(&mut key, b"hello");
+        assert_eq!(key["value"], "hello");
+        assert_eq!(key["value_bytes"], 5);
+
+        // a character straddling the cut is left out
+        let value = format!("{}é", "a".repeat(VALUE_PREVIEW_BYTES - 1));
+        let mut key = json!({ "name": "long" });
+        preview(&mut key, value.as_bytes());
+        assert_eq!(key["value"], "a".repeat(VALUE_PREVIEW_BYTES - 1));
+        assert_eq!(key["value_bytes"], VALUE_PREVIEW_BYTES + 1);
+
+        let mut key = json!({ "name": "binary" });
+        preview(&mut key, &[0xff, 0xfe, 0x00]);
+        assert_eq!(key["value"], "//4A");
+        assert_eq!(key["value_encoding"], "base64");
+    }
+}
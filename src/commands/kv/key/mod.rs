@@ -5,5 +5,5 @@ mod put;
 
 pub use delete::delete;
 pub use get::get;
-pub use list::list;
+pub use list::{list, ListOptions};
 pub use put::{put, KVMetaData};
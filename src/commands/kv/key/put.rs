@@ -9,7 +9,7 @@ use cloudflare::framework::response::ApiFailure;
 use url::Url;
 
 use crate::commands::kv;
-use crate::http;
+use crate::http::{self, Traced};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdOut};
@@ -25,11 +25,10 @@ pub struct KVMetaData {
 pub fn put(target: &Target, user: &GlobalUser, data: KVMetaData) -> Result<(), failure::Error> {
     kv::validate_target(target)?;
 
-    let api_endpoint = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
-        target.account_id,
+    let api_endpoint = http::endpoints::kv_value(
+        &target.account_id,
         &data.namespace_id,
-        kv::url_encode_key(&data.key)
+        &kv::url_encode_key(&data.key),
     );
 
     // Add expiration and expiration_ttl query options as necessary.
@@ -53,7 +52,7 @@ pub fn put(target: &Target, user: &GlobalUser, data: KVMetaData) -> Result<(), f
         match &metadata(&data.value) {
             Ok(file_type) if file_type.is_file() => {
                 let file = fs::File::open(&data.value)?;
-                client.put(&url_into_str).body(file).send()?
+                client.put(&url_into_str).body(file).send_traced()?
             }
             Ok(file_type) if file_type.is_dir() => failure::bail!(
                 "--path argument takes a file, {} is a directory",
@@ -63,7 +62,7 @@ pub fn put(target: &Target, user: &GlobalUser, data: KVMetaData) -> Result<(), f
             Err(e) => failure::bail!("{}", e),
         }
     } else {
-        client.put(&url_into_str).body(data.value).send()?
+        client.put(&url_into_str).body(data.value).send_traced()?
     };
 
     let response_status = res.status();
@@ -74,10 +73,7 @@ pub fn put(target: &Target, user: &GlobalUser, data: KVMetaData) -> Result<(), f
         // it will be redundant when we switch to using cloudflare-rs for all API requests.
         let parsed = res.json();
         let errors = parsed.unwrap_or_default();
-        print!(
-            "{}",
-            kv::format_error(ApiFailure::Error(response_status, errors))
-        );
+        return Err(kv::api_error(ApiFailure::Error(response_status, errors)));
     }
 
     Ok(())
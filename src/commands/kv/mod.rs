@@ -16,6 +16,10 @@ pub fn format_error(e: ApiFailure) -> String {
     http::format_error(e, Some(&kv_help))
 }
 
+pub fn api_error(e: ApiFailure) -> failure::Error {
+    http::api_error(e, Some(&kv_help))
+}
+
 // kv_help() provides more detailed explanations of Workers KV API error codes.
 // See https://api.cloudflare.com/#workers-kv-namespace-errors for details.
 fn kv_help(error_code: u16) -> &'static str {
@@ -103,6 +107,7 @@ fn url_encode_key(key: &str) -> String {
 #[cfg(test)]
 mod tests {
     use crate::commands::kv;
+    use crate::settings::metadata::Annotations;
     use crate::settings::toml::{KvNamespace, Target, TargetType};
 
     #[test]
@@ -119,12 +124,25 @@ mod tests {
                     binding: "KV".to_string(),
                 },
             ],
+            mtls_certificates: Vec::new(),
+            email: None,
+            tail_consumers: Vec::new(),
             name: "test-target".to_string(),
             target_type: TargetType::Webpack,
             webpack_config: None,
+            node_compat: false,
+            minify: None,
+            keep_names: false,
+            drop_console: false,
+            main: None,
             site: None,
             vars: None,
             text_blobs: None,
+            rules: Vec::new(),
+            tools: Default::default(),
+            build: Default::default(),
+            dispatch_namespace: None,
+            annotations: Annotations::default(),
         };
         assert!(kv::get_namespace_id(&target_with_dup_kv_bindings, "").is_err());
     }
@@ -1,13 +1,18 @@
+use std::path::Path;
+
 use regex::Regex;
 
-use crate::commands::kv;
+use crate::commands::{kv, quota};
+use crate::deploy::DeployState;
 use crate::http;
 use crate::kv::namespace::create;
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::{ConfigKvNamespace, KvNamespace, Manifest};
+use crate::settings::toml::{ConfigKvNamespace, ConfigWriter, KvNamespace, Manifest};
 use crate::terminal::message::{Message, StdOut};
+
 pub fn run(
     manifest: &Manifest,
+    config_path: &Path,
     is_preview: bool,
     env: Option<&str>,
     user: &GlobalUser,
@@ -21,6 +26,7 @@ pub fn run(
     if is_preview {
         title.push_str("_preview");
     }
+    quota::check_kv_namespaces(user, &account_id, 1);
     let msg = format!("Creating namespace with title \"{}\"", title);
     StdOut::working(&msg);
 
@@ -29,28 +35,47 @@ pub fn run(
 
     match result {
         Ok(success) => {
-            let namespace = success.result;
+            let namespace = KvNamespace {
+                binding: binding.to_string(),
+                id: success.result.id,
+            };
             StdOut::success("Success!");
-            println!(
-                "{}",
-                toml_modification_instructions(
-                    KvNamespace {
-                        binding: binding.to_string(),
-                        id: namespace.id,
-                    },
-                    manifest.kv_namespaces.as_ref(),
-                    env,
-                    is_preview,
-                )
-            );
+
+            let mut state = DeployState::load(env)?;
+            state.add_kv_namespace(&namespace, is_preview);
+            state.save(env)?;
+
+            // fill in the id if the binding is already in the configuration file
+            let field = if is_preview { "preview_id" } else { "id" };
+            let mut config = ConfigWriter::open(config_path)?;
+            if config.set_kv_namespace(env, binding, field, &namespace.id) {
+                config.save(config_path)?;
+                StdOut::info(&format!(
+                    "Saved {} = \"{}\" for {} to {}",
+                    field,
+                    namespace.id,
+                    binding,
+                    config_path.display()
+                ));
+            } else {
+                StdOut::message(&format!(
+                    "{}",
+                    toml_modification_instructions(
+                        namespace,
+                        manifest.kv_namespaces.as_ref(),
+                        env,
+                        is_preview,
+                    )
+                ));
+            }
         }
-        Err(e) => print!("{}", kv::format_error(e)),
+        Err(e) => return Err(kv::api_error(e)),
     }
 
     Ok(())
 }
 
-fn validate_binding(binding: &str) -> Result<(), failure::Error> {
+pub(super) fn validate_binding(binding: &str) -> Result<(), failure::Error> {
     let re = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*$").unwrap();
     if !re.is_match(binding) {
         failure::bail!(
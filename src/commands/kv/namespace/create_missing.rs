@@ -0,0 +1,128 @@
+use std::path::Path;
+use std::thread;
+
+use super::create::validate_binding;
+use crate::commands::{kv, quota};
+use crate::deploy::DeployState;
+use crate::error::WranglerError;
+use crate::http;
+use crate::kv::namespace::create;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{ConfigKvNamespace, ConfigWriter, KvNamespace, Manifest};
+use crate::terminal::message::{Message, StdOut};
+
+/// Creates the KV namespaces that `env` declares without an id, binds them, and saves
+/// their ids to the configuration file. The namespaces are created concurrently.
+pub fn create_missing(
+    user: &GlobalUser,
+    manifest: &mut Manifest,
+    config_path: &Path,
+    env: Option<&str>,
+) -> Result<(), failure::Error> {
+    let missing: Vec<String> = match config_namespaces(manifest, env) {
+        Some(namespaces) => namespaces
+            .iter()
+            .filter(|ns| ns.id.is_none())
+            .map(|ns| ns.binding.clone())
+            .collect(),
+        None => Vec::new(),
+    };
+    if missing.is_empty() {
+        return Ok(());
+    }
+    for binding in &missing {
+        validate_binding(binding)?;
+    }
+
+    let account_id = manifest.get_account_id(env)?;
+    let worker_name = manifest.worker_name(env);
+    quota::check_kv_namespaces(user, &account_id, missing.len());
+    StdOut::working(&format!(
+        "Creating KV namespaces for {}",
+        missing.join(", ")
+    ));
+
+    let mut handles = Vec::new();
+    for binding in missing {
+        let client = http::cf_v4_client(user)?;
+        let account_id = account_id.clone();
+        let title = format!("{}-{}", worker_name, binding);
+        handles.push(thread::spawn(move || {
+            match create(&client, &account_id, &title) {
+                Ok(success) => Ok(KvNamespace {
+                    binding,
+                    id: success.result.id,
+                }),
+                Err(e) => Err(format!("{}: {}", binding, kv::format_error(e))),
+            }
+        }));
+    }
+
+    let mut created = Vec::new();
+    let mut errors = Vec::new();
+    for handle in handles {
+        match handle.join() {
+            Ok(Ok(namespace)) => created.push(namespace),
+            Ok(Err(e)) => errors.push(e),
+            Err(_) => errors.push("namespace creation panicked".to_string()),
+        }
+    }
+
+    let mut state = DeployState::load(env)?;
+    let mut config = ConfigWriter::open(config_path)?;
+    for namespace in &created {
+        StdOut::success(&format!(
+            "Created KV namespace {} with id {}",
+            namespace.binding, namespace.id
+        ));
+        state.add_kv_namespace(namespace, false);
+        if let Some(namespaces) = config_namespaces_mut(manifest, env) {
+            for ns in namespaces.iter_mut() {
+                if ns.binding == namespace.binding {
+                    ns.id = Some(namespace.id.clone());
+                }
+            }
+        }
+        if !config.set_kv_namespace(env, &namespace.binding, "id", &namespace.id) {
+            StdOut::warn(&format!(
+                "Could not find the {} binding in {}. Add `id = \"{}\"` to it yourself.",
+                namespace.binding,
+                config_path.display(),
+                namespace.id
+            ));
+        }
+    }
+    state.save(env)?;
+    config.save(config_path)?;
+
+    if !errors.is_empty() {
+        return Err(WranglerError::Api(format!(
+            "Could not create all KV namespaces:\n{}",
+            errors.join("\n")
+        ))
+        .into());
+    }
+    StdOut::info(&format!("Saved the new ids to {}", config_path.display()));
+    Ok(())
+}
+
+// environments don't inherit kv_namespaces, so only look at the ones for `env`
+fn config_namespaces<'a>(
+    manifest: &'a Manifest,
+    env: Option<&str>,
+) -> Option<&'a Vec<ConfigKvNamespace>> {
+    match env {
+        Some(env) => manifest.env.as_ref()?.get(env)?.kv_namespaces.as_ref(),
+        None => manifest.kv_namespaces.as_ref(),
+    }
+}
+
+fn config_namespaces_mut<'a>(
+    manifest: &'a mut Manifest,
+    env: Option<&str>,
+) -> Option<&'a mut Vec<ConfigKvNamespace>> {
+    match env {
+        Some(env) => manifest.env.as_mut()?.get_mut(env)?.kv_namespaces.as_mut(),
+        None => manifest.kv_namespaces.as_mut(),
+    }
+}
@@ -32,7 +32,7 @@ pub fn run(target: &Target, user: &GlobalUser, id: &str) -> Result<(), failure::
                 "Make sure to remove this \"kv-namespace\" entry from your configuration file!",
             )
         }
-        Err(e) => print!("{}", kv::format_error(e)),
+        Err(e) => return Err(kv::api_error(e)),
     }
 
     Ok(())
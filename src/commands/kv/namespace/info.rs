@@ -0,0 +1,76 @@
+use chrono::{DateTime, Local};
+
+use crate::commands::kv;
+use crate::deploy::DeployState;
+use crate::http;
+use crate::kv::key::KeyList;
+use crate::kv::namespace::list;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+
+// Counting keys means listing them, 1000 to a request, so large namespaces are only
+// counted this far.
+const MAX_COUNTED_KEYS: usize = 10_000;
+
+/// Prints the title of a namespace, roughly how many keys it holds, and what this
+/// project knows about it: the bindings it is bound to, and when wrangler created it.
+pub fn run(
+    target: &Target,
+    user: &GlobalUser,
+    env: Option<&str>,
+    id: &str,
+) -> Result<(), failure::Error> {
+    kv::validate_target(target)?;
+
+    let namespace = match list(user, target)?.into_iter().find(|ns| ns.id == id) {
+        Some(namespace) => namespace,
+        None => failure::bail!(
+            "There is no namespace {} in this account. Run `wrangler kv:namespace list` to see your existing namespaces with IDs",
+            id
+        ),
+    };
+
+    let client = http::cf_v4_client(user)?;
+    let mut keys = 0;
+    for key in KeyList::new(target, client, id, None)?.take(MAX_COUNTED_KEYS + 1) {
+        if let Err(e) = key {
+            return Err(kv::api_error(e));
+        }
+        keys += 1;
+    }
+    let keys = if keys > MAX_COUNTED_KEYS {
+        format!("more than {}", MAX_COUNTED_KEYS)
+    } else {
+        keys.to_string()
+    };
+
+    let bindings: Vec<&str> = target
+        .kv_namespaces
+        .iter()
+        .filter(|ns| ns.id == id)
+        .map(|ns| ns.binding.as_str())
+        .collect();
+    let state = DeployState::load(env)?;
+    let created = match state.kv_namespaces.iter().find(|ns| ns.id == id) {
+        Some(managed) => {
+            let mut created = format!("by wrangler, for the binding {}", managed.binding);
+            if let Some(created_at) = managed.created_at {
+                let created_at: DateTime<Local> = created_at.into();
+                created.push_str(&format!(" on {}", created_at.format("%Y-%m-%d %H:%M")));
+            }
+            created
+        }
+        None => "not recorded, it wasn't created by wrangler in this project".to_string(),
+    };
+
+    StdOut::message(&format!("id:       {}", namespace.id));
+    StdOut::message(&format!("title:    {}", namespace.title));
+    StdOut::message(&format!("keys:     {}", keys));
+    if !bindings.is_empty() {
+        StdOut::message(&format!("bound as: {}", bindings.join(", ")));
+    }
+    StdOut::message(&format!("created:  {}", created));
+
+    Ok(())
+}
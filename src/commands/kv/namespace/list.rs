@@ -1,19 +1,27 @@
 extern crate serde_json;
 
 use crate::commands::kv;
-use crate::http;
 use crate::kv::namespace::list;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::Table;
 
 pub fn run(target: &Target, user: &GlobalUser) -> Result<(), failure::Error> {
     kv::validate_target(target)?;
 
-    let client = http::cf_v4_client(user)?;
-    let result = list(&client, target);
+    let result = list(user, target);
     match result {
+        // scripts that read the list get json
+        Ok(namespaces) if !atty::is(atty::Stream::Stdout) => {
+            StdOut::message(&serde_json::to_string(&namespaces)?);
+        }
         Ok(namespaces) => {
-            println!("{}", serde_json::to_string(&namespaces)?);
+            let mut table = Table::new(&["Id", "Title"]);
+            for namespace in namespaces {
+                table.add_row(vec![namespace.id, namespace.title]);
+            }
+            table.print();
         }
         Err(e) => failure::bail!(e),
     }
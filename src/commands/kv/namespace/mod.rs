@@ -1,7 +1,13 @@
 mod create;
+mod create_missing;
 mod delete;
+mod info;
 mod list;
+mod rename;
 
 pub use create::run as create;
+pub use create_missing::create_missing;
 pub use delete::run as delete;
+pub use info::run as info;
 pub use list::run as list;
+pub use rename::run as rename;
@@ -0,0 +1,27 @@
+use crate::commands::kv;
+use crate::http;
+use crate::kv::namespace::rename;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+
+pub fn run(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    title: &str,
+) -> Result<(), failure::Error> {
+    kv::validate_target(target)?;
+    if title.trim().is_empty() {
+        failure::bail!("A namespace's title can't be empty")
+    }
+    let client = http::cf_v4_client(user)?;
+
+    StdOut::working(&format!("Renaming namespace {} to \"{}\"", id, title));
+    match rename(&client, &target.account_id, id, title) {
+        Ok(_) => StdOut::success("Success"),
+        Err(e) => return Err(kv::api_error(e)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::fs;
+
+use crate::support::run_log;
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+/// Prints the log of the last run, one JSON object per line, to look into a failure
+/// after the fact.
+pub fn show_last() -> Result<(), failure::Error> {
+    let path = match run_log::logs()?.pop() {
+        Some(path) => path,
+        None => {
+            StdOut::info("There are no logs yet. wrangler keeps one of each command it runs.");
+            return Ok(());
+        }
+    };
+    StdErr::info(&format!("Log of the last run, from {}", path.display()));
+    StdOut::message(fs::read_to_string(&path)?.trim_end());
+    Ok(())
+}
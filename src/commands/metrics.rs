@@ -0,0 +1,280 @@
+use chrono::{Duration, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::http::{self, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+use crate::terminal::table::Table;
+
+const QUERY: &str = r#"query WorkerMetrics($accountTag: string, $scriptName: string, $since: Time, $until: Time) {
+  viewer {
+    accounts(filter: { accountTag: $accountTag }) {
+      workersInvocationsAdaptive(
+        limit: 1
+        filter: { scriptName: $scriptName, datetime_geq: $since, datetime_leq: $until }
+      ) {
+        sum {
+          requests
+          errors
+          subrequests
+          duration
+        }
+        quantiles {
+          cpuTimeP50
+          cpuTimeP90
+          cpuTimeP99
+        }
+      }
+    }
+  }
+}"#;
+
+/// Request totals and CPU time percentiles for a script over a period of time.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Metrics {
+    pub since: String,
+    pub until: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub subrequests: u64,
+    /// Wall time multiplied by memory, in GB-seconds
+    pub duration: f64,
+    /// CPU time percentiles in microseconds
+    pub cpu_time_p50: f64,
+    pub cpu_time_p90: f64,
+    pub cpu_time_p99: f64,
+}
+
+/// Queries the GraphQL analytics API for the invocations of the target's script over
+/// the last `since` (e.g. "1h", "30m", "7d") and prints them as a table, or as JSON.
+pub fn metrics(
+    target: &Target,
+    user: &GlobalUser,
+    since: &str,
+    json: bool,
+) -> Result<(), failure::Error> {
+    let metrics = fetch(target, user, parse_period(since)?)?;
+    if json {
+        StdOut::message(&serde_json::to_string_pretty(&metrics)?);
+    } else {
+        print_table(&target.name, &metrics);
+    }
+    Ok(())
+}
+
+/// Fetches the metrics for the target's script over the last `period`.
+pub fn fetch(
+    target: &Target,
+    user: &GlobalUser,
+    period: Duration,
+) -> Result<Metrics, failure::Error> {
+    let until = Utc::now();
+    let since = until - period;
+
+    let client = http::legacy_auth_client(user);
+    let body = json!({
+        "query": QUERY,
+        "variables": {
+            "accountTag": target.account_id,
+            "scriptName": target.name,
+            "since": since.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "until": until.to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    });
+    let res = client
+        .post(&http::endpoints::graphql())
+        .json(&body)
+        .send_traced()?;
+    let status = res.status();
+    if !status.is_success() {
+        return Err(http::status_error(status, &res.text()?));
+    }
+    let response: GraphQLResponse = serde_json::from_str(&res.text()?)?;
+
+    let mut metrics = response.into_metrics(&target.name)?;
+    metrics.since = since.to_rfc3339_opts(SecondsFormat::Secs, true);
+    metrics.until = until.to_rfc3339_opts(SecondsFormat::Secs, true);
+    Ok(metrics)
+}
+
+fn print_table(script_name: &str, metrics: &Metrics) {
+    let error_rate = if metrics.requests > 0 {
+        format!(
+            " ({:.2}%)",
+            metrics.errors as f64 / metrics.requests as f64 * 100.0
+        )
+    } else {
+        String::new()
+    };
+
+    let mut table = Table::new(&["Metric", "Value"]);
+    let rows = vec![
+        ("Requests", metrics.requests.to_string()),
+        ("Errors", format!("{}{}", metrics.errors, error_rate)),
+        ("Subrequests", metrics.subrequests.to_string()),
+        ("Duration", format!("{:.2} GB-s", metrics.duration)),
+        ("CPU time p50", format_cpu_time(metrics.cpu_time_p50)),
+        ("CPU time p90", format_cpu_time(metrics.cpu_time_p90)),
+        ("CPU time p99", format_cpu_time(metrics.cpu_time_p99)),
+    ];
+    for (name, value) in rows {
+        table.add_row(vec![name.to_string(), value]);
+    }
+
+    StdOut::message(&format!(
+        "Metrics for {} from {} to {}",
+        styles::highlight(script_name),
+        metrics.since,
+        metrics.until
+    ));
+    table.print();
+}
+
+// the API reports CPU time in microseconds
+fn format_cpu_time(micros: f64) -> String {
+    format!("{:.2}ms", micros / 1000.0)
+}
+
+/// Parses periods like "90s", "30m", "1h" or "7d".
+pub fn parse_period(period: &str) -> Result<Duration, failure::Error> {
+    let invalid = || {
+        failure::format_err!(
+            "--since expects a number followed by s, m, h or d (e.g. 1h), not {:?}",
+            period
+        )
+    };
+    let unit = period.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = period[..period.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+    match unit {
+        's' => Ok(Duration::seconds(amount)),
+        'm' => Ok(Duration::minutes(amount)),
+        'h' => Ok(Duration::hours(amount)),
+        'd' => Ok(Duration::days(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+#[derive(Deserialize)]
+struct GraphQLResponse {
+    data: Option<GraphQLData>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct GraphQLData {
+    viewer: Viewer,
+}
+
+#[derive(Deserialize)]
+struct Viewer {
+    accounts: Vec<Account>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Account {
+    workers_invocations_adaptive: Vec<Invocations>,
+}
+
+#[derive(Deserialize)]
+struct Invocations {
+    sum: Sums,
+    quantiles: Quantiles,
+}
+
+#[derive(Deserialize)]
+struct Sums {
+    requests: u64,
+    errors: u64,
+    subrequests: u64,
+    duration: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Quantiles {
+    cpu_time_p50: f64,
+    cpu_time_p90: f64,
+    cpu_time_p99: f64,
+}
+
+impl GraphQLResponse {
+    fn into_metrics(self, script_name: &str) -> Result<Metrics, failure::Error> {
+        if let Some(errors) = self.errors.filter(|errors| !errors.is_empty()) {
+            let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+            failure::bail!(
+                "Could not fetch metrics for {}: {}",
+                script_name,
+                messages.join(", ")
+            )
+        }
+        let account = match self
+            .data
+            .and_then(|data| data.viewer.accounts.into_iter().next())
+        {
+            Some(account) => account,
+            None => failure::bail!("Your account has no analytics for {}", script_name),
+        };
+
+        // no invocations in the period means there is nothing to aggregate
+        let mut metrics = Metrics::default();
+        if let Some(invocations) = account.workers_invocations_adaptive.into_iter().next() {
+            metrics.requests = invocations.sum.requests;
+            metrics.errors = invocations.sum.errors;
+            metrics.subrequests = invocations.sum.subrequests;
+            metrics.duration = invocations.sum.duration;
+            metrics.cpu_time_p50 = invocations.quantiles.cpu_time_p50;
+            metrics.cpu_time_p90 = invocations.quantiles.cpu_time_p90;
+            metrics.cpu_time_p99 = invocations.quantiles.cpu_time_p99;
+        }
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_periods() {
+        assert_eq!(parse_period("90s").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_period("1h").unwrap(), Duration::hours(1));
+        assert_eq!(parse_period("7d").unwrap(), Duration::days(7));
+        assert!(parse_period("h").is_err());
+        assert!(parse_period("0m").is_err());
+        assert!(parse_period("1w").is_err());
+    }
+
+    #[test]
+    fn it_reads_graphql_responses() {
+        let response: GraphQLResponse = serde_json::from_str(
+            r#"{
+                "data": { "viewer": { "accounts": [{ "workersInvocationsAdaptive": [{
+                    "sum": { "requests": 200, "errors": 3, "subrequests": 40, "duration": 12.5 },
+                    "quantiles": { "cpuTimeP50": 1200.5, "cpuTimeP90": 3000, "cpuTimeP99": 9000 }
+                }] }] } },
+                "errors": null
+            }"#,
+        )
+        .unwrap();
+
+        let metrics = response.into_metrics("worker").unwrap();
+        assert_eq!(metrics.requests, 200);
+        assert_eq!(metrics.errors, 3);
+        assert_eq!(metrics.subrequests, 40);
+        assert_eq!(format_cpu_time(metrics.cpu_time_p50), "1.20ms");
+    }
+}
@@ -1,41 +1,69 @@
 use std::process::Command;
 
+pub mod account;
+pub mod alert;
+pub mod artifact;
 pub mod build;
 pub mod config;
+pub mod deployments;
+pub mod destroy;
 pub mod dev;
+pub mod diff;
+pub mod dispatch_namespace;
+pub mod email;
+pub mod env;
 pub mod generate;
 pub mod init;
 pub mod kv;
 pub mod login;
+pub mod logs;
+pub mod metrics;
+pub mod mtls_certificate;
+pub mod pages;
+pub mod plugin;
 mod preview;
+pub mod preview_branch;
 pub mod publish;
+pub mod quota;
 pub mod route;
 pub mod secret;
 pub mod subdomain;
 pub mod tail;
+pub mod telemetry;
+pub mod test;
+pub mod tools;
+pub mod types;
+pub mod usage;
 pub mod whoami;
 
 pub use self::config::global_config;
 pub use self::preview::run as preview;
 pub use build::build;
+pub use destroy::destroy;
 pub use dev::dev;
+pub use diff::diff;
 pub use generate::generate;
 pub use init::init;
+pub use metrics::metrics;
 pub use publish::publish;
 pub use secret::{create_secret, delete_secret, list_secrets};
 pub use subdomain::get_subdomain;
 pub use subdomain::set_subdomain;
+pub use test::test;
+pub use usage::usage;
 pub use whoami::whoami;
 
 use regex::Regex;
 
+use crate::interrupt;
+
 pub const DEFAULT_CONFIG_PATH: &str = "./wrangler.toml";
 
 // Run the given command and return its stdout.
 pub fn run(mut command: Command, command_name: &str) -> Result<(), failure::Error> {
     log::info!("Running {:?}", command);
 
-    let status = command.status()?;
+    let status = interrupt::status(&mut command)?;
 
     if !status.success() {
         failure::bail!(
@@ -47,13 +75,62 @@ pub fn run(mut command: Command, command_name: &str) -> Result<(), failure::Erro
     Ok(())
 }
 
+// The longest name the API accepts for a script or a dispatch namespace.
+const MAX_NAME_LENGTH: usize = 63;
+
 // Ensures that Worker name is valid.
 pub fn validate_worker_name(name: &str) -> Result<(), failure::Error> {
+    validate_name("Worker", name)
+}
+
+// Ensures that a dispatch namespace name is valid, as they follow the same rules.
+pub fn validate_namespace_name(name: &str) -> Result<(), failure::Error> {
+    validate_name("Dispatch namespace", name)
+}
+
+// Checks a name against the API's rules, so it fails before any request is made, with a
+// name that would pass when one can be derived.
+fn validate_name(kind: &str, name: &str) -> Result<(), failure::Error> {
     let re = Regex::new(r"^[a-z0-9_][a-z0-9-_]*$").unwrap();
-    if !re.is_match(&name) {
-        failure::bail!("Worker name \"{}\" invalid. Ensure that you only use lowercase letters, dashes, underscores, and numbers.", name)
+    let problem = if name.is_empty() {
+        "It can't be empty.".to_string()
+    } else if name.len() > MAX_NAME_LENGTH {
+        format!("It can be at most {} characters long.", MAX_NAME_LENGTH)
+    } else if !re.is_match(&name) {
+        "Ensure that you only use lowercase letters, dashes, underscores, and numbers.".to_string()
+    } else {
+        return Ok(());
+    };
+
+    match suggest_name(name) {
+        Some(suggestion) => failure::bail!(
+            "{} name \"{}\" invalid. {} Try \"{}\" instead.",
+            kind,
+            name,
+            problem,
+            suggestion
+        ),
+        None => failure::bail!("{} name \"{}\" invalid. {}", kind, name, problem),
+    }
+}
+
+// "My Site.v2" becomes "my-site-v2"
+fn suggest_name(name: &str) -> Option<String> {
+    let mut suggestion = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            suggestion.push(c.to_ascii_lowercase());
+        } else if !suggestion.is_empty() && !suggestion.ends_with('-') {
+            suggestion.push('-');
+        }
+    }
+    suggestion.truncate(MAX_NAME_LENGTH);
+    let suggestion = suggestion.trim_end_matches('-');
+    if suggestion.is_empty() {
+        None
+    } else {
+        Some(suggestion.to_string())
     }
-    Ok(())
 }
 
 #[cfg(test)]
@@ -75,4 +152,19 @@ mod tests {
             assert!(validate_worker_name(name).is_ok());
         }
     }
+
+    #[test]
+    fn it_suggests_a_valid_worker_name() {
+        let err = validate_worker_name("My Site.v2").unwrap_err();
+        assert!(err.to_string().ends_with("Try \"my-site-v2\" instead."));
+        assert_eq!(suggest_name("mySite"), Some("mysite".to_string()));
+        assert_eq!(suggest_name("..."), None);
+
+        let long_name = "a".repeat(MAX_NAME_LENGTH + 1);
+        assert!(validate_worker_name(&long_name)
+            .unwrap_err()
+            .to_string()
+            .contains("at most 63 characters"));
+        assert!(validate_worker_name(&long_name[1..]).is_ok());
+    }
 }
@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::commands::account;
+use crate::http::{self, read_result, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::Table;
+
+#[derive(Debug, Deserialize)]
+pub struct Certificate {
+    pub id: String,
+    pub name: Option<String>,
+    pub issuer: Option<String>,
+    pub expires_on: Option<String>,
+}
+
+fn certificates_addr(target: &Target) -> Result<String, failure::Error> {
+    account::check_account_id(target, "mTLS certificates")?;
+    Ok(http::endpoints::mtls_certificates(&target.account_id))
+}
+
+/// Uploads a client certificate and its private key (both PEM encoded) to the account,
+/// and prints the `[[mtls_certificates]]` entry that binds it to a worker.
+pub fn upload(
+    target: &Target,
+    user: &GlobalUser,
+    cert_path: &Path,
+    key_path: &Path,
+    name: Option<&str>,
+) -> Result<(), failure::Error> {
+    let addr = certificates_addr(target)?;
+    let certificates = read_pem(cert_path, "certificate")?;
+    let private_key = read_pem(key_path, "private key")?;
+
+    let mut body = json!({
+        "ca": false,
+        "certificates": certificates,
+        "private_key": private_key,
+    });
+    if let Some(name) = name {
+        body["name"] = json!(name);
+    }
+
+    StdOut::working("Uploading mTLS certificate");
+    let client = http::legacy_auth_client(user);
+    let res = client.post(&addr).json(&body).send_traced()?;
+    let certificate: Certificate = match read_result(res)? {
+        Some(certificate) => certificate,
+        None => failure::bail!("The API did not return the uploaded certificate"),
+    };
+
+    StdOut::success(&format!(
+        "Uploaded mTLS certificate with id {}",
+        certificate.id
+    ));
+    StdOut::info(&format!(
+        "Add the following to your configuration file to bind it to your worker:\n[[mtls_certificates]]\nbinding = \"MY_CERT\"\ncertificate_id = \"{}\"",
+        certificate.id
+    ));
+    Ok(())
+}
+
+/// Lists the mTLS certificates uploaded to the account.
+pub fn list(target: &Target, user: &GlobalUser) -> Result<(), failure::Error> {
+    let addr = certificates_addr(target)?;
+    let client = http::legacy_auth_client(user);
+    let res = client.get(&addr).send_traced()?;
+    let certificates: Vec<Certificate> = read_result(res)?.unwrap_or_default();
+
+    if certificates.is_empty() {
+        StdOut::info("There are no mTLS certificates in this account.");
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Id", "Name", "Issuer", "Expires"]);
+    for certificate in certificates {
+        table.add_row(vec![
+            certificate.id,
+            certificate.name.unwrap_or_else(|| "-".to_string()),
+            certificate.issuer.unwrap_or_else(|| "-".to_string()),
+            certificate.expires_on.unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// Deletes an mTLS certificate from the account after asking for confirmation.
+pub fn delete(target: &Target, user: &GlobalUser, id: &str) -> Result<(), failure::Error> {
+    let addr = format!("{}/{}", certificates_addr(target)?, id);
+
+    match interactive::confirm(&format!(
+        "Are you sure you want to delete mTLS certificate {}? Workers bound to it will fail to publish.",
+        id
+    )) {
+        Ok(true) => (),
+        Ok(false) => {
+            StdOut::info(&format!("Not deleting mTLS certificate {}", id));
+            return Ok(());
+        }
+        Err(e) => failure::bail!(e),
+    }
+
+    StdOut::working(&format!("Deleting mTLS certificate {}", id));
+    let client = http::legacy_auth_client(user);
+    let res = client.delete(&addr).send_traced()?;
+    read_result::<serde_json::Value>(res)?;
+
+    StdOut::success("Success");
+    if target
+        .mtls_certificates
+        .iter()
+        .any(|cert| cert.certificate_id == id)
+    {
+        StdOut::warn(
+            "Make sure to remove this \"mtls_certificates\" entry from your configuration file!",
+        )
+    }
+    Ok(())
+}
+
+fn read_pem(path: &Path, what: &str) -> Result<String, failure::Error> {
+    let pem = match fs::read_to_string(path) {
+        Ok(pem) => pem,
+        Err(e) => failure::bail!("Could not read the {} at {}: {}", what, path.display(), e),
+    };
+    if !pem.contains("-----BEGIN ") {
+        failure::bail!("{} is not a PEM encoded {}", path.display(), what)
+    }
+    Ok(pem)
+}
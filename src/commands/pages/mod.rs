@@ -0,0 +1,84 @@
+mod publish;
+
+pub use publish::publish;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::http::{self, read_result, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::Table;
+
+#[derive(Debug, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub subdomain: Option<String>,
+    #[serde(default)]
+    pub domains: Vec<String>,
+    pub production_branch: Option<String>,
+}
+
+/// Lists the Pages projects in the account.
+pub fn list_projects(user: &GlobalUser, account_id: &str) -> Result<(), failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let res = client
+        .get(&http::endpoints::pages_projects(account_id))
+        .send_traced()?;
+    let projects: Vec<Project> = read_result(res)?.unwrap_or_default();
+
+    if projects.is_empty() {
+        StdOut::info("There are no Pages projects in this account. Create one with `wrangler pages project create`.");
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Name", "Domains", "Production branch"]);
+    for project in projects {
+        let mut domains = project.domains.clone();
+        if let Some(subdomain) = &project.subdomain {
+            if !domains.contains(subdomain) {
+                domains.insert(0, subdomain.clone());
+            }
+        }
+        table.add_row(vec![
+            project.name,
+            domains.join(", "),
+            project.production_branch.unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// Creates a Pages project for direct uploads. Deployments to `production_branch` are
+/// served from the project's pages.dev subdomain, other branches get preview URLs.
+pub fn create_project(
+    user: &GlobalUser,
+    account_id: &str,
+    name: &str,
+    production_branch: &str,
+) -> Result<(), failure::Error> {
+    StdOut::working(&format!("Creating Pages project {}", name));
+    let client = http::legacy_auth_client(user);
+    let res = client
+        .post(&http::endpoints::pages_projects(account_id))
+        .json(&json!({ "name": name, "production_branch": production_branch }))
+        .send_traced()?;
+    let project: Project = match read_result(res)? {
+        Some(project) => project,
+        None => failure::bail!("The API did not return the new project"),
+    };
+
+    match &project.subdomain {
+        Some(subdomain) => StdOut::success(&format!(
+            "Created Pages project {}, which will be served from https://{}",
+            project.name, subdomain
+        )),
+        None => StdOut::success(&format!("Created Pages project {}", project.name)),
+    }
+    StdOut::info(&format!(
+        "Deploy to it with `wrangler pages publish <directory> --project-name {}`",
+        project.name
+    ));
+    Ok(())
+}
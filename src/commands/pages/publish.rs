@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use reqwest::blocking::multipart::Form;
+use reqwest::blocking::Client;
+use ring::digest::{digest, SHA256};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::read_result;
+use crate::http::{self, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+// Pages limits
+const MAX_ASSET_SIZE: u64 = 25 * 1024 * 1024;
+const MAX_ASSET_COUNT: usize = 20_000;
+// how much to send per upload request
+const MAX_BATCH_FILES: usize = 1000;
+const MAX_BATCH_BYTES: usize = 40 * 1024 * 1024;
+const IGNORED: &[&str] = &[".git", ".DS_Store", ".wrangler", "node_modules"];
+
+// A file to deploy, addressed by the hash of its contents.
+#[derive(Debug)]
+struct Asset {
+    url_path: String,
+    file: PathBuf,
+    hash: String,
+    content_type: String,
+}
+
+#[derive(Deserialize)]
+struct UploadToken {
+    jwt: String,
+}
+
+#[derive(Deserialize)]
+struct Deployment {
+    url: Option<String>,
+}
+
+/// Deploys the static assets in `directory` to the Pages project `project_name`. Only
+/// files that the project doesn't have yet are uploaded.
+pub fn publish(
+    user: &GlobalUser,
+    account_id: &str,
+    project_name: &str,
+    directory: &Path,
+    branch: Option<&str>,
+    commit_message: Option<&str>,
+) -> Result<(), failure::Error> {
+    if !directory.is_dir() {
+        failure::bail!("{} is not a directory", directory.display())
+    }
+    let assets = collect_assets(directory)?;
+    if assets.is_empty() {
+        failure::bail!("There are no files to deploy in {}", directory.display())
+    }
+
+    let client = http::legacy_auth_client(user);
+    let project_addr = format!(
+        "{}/{}",
+        http::endpoints::pages_projects(account_id),
+        project_name
+    );
+    let res = client
+        .get(&format!("{}/upload-token", project_addr))
+        .send_traced()?;
+    let jwt = match read_result::<UploadToken>(res)? {
+        Some(token) => token.jwt,
+        None => failure::bail!("Could not get an upload token for {}", project_name),
+    };
+    let assets_client = http::client();
+
+    let hashes: Vec<&str> = assets.iter().map(|asset| asset.hash.as_str()).collect();
+    let res = assets_client
+        .post(&http::endpoints::pages_assets("check-missing"))
+        .bearer_auth(&jwt)
+        .json(&json!({ "hashes": hashes }))
+        .send_traced()?;
+    let missing: Vec<String> = read_result(res)?.unwrap_or_default();
+
+    let to_upload: Vec<&Asset> = assets
+        .iter()
+        .filter(|asset| missing.contains(&asset.hash))
+        .collect();
+    StdOut::working(&format!(
+        "Uploading {} of {} files ({} already uploaded)",
+        to_upload.len(),
+        assets.len(),
+        assets.len() - to_upload.len()
+    ));
+    for batch in batches(&to_upload)? {
+        let mut payload = Vec::new();
+        for asset in batch {
+            payload.push(json!({
+                "key": asset.hash,
+                "value": base64::encode(fs::read(&asset.file)?),
+                "metadata": { "contentType": asset.content_type },
+                "base64": true,
+            }));
+        }
+        let res = assets_client
+            .post(&http::endpoints::pages_assets("upload"))
+            .bearer_auth(&jwt)
+            .json(&payload)
+            .send_traced()?;
+        read_result::<serde_json::Value>(res)?;
+    }
+
+    let res = assets_client
+        .post(&http::endpoints::pages_assets("upsert-hashes"))
+        .bearer_auth(&jwt)
+        .json(&json!({ "hashes": hashes }))
+        .send_traced()?;
+    read_result::<serde_json::Value>(res)?;
+
+    let url = create_deployment(&client, &project_addr, &assets, branch, commit_message)?;
+    match url {
+        Some(url) => StdOut::success(&format!(
+            "Deployed {} to {}",
+            project_name,
+            styles::url(&url)
+        )),
+        None => StdOut::success(&format!("Deployed {}", project_name)),
+    }
+    Ok(())
+}
+
+fn create_deployment(
+    client: &Client,
+    project_addr: &str,
+    assets: &[Asset],
+    branch: Option<&str>,
+    commit_message: Option<&str>,
+) -> Result<Option<String>, failure::Error> {
+    let manifest: BTreeMap<&str, &str> = assets
+        .iter()
+        .map(|asset| (asset.url_path.as_str(), asset.hash.as_str()))
+        .collect();
+
+    let mut form = Form::new().text("manifest", serde_json::to_string(&manifest)?);
+    if let Some(branch) = branch {
+        form = form.text("branch", branch.to_string());
+    }
+    if let Some(commit_message) = commit_message {
+        form = form.text("commit_message", commit_message.to_string());
+    }
+
+    StdOut::working("Creating deployment");
+    let res = client
+        .post(&format!("{}/deployments", project_addr))
+        .multipart(form)
+        .send_traced()?;
+    Ok(read_result::<Deployment>(res)?.and_then(|deployment| deployment.url))
+}
+
+fn collect_assets(directory: &Path) -> Result<Vec<Asset>, failure::Error> {
+    let walker = WalkBuilder::new(directory)
+        .standard_filters(false)
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !IGNORED.contains(&name.as_ref())
+        })
+        .build();
+
+    let mut assets = Vec::new();
+    for entry in walker {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let size = entry.metadata()?.len();
+        if size > MAX_ASSET_SIZE {
+            failure::bail!(
+                "{} is {} bytes, but Pages only serves files up to {} bytes",
+                path.display(),
+                size,
+                MAX_ASSET_SIZE
+            )
+        }
+
+        let contents = fs::read(path)?;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+        assets.push(Asset {
+            url_path: url_path(path.strip_prefix(directory)?),
+            file: path.to_path_buf(),
+            hash: hash_asset(&contents, &extension),
+            content_type: mime_guess::from_path(path)
+                .first_or_octet_stream()
+                .to_string(),
+        });
+    }
+
+    if assets.len() > MAX_ASSET_COUNT {
+        failure::bail!(
+            "{} has {} files, but a Pages deployment can have at most {}",
+            directory.display(),
+            assets.len(),
+            MAX_ASSET_COUNT
+        )
+    }
+    Ok(assets)
+}
+
+// "/blog/index.html" for blog/index.html, with forward slashes on every platform
+fn url_path(relative: &Path) -> String {
+    let mut url_path = String::new();
+    for component in relative.components() {
+        url_path.push('/');
+        url_path.push_str(&component.as_os_str().to_string_lossy());
+    }
+    url_path
+}
+
+// Assets are keyed by their contents and extension, so a file that was uploaded by an
+// earlier deployment isn't uploaded again.
+fn hash_asset(contents: &[u8], extension: &str) -> String {
+    let mut input = base64::encode(contents);
+    input.push_str(extension);
+    digest(&SHA256, input.as_bytes())
+        .as_ref()
+        .iter()
+        .take(16)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Splits the assets into upload requests that stay under the request size limits.
+fn batches<'a>(assets: &[&'a Asset]) -> Result<Vec<Vec<&'a Asset>>, failure::Error> {
+    let mut batches = Vec::new();
+    let mut batch = Vec::new();
+    let mut batch_bytes = 0;
+    for asset in assets {
+        // base64 grows the files by a third
+        let size = fs::metadata(&asset.file)?.len() as usize * 4 / 3;
+        if !batch.is_empty()
+            && (batch.len() == MAX_BATCH_FILES || batch_bytes + size > MAX_BATCH_BYTES)
+        {
+            batches.push(batch);
+            batch = Vec::new();
+            batch_bytes = 0;
+        }
+        batch.push(*asset);
+        batch_bytes += size;
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_url_paths() {
+        assert_eq!(
+            url_path(&Path::new("blog").join("index.html")),
+            "/blog/index.html"
+        );
+        assert_eq!(url_path(Path::new("404.html")), "/404.html");
+    }
+
+    #[test]
+    fn it_hashes_contents_and_extension() {
+        let hash = hash_asset(b"<h1>hi</h1>", "html");
+        assert_eq!(hash.len(), 32);
+        assert_eq!(hash, hash_asset(b"<h1>hi</h1>", "html"));
+        assert_ne!(hash, hash_asset(b"<h1>hi</h1>", "txt"));
+        assert_ne!(hash, hash_asset(b"<h1>bye</h1>", "html"));
+    }
+}
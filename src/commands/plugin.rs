@@ -0,0 +1,170 @@
+//! Plugins are executables named `wrangler-<name>` on the PATH, run by `wrangler <name>`
+//! when there is no built in command of that name. They get every argument after the
+//! name, and wrangler hands them what it knows through the environment:
+//!
+//! - `CF_API_TOKEN`, or `CF_EMAIL` and `CF_API_KEY`: the credentials wrangler would use,
+//!   when it has any.
+//! - `WRANGLER_API_BASE_URL`: the base URL of the Cloudflare API.
+//! - `WRANGLER_PLUGIN_CONTEXT`: a JSON object with
+//!   - `version`: the version of this handoff, 1, which changes when fields are removed
+//!     or change meaning
+//!   - `wrangler_version`
+//!   - `config_path`: the configuration file, read from `--config`/`-c` among the
+//!     arguments or `./wrangler.toml`, or null when there isn't one
+//!   - `environment`: `--env`/`-e` among the arguments, or null
+//!   - `target`: `name`, `account_id`, `type`, `kv_namespaces` (`binding` and `id`),
+//!     `vars` and `routes` (`pattern` and `zone_id`) as the environment resolves them,
+//!     or null when there is no configuration file. When the file can't be resolved,
+//!     `target_error` says why instead.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+use crate::commands::DEFAULT_CONFIG_PATH;
+use crate::deploy::DeployTarget;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Manifest;
+
+const CONTEXT_VERSION: u32 = 1;
+
+/// The `wrangler-<name>` executable on the PATH, if there is one.
+pub fn find(name: &str) -> Option<PathBuf> {
+    // a name like "../x" would find an executable outside the PATH
+    if name.is_empty() || name.contains(|c| c == '/' || c == '\\') {
+        return None;
+    }
+    which::which(format!("wrangler-{}", name)).ok()
+}
+
+/// Runs the plugin at `path` with `args`, and returns its exit code.
+pub fn run(path: &Path, args: &[String]) -> Result<i32, failure::Error> {
+    let config_path = flag_value(args, "--config", "-c")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+    let environment = flag_value(args, "--env", "-e");
+
+    let mut command = Command::new(path);
+    command.args(args).env(
+        "WRANGLER_PLUGIN_CONTEXT",
+        context(&config_path, environment.as_deref()).to_string(),
+    );
+    command.env("WRANGLER_API_BASE_URL", http::api_base_url());
+    match GlobalUser::new() {
+        Ok(GlobalUser::TokenAuth { api_token }) => {
+            command.env("CF_API_TOKEN", api_token);
+        }
+        Ok(GlobalUser::GlobalKeyAuth { email, api_key }) => {
+            command.env("CF_EMAIL", email).env("CF_API_KEY", api_key);
+        }
+        // the plugin may not need the API at all
+        Err(e) => log::info!("No credentials to hand to the plugin: {}", e),
+    }
+
+    log::info!("Running plugin {:?}", command);
+    let status = command.status().map_err(|e| {
+        failure::format_err!("The plugin {} could not be run: {}", path.display(), e)
+    })?;
+    // a plugin killed by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
+
+fn context(config_path: &Path, environment: Option<&str>) -> Value {
+    let mut context = json!({
+        "version": CONTEXT_VERSION,
+        "wrangler_version": env!("CARGO_PKG_VERSION"),
+        "config_path": null,
+        "environment": environment,
+        "target": null,
+    });
+    if !config_path.is_file() {
+        return context;
+    }
+
+    context["config_path"] = json!(env::current_dir()
+        .map(|dir| dir.join(config_path))
+        .unwrap_or_else(|_| config_path.to_path_buf()));
+    match target(config_path, environment) {
+        Ok(target) => context["target"] = target,
+        Err(e) => context["target_error"] = json!(e.to_string()),
+    }
+    context
+}
+
+fn target(config_path: &Path, environment: Option<&str>) -> Result<Value, failure::Error> {
+    let manifest = Manifest::new(config_path)?;
+    let target = manifest.get_target(environment, false)?;
+    let routes: Vec<Value> = manifest
+        .get_deployments(environment)?
+        .iter()
+        .filter_map(|deployment| match deployment {
+            DeployTarget::Zoned(zoned) => Some(zoned),
+            _ => None,
+        })
+        .flat_map(|zoned| {
+            zoned
+                .routes
+                .iter()
+                .map(move |route| json!({ "pattern": route.pattern, "zone_id": zoned.zone_id }))
+        })
+        .collect();
+
+    Ok(json!({
+        "name": target.name,
+        "account_id": target.account_id,
+        "type": target.target_type.to_string(),
+        "kv_namespaces": target
+            .kv_namespaces
+            .iter()
+            .map(|ns| json!({ "binding": ns.binding, "id": ns.id }))
+            .collect::<Vec<_>>(),
+        "vars": target.vars,
+        "routes": routes,
+    }))
+}
+
+// the value of `--long value`, `--long=value` or `-s value` among a plugin's arguments
+fn flag_value(args: &[String], long: &str, short: &str) -> Option<String> {
+    let prefix = format!("{}=", long);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == long || arg == short {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(prefix.as_str()) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_config_and_env_from_plugin_arguments() {
+        let args: Vec<String> = vec!["deploy", "--env=staging", "-c", "other.toml", "--force"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            flag_value(&args, "--env", "-e"),
+            Some("staging".to_string())
+        );
+        assert_eq!(
+            flag_value(&args, "--config", "-c"),
+            Some("other.toml".to_string())
+        );
+        assert_eq!(flag_value(&args[..1], "--env", "-e"), None);
+
+        assert_eq!(find("../evil"), None);
+        let context = context(Path::new("does-not-exist.toml"), Some("staging"));
+        assert_eq!(context["version"], CONTEXT_VERSION);
+        assert_eq!(context["environment"], "staging");
+        assert!(context["target"].is_null());
+    }
+}
@@ -0,0 +1,170 @@
+use std::time::{Duration, SystemTime};
+
+use crate::commands::destroy::{self, DestroyOpt};
+use crate::commands::publish;
+use crate::commands::subdomain::Subdomain;
+use crate::commands::validate_worker_name;
+use crate::deploy::state::{self, PreviewDeployment};
+use crate::deploy::{DeployTarget, ZonelessTarget};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
+
+// Script names are limited to 63 characters
+const MAX_SCRIPT_NAME_LENGTH: usize = 63;
+const ONE_DAY: u64 = 60 * 60 * 24;
+
+/// Publishes the target to workers.dev under a script name derived from `branch`,
+/// and records it so `wrangler preview cleanup` can delete it later.
+pub fn publish(
+    user: &GlobalUser,
+    target: &mut Target,
+    branch: &str,
+    force: bool,
+    out: Output,
+) -> Result<(), failure::Error> {
+    let script_name = branch_script_name(&target.name, branch)?;
+    target.name = script_name.clone();
+
+    // preview deployments only ever go to workers.dev, never to routes or schedules
+    let deployments = vec![DeployTarget::Zoneless(ZonelessTarget {
+        account_id: target.account_id.clone(),
+        script_name: script_name.clone(),
+    })];
+
+    let state_name = state::preview_state_name(&script_name);
+    publish::publish(
+        user,
+        target,
+        deployments,
+        Some(state_name.as_str()),
+        force,
+        out,
+    )?;
+
+    let url = match Subdomain::get(&target.account_id, user)? {
+        Some(subdomain) => format!("https://{}.{}.workers.dev", script_name, subdomain),
+        None => failure::bail!("Could not find your workers.dev subdomain"),
+    };
+    StdErr::success(&format!("Preview of branch {} is live at {}", branch, url));
+
+    let mut deployments = state::load_preview_deployments()?;
+    deployments.retain(|d| d.script_name != script_name);
+    deployments.push(PreviewDeployment {
+        branch: branch.to_string(),
+        script_name,
+        account_id: target.account_id.clone(),
+        url,
+        published_at: SystemTime::now(),
+    });
+    state::save_preview_deployments(&deployments)
+}
+
+/// Deletes preview deployments that haven't been published in `older_than_days` days,
+/// or all of them if `older_than_days` is `None`.
+pub fn cleanup(
+    user: &GlobalUser,
+    target: &Target,
+    older_than_days: Option<u64>,
+    dry_run: bool,
+) -> Result<(), failure::Error> {
+    let deployments = state::load_preview_deployments()?;
+    let cutoff =
+        older_than_days.map(|days| SystemTime::now() - Duration::from_secs(days * ONE_DAY));
+
+    let (stale, fresh): (Vec<PreviewDeployment>, Vec<PreviewDeployment>) =
+        deployments.into_iter().partition(|d| match cutoff {
+            Some(cutoff) => d.published_at < cutoff,
+            None => true,
+        });
+
+    if stale.is_empty() {
+        StdOut::info("No stale preview deployments to clean up.");
+        return Ok(());
+    }
+
+    let mut remaining = fresh;
+    for deployment in stale {
+        let mut branch_target = target.clone();
+        branch_target.name = deployment.script_name.clone();
+        branch_target.account_id = deployment.account_id.clone();
+
+        StdOut::working(&format!(
+            "Cleaning up the preview of branch {} ({})",
+            deployment.branch, deployment.url
+        ));
+
+        let opt = DestroyOpt {
+            kv_namespaces: true,
+            dry_run,
+            yes: true,
+            ..Default::default()
+        };
+        let state_name = deployment.state_name();
+        match destroy::destroy(&branch_target, user, Some(state_name.as_str()), opt) {
+            Ok(_) if !dry_run => (),
+            Ok(_) => remaining.push(deployment),
+            Err(e) => {
+                StdOut::warn(&format!(
+                    "Could not clean up {}: {}",
+                    deployment.script_name, e
+                ));
+                remaining.push(deployment);
+            }
+        }
+    }
+
+    state::save_preview_deployments(&remaining)
+}
+
+// Derives a script name like `my-worker-feat-x` from a worker name and a branch like `feat/x`.
+pub fn branch_script_name(worker_name: &str, branch: &str) -> Result<String, failure::Error> {
+    let mut slug = String::new();
+    for c in branch.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+
+    if slug.is_empty() {
+        failure::bail!("Branch name \"{}\" can't be used in a script name", branch)
+    }
+
+    let mut name = format!("{}-{}", worker_name, slug);
+    name.truncate(MAX_SCRIPT_NAME_LENGTH);
+    let name = name.trim_end_matches('-').to_string();
+
+    validate_worker_name(&name)?;
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_derives_script_names_from_branches() {
+        assert_eq!(
+            branch_script_name("my-worker", "feat/X").unwrap(),
+            "my-worker-feat-x"
+        );
+        assert_eq!(
+            branch_script_name("my-worker", "fix//some_thing--else/").unwrap(),
+            "my-worker-fix-some-thing-else"
+        );
+    }
+
+    #[test]
+    fn it_truncates_long_script_names() {
+        let name = branch_script_name("my-worker", &"a".repeat(100)).unwrap();
+        assert_eq!(name.len(), MAX_SCRIPT_NAME_LENGTH);
+    }
+
+    #[test]
+    fn it_rejects_empty_branch_names() {
+        assert!(branch_script_name("my-worker", "///").is_err());
+    }
+}
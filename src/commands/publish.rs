@@ -1,19 +1,30 @@
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
 
-use indicatif::{ProgressBar, ProgressStyle};
+use lazy_static::lazy_static;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
 use crate::build::build_target;
-use crate::deploy::{self, DeploymentSet};
-use crate::http::{self, Feature};
+use crate::commands::{deployments, quota};
+use crate::deploy::{self, DeployState, DeploymentSet, Fingerprint, PublishLock};
+use crate::error::WranglerError;
+use crate::http::{self, Feature, Traced};
+use crate::install::checksum::sha256_hex;
+use crate::interrupt;
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::Target;
+use crate::settings::toml::{Target, TargetType};
 use crate::sites;
-use crate::terminal::emoji;
-use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::message::{self, Message, Output, StdErr, StdOut};
+use crate::terminal::progress::Unit;
+use crate::terminal::table::Table;
+use crate::terminal::{emoji, github};
 use crate::upload;
+use crate::wranglerjs::output::Diagnostic;
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct PublishOutput {
@@ -21,18 +32,210 @@ pub struct PublishOutput {
     pub name: String,
     pub urls: Vec<String>,
     pub schedules: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub build_warnings: Vec<Diagnostic>,
+    /// the environment, when several were published at once
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub environment: Option<String>,
+    /// why the environment could not be published
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+    /// nothing had changed since the last publish, so nothing was uploaded
+    #[serde(skip)]
+    pub unchanged: bool,
+}
+
+/// An environment to publish with `wrangler publish --env a --env b`, resolved from the
+/// configuration file.
+pub struct Environment {
+    pub name: String,
+    pub target: Target,
+    pub deployments: DeploymentSet,
+}
+
+lazy_static! {
+    // Environments published together build into the same directory, so one has to keep
+    // what its build produced before the next one rebuilds it.
+    static ref BUILD_LOCK: Mutex<()> = Mutex::new(());
 }
 
 pub fn publish(
     user: &GlobalUser,
     target: &mut Target,
     deployments: DeploymentSet,
+    env: Option<&str>,
+    force: bool,
+    out: Output,
+) -> Result<(), failure::Error> {
+    let output = publish_target(user, target, deployments, env, force, false)?;
+    if github::enabled() {
+        if let Err(e) = set_step_outputs(user, target, &output) {
+            StdErr::warn(&format!("Could not set the outputs of the step: {}", e));
+        }
+    }
+    if out == Output::Json {
+        StdOut::as_json(&output);
+    }
+    Ok(())
+}
+
+// In GitHub Actions, the URL the worker was published to and the version now running are
+// outputs of the step, for later steps to test or tag without reading the log.
+fn set_step_outputs(
+    user: &GlobalUser,
+    target: &Target,
+    output: &PublishOutput,
+) -> Result<(), failure::Error> {
+    if let Some(url) = output.urls.first() {
+        github::set_output("url", url)?;
+    }
+    if let Some(version) = deployments::latest_version(target, user)? {
+        github::set_output("version-id", &version)?;
+    }
+    Ok(())
+}
+
+/// Publishes several environments at once, each from its own thread, and prints a table
+/// of how each one went. Fails if any of them failed.
+pub fn publish_environments(
+    user: &GlobalUser,
+    environments: Vec<Environment>,
+    force: bool,
     out: Output,
 ) -> Result<(), failure::Error> {
+    let names: Vec<String> = environments.iter().map(|env| env.name.clone()).collect();
+    let workers: Vec<String> = environments
+        .iter()
+        .map(|env| env.target.name.clone())
+        .collect();
+    StdErr::working(&format!("Publishing {}", names.join(", ")));
+
+    let handles: Vec<_> = environments
+        .into_iter()
+        .map(|env| {
+            let user = user.clone();
+            thread::spawn(move || {
+                let Environment {
+                    name,
+                    mut target,
+                    deployments,
+                } = env;
+                publish_target(&user, &mut target, deployments, Some(&name), force, true)
+            })
+        })
+        .collect();
+    let results: Vec<Result<PublishOutput, failure::Error>> = handles
+        .into_iter()
+        .map(|handle| match handle.join() {
+            Ok(result) => result,
+            Err(_) => Err(failure::format_err!("the publish thread panicked")),
+        })
+        .collect();
+
+    let mut table = Table::new(&["Environment", "Worker", "Result", "URLs"]);
+    let mut outputs = Vec::new();
+    let mut failed = Vec::new();
+    for ((name, worker), result) in names.iter().zip(workers).zip(results) {
+        let mut output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                failed.push(name.as_str());
+                PublishOutput {
+                    name: worker,
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+        output.environment = Some(name.clone());
+        let status = match &output.error {
+            Some(error) => format!("failed: {}", error),
+            None if output.unchanged => "unchanged".to_string(),
+            None => "published".to_string(),
+        };
+        table.add_row(vec![
+            name.clone(),
+            output.name.clone(),
+            status,
+            output.urls.join(", "),
+        ]);
+        outputs.push(output);
+    }
+    if out == Output::Json {
+        StdOut::as_json(&outputs);
+    } else {
+        table.print();
+    }
+
+    if !failed.is_empty() {
+        failure::bail!("Could not publish {}", failed.join(", "))
+    }
+    Ok(())
+}
+
+fn publish_target(
+    user: &GlobalUser,
+    target: &mut Target,
+    deployments: DeploymentSet,
+    env: Option<&str>,
+    force: bool,
+    concurrent: bool,
+) -> Result<PublishOutput, failure::Error> {
     validate_target_required_fields_present(target)?;
+    http::require_scopes(user, &required_scopes(target, &deployments))?;
+    validate_tail_consumers(user, target)?;
+    for deployment in &deployments {
+        if let deploy::DeployTarget::Schedule(schedule) = deployment {
+            quota::check_cron_triggers(&schedule.script_name, &schedule.crons);
+        }
+    }
+    let _interrupt = interrupt::operation(format!("publishing {}", target.name));
+    let _lock = PublishLock::acquire(user, target, env)?;
 
-    let deploy = |target: &Target| match deploy::worker(&user, &deployments) {
-        Ok(deploy::DeployResults { urls, schedules }) => {
+    let mut state = DeployState::load(env)?;
+
+    let build_lock = match target.target_type {
+        TargetType::JavaScript => None,
+        _ if concurrent && target.artifact.is_none() => {
+            Some(BUILD_LOCK.lock().unwrap_or_else(|e| e.into_inner()))
+        }
+        _ => None,
+    };
+
+    // Build the script before uploading and log build result
+    let build_warnings = match build_target(&target) {
+        Ok(output) => {
+            StdErr::success(&output.message);
+            let attach = target.build.sbom.as_ref().map_or(false, |sbom| sbom.attach);
+            if let (true, Some(sbom)) = (attach, &output.sbom) {
+                let digest = sha256_hex(&fs::read(sbom)?);
+                target.annotations.sbom = Some(format!("sha256:{}", digest));
+            }
+            output.warnings
+        }
+        Err(e) => return Err(e),
+    };
+    // the script is uploaded from a copy of the build, so the next environment can build
+    // while this one uploads
+    let _built = match build_lock {
+        Some(build_lock) => {
+            let dir = tempfile::tempdir()?;
+            upload::form::write_artifact(target, dir.path(), None)?;
+            target.artifact = Some(dir.path().to_path_buf());
+            drop(build_lock);
+            Some(dir)
+        }
+        None => None,
+    };
+    // warnings about the configuration or from the build stop a strict publish before
+    // anything is uploaded
+    message::check_strict()?;
+
+    let deploy = || match deploy::worker(&user, &deployments) {
+        Ok(results) => {
+            let deploy::DeployResults {
+                urls, schedules, ..
+            } = &results;
             let result_msg = match (urls.as_slice(), schedules.as_slice()) {
                 ([], []) => "Successfully published your script".to_owned(),
                 ([], schedules) => format!(
@@ -50,29 +253,11 @@ pub fn publish(
                 ),
             };
             StdErr::success(&result_msg);
-            if out == Output::Json {
-                StdOut::as_json(&PublishOutput {
-                    success: true,
-                    name: target.name.clone(),
-                    urls,
-                    schedules,
-                });
-            }
-            Ok(())
+            Ok(results)
         }
         Err(e) => Err(e),
     };
-
-    // Build the script before uploading and log build result
-    let build_result = build_target(&target);
-    match build_result {
-        Ok(msg) => {
-            StdErr::success(&msg);
-            Ok(())
-        }
-        Err(e) => Err(e),
-    }?;
-    if let Some(site_config) = &target.site {
+    let results = if let Some(site_config) = &target.site {
         let path = &site_config.bucket.clone();
         validate_bucket_location(path)?;
 
@@ -81,16 +266,14 @@ pub fn publish(
         let (to_upload, to_delete, asset_manifest) =
             sites::sync(target, user, &site_namespace.id, &path)?;
 
+        let fingerprint = Fingerprint::new(target, Some(asset_manifest.clone()), &deployments)?;
+        if !force && fingerprint.is_published(target, &state) {
+            return Ok(skip_unchanged(target, build_warnings));
+        }
+
         // First, upload all existing files in bucket directory
         StdErr::working("Uploading site files");
-        let upload_progress_bar = if to_upload.len() > bulk::BATCH_KEY_MAX {
-            let upload_progress_bar = ProgressBar::new(to_upload.len() as u64);
-            upload_progress_bar
-                .set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-            Some(upload_progress_bar)
-        } else {
-            None
-        };
+        let upload_progress_bar = bulk::progress_bar(&to_upload);
 
         bulk::put(
             target,
@@ -101,7 +284,7 @@ pub fn publish(
         )?;
 
         if let Some(pb) = upload_progress_bar {
-            pb.finish_with_message("Done Uploading");
+            pb.finish("Done Uploading");
         }
 
         let upload_client = http::featured_legacy_auth_client(user, Feature::Sites);
@@ -109,18 +292,22 @@ pub fn publish(
         // Next, upload and deploy the worker with the updated asset_manifest
         upload::script(&upload_client, &target, Some(asset_manifest))?;
 
-        deploy(target)?;
+        let results = deploy()?;
+        state.record_deploy(target, &deployments, &results);
+        state.add_kv_namespace(&site_namespace, false);
+        state.fingerprint = Some(fingerprint.to_string());
+        state.save(env)?;
 
         // Finally, remove any stale files
         if !to_delete.is_empty() {
             StdErr::info("Deleting stale files...");
 
             let delete_progress_bar = if to_delete.len() > bulk::BATCH_KEY_MAX {
-                let delete_progress_bar = ProgressBar::new(to_delete.len() as u64);
-                delete_progress_bar.set_style(
-                    ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"),
-                );
-                Some(delete_progress_bar)
+                Some(StdErr::progress(
+                    "Deleting",
+                    to_delete.len() as u64,
+                    Unit::Items("files"),
+                ))
             } else {
                 None
             };
@@ -134,17 +321,48 @@ pub fn publish(
             )?;
 
             if let Some(pb) = delete_progress_bar {
-                pb.finish_with_message("Done deleting");
+                pb.finish("Done deleting");
             }
         }
+        results
     } else {
+        let fingerprint = Fingerprint::new(target, None, &deployments)?;
+        if !force && fingerprint.is_published(target, &state) {
+            return Ok(skip_unchanged(target, build_warnings));
+        }
+
         let upload_client = http::legacy_auth_client(user);
 
         upload::script(&upload_client, &target, None)?;
-        deploy(target)?;
-    }
+        let results = deploy()?;
+        state.record_deploy(target, &deployments, &results);
+        state.fingerprint = Some(fingerprint.to_string());
+        state.save(env)?;
+        results
+    };
 
-    Ok(())
+    Ok(PublishOutput {
+        success: true,
+        name: target.name.clone(),
+        urls: results.urls,
+        schedules: results.schedules,
+        build_warnings,
+        ..Default::default()
+    })
+}
+
+fn skip_unchanged(target: &Target, build_warnings: Vec<Diagnostic>) -> PublishOutput {
+    StdErr::info(&format!(
+        "No changes to {} since it was last published, skipping upload. Pass --force to publish anyway.",
+        target.name
+    ));
+    PublishOutput {
+        success: true,
+        name: target.name.clone(),
+        build_warnings,
+        unchanged: true,
+        ..Default::default()
+    }
 }
 
 // We don't want folks setting their bucket to the top level directory,
@@ -176,6 +394,65 @@ pub fn validate_bucket_location(bucket: &PathBuf) -> Result<(), failure::Error>
     Ok(())
 }
 
+// Tail consumers that don't exist would make the upload fail with an unhelpful error,
+// and a worker consuming its own events would loop.
+fn validate_tail_consumers(user: &GlobalUser, target: &Target) -> Result<(), failure::Error> {
+    if target.tail_consumers.is_empty() {
+        return Ok(());
+    }
+    let client = http::legacy_auth_client(user);
+    for consumer in &target.tail_consumers {
+        let script_name = consumer.script_name();
+        if script_name == target.name {
+            failure::bail!(
+                "{} {} can't be a tail consumer of itself",
+                emoji::WARN,
+                script_name
+            )
+        }
+
+        let res = client
+            .get(&http::endpoints::script(&target.account_id, script_name))
+            .send_traced()?;
+        match res.status() {
+            status if status.is_success() => (),
+            StatusCode::NOT_FOUND => {
+                return Err(WranglerError::Config(format!(
+                    "{} The tail consumer {} does not exist. Publish it before publishing {}.",
+                    emoji::WARN,
+                    script_name,
+                    target.name
+                ))
+                .into())
+            }
+            status => {
+                return Err(WranglerError::Api(format!(
+                    "Could not check the tail consumer {}. Status: {}, Details {}",
+                    script_name,
+                    status,
+                    res.text()?
+                ))
+                .into())
+            }
+        }
+    }
+    Ok(())
+}
+
+fn required_scopes(target: &Target, deployments: &DeploymentSet) -> Vec<http::Scope> {
+    let mut scopes = vec![http::Scope::WorkersScriptsWrite];
+    if deployments
+        .iter()
+        .any(|d| matches!(d, deploy::DeployTarget::Zoned(_)))
+    {
+        scopes.push(http::Scope::WorkersRoutesWrite);
+    }
+    if target.site.is_some() {
+        scopes.push(http::Scope::WorkersKvStorageWrite);
+    }
+    scopes
+}
+
 fn validate_target_required_fields_present(target: &Target) -> Result<(), failure::Error> {
     let mut missing_fields = Vec::new();
 
@@ -203,13 +480,14 @@ fn validate_target_required_fields_present(target: &Target) -> Result<(), failur
     };
 
     if !missing_fields.is_empty() {
-        failure::bail!(
+        return Err(WranglerError::Config(format!(
             "{} Your configuration file is missing the {} {:?} which {} required to publish your worker!",
             emoji::WARN,
             field_pluralization,
             missing_fields,
             is_are,
-        );
+        ))
+        .into());
     };
 
     Ok(())
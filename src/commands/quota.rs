@@ -0,0 +1,72 @@
+use crate::kv;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+// Published limits, which are the same on the Free and Paid plans. Accounts can have them
+// raised, so going over them is only a warning.
+const KV_NAMESPACES_PER_ACCOUNT: usize = 100;
+const CRON_TRIGGERS_PER_SCRIPT: usize = 3;
+
+/// Warns when creating `new` KV namespaces would take the account over its limit, as the
+/// API would only reject them partway through.
+pub fn check_kv_namespaces(user: &GlobalUser, account_id: &str, new: usize) {
+    let target = Target {
+        account_id: account_id.to_string(),
+        ..Default::default()
+    };
+    // the check is best effort, the creation itself will report what went wrong
+    let current = match kv::namespace::list(user, &target) {
+        Ok(namespaces) => namespaces.len(),
+        Err(e) => {
+            log::info!("Could not count the account's KV namespaces: {}", e);
+            return;
+        }
+    };
+    if let Some(warning) = over_quota("KV namespaces", current, new, KV_NAMESPACES_PER_ACCOUNT) {
+        StdErr::warn(&warning);
+    }
+}
+
+/// Warns when a script would have more cron triggers than the API allows, before any of
+/// the publish has happened.
+pub fn check_cron_triggers(script_name: &str, crons: &[String]) {
+    let what = format!("cron triggers on {}", script_name);
+    if let Some(warning) = over_quota(&what, 0, crons.len(), CRON_TRIGGERS_PER_SCRIPT) {
+        StdErr::warn(&warning);
+    }
+}
+
+fn over_quota(what: &str, current: usize, new: usize, limit: usize) -> Option<String> {
+    if current + new <= limit {
+        return None;
+    }
+    let mut warning = format!(
+        "This would make {} {}, over the limit of {}",
+        current + new,
+        what,
+        limit
+    );
+    if current > 0 {
+        warning.push_str(&format!(" (there are {} already)", current));
+    }
+    warning.push_str(". Unless your account's limit has been raised, the API will reject it.");
+    Some(warning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_warns_past_the_limit() {
+        assert_eq!(over_quota("KV namespaces", 98, 2, 100), None);
+        assert_eq!(
+            over_quota("KV namespaces", 99, 2, 100).unwrap(),
+            "This would make 101 KV namespaces, over the limit of 100 (there are 99 already). Unless your account's limit has been raised, the API will reject it."
+        );
+        assert!(over_quota("cron triggers on worker", 0, 4, 3)
+            .unwrap()
+            .starts_with("This would make 4 cron triggers on worker, over the limit of 3."));
+    }
+}
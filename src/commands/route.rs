@@ -1,26 +1,29 @@
 extern crate serde_json;
 
-use cloudflare::endpoints::workers::{DeleteRoute, ListRoutes};
-use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::endpoints::workers::DeleteRoute;
 
-use crate::http;
+use crate::deploy;
+use crate::http::{self, TracedClient};
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::message::{Message, StdOut};
-pub fn list(zone_identifier: String, user: &GlobalUser) -> Result<(), failure::Error> {
-    let client = http::cf_v4_client(user)?;
-
-    let result = client.request(&ListRoutes {
-        zone_identifier: &zone_identifier,
-    });
+use crate::terminal::table::Table;
 
-    match result {
-        Ok(success) => {
-            let routes = success.result;
-            println!("{}", serde_json::to_string(&routes)?);
-        }
-
-        Err(e) => failure::bail!("{}", http::format_error(e, None)),
+pub fn list(zone_identifier: String, user: &GlobalUser) -> Result<(), failure::Error> {
+    let routes = deploy::list_routes(user, &zone_identifier)?;
+    // scripts that read the list get json
+    if !atty::is(atty::Stream::Stdout) {
+        StdOut::message(&serde_json::to_string(&routes)?);
+        return Ok(());
+    }
+    let mut table = Table::new(&["Id", "Pattern", "Script"]);
+    for route in routes {
+        table.add_row(vec![
+            route.id,
+            route.pattern,
+            route.script.unwrap_or_default(),
+        ]);
     }
+    table.print();
     Ok(())
 }
 
@@ -31,18 +34,19 @@ pub fn delete(
 ) -> Result<(), failure::Error> {
     let client = http::cf_v4_client(user)?;
 
-    let result = client.request(&DeleteRoute {
+    let result = client.request_traced(&DeleteRoute {
         zone_identifier: &zone_identifier,
         identifier: route_id,
     });
 
     match result {
         Ok(success) => {
+            http::cache::invalidate(&http::endpoints::routes(&zone_identifier));
             let msg = format!("Successfully deleted route with id {}", success.result.id);
             StdOut::success(&msg);
         }
 
-        Err(e) => failure::bail!("{}", http::format_error(e, Some(&error_suggestions))),
+        Err(e) => return Err(http::api_error(e, Some(&error_suggestions))),
     }
     Ok(())
 }
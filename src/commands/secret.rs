@@ -1,16 +1,20 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use cloudflare::endpoints::workers::{CreateSecret, CreateSecretParams, DeleteSecret, ListSecrets};
-use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
-use crate::http;
+use crate::commands::diff::{diff_maps, live_secrets};
+use crate::http::{self, TracedClient};
+use crate::settings::env_file;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdOut};
-use crate::terminal::{emoji, interactive};
+use crate::terminal::{emoji, interactive, styles};
 use crate::upload;
 
-fn format_error(e: ApiFailure) -> String {
-    http::format_error(e, Some(&secret_errors))
+fn api_error(e: ApiFailure) -> failure::Error {
+    http::api_error(e, Some(&secret_errors))
 }
 
 fn validate_target(target: &Target) -> Result<(), failure::Error> {
@@ -93,7 +97,7 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
         secret_type: "secret_text".to_string(),
     };
 
-    let response = client.request(&CreateSecret {
+    let response = client.request_traced(&CreateSecret {
         account_identifier: &target.account_id,
         script_name: &target.name,
         params: params.clone(),
@@ -102,10 +106,10 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
     match response {
         Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
         Err(e) => match upload_draft_worker(&e, user, target) {
-            None => failure::bail!(format_error(e)),
+            None => return Err(api_error(e)),
             Some(draft_upload_response) => match draft_upload_response {
                 Ok(_) => {
-                    let retry_response = client.request(&CreateSecret {
+                    let retry_response = client.request_traced(&CreateSecret {
                         account_identifier: &target.account_id,
                         script_name: &target.name,
                         params,
@@ -113,7 +117,7 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
 
                     match retry_response {
                         Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
-                        Err(e) => failure::bail!(format_error(e)),
+                        Err(e) => return Err(api_error(e)),
                     }
                 }
                 Err(e) => failure::bail!(e),
@@ -146,7 +150,7 @@ pub fn delete_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
 
     let client = http::cf_v4_client(user)?;
 
-    let response = client.request(&DeleteSecret {
+    let response = client.request_traced(&DeleteSecret {
         account_identifier: &target.account_id,
         script_name: &target.name,
         secret_name: &name,
@@ -154,17 +158,68 @@ pub fn delete_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
 
     match response {
         Ok(_) => StdOut::success(&format!("Success! Deleted secret {}.", name)),
-        Err(e) => failure::bail!(format_error(e)),
+        Err(e) => return Err(api_error(e)),
     }
 
     Ok(())
 }
 
+/// Compares the names of the secrets set on each target's script with the variables in a
+/// .env file. Secret values can't be read back, so only names are compared. Fails when
+/// any script is missing a secret or has one the file doesn't.
+pub fn diff_secrets(
+    user: &GlobalUser,
+    targets: &[Target],
+    env_file: &Path,
+) -> Result<(), failure::Error> {
+    let local: BTreeMap<String, String> = env_file::load(env_file)?
+        .into_iter()
+        .map(|(name, _)| (name, String::new()))
+        .collect();
+
+    let mut differing = Vec::new();
+    for target in targets {
+        validate_target(target)?;
+        let remote: BTreeMap<String, String> = live_secrets(user, target)?
+            .into_iter()
+            .map(|name| (name, String::new()))
+            .collect();
+        let changes = diff_maps(&local, &remote);
+        if changes.is_empty() {
+            StdOut::success(&format!(
+                "{} has the secrets in {}",
+                target.name,
+                env_file.display()
+            ));
+            continue;
+        }
+
+        differing.push(target.name.as_str());
+        StdOut::message(&format!(
+            "{} (+ missing from the script, - not in {})",
+            styles::highlight(&target.name),
+            env_file.display()
+        ));
+        for change in changes {
+            StdOut::message(&format!("  {}", change));
+        }
+    }
+
+    if !differing.is_empty() {
+        failure::bail!(
+            "The secrets of {} don't match {}",
+            differing.join(", "),
+            env_file.display()
+        )
+    }
+    Ok(())
+}
+
 pub fn list_secrets(user: &GlobalUser, target: &Target) -> Result<(), failure::Error> {
     validate_target(target)?;
     let client = http::cf_v4_client(user)?;
 
-    let response = client.request(&ListSecrets {
+    let response = client.request_traced(&ListSecrets {
         account_identifier: &target.account_id,
         script_name: &target.name,
     });
@@ -172,9 +227,9 @@ pub fn list_secrets(user: &GlobalUser, target: &Target) -> Result<(), failure::E
     match response {
         Ok(success) => {
             let secrets = success.result;
-            println!("{}", serde_json::to_string(&secrets)?);
+            StdOut::message(&serde_json::to_string(&secrets)?);
         }
-        Err(e) => failure::bail!(format_error(e)),
+        Err(e) => return Err(api_error(e)),
     }
 
     Ok(())
@@ -1,4 +1,5 @@
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, Traced};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdOut};
@@ -13,26 +14,17 @@ pub struct Subdomain {
 
 impl Subdomain {
     pub fn get(account_id: &str, user: &GlobalUser) -> Result<Option<String>, failure::Error> {
-        let addr = subdomain_addr(account_id);
+        let addr = http::endpoints::subdomain(account_id);
 
         let client = http::legacy_auth_client(user);
 
-        let response = client.get(&addr).send()?;
-
-        if !response.status().is_success() {
-            failure::bail!(
-                "{} There was an error fetching your subdomain.\n Status Code: {}\n Msg: {}",
-                emoji::WARN,
-                response.status(),
-                response.text()?,
-            )
-        }
-        let response: SubdomainResponse = serde_json::from_str(&response.text()?)?;
+        let response = http::cache::get(&client, &addr)?.text("fetching your subdomain")?;
+        let response: SubdomainResponse = serde_json::from_str(&response)?;
         Ok(response.result.map(|r| r.subdomain))
     }
 
     pub fn put(name: &str, account_id: &str, user: &GlobalUser) -> Result<(), failure::Error> {
-        let addr = subdomain_addr(account_id);
+        let addr = http::endpoints::subdomain(account_id);
         let subdomain = Subdomain {
             subdomain: name.to_string(),
         };
@@ -44,7 +36,7 @@ impl Subdomain {
             .put(&addr)
             .header("allow-rename", "1")
             .body(subdomain_request)
-            .send()?;
+            .send_traced()?;
 
         let response_status = response.status();
         if !response_status.is_success() {
@@ -66,8 +58,9 @@ impl Subdomain {
                     response_text
                 )
             };
-            failure::bail!(msg)
+            return Err(WranglerError::Api(msg).into());
         }
+        http::cache::invalidate(&addr);
         StdOut::success(&format!("Success! You've registered {}.", name));
         Ok(())
     }
@@ -100,13 +93,6 @@ struct Error {
     code: i64,
 }
 
-fn subdomain_addr(account_id: &str) -> String {
-    format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/subdomain",
-        account_id
-    )
-}
-
 fn register_subdomain(
     name: &str,
     user: &GlobalUser,
@@ -187,22 +173,23 @@ fn get_subdomain_scripts(
     account_id: &str,
     user: &GlobalUser,
 ) -> Result<Vec<String>, failure::Error> {
-    let addr = scripts_addr(account_id);
+    let addr = http::endpoints::scripts(account_id);
 
     let client = http::legacy_auth_client(user);
 
     let response = client
         .get(&addr)
         .query(&[("include_subdomain_availability", "1")])
-        .send()?;
+        .send_traced()?;
 
     if !response.status().is_success() {
-        failure::bail!(
+        return Err(WranglerError::Api(format!(
             "{} There was an error fetching scripts.\n Status Code: {}\n Msg: {}",
             emoji::WARN,
             response.status(),
             response.text()?,
-        )
+        ))
+        .into());
     }
     let response: ScriptResponse = serde_json::from_str(&response.text()?)?;
     let mut scripts: Vec<String> = Vec::new();
@@ -213,10 +200,3 @@ fn get_subdomain_scripts(
     }
     Ok(scripts)
 }
-
-fn scripts_addr(account_id: &str) -> String {
-    format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
-        account_id
-    )
-}
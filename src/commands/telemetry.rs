@@ -0,0 +1,53 @@
+use crate::telemetry::{self, Event, TELEMETRY_URL};
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+pub fn enable() -> Result<(), failure::Error> {
+    telemetry::set_enabled(true)?;
+    StdOut::success("Telemetry enabled, thank you! After each command wrangler sends its name, how long it took, whether it succeeded and the wrangler version, and nothing else.");
+    StdOut::info(&format!(
+        "Run {} to see what has been sent, or {} to stop.",
+        styles::highlight("`wrangler telemetry show`"),
+        styles::highlight("`wrangler telemetry disable`")
+    ));
+    Ok(())
+}
+
+pub fn disable() -> Result<(), failure::Error> {
+    telemetry::set_enabled(false)?;
+    StdOut::success("Telemetry disabled. wrangler won't send anything about the commands you run.");
+    Ok(())
+}
+
+/// Prints whether telemetry is enabled, where events go and the events sent most
+/// recently, exactly as they were sent.
+pub fn show() -> Result<(), failure::Error> {
+    if !telemetry::enabled() {
+        StdOut::info(&format!(
+            "Telemetry is disabled, so nothing is sent. Run {} to opt in.",
+            styles::highlight("`wrangler telemetry enable`")
+        ));
+        StdOut::info("Once enabled, wrangler sends one event like this after each command:");
+        let example = Event {
+            command: "publish".to_string(),
+            duration_ms: 5320,
+            success: true,
+            wrangler_version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        StdOut::message(&serde_json::to_string_pretty(&example)?);
+        return Ok(());
+    }
+
+    StdOut::info(&format!(
+        "Telemetry is enabled. Events are sent to {}",
+        styles::url(TELEMETRY_URL)
+    ));
+    let events = telemetry::sent_events();
+    if events.is_empty() {
+        StdOut::info("No events have been sent yet.");
+    }
+    for event in events {
+        StdOut::message(&serde_json::to_string(&event)?);
+    }
+    Ok(())
+}
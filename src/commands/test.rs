@@ -0,0 +1,80 @@
+use std::net::TcpStream;
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::dev::{self, Protocol, ServerConfig};
+use crate::deploy::DeploymentSet;
+use crate::interrupt;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+
+// building and uploading the worker happens before the server listens
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Serves the worker like `wrangler dev`, with its vars and KV preview namespaces bound,
+/// then runs `command` against it. The command finds the server in `WRANGLER_DEV_URL`,
+/// and the tests pass when it exits successfully.
+pub fn test(
+    target: Target,
+    deployments: DeploymentSet,
+    user: Option<GlobalUser>,
+    server_config: ServerConfig,
+    command: &[&str],
+    verbose: bool,
+) -> Result<(), failure::Error> {
+    let address = server_config.listening_address;
+
+    let (error_tx, error_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = dev::dev(
+            target,
+            deployments,
+            user,
+            server_config,
+            Protocol::Http,
+            Protocol::Https,
+            verbose,
+        );
+        if let Err(e) = res {
+            error_tx.send(e).ok();
+        }
+    });
+
+    StdOut::working("Starting the worker");
+    let started = Instant::now();
+    while TcpStream::connect(address).is_err() {
+        if let Ok(e) = error_rx.try_recv() {
+            failure::bail!("Could not start the worker: {}", e)
+        }
+        if started.elapsed() > STARTUP_TIMEOUT {
+            failure::bail!(
+                "The worker did not start listening on {} within {} seconds",
+                address,
+                STARTUP_TIMEOUT.as_secs()
+            )
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    let url = format!("http://{}", address);
+    StdOut::working(&format!("Running `{}` against {}", command.join(" "), url));
+    let mut test_command = Command::new(command[0]);
+    test_command
+        .args(&command[1..])
+        .env("WRANGLER_DEV_URL", &url);
+    let status = interrupt::status(&mut test_command)?;
+
+    if !status.success() {
+        failure::bail!(
+            "Tests failed: `{}` exited with {}",
+            command.join(" "),
+            status
+        )
+    }
+    StdOut::success("Tests passed");
+    Ok(())
+}
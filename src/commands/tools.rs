@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+use number_prefix::NumberPrefix;
+use semver::Version;
+
+use crate::install;
+use crate::settings::toml::{Target, TargetType};
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::Table;
+use crate::wranglerjs;
+
+// A tool version installed in the cache, in a directory named `<tool>-<version>`
+struct InstalledTool {
+    name: String,
+    version: Version,
+    path: PathBuf,
+    size: u64,
+    last_used: SystemTime,
+}
+
+/// Downloads every tool `target` needs to build into `dir`, at the versions pinned in
+/// `[tools]`. Point `WRANGLER_TOOLS_DIR` at it to build without network access.
+pub fn vendor(target: &Target, dir: &Path) -> Result<(), failure::Error> {
+    match target.target_type {
+        TargetType::JavaScript => {
+            StdOut::info("JavaScript projects don't need any tools to build.");
+            return Ok(());
+        }
+        TargetType::Rust => {
+            StdOut::working("Downloading wasm-pack");
+            let version = target.tools.wasm_pack_version()?;
+            install::vendor("wasm-pack", "rustwasm", true, version, dir)?;
+        }
+        TargetType::Webpack => {
+            StdOut::working("Downloading wranglerjs");
+            let version = target.tools.wranglerjs_version()?;
+            let download = install::vendor(
+                wranglerjs::TOOL_NAME,
+                wranglerjs::TOOL_AUTHOR,
+                false,
+                version,
+                dir,
+            )?;
+            // wranglerjs needs its node_modules, which npm would otherwise fetch on first build
+            wranglerjs::run_npm_install(&download.path())?;
+        }
+    }
+
+    StdOut::success(&format!(
+        "Tools downloaded to {}. Set WRANGLER_TOOLS_DIR={} to build with them offline.",
+        dir.display(),
+        dir.display()
+    ));
+    Ok(())
+}
+
+/// Lists the tools in the cache with their sizes and when they were last used, and
+/// removes every version but the newest of each tool (or everything, with `all`).
+pub fn clean(all: bool, dry_run: bool) -> Result<(), failure::Error> {
+    let cache_dir = install::cache_dir();
+    let mut installed = installed_tools(&cache_dir)?;
+    if installed.is_empty() {
+        StdOut::info(&format!(
+            "No tools are installed in {}",
+            cache_dir.display()
+        ));
+        return Ok(());
+    }
+    installed.sort_by(|a, b| a.name.cmp(&b.name).then(b.version.cmp(&a.version)));
+
+    let mut newest: HashMap<&str, &Version> = HashMap::new();
+    for tool in &installed {
+        newest.entry(&tool.name).or_insert(&tool.version);
+    }
+    let to_remove: Vec<&InstalledTool> = installed
+        .iter()
+        .filter(|tool| all || newest.get(tool.name.as_str()) != Some(&&tool.version))
+        .collect();
+
+    let mut table = Table::new(&["Tool", "Version", "Size", "Last used", "Action"]);
+    for tool in &installed {
+        let last_used: DateTime<Local> = tool.last_used.into();
+        let action = if to_remove.iter().any(|t| t.path == tool.path) {
+            "remove"
+        } else {
+            "keep"
+        };
+        table.add_row(vec![
+            tool.name.clone(),
+            tool.version.to_string(),
+            human_size(tool.size),
+            last_used.format("%Y-%m-%d").to_string(),
+            action.to_string(),
+        ]);
+    }
+    StdOut::message(&cache_dir.display().to_string());
+    table.print();
+
+    if to_remove.is_empty() {
+        StdOut::info("Nothing to clean up.");
+        return Ok(());
+    }
+    let freed: u64 = to_remove.iter().map(|tool| tool.size).sum();
+    if dry_run {
+        StdOut::info(&format!(
+            "Dry run, nothing was removed. Cleaning up would free {}.",
+            human_size(freed)
+        ));
+        return Ok(());
+    }
+    if !interactive::confirm(&format!(
+        "Remove {} tool version(s), freeing {}?",
+        to_remove.len(),
+        human_size(freed)
+    ))? {
+        return Ok(());
+    }
+
+    for tool in to_remove {
+        fs::remove_dir_all(&tool.path)?;
+    }
+    StdOut::success(&format!("Freed {}", human_size(freed)));
+    Ok(())
+}
+
+fn installed_tools(cache_dir: &Path) -> Result<Vec<InstalledTool>, failure::Error> {
+    let mut installed = Vec::new();
+    if !cache_dir.exists() {
+        return Ok(installed);
+    }
+
+    for entry in fs::read_dir(cache_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_dir() {
+            continue;
+        }
+        if let Some((name, version)) = split_tool_dir(&file_name) {
+            let metadata = entry.metadata()?;
+            // access times aren't recorded on every file system, so fall back to modification
+            let last_used = match (metadata.accessed(), metadata.modified()) {
+                (Ok(accessed), Ok(modified)) => accessed.max(modified),
+                (_, Ok(modified)) => modified,
+                _ => SystemTime::UNIX_EPOCH,
+            };
+            installed.push(InstalledTool {
+                name: name.to_string(),
+                version,
+                size: dir_size(&path)?,
+                path,
+                last_used,
+            });
+        }
+    }
+
+    Ok(installed)
+}
+
+// Splits `wasm-pack-0.9.1` into `wasm-pack` and `0.9.1`; tool names can contain dashes too.
+fn split_tool_dir(dir_name: &str) -> Option<(&str, Version)> {
+    dir_name
+        .match_indices('-')
+        .find_map(|(i, _)| match Version::parse(&dir_name[i + 1..]) {
+            Ok(version) => Some((&dir_name[..i], version)),
+            Err(_) => None,
+        })
+}
+
+fn dir_size(path: &Path) -> Result<u64, failure::Error> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+fn human_size(bytes: u64) -> String {
+    match NumberPrefix::binary(bytes as f64) {
+        NumberPrefix::Standalone(bytes) => format!("{} bytes", bytes),
+        NumberPrefix::Prefixed(prefix, n) => format!("{:.1} {}B", n, prefix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_tool_directories() {
+        assert_eq!(
+            split_tool_dir("wasm-pack-0.9.1"),
+            Some(("wasm-pack", Version::parse("0.9.1").unwrap()))
+        );
+        assert_eq!(
+            split_tool_dir("wranglerjs-1.13.0-rc.1"),
+            Some(("wranglerjs", Version::parse("1.13.0-rc.1").unwrap()))
+        );
+        assert_eq!(split_tool_dir("downloads"), None);
+    }
+}
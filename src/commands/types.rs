@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::settings::toml::{ModuleType, Target, TargetType};
+use crate::terminal::message::{Message, StdOut};
+use crate::upload::form::module_file;
+
+pub const DEFAULT_TYPES_PATH: &str = "worker-configuration.d.ts";
+pub const DEFAULT_RUST_BINDINGS_PATH: &str = "src/bindings.rs";
+
+const HEADER: &str =
+    "// Generated by `wrangler types` from wrangler.toml. Do not edit by hand.\n\n";
+
+// what a binding is exposed to the worker as
+#[derive(Debug, PartialEq)]
+enum Binding<'a> {
+    KvNamespace,
+    Fetcher,
+    SendEmail,
+    Var(&'a serde_json::Value),
+    Text,
+    Data,
+    WasmModule,
+}
+
+/// Writes declarations for the target's bindings so the compiler catches a misspelled or
+/// missing binding. Rust targets get a module of typed constants, to `output` or
+/// src/bindings.rs. Other targets get TypeScript declarations, to `output` or
+/// worker-configuration.d.ts: an `Env` interface, and the globals the bindings are
+/// exposed as to service workers.
+pub fn types(target: &Target, output: Option<&Path>) -> Result<(), failure::Error> {
+    let rule_files: Vec<(String, ModuleType)> = module_file::from_target(target)?
+        .iter()
+        .map(|file| (file.name(), file.module_type()))
+        .collect();
+    let bindings = bindings(target, &rule_files);
+    let rust = target.target_type == TargetType::Rust;
+    let (contents, default_path) = if rust {
+        (rust_module(&bindings)?, DEFAULT_RUST_BINDINGS_PATH)
+    } else {
+        (declarations(&bindings), DEFAULT_TYPES_PATH)
+    };
+    let output = output.unwrap_or_else(|| Path::new(default_path));
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, contents)?;
+    StdOut::success(&format!(
+        "Wrote the types of {} bindings to {}",
+        bindings.len(),
+        output.display()
+    ));
+    if rust {
+        StdOut::info("Add `mod bindings;` to src/lib.rs to use them");
+    }
+    Ok(())
+}
+
+// `rule_files` are the names and types of the files the target's [[rules]] attach
+fn bindings<'a>(
+    target: &'a Target,
+    rule_files: &'a [(String, ModuleType)],
+) -> BTreeMap<&'a str, Binding<'a>> {
+    let mut bindings = BTreeMap::new();
+
+    for kv in &target.kv_namespaces {
+        bindings.insert(kv.binding.as_str(), Binding::KvNamespace);
+    }
+    if target.site.is_some() {
+        bindings.insert("__STATIC_CONTENT", Binding::KvNamespace);
+        bindings.insert("__STATIC_CONTENT_MANIFEST", Binding::Text);
+    }
+    for certificate in &target.mtls_certificates {
+        bindings.insert(certificate.binding.as_str(), Binding::Fetcher);
+    }
+    if let Some(email) = &target.email {
+        for send_email in &email.send_email {
+            bindings.insert(send_email.name.as_str(), Binding::SendEmail);
+        }
+    }
+    if let Some(vars) = &target.vars {
+        for (name, value) in vars {
+            bindings.insert(name.as_str(), Binding::Var(value));
+        }
+    }
+    if let Some(text_blobs) = &target.text_blobs {
+        for name in text_blobs.keys() {
+            bindings.insert(name.as_str(), Binding::Text);
+        }
+    }
+    for (name, module_type) in rule_files {
+        let binding = match module_type {
+            ModuleType::Text => Binding::Text,
+            ModuleType::Data => Binding::Data,
+            ModuleType::CompiledWasm => Binding::WasmModule,
+        };
+        bindings.insert(name.as_str(), binding);
+    }
+    if target.target_type == TargetType::Rust {
+        bindings.insert("wasm", Binding::WasmModule);
+    }
+
+    bindings
+}
+
+// the TypeScript type, from @cloudflare/workers-types
+fn ts_type(binding: &Binding) -> String {
+    match binding {
+        Binding::KvNamespace => "KVNamespace".to_string(),
+        Binding::Fetcher => "Fetcher".to_string(),
+        Binding::SendEmail => "SendEmail".to_string(),
+        Binding::Var(value) => json_type(value),
+        Binding::Text => "string".to_string(),
+        Binding::Data => "ArrayBuffer".to_string(),
+        Binding::WasmModule => "WebAssembly.Module".to_string(),
+    }
+}
+
+// the newtype the binding's name is wrapped in, so it can't be passed where another kind
+// of binding is expected
+fn rust_type(binding: &Binding) -> &'static str {
+    match binding {
+        Binding::KvNamespace => "KvNamespace",
+        Binding::Fetcher => "Fetcher",
+        Binding::SendEmail => "SendEmail",
+        Binding::Var(serde_json::Value::String(_)) | Binding::Text => "Var",
+        Binding::Var(_) => "JsonVar",
+        Binding::Data => "DataBlob",
+        Binding::WasmModule => "WasmModule",
+    }
+}
+
+// The type of a JSON var, e.g. `{ retries: number; hosts: string[] }`
+fn json_type(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "null".to_string(),
+        serde_json::Value::Bool(_) => "boolean".to_string(),
+        serde_json::Value::Number(_) => "number".to_string(),
+        serde_json::Value::String(_) => "string".to_string(),
+        serde_json::Value::Array(items) => match items.first() {
+            Some(item) => format!("{}[]", json_type(item)),
+            None => "unknown[]".to_string(),
+        },
+        serde_json::Value::Object(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", property_name(name), json_type(value)))
+                .collect();
+            format!("{{ {} }}", fields.join("; "))
+        }
+    }
+}
+
+fn property_name(name: &str) -> String {
+    let is_identifier = name.chars().enumerate().all(|(i, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+    });
+    if is_identifier && !name.is_empty() {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+fn declarations(bindings: &BTreeMap<&str, Binding>) -> String {
+    let mut out = String::from(HEADER);
+
+    out.push_str("interface Env {\n");
+    for (name, binding) in bindings {
+        out.push_str(&format!(
+            "  {}: {};\n",
+            property_name(name),
+            ts_type(binding)
+        ));
+    }
+    out.push_str("}\n");
+
+    // service workers get their bindings as globals
+    if !bindings.is_empty() {
+        out.push('\n');
+    }
+    for (name, binding) in bindings {
+        out.push_str(&format!("declare const {}: {};\n", name, ts_type(binding)));
+    }
+    out
+}
+
+// A constant for each binding, e.g. `pub const CACHE: KvNamespace = KvNamespace("CACHE");`
+// for `env.kv(CACHE.name())`, and the newtypes they use.
+fn rust_module(bindings: &BTreeMap<&str, Binding>) -> Result<String, failure::Error> {
+    let mut out = String::from(HEADER);
+
+    let mut types: Vec<&str> = bindings.values().map(rust_type).collect();
+    types.sort();
+    types.dedup();
+    for name in types {
+        out.push_str(&format!(
+            r#"#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct {name}(&'static str);
+
+impl {name} {{
+    pub const fn name(&self) -> &'static str {{
+        self.0
+    }}
+}}
+
+impl AsRef<str> for {name} {{
+    fn as_ref(&self) -> &str {{
+        self.0
+    }}
+}}
+
+"#,
+            name = name
+        ));
+    }
+
+    let mut constants: BTreeMap<String, (&str, &str)> = BTreeMap::new();
+    for (name, binding) in bindings {
+        let constant = constant_name(name);
+        if let Some((other, _)) = constants.insert(constant.clone(), (name, rust_type(binding))) {
+            failure::bail!(
+                "The bindings {} and {} would both be the Rust constant {}. Rename one of them.",
+                other,
+                name,
+                constant
+            )
+        }
+    }
+    for (constant, (name, binding_type)) in constants {
+        out.push_str(&format!(
+            "pub const {}: {} = {}({:?});\n",
+            constant, binding_type, binding_type, name
+        ));
+    }
+    Ok(out)
+}
+
+// "myKv" and "my-kv" both become MY_KV
+fn constant_name(name: &str) -> String {
+    let mut constant = String::new();
+    let mut previous_lowercase = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && previous_lowercase {
+                constant.push('_');
+            }
+            previous_lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+            constant.push(c.to_ascii_uppercase());
+        } else {
+            constant.push('_');
+            previous_lowercase = false;
+        }
+    }
+    if constant.is_empty() || constant.starts_with(|c: char| c.is_ascii_digit()) {
+        constant.insert(0, '_');
+    }
+    constant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::settings::toml::KvNamespace;
+
+    #[test]
+    fn it_declares_bindings() {
+        let mut vars = HashMap::new();
+        vars.insert(
+            "API_URL".to_string(),
+            serde_json::json!("https://example.com"),
+        );
+        vars.insert(
+            "LIMITS".to_string(),
+            serde_json::json!({ "retries": 3, "hosts": ["a.example.com"], "x-debug": false }),
+        );
+        let target = Target {
+            kv_namespaces: vec![KvNamespace {
+                id: "0f2ac74b498b48028cb68387c421e279".to_string(),
+                binding: "CACHE".to_string(),
+            }],
+            vars: Some(vars),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            declarations(&bindings(&target, &[])),
+            r#"// Generated by `wrangler types` from wrangler.toml. Do not edit by hand.
+
+interface Env {
+  API_URL: string;
+  CACHE: KVNamespace;
+  LIMITS: { hosts: string[]; retries: number; "x-debug": boolean };
+}
+
+declare const API_URL: string;
+declare const CACHE: KVNamespace;
+declare const LIMITS: { hosts: string[]; retries: number; "x-debug": boolean };
+"#
+        );
+
+        let rule_files = vec![
+            ("_404_html".to_string(), ModuleType::Text),
+            ("logo_png".to_string(), ModuleType::Data),
+        ];
+        let declared = declarations(&bindings(&Target::default(), &rule_files));
+        assert!(declared.contains("declare const _404_html: string;"));
+        assert!(declared.contains("declare const logo_png: ArrayBuffer;"));
+    }
+
+    #[test]
+    fn it_generates_rust_constants() {
+        let mut vars = HashMap::new();
+        vars.insert(
+            "apiUrl".to_string(),
+            serde_json::json!("https://example.com"),
+        );
+        let target = Target {
+            target_type: TargetType::Rust,
+            kv_namespaces: vec![KvNamespace {
+                id: "0f2ac74b498b48028cb68387c421e279".to_string(),
+                binding: "CACHE".to_string(),
+            }],
+            vars: Some(vars),
+            ..Default::default()
+        };
+
+        let module = rust_module(&bindings(&target, &[])).unwrap();
+        assert!(module.contains("pub struct KvNamespace(&'static str);"));
+        assert!(module.contains("pub struct Var(&'static str);"));
+        assert!(module.contains("pub struct WasmModule(&'static str);"));
+        assert!(!module.contains("pub struct Fetcher"));
+        assert!(module.ends_with(
+            r#"pub const API_URL: Var = Var("apiUrl");
+pub const CACHE: KvNamespace = KvNamespace("CACHE");
+pub const WASM: WasmModule = WasmModule("wasm");
+"#
+        ));
+
+        let mut vars = HashMap::new();
+        vars.insert("my-var".to_string(), serde_json::json!("a"));
+        vars.insert("MY_VAR".to_string(), serde_json::json!("b"));
+        let target = Target {
+            vars: Some(vars),
+            ..Default::default()
+        };
+        assert!(rust_module(&bindings(&target, &[])).is_err());
+    }
+}
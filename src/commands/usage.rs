@@ -0,0 +1,141 @@
+use chrono::Duration;
+
+use crate::commands::metrics::{self, Metrics};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+use crate::terminal::table::Table;
+
+const MILLION: f64 = 1_000_000.0;
+const DAYS_PER_MONTH: i64 = 30;
+
+// Published Workers Paid plan pricing in USD. Both usage models share the base price.
+const BASE_PRICE: f64 = 5.0;
+const BUNDLED_INCLUDED_REQUESTS: f64 = 10.0 * MILLION;
+const BUNDLED_PRICE_PER_MILLION_REQUESTS: f64 = 0.50;
+const BUNDLED_CPU_LIMIT_MS: f64 = 50.0;
+const UNBOUND_INCLUDED_REQUESTS: f64 = 1.0 * MILLION;
+const UNBOUND_PRICE_PER_MILLION_REQUESTS: f64 = 0.15;
+const UNBOUND_INCLUDED_DURATION: f64 = 400_000.0;
+const UNBOUND_PRICE_PER_MILLION_GB_S: f64 = 12.50;
+
+/// What a month of usage would cost under each usage model.
+#[derive(Debug, PartialEq)]
+pub struct Estimate {
+    pub monthly_requests: f64,
+    pub monthly_duration: f64,
+    pub bundled: f64,
+    pub unbound: f64,
+}
+
+impl Estimate {
+    /// Extrapolates metrics collected over `period` to a 30 day month.
+    pub fn new(metrics: &Metrics, period: Duration) -> Estimate {
+        let scale = Duration::days(DAYS_PER_MONTH).num_seconds() as f64
+            / period.num_seconds().max(1) as f64;
+        let monthly_requests = metrics.requests as f64 * scale;
+        let monthly_duration = metrics.duration * scale;
+
+        let bundled = BASE_PRICE
+            + overage(monthly_requests, BUNDLED_INCLUDED_REQUESTS)
+                * BUNDLED_PRICE_PER_MILLION_REQUESTS;
+        let unbound = BASE_PRICE
+            + overage(monthly_requests, UNBOUND_INCLUDED_REQUESTS)
+                * UNBOUND_PRICE_PER_MILLION_REQUESTS
+            + overage(monthly_duration, UNBOUND_INCLUDED_DURATION) * UNBOUND_PRICE_PER_MILLION_GB_S;
+
+        Estimate {
+            monthly_requests,
+            monthly_duration,
+            bundled,
+            unbound,
+        }
+    }
+}
+
+// usage beyond what is included, in millions
+fn overage(usage: f64, included: f64) -> f64 {
+    (usage - included).max(0.0) / MILLION
+}
+
+/// Estimates the monthly bill for the target's script under the bundled and unbound
+/// usage models, based on its traffic over the last `since` (e.g. "7d").
+pub fn usage(target: &Target, user: &GlobalUser, since: &str) -> Result<(), failure::Error> {
+    let period = metrics::parse_period(since)?;
+    let metrics = metrics::fetch(target, user, period)?;
+    let estimate = Estimate::new(&metrics, period);
+
+    StdOut::message(&format!(
+        "Usage of {} from {} to {}, extrapolated to {} days",
+        styles::highlight(&target.name),
+        metrics.since,
+        metrics.until,
+        DAYS_PER_MONTH
+    ));
+
+    let mut table = Table::new(&[
+        "Usage model",
+        "Requests / month",
+        "Duration / month",
+        "Estimated cost / month",
+    ]);
+    let requests = format!("{:.0}", estimate.monthly_requests);
+    for (model, cost, duration) in &[
+        ("bundled", estimate.bundled, "-".to_string()),
+        (
+            "unbound",
+            estimate.unbound,
+            format!("{:.0} GB-s", estimate.monthly_duration),
+        ),
+    ] {
+        table.add_row(vec![
+            model.to_string(),
+            requests.clone(),
+            duration.clone(),
+            format!("${:.2}", cost),
+        ]);
+    }
+    table.print();
+
+    let p99_ms = metrics.cpu_time_p99 / 1000.0;
+    if p99_ms > BUNDLED_CPU_LIMIT_MS {
+        StdOut::warn(&format!(
+            "1% of requests use more than {:.0}ms of CPU time, above the {}ms limit of the bundled usage model.",
+            p99_ms, BUNDLED_CPU_LIMIT_MS
+        ));
+    }
+    let cheaper = if estimate.unbound < estimate.bundled {
+        "unbound"
+    } else {
+        "bundled"
+    };
+    StdOut::info(&format!(
+        "Based on this traffic, the {} usage model would cost less. Estimates use published prices and exclude taxes, KV and Durable Objects.",
+        cheaper
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_estimates_monthly_costs() {
+        // a week of 7M requests and 140,000 GB-s is 30M requests and 600,000 GB-s a month
+        let metrics = Metrics {
+            requests: 7_000_000,
+            duration: 140_000.0,
+            ..Metrics::default()
+        };
+        let estimate = Estimate::new(&metrics, Duration::days(7));
+
+        assert_eq!(estimate.monthly_requests.round(), 30_000_000.0);
+        assert_eq!(estimate.monthly_duration.round(), 600_000.0);
+        // $5 + 20M extra requests at $0.50
+        assert_eq!(format!("{:.2}", estimate.bundled), "15.00");
+        // $5 + 29M extra requests at $0.15 + 200,000 extra GB-s at $12.50 per million
+        assert_eq!(format!("{:.2}", estimate.unbound), "11.85");
+    }
+}
@@ -1,14 +1,12 @@
-use crate::http;
+use crate::http::{self, TracedClient};
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::Table;
 use crate::terminal::{emoji, styles};
 use cloudflare::endpoints::account::{self, Account};
 use cloudflare::endpoints::user::GetUserDetails;
-use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
-use prettytable::{Cell, Row, Table};
-
 pub fn whoami(user: &GlobalUser) -> Result<(), failure::Error> {
     let mut missing_permissions: Vec<String> = Vec::with_capacity(2);
     // Attempt to print email for both GlobalKeyAuth and TokenAuth users
@@ -53,8 +51,8 @@ pub fn whoami(user: &GlobalUser) -> Result<(), failure::Error> {
         msg.push_str(&format!("\n\nPlease generate a new token and authenticate with {} or {}\nfor more information when running {}", login_msg, config_msg, whoami_msg));
     }
     StdOut::billboard(&msg);
-    if table.len() > 1 {
-        println!("{}", &table);
+    if !table.is_empty() {
+        table.print();
     }
     Ok(())
 }
@@ -64,7 +62,7 @@ fn fetch_api_token_email(
     missing_permissions: &mut Vec<String>,
 ) -> Result<Option<String>, failure::Error> {
     let client = http::cf_v4_client(user)?;
-    let response = client.request(&GetUserDetails {});
+    let response = client.request_traced(&GetUserDetails {});
     match response {
         Ok(res) => Ok(Some(res.result.email)),
         Err(e) => match e {
@@ -75,17 +73,17 @@ fn fetch_api_token_email(
                 }
                 Ok(None)
             }
-            ApiFailure::Invalid(_) => failure::bail!(http::format_error(e, None)),
+            ApiFailure::Invalid(_) => Err(http::api_error(e, None)),
         },
     }
 }
 
 fn fetch_accounts(user: &GlobalUser) -> Result<Vec<Account>, failure::Error> {
     let client = http::cf_v4_client(user)?;
-    let response = client.request(&account::ListAccounts { params: None });
+    let response = client.request_traced(&account::ListAccounts { params: None });
     match response {
         Ok(res) => Ok(res.result),
-        Err(e) => failure::bail!(http::format_error(e, None)),
+        Err(e) => Err(http::api_error(e, None)),
     }
 }
 
@@ -94,9 +92,7 @@ fn format_accounts(
     accounts: Vec<Account>,
     missing_permissions: &mut Vec<String>,
 ) -> Table {
-    let mut table = Table::new();
-    let table_head = Row::new(vec![Cell::new("Account Name"), Cell::new("Account ID")]);
-    table.add_row(table_head);
+    let mut table = Table::new(&["Account Name", "Account ID"]);
 
     if let GlobalUser::TokenAuth { .. } = user {
         if accounts.is_empty() {
@@ -105,8 +101,7 @@ fn format_accounts(
     }
 
     for account in accounts {
-        let row = Row::new(vec![Cell::new(&account.name), Cell::new(&account.id)]);
-        table.add_row(row);
+        table.add_row(vec![account.name, account.id]);
     }
     table
 }
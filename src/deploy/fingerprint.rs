@@ -0,0 +1,40 @@
+use std::fmt;
+use std::hash::Hasher;
+
+use twox_hash::XxHash64;
+
+use crate::deploy::{DeployState, DeploymentSet};
+use crate::settings::toml::Target;
+use crate::sites::AssetManifest;
+use crate::upload;
+
+/// A content hash of everything `wrangler publish` sends to the API for a target:
+/// the script upload form and the routes and schedules it is deployed to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fingerprint(String);
+
+impl Fingerprint {
+    pub fn new(
+        target: &Target,
+        asset_manifest: Option<AssetManifest>,
+        deployments: &DeploymentSet,
+    ) -> Result<Self, failure::Error> {
+        let mut hasher = XxHash64::default();
+        hasher.write_u64(upload::form::fingerprint(target, asset_manifest)?);
+        hasher.write(format!("{:?}", deployments).as_bytes());
+
+        Ok(Fingerprint(format!("{:x}", hasher.finish())))
+    }
+
+    /// Whether this fingerprint matches the one recorded by the last successful publish
+    /// of this target from this project.
+    pub fn is_published(&self, target: &Target, state: &DeployState) -> bool {
+        state.is_for(target) && state.fingerprint.as_ref() == Some(&self.0)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
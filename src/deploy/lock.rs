@@ -0,0 +1,294 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::blocking::multipart::Form;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::deploy::state::state_path;
+use crate::error::WranglerError;
+use crate::http::{self, Traced};
+use crate::interrupt;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+// a publish that crashed without releasing its lock stops blocking others after this long
+const STALE_AFTER: Duration = Duration::from_secs(15 * 60);
+const LOCK_TAG_PREFIX: &str = "wrangler-publish-lock-";
+
+static REMOTE: AtomicBool = AtomicBool::new(false);
+
+/// Also marks the script itself while publishing, for `--remote-lock`, so publishes from
+/// other machines (e.g. parallel CI jobs) see each other.
+pub fn use_remote_lock() {
+    REMOTE.store(true, Ordering::SeqCst);
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct LockFile {
+    pid: u32,
+    /// seconds since the unix epoch
+    started: u64,
+}
+
+/// An advisory lock on publishing a script from an environment, released when dropped.
+/// It is held in `.wrangler/state/<env>.lock`, and with `--remote-lock` also as a tag on
+/// the script, so two publishes of the same worker don't interleave their uploads.
+pub struct PublishLock {
+    path: PathBuf,
+    remote: Option<RemoteLock>,
+}
+
+struct RemoteLock {
+    user: GlobalUser,
+    settings_addr: String,
+    tag: String,
+}
+
+impl PublishLock {
+    pub fn acquire(
+        user: &GlobalUser,
+        target: &Target,
+        env: Option<&str>,
+    ) -> Result<Self, failure::Error> {
+        let path = state_path(env)?.with_extension("lock");
+        acquire_local(&path, &target.name, now())?;
+        let mut lock = PublishLock { path, remote: None };
+
+        if REMOTE.load(Ordering::SeqCst) {
+            lock.remote = RemoteLock::acquire(user, target)?;
+        }
+        Ok(lock)
+    }
+}
+
+impl Drop for PublishLock {
+    fn drop(&mut self) {
+        if let Some(remote) = &self.remote {
+            if let Err(e) = remote.release() {
+                StdErr::warn(&format!(
+                    "Could not remove the publish lock from the script, it will expire in {} minutes: {}",
+                    STALE_AFTER.as_secs() / 60,
+                    e
+                ));
+            }
+        }
+        let _ = fs::remove_file(&self.path);
+        interrupt::forget_temp_file(&self.path);
+    }
+}
+
+fn acquire_local(path: &Path, script_name: &str, now: u64) -> Result<(), failure::Error> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    for _ in 0..2 {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                let lock = LockFile {
+                    pid: process::id(),
+                    started: now,
+                };
+                file.write_all(serde_json::to_string(&lock)?.as_bytes())?;
+                interrupt::remove_on_interrupt(path);
+                return Ok(());
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let held: Option<LockFile> = fs::read_to_string(path)
+                    .ok()
+                    .and_then(|contents| serde_json::from_str(&contents).ok());
+                match held {
+                    Some(held) if !is_stale(held.started, now) => failure::bail!(
+                        "{} is already being published by process {}, which started {} ago. Try again once it has finished, or delete {} if it is no longer running.",
+                        script_name,
+                        held.pid,
+                        format_age(held.started, now),
+                        path.display()
+                    ),
+                    _ => {
+                        log::info!("Replacing the stale publish lock {}", path.display());
+                        fs::remove_file(path)?;
+                    }
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    failure::bail!("Could not lock {}", path.display())
+}
+
+#[derive(Deserialize)]
+struct SettingsResponse {
+    result: Option<ScriptSettings>,
+}
+
+#[derive(Default, Deserialize)]
+struct ScriptSettings {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl RemoteLock {
+    // Returns None for a script that doesn't exist yet, as there is nothing to tag.
+    fn acquire(user: &GlobalUser, target: &Target) -> Result<Option<Self>, failure::Error> {
+        let lock = RemoteLock {
+            user: user.clone(),
+            settings_addr: http::endpoints::script_settings(&target.account_id, &target.name),
+            tag: format!("{}{}-{}", LOCK_TAG_PREFIX, now(), process::id()),
+        };
+        let tags = match lock.tags()? {
+            Some(tags) => tags,
+            None => return Ok(None),
+        };
+        if let Some(held) = live_locks(&tags, now()).first() {
+            failure::bail!(
+                "{} is already being published from another machine, which started {} ago. Try again once it has finished.",
+                target.name,
+                format_age(held.0, now())
+            )
+        }
+
+        let mut tags: Vec<String> = tags
+            .into_iter()
+            .filter(|tag| !tag.starts_with(LOCK_TAG_PREFIX))
+            .collect();
+        tags.push(lock.tag.clone());
+        lock.set_tags(&tags)?;
+
+        // Checking and tagging aren't atomic, so look again for a publish that tagged the
+        // script at the same time. Both back off rather than risk both going ahead.
+        let tags = lock.tags()?.unwrap_or_default();
+        let others = live_locks(&tags, now())
+            .into_iter()
+            .filter(|(_, tag)| *tag != lock.tag)
+            .count();
+        if others > 0 {
+            lock.release()?;
+            failure::bail!(
+                "{} started publishing from another machine at the same time. Try again once it has finished.",
+                target.name
+            )
+        }
+        Ok(Some(lock))
+    }
+
+    fn release(&self) -> Result<(), failure::Error> {
+        if let Some(tags) = self.tags()? {
+            let tags: Vec<String> = tags.into_iter().filter(|tag| *tag != self.tag).collect();
+            self.set_tags(&tags)?;
+        }
+        Ok(())
+    }
+
+    fn tags(&self) -> Result<Option<Vec<String>>, failure::Error> {
+        let res = http::legacy_auth_client(&self.user)
+            .get(&self.settings_addr)
+            .send_traced()?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let response: SettingsResponse = serde_json::from_str(&res.text()?)?;
+                Ok(Some(response.result.unwrap_or_default().tags))
+            }
+            status => Err(WranglerError::Api(format!(
+                "Could not read the script's settings. Status: {}, Details {}",
+                status,
+                res.text()?
+            ))
+            .into()),
+        }
+    }
+
+    fn set_tags(&self, tags: &[String]) -> Result<(), failure::Error> {
+        let form = Form::new().text("settings", json!({ "tags": tags }).to_string());
+        let res = http::legacy_auth_client(&self.user)
+            .patch(&self.settings_addr)
+            .multipart(form)
+            .send_traced()?;
+        if !res.status().is_success() {
+            return Err(WranglerError::Api(format!(
+                "Could not tag the script. Status: {}, Details {}",
+                res.status(),
+                res.text()?
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+// the lock tags that aren't stale, with when they were taken, oldest first
+fn live_locks(tags: &[String], now: u64) -> Vec<(u64, &str)> {
+    let mut locks: Vec<(u64, &str)> = tags
+        .iter()
+        .filter_map(|tag| {
+            let rest = tag.strip_prefix(LOCK_TAG_PREFIX)?;
+            let started = rest.split('-').next()?.parse().ok()?;
+            Some((started, tag.as_str()))
+        })
+        .filter(|(started, _)| !is_stale(*started, now))
+        .collect();
+    locks.sort();
+    locks
+}
+
+fn is_stale(started: u64, now: u64) -> bool {
+    now.saturating_sub(started) > STALE_AFTER.as_secs()
+}
+
+fn format_age(started: u64, now: u64) -> String {
+    let secs = now.saturating_sub(started);
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m", secs / 60)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_backs_off_from_a_held_lock_until_it_is_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("production.lock");
+
+        acquire_local(&path, "my-worker", 1000).unwrap();
+        let err = acquire_local(&path, "my-worker", 1060).unwrap_err();
+        assert!(err.to_string().contains("already being published"));
+
+        let later = 1000 + STALE_AFTER.as_secs() + 1;
+        acquire_local(&path, "my-worker", later).unwrap();
+        interrupt::forget_temp_file(&path);
+    }
+
+    #[test]
+    fn it_ignores_stale_and_foreign_tags() {
+        let tags = vec![
+            "team-payments".to_string(),
+            format!("{}2000-42", LOCK_TAG_PREFIX),
+            format!("{}100-7", LOCK_TAG_PREFIX),
+            format!("{}1900-8", LOCK_TAG_PREFIX),
+        ];
+        let locks = live_locks(&tags, 2100);
+        assert_eq!(
+            locks,
+            vec![
+                (1900, "wrangler-publish-lock-1900-8"),
+                (2000, "wrangler-publish-lock-2000-42")
+            ]
+        );
+    }
+}
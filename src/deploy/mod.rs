@@ -1,11 +1,19 @@
+mod fingerprint;
+pub mod lock;
 mod schedule;
+pub mod state;
 mod zoned;
 mod zoneless;
 
+pub use fingerprint::Fingerprint;
+pub use lock::PublishLock;
 pub use schedule::ScheduleTarget;
-pub use zoned::ZonedTarget;
+pub use state::DeployState;
+pub use zoned::{list_routes, RouteUploadResult, ZonedTarget};
 pub use zoneless::ZonelessTarget;
 
+use std::collections::HashMap;
+
 use crate::settings::global_user::GlobalUser;
 
 /// A set of deploy targets.
@@ -26,8 +34,15 @@ pub fn worker(
     for target in deploy_targets {
         match target {
             DeployTarget::Zoned(zoned) => {
-                let route_urls = zoned.deploy(user)?;
-                results.urls.extend(route_urls);
+                let route_results = zoned.deploy(user)?;
+                results
+                    .urls
+                    .extend(route_results.iter().map(|r| r.to_string()));
+                results
+                    .routes
+                    .entry(zoned.zone_id.clone())
+                    .or_insert_with(Vec::new)
+                    .extend(route_results);
             }
             DeployTarget::Zoneless(zoneless) => {
                 let worker_dev = zoneless.deploy(user)?;
@@ -47,4 +62,6 @@ pub fn worker(
 pub struct DeployResults {
     pub urls: Vec<String>,
     pub schedules: Vec<String>,
+    /// The outcome of each route deploy, keyed by zone id
+    pub routes: HashMap<String, Vec<RouteUploadResult>>,
 }
@@ -1,4 +1,4 @@
-use crate::http;
+use crate::http::{self, Traced};
 use crate::settings::global_user::GlobalUser;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -25,10 +25,8 @@ impl ScheduleTarget {
 
     pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>, failure::Error> {
         log::info!("publishing schedules");
-        let schedule_worker_addr = format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/schedules",
-            self.account_id, self.script_name,
-        );
+        let schedule_worker_addr =
+            http::endpoints::script_schedules(&self.account_id, &self.script_name);
 
         let client = http::legacy_auth_client(user);
 
@@ -37,14 +35,10 @@ impl ScheduleTarget {
             .put(&schedule_worker_addr)
             .header("Content-Type", "application/json")
             .body(build_schedules_request(&self.crons))
-            .send()?;
+            .send_traced()?;
 
         if !res.status().is_success() {
-            failure::bail!(
-                "Something went wrong! Status: {}, Details {}",
-                res.status(),
-                res.text()?
-            )
+            return Err(http::status_error(res.status(), &res.text()?));
         }
 
         Ok(self.crons.clone())
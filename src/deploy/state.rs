@@ -0,0 +1,302 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::deploy::{DeployResults, DeployTarget, RouteUploadResult};
+use crate::settings::toml::{KvNamespace, Target};
+
+const STATE_DIR: &str = ".wrangler/state";
+const DEFAULT_STATE_NAME: &str = "default";
+const PREVIEW_DEPLOYMENTS_FILE: &str = "preview-branches.json";
+
+/// A record of the resources wrangler has created for an environment, kept in
+/// `.wrangler/state/<env>.json`. Anything that isn't listed here was either
+/// created by hand or already existed when wrangler first deployed.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct DeployState {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub account_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub workers_dev: bool,
+    #[serde(default)]
+    pub routes: Vec<ManagedRoute>,
+    #[serde(default)]
+    pub kv_namespaces: Vec<ManagedKvNamespace>,
+    #[serde(default)]
+    pub schedules: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ManagedRoute {
+    pub zone_id: String,
+    pub id: Option<String>,
+    pub pattern: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ManagedKvNamespace {
+    pub binding: String,
+    pub id: String,
+    #[serde(default)]
+    pub preview: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<SystemTime>,
+}
+
+impl DeployState {
+    /// Loads the state for an environment, or an empty state if
+    /// nothing has been recorded for it yet.
+    pub fn load(env: Option<&str>) -> Result<Self, failure::Error> {
+        let path = state_path(env)?;
+        if !path.exists() {
+            return Ok(DeployState::default());
+        }
+
+        log::info!("Reading deploy state from {}", path.display());
+        let contents = fs::read_to_string(&path)?;
+        match serde_json::from_str(&contents) {
+            Ok(state) => Ok(state),
+            Err(e) => failure::bail!(
+                "Could not parse deploy state at {}: {}\nIf this file was edited by hand, delete it and run `wrangler publish` again.",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    pub fn save(&self, env: Option<&str>) -> Result<(), failure::Error> {
+        let path = state_path(env)?;
+        log::info!("Writing deploy state to {}", path.display());
+
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// Whether this state was recorded for the same script on the same account as `target`.
+    pub fn is_for(&self, target: &Target) -> bool {
+        self.name == target.name && self.account_id == target.account_id
+    }
+
+    /// Updates the state with the outcome of `deploy::worker`. Routes wrangler created are
+    /// recorded as managed; routes that already pointed at the script are left alone.
+    pub fn record_deploy(
+        &mut self,
+        target: &Target,
+        deployments: &[DeployTarget],
+        results: &DeployResults,
+    ) {
+        if !self.name.is_empty() && !self.is_for(target) {
+            // the script was renamed or moved to another account, so whatever we
+            // recorded before belongs to a different worker
+            *self = DeployState::default();
+        }
+        self.name = target.name.clone();
+        self.account_id = target.account_id.clone();
+
+        self.workers_dev = deployments
+            .iter()
+            .any(|d| matches!(d, DeployTarget::Zoneless(_)));
+        self.schedules = results.schedules.clone();
+
+        for (zone_id, route_results) in &results.routes {
+            for result in route_results {
+                if let RouteUploadResult::New(route) = result {
+                    self.add_route(ManagedRoute {
+                        zone_id: zone_id.clone(),
+                        id: route.id.clone(),
+                        pattern: route.pattern.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn add_route(&mut self, route: ManagedRoute) {
+        self.routes
+            .retain(|r| !(r.zone_id == route.zone_id && r.pattern == route.pattern));
+        self.routes.push(route);
+    }
+
+    pub fn add_kv_namespace(&mut self, namespace: &KvNamespace, preview: bool) {
+        // the Workers Sites namespace is added again on every publish
+        let created_at = self
+            .kv_namespaces
+            .iter()
+            .find(|ns| ns.id == namespace.id)
+            .map_or_else(|| Some(SystemTime::now()), |ns| ns.created_at);
+        self.kv_namespaces.retain(|ns| ns.id != namespace.id);
+        self.kv_namespaces.push(ManagedKvNamespace {
+            binding: namespace.binding.clone(),
+            id: namespace.id.clone(),
+            preview,
+            created_at,
+        });
+    }
+}
+
+pub fn state_path(env: Option<&str>) -> Result<PathBuf, failure::Error> {
+    let file_name = format!("{}.json", env.unwrap_or(DEFAULT_STATE_NAME));
+    Ok(env::current_dir()?.join(STATE_DIR).join(file_name))
+}
+
+/// A worker published with `wrangler publish --preview-branch`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PreviewDeployment {
+    pub branch: String,
+    pub script_name: String,
+    pub account_id: String,
+    pub url: String,
+    pub published_at: SystemTime,
+}
+
+impl PreviewDeployment {
+    /// The name its deploy state is recorded under, which keeps
+    /// it apart from the state of the environments in wrangler.toml.
+    pub fn state_name(&self) -> String {
+        preview_state_name(&self.script_name)
+    }
+}
+
+pub fn preview_state_name(script_name: &str) -> String {
+    format!("preview.{}", script_name)
+}
+
+/// User scripts in a dispatch namespace are tracked apart from the account-level script
+/// of the same name.
+pub fn dispatch_state_name(namespace: &str, script_name: &str) -> String {
+    format!("dispatch.{}.{}", namespace, script_name)
+}
+
+pub fn load_preview_deployments() -> Result<Vec<PreviewDeployment>, failure::Error> {
+    let path = env::current_dir()?
+        .join(STATE_DIR)
+        .join(PREVIEW_DEPLOYMENTS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+pub fn save_preview_deployments(deployments: &[PreviewDeployment]) -> Result<(), failure::Error> {
+    let path = env::current_dir()?
+        .join(STATE_DIR)
+        .join(PREVIEW_DEPLOYMENTS_FILE);
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, serde_json::to_string_pretty(deployments)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::deploy::ZonelessTarget;
+    use crate::settings::toml::Route;
+
+    fn target() -> Target {
+        Target {
+            account_id: "account".to_string(),
+            name: "worker".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn route(pattern: &str, id: &str) -> Route {
+        Route {
+            id: Some(id.to_string()),
+            script: Some("worker".to_string()),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_records_only_created_routes() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "zone".to_string(),
+            vec![
+                RouteUploadResult::New(route("example.com/*", "1")),
+                RouteUploadResult::Same(route("example.com/a", "2")),
+            ],
+        );
+        let results = DeployResults {
+            routes,
+            ..Default::default()
+        };
+
+        let mut state = DeployState::default();
+        state.record_deploy(&target(), &[], &results);
+
+        assert_eq!(state.routes.len(), 1);
+        assert_eq!(state.routes[0].pattern, "example.com/*");
+        assert_eq!(state.routes[0].id, Some("1".to_string()));
+    }
+
+    #[test]
+    fn it_keeps_routes_from_previous_deploys() {
+        let mut state = DeployState {
+            name: "worker".to_string(),
+            account_id: "account".to_string(),
+            ..Default::default()
+        };
+        state.add_route(ManagedRoute {
+            zone_id: "zone".to_string(),
+            id: Some("1".to_string()),
+            pattern: "example.com/*".to_string(),
+        });
+
+        let deployments = vec![DeployTarget::Zoneless(ZonelessTarget {
+            account_id: "account".to_string(),
+            script_name: "worker".to_string(),
+        })];
+        state.record_deploy(&target(), &deployments, &DeployResults::default());
+
+        assert_eq!(state.routes.len(), 1);
+        assert!(state.workers_dev);
+    }
+
+    #[test]
+    fn it_resets_state_for_a_different_worker() {
+        let mut state = DeployState {
+            name: "old-worker".to_string(),
+            account_id: "account".to_string(),
+            schedules: vec!["* * * * *".to_string()],
+            ..Default::default()
+        };
+        state.record_deploy(&target(), &[], &DeployResults::default());
+
+        assert_eq!(state.name, "worker");
+        assert!(state.schedules.is_empty());
+    }
+
+    #[test]
+    fn it_keeps_when_a_namespace_was_created() {
+        let namespace = KvNamespace {
+            binding: "__STATIC_CONTENT".to_string(),
+            id: "abc".to_string(),
+        };
+        let mut state = DeployState::default();
+        state.add_kv_namespace(&namespace, false);
+        let created_at = state.kv_namespaces[0].created_at;
+        assert!(created_at.is_some());
+
+        state.add_kv_namespace(&namespace, false);
+        assert_eq!(state.kv_namespaces.len(), 1);
+        assert_eq!(state.kv_namespaces[0].created_at, created_at);
+    }
+}
@@ -1,11 +1,10 @@
 use std::fmt;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use cloudflare::endpoints::workers::{CreateRoute, CreateRouteParams, ListRoutes};
-use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::endpoints::workers::{CreateRoute, CreateRouteParams, WorkersRoute};
 
-use crate::http;
+use crate::http::{self, TracedClient};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{Route, RouteConfig};
 use crate::terminal::message::{Message, StdOut};
@@ -52,14 +51,10 @@ impl ZonedTarget {
         }
     }
 
-    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>, failure::Error> {
+    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<RouteUploadResult>, failure::Error> {
         log::info!("publishing to zone {}", self.zone_id);
 
-        let published_routes = publish_routes(&user, self)?;
-
-        let display_results: Vec<String> = published_routes.iter().map(|r| r.to_string()).collect();
-
-        Ok(display_results)
+        publish_routes(&user, self)
     }
 }
 
@@ -82,14 +77,24 @@ pub fn publish_routes(
 }
 
 fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>, failure::Error> {
-    let client = http::cf_v4_client(user)?;
+    let routes = list_routes(user, zone_identifier)?;
 
-    let routes: Vec<Route> = match client.request(&ListRoutes { zone_identifier }) {
-        Ok(success) => success.result.iter().map(Route::from).collect(),
-        Err(e) => failure::bail!("{}", http::format_error(e, None)), // TODO: add suggestion fn
-    };
+    Ok(routes.iter().map(Route::from).collect())
+}
+
+/// The routes of the zone. They're fetched through the response cache, as `route list`
+/// and `diff` often follow a publish that has just listed them.
+pub fn list_routes(user: &GlobalUser, zone_id: &str) -> Result<Vec<WorkersRoute>, failure::Error> {
+    let client = http::legacy_auth_client(user);
+    let response = http::cache::get(&client, &http::endpoints::routes(zone_id))?
+        .text("listing the routes of your zone")?;
+    let response: ListRoutesResponse = serde_json::from_str(&response)?;
+    Ok(response.result)
+}
 
-    Ok(routes)
+#[derive(Deserialize)]
+struct ListRoutesResponse {
+    result: Vec<WorkersRoute>,
 }
 
 fn create(
@@ -100,19 +105,22 @@ fn create(
     let client = http::cf_v4_client(user)?;
 
     log::info!("Creating your route {:#?}", &route.pattern,);
-    match client.request(&CreateRoute {
+    match client.request_traced(&CreateRoute {
         zone_identifier,
         params: CreateRouteParams {
             pattern: route.pattern.clone(),
             script: route.script.clone(),
         },
     }) {
-        Ok(response) => Ok(Route {
-            id: Some(response.result.id),
-            pattern: route.pattern.clone(),
-            script: route.script.clone(),
-        }),
-        Err(e) => failure::bail!("{}", http::format_error(e, Some(&routes_error_help))),
+        Ok(response) => {
+            http::cache::invalidate(&http::endpoints::routes(zone_identifier));
+            Ok(Route {
+                id: Some(response.result.id),
+                pattern: route.pattern.clone(),
+                script: route.script.clone(),
+            })
+        }
+        Err(e) => Err(http::api_error(e, Some(&routes_error_help))),
     }
 }
 
@@ -1,5 +1,5 @@
 use crate::commands::subdomain::Subdomain;
-use crate::http;
+use crate::http::{self, Traced};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::RouteConfig;
 
@@ -30,10 +30,7 @@ impl ZonelessTarget {
             None => failure::bail!("Before publishing to workers.dev, you must register a subdomain. Please choose a name for your subdomain and run `wrangler subdomain <name>`.")
         };
 
-        let sd_worker_addr = format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/subdomain",
-            self.account_id, self.script_name,
-        );
+        let sd_worker_addr = http::endpoints::script_subdomain(&self.account_id, &self.script_name);
 
         let client = http::legacy_auth_client(user);
 
@@ -42,14 +39,10 @@ impl ZonelessTarget {
             .post(&sd_worker_addr)
             .header("Content-type", "application/json")
             .body(build_subdomain_request())
-            .send()?;
+            .send_traced()?;
 
         if !res.status().is_success() {
-            failure::bail!(
-                "Something went wrong! Status: {}, Details {}",
-                res.status(),
-                res.text()?
-            )
+            return Err(http::status_error(res.status(), &res.text()?));
         }
 
         let deploy_address = format!("https://{}.{}.workers.dev", self.script_name, subdomain);
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use failure::Fail;
+
+/// Errors with a stable code and process exit code, so scripts can tell
+/// failure classes apart:
+///
+/// | exit code | failure                                |
+/// |-----------|----------------------------------------|
+/// | 0         | success                                |
+/// | 1         | any other error                        |
+/// | 2         | configuration error                    |
+/// | 3         | authentication error                   |
+/// | 4         | API error                              |
+/// | 5         | build error                            |
+/// | 6         | warnings were printed with `--strict`  |
+/// | 7         | the network is unavailable             |
+///
+/// They are `failure` errors like every other error in wrangler, as are the errors of
+/// cloudflare-rs and binary-install, so commands return them as `failure::Error` and
+/// `exit_code` finds them again.
+#[derive(Debug)]
+pub enum WranglerError {
+    /// The configuration file is missing, invalid, or incomplete
+    Config(String),
+    /// No credentials, or credentials the API rejected
+    Auth(String),
+    /// The Cloudflare API returned an error
+    Api(String),
+    /// The project failed to build, with the error the build failed with
+    Build(failure::Error),
+    /// Warnings were printed while running with `--strict`
+    Strict(String),
+    /// The command needs the network, which is unavailable or turned off with `--offline`
+    Network(String),
+}
+
+impl fmt::Display for WranglerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WranglerError::Config(msg)
+            | WranglerError::Auth(msg)
+            | WranglerError::Api(msg)
+            | WranglerError::Strict(msg)
+            | WranglerError::Network(msg) => write!(f, "{}", msg),
+            WranglerError::Build(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Fail for WranglerError {
+    // a build error reads as the error the build failed with, so its causes are printed
+    // after it, logged and annotated like those of any other error
+    fn cause(&self) -> Option<&dyn Fail> {
+        match self {
+            WranglerError::Build(e) => e.as_fail().cause(),
+            _ => None,
+        }
+    }
+}
+
+impl WranglerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            WranglerError::Config(_) => "config",
+            WranglerError::Auth(_) => "auth",
+            WranglerError::Api(_) => "api",
+            WranglerError::Build(_) => "build",
+            WranglerError::Strict(_) => "strict",
+            WranglerError::Network(_) => "network",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            WranglerError::Config(_) => 2,
+            WranglerError::Auth(_) => 3,
+            WranglerError::Api(_) => 4,
+            WranglerError::Build(_) => 5,
+            WranglerError::Strict(_) => 6,
+            WranglerError::Network(_) => 7,
+        }
+    }
+}
+
+/// The process exit code for an error returned from a command.
+pub fn exit_code(e: &failure::Error) -> i32 {
+    match e.downcast_ref::<WranglerError>() {
+        Some(e) => e.exit_code(),
+        None => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_maps_errors_to_exit_codes() {
+        let config: failure::Error = WranglerError::Config("missing name".to_string()).into();
+        let other = failure::format_err!("something else");
+
+        assert_eq!(exit_code(&config), 2);
+        assert_eq!(exit_code(&other), 1);
+    }
+
+    #[test]
+    fn it_keeps_the_causes_of_build_errors() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "webpack not found");
+        let failed = failure::Error::from(missing).context("Could not run the build command");
+        let build: failure::Error = WranglerError::Build(failed.into()).into();
+
+        let causes: Vec<String> = build.iter_causes().map(|e| e.to_string()).collect();
+        assert_eq!(exit_code(&build), 5);
+        assert_eq!(build.to_string(), "Could not run the build command");
+        assert_eq!(causes, vec!["webpack not found"]);
+    }
+}
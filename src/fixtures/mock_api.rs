@@ -0,0 +1,282 @@
+use std::io::prelude::*;
+use std::io::{BufRead, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Value};
+
+/// A stand-in for the Cloudflare API, served from a thread of the test process. Run
+/// wrangler with `WRANGLER_API_BASE_URL` set to `url()` and it sends every API request
+/// here, where it gets the responses registered with `respond`.
+pub struct MockApi {
+    port: u16,
+    state: Arc<Mutex<State>>,
+    stopped: Arc<AtomicBool>,
+}
+
+/// A request the mock API received.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// The path under the base URL, e.g. `/accounts/1/storage/kv/namespaces`.
+    pub path: String,
+    pub query: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Default)]
+struct State {
+    responses: Vec<Response>,
+    requests: Vec<RecordedRequest>,
+}
+
+struct Response {
+    method: String,
+    path: String,
+    status: u16,
+    body: String,
+}
+
+const BASE_PATH: &str = "/client/v4";
+
+impl MockApi {
+    pub fn start() -> MockApi {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(State::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let (accepted_state, accepted_stopped) = (state.clone(), stopped.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accepted_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let state = accepted_state.clone();
+                    thread::spawn(move || serve(stream, &state));
+                }
+            }
+        });
+
+        MockApi {
+            port,
+            state,
+            stopped,
+        }
+    }
+
+    /// The base URL to set `WRANGLER_API_BASE_URL` to.
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, BASE_PATH)
+    }
+
+    /// Answers `method` requests for `path`, under the base URL and without the query
+    /// string, with `status` and `body`. A later response for the same request replaces
+    /// an earlier one.
+    pub fn respond(&self, method: &str, path: &str, status: u16, body: Value) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .responses
+            .retain(|response| !(response.method == method && response.path == path));
+        state.responses.push(Response {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            body: body.to_string(),
+        });
+    }
+
+    /// Answers `method` requests for `path` with a successful API response wrapping
+    /// `result`.
+    pub fn respond_with_result(&self, method: &str, path: &str, result: Value) {
+        self.respond(method, path, 200, api_success(result));
+    }
+
+    /// The requests received so far, in the order they arrived.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockApi {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wake the accepting thread so it sees it should stop
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+    }
+}
+
+/// The body of a successful API response, as the API wraps every result.
+pub fn api_success(result: Value) -> Value {
+    let count = result.as_array().map_or(1, Vec::len);
+    json!({
+        "success": true,
+        "errors": [],
+        "messages": [],
+        "result": result,
+        "result_info": {
+            "page": 1,
+            "per_page": 100,
+            "count": count,
+            "total_count": count,
+        },
+    })
+}
+
+fn api_error(code: u32, message: &str) -> Value {
+    json!({
+        "success": false,
+        "errors": [{ "code": code, "message": message }],
+        "messages": [],
+        "result": null,
+    })
+}
+
+fn serve(stream: TcpStream, state: &Mutex<State>) {
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    });
+    let request = match read_request(&mut reader) {
+        Some(request) => request,
+        None => return,
+    };
+
+    let (status, body) = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(request.clone());
+        match state
+            .responses
+            .iter()
+            .find(|response| response.method == request.method && response.path == request.path)
+        {
+            Some(response) => (response.status, response.body.clone()),
+            None => (
+                404,
+                api_error(
+                    10000,
+                    &format!("no mock response for {} {}", request.method, request.path),
+                )
+                .to_string(),
+            ),
+        }
+    };
+
+    let mut stream = stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    );
+    let _ = stream.flush();
+}
+
+fn read_request(reader: &mut impl BufRead) -> Option<RecordedRequest> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+
+    let mut content_length = 0;
+    let mut chunked = false;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).ok()?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let mut header = header.splitn(2, ':');
+        let name = header.next()?.trim().to_ascii_lowercase();
+        let value = header.next().unwrap_or("").trim();
+        match name.as_str() {
+            "content-length" => content_length = value.parse().ok()?,
+            "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+            _ => (),
+        }
+    }
+
+    let body = if chunked {
+        read_chunked(reader)?
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).ok()?;
+        body
+    };
+
+    let (path, query) = match target.find('?') {
+        Some(i) => (&target[..i], Some(target[i + 1..].to_string())),
+        None => (target, None),
+    };
+    Some(RecordedRequest {
+        method,
+        path: path.trim_start_matches(BASE_PATH).to_string(),
+        query,
+        body,
+    })
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut size = String::new();
+        reader.read_line(&mut size).ok()?;
+        let size = usize::from_str_radix(size.trim().split(';').next()?, 16).ok()?;
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk).ok()?;
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_answers_registered_requests() {
+        let api = MockApi::start();
+        api.respond_with_result("GET", "/zones/1/workers/routes", json!([]));
+
+        let mut stream = TcpStream::connect(("127.0.0.1", api.port)).unwrap();
+        write!(
+            stream,
+            "GET /client/v4/zones/1/workers/routes?page=1 HTTP/1.1\r\nHost: localhost\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("\"success\":true"));
+        let requests = api.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/zones/1/workers/routes");
+        assert_eq!(requests[0].query, Some("page=1".to_string()));
+    }
+}
@@ -1,4 +1,6 @@
+mod mock_api;
 mod wrangler_toml;
+pub use mock_api::{api_success, MockApi, RecordedRequest};
 pub use wrangler_toml::{EnvConfig, KvConfig, SiteConfig, Triggers, WranglerToml, TEST_ENV_NAME};
 
 use std::fs;
@@ -0,0 +1,184 @@
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash64;
+
+use crate::error::WranglerError;
+use crate::http::Traced;
+use crate::settings::get_wrangler_home_dir;
+use crate::terminal::emoji;
+
+// how long a response is reused without asking the API whether it has changed
+const FRESH_FOR: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    etag: Option<String>,
+    fetched_at: SystemTime,
+    body: String,
+}
+
+/// A response to a GET, either from the API or from the cache.
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl CachedResponse {
+    /// The body of a successful response, or an error naming what was being done.
+    pub fn text(self, doing: &str) -> Result<String, failure::Error> {
+        if !self.status.is_success() {
+            return Err(WranglerError::Api(format!(
+                "{} There was an error {}.\n Status Code: {}\n Msg: {}",
+                emoji::WARN,
+                doing,
+                self.status,
+                self.body,
+            ))
+            .into());
+        }
+        Ok(self.body)
+    }
+}
+
+/// GETs `url` with `client`, so that commands run in quick succession don't all wait on
+/// the API for the same list. A response fetched in the last few seconds is reused as
+/// is, and an older one is revalidated with its ETag. Only successful responses are
+/// cached, and the cache is best effort: when it can't be read or written, the request
+/// is made as usual.
+pub fn get(client: &Client, url: &str) -> Result<CachedResponse, failure::Error> {
+    let path = entry_path(url)?;
+    let cached = fs::read(&path)
+        .ok()
+        .and_then(|entry| serde_json::from_slice::<Entry>(&entry).ok())
+        .filter(|entry| entry.url == url);
+
+    if let Some(entry) = &cached {
+        if is_fresh(entry, SystemTime::now()) {
+            log::info!("Using the cached response for {}", url);
+            return Ok(CachedResponse {
+                status: StatusCode::OK,
+                body: entry.body.clone(),
+            });
+        }
+    }
+
+    let mut request = client.get(url);
+    if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_ref()) {
+        request = request.header(IF_NONE_MATCH, etag.as_str());
+    }
+    let response = request.send_traced()?;
+    let status = response.status();
+
+    let entry = match cached {
+        Some(mut entry) if status == StatusCode::NOT_MODIFIED => {
+            log::info!("{} has not changed since it was cached", url);
+            entry.fetched_at = SystemTime::now();
+            entry
+        }
+        _ if status.is_success() => Entry {
+            url: url.to_string(),
+            etag: response
+                .headers()
+                .get(ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map(String::from),
+            fetched_at: SystemTime::now(),
+            body: response.text()?,
+        },
+        _ => {
+            return Ok(CachedResponse {
+                status,
+                body: response.text()?,
+            })
+        }
+    };
+
+    if let Err(e) = save(&path, &entry) {
+        log::info!("Could not cache the response for {}: {}", url, e);
+    }
+    Ok(CachedResponse {
+        status: StatusCode::OK,
+        body: entry.body,
+    })
+}
+
+/// Drops the cached responses for every URL starting with `url`, after a change that
+/// would make them stale, e.g. the namespace list pages after creating a namespace.
+pub fn invalidate(url: &str) {
+    let dir = match cache_dir() {
+        Ok(dir) => dir,
+        Err(_) => return,
+    };
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        let entry = fs::read(&path)
+            .ok()
+            .and_then(|entry| serde_json::from_slice::<Entry>(&entry).ok());
+        // entries that can't be read are dropped too
+        let stale = match entry {
+            Some(entry) => entry.url.starts_with(url),
+            None => true,
+        };
+        if stale {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn is_fresh(entry: &Entry, now: SystemTime) -> bool {
+    match now.duration_since(entry.fetched_at) {
+        Ok(age) => age < FRESH_FOR,
+        // the clock went backwards, so don't trust the entry's age
+        Err(_) => false,
+    }
+}
+
+fn save(path: &Path, entry: &Entry) -> Result<(), failure::Error> {
+    fs::create_dir_all(cache_dir()?)?;
+    fs::write(path, serde_json::to_vec(entry)?)?;
+    Ok(())
+}
+
+fn cache_dir() -> Result<PathBuf, failure::Error> {
+    Ok(get_wrangler_home_dir()?.join("cache").join("http"))
+}
+
+fn entry_path(url: &str) -> Result<PathBuf, failure::Error> {
+    let mut hasher = XxHash64::default();
+    hasher.write(url.as_bytes());
+    Ok(cache_dir()?.join(format!("{:016x}.json", hasher.finish())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reuses_recent_responses() {
+        let fetched_at = SystemTime::now();
+        let entry = Entry {
+            url: "https://api.cloudflare.com/client/v4/zones/1/workers/routes".to_string(),
+            etag: Some("\"abc\"".to_string()),
+            fetched_at,
+            body: "{}".to_string(),
+        };
+
+        assert!(is_fresh(&entry, fetched_at + Duration::from_secs(5)));
+        assert!(!is_fresh(&entry, fetched_at + FRESH_FOR));
+        assert!(!is_fresh(&entry, fetched_at - Duration::from_secs(1)));
+    }
+}
@@ -1,3 +1,4 @@
+use std::env;
 use std::time::Duration;
 
 use cloudflare::framework::async_api;
@@ -5,11 +6,46 @@ use cloudflare::framework::auth::Credentials;
 use cloudflare::framework::response::ApiFailure;
 use cloudflare::framework::{Environment, HttpApiClient, HttpApiClientConfig};
 use http::StatusCode;
+use reqwest::blocking::Response;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
 
+use crate::error::WranglerError;
 use crate::http::{feature::headers, Feature, DEFAULT_HTTP_TIMEOUT_SECONDS};
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::emoji;
 use crate::terminal::message::{Message, StdOut};
+
+const API_BASE_URL: &str = "https://api.cloudflare.com/client/v4";
+
+/// The URL API paths are joined to: `$WRANGLER_API_BASE_URL` when it is set, e.g. to point
+/// wrangler at a mock of the API in tests, otherwise the Cloudflare API.
+pub fn api_base_url() -> String {
+    match env::var("WRANGLER_API_BASE_URL") {
+        Ok(url) if !url.is_empty() => url.trim_end_matches('/').to_string(),
+        _ => API_BASE_URL.to_string(),
+    }
+}
+
+/// Whether `$WRANGLER_API_BASE_URL` points wrangler somewhere other than the Cloudflare API.
+pub fn api_base_url_overridden() -> bool {
+    api_base_url() != API_BASE_URL
+}
+
+/// The environment for the cloudflare-rs clients, which point at `api_base_url`. They
+/// join paths to its URL, so it needs the trailing slash.
+pub fn api_environment() -> Result<Environment, failure::Error> {
+    if !api_base_url_overridden() {
+        return Ok(Environment::Production);
+    }
+    let url = api_base_url();
+    match Url::parse(&format!("{}/", url)) {
+        Ok(url) => Ok(Environment::Custom(url)),
+        Err(e) => failure::bail!("WRANGLER_API_BASE_URL ({}) is not a valid URL: {}", url, e),
+    }
+}
+
 pub fn cf_v4_client(user: &GlobalUser) -> Result<HttpApiClient, failure::Error> {
     let config = HttpApiClientConfig {
         http_timeout: Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS),
@@ -19,7 +55,7 @@ pub fn cf_v4_client(user: &GlobalUser) -> Result<HttpApiClient, failure::Error>
     HttpApiClient::new(
         Credentials::from(user.to_owned()),
         config,
-        Environment::Production,
+        api_environment()?,
     )
 }
 
@@ -35,7 +71,7 @@ pub fn featured_cf_v4_client(
     HttpApiClient::new(
         Credentials::from(user.to_owned()),
         config,
-        Environment::Production,
+        api_environment()?,
     )
 }
 
@@ -46,7 +82,7 @@ pub fn cf_v4_api_client_async(
     async_api::Client::new(
         Credentials::from(user.to_owned()),
         config,
-        Environment::Production,
+        api_environment()?,
     )
 }
 
@@ -75,6 +111,42 @@ pub fn format_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static s
     }
 }
 
+/// The error for a request the API failed, formatted like `format_error`, which wrangler
+/// exits with the API error code for.
+pub fn api_error(
+    e: ApiFailure,
+    err_helper: Option<&dyn Fn(u16) -> &'static str>,
+) -> failure::Error {
+    WranglerError::Api(format_error(e, err_helper)).into()
+}
+
+/// The error for a response from the API with a status other than 2xx, for requests made
+/// without the cloudflare-rs client.
+pub fn status_error(status: StatusCode, details: &str) -> failure::Error {
+    WranglerError::Api(format!(
+        "Something went wrong! Status: {}, Details {}",
+        status, details
+    ))
+    .into()
+}
+
+/// The `result` of a response from the API, for requests made without the cloudflare-rs
+/// client. A status other than 2xx is an error.
+pub fn read_result<T: DeserializeOwned>(res: Response) -> Result<Option<T>, failure::Error> {
+    let status = res.status();
+    let text = res.text()?;
+    if !status.is_success() {
+        return Err(status_error(status, &text));
+    }
+    let response: ApiResponse<T> = serde_json::from_str(&text)?;
+    Ok(response.result)
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    result: Option<T>,
+}
+
 // For handling cases where the API gateway returns errors via HTTP status codes
 // (no API-specific, more granular error code is given).
 fn print_status_code_context(status_code: StatusCode) {
@@ -0,0 +1,44 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turns off gzip compression of large request bodies, for `--no-compress` or when the
+/// API turns a compressed body down.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+pub fn gzip(body: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn it_gzips_bodies() {
+        let body =
+            br#"[{"key":"index.html","value":"PGgxPmhpPC9oMT4=","base64":true}]"#.repeat(100);
+        let compressed = gzip(&body).unwrap();
+        assert!(compressed.len() < body.len());
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, body);
+    }
+}
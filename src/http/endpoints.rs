@@ -0,0 +1,176 @@
+//! The URLs of the API endpoints wrangler calls without going through cloudflare-rs. Each
+//! is built on `api_base_url()`, so `$WRANGLER_API_BASE_URL` moves all of them at once.
+
+use crate::http::api_base_url;
+
+fn account(account_id: &str) -> String {
+    format!("{}/accounts/{}", api_base_url(), account_id)
+}
+
+pub fn memberships() -> String {
+    format!("{}/memberships", api_base_url())
+}
+
+pub fn graphql() -> String {
+    format!("{}/graphql", api_base_url())
+}
+
+/// Where `wrangler login` polls for the token it was granted.
+pub fn workers_token() -> String {
+    format!("{}/workers/token", api_base_url())
+}
+
+pub fn verify_token() -> String {
+    format!("{}/user/tokens/verify", api_base_url())
+}
+
+pub fn token(token_id: &str) -> String {
+    format!("{}/user/tokens/{}", api_base_url(), token_id)
+}
+
+pub fn scripts(account_id: &str) -> String {
+    format!("{}/workers/scripts", account(account_id))
+}
+
+pub fn script(account_id: &str, script_name: &str) -> String {
+    format!("{}/{}", scripts(account_id), script_name)
+}
+
+pub fn script_tails(account_id: &str, script_name: &str) -> String {
+    format!("{}/tails", script(account_id, script_name))
+}
+
+pub fn script_tail(account_id: &str, script_name: &str, tail_id: &str) -> String {
+    format!("{}/{}", script_tails(account_id, script_name), tail_id)
+}
+
+pub fn script_settings(account_id: &str, script_name: &str) -> String {
+    format!("{}/settings", script(account_id, script_name))
+}
+
+pub fn script_schedules(account_id: &str, script_name: &str) -> String {
+    format!("{}/schedules", script(account_id, script_name))
+}
+
+/// Whether the script is published on the account's workers.dev subdomain.
+pub fn script_subdomain(account_id: &str, script_name: &str) -> String {
+    format!("{}/subdomain", script(account_id, script_name))
+}
+
+pub fn script_preview(account_id: &str, script_name: &str) -> String {
+    format!("{}/preview", script(account_id, script_name))
+}
+
+pub fn script_edge_preview(account_id: &str, script_name: &str) -> String {
+    format!("{}/edge-preview", script(account_id, script_name))
+}
+
+pub fn dispatch_namespaces(account_id: &str) -> String {
+    format!("{}/workers/dispatch/namespaces", account(account_id))
+}
+
+pub fn dispatch_namespace_script(account_id: &str, namespace: &str, script_name: &str) -> String {
+    format!(
+        "{}/{}/scripts/{}",
+        dispatch_namespaces(account_id),
+        namespace,
+        script_name
+    )
+}
+
+/// The account's workers.dev subdomain.
+pub fn subdomain(account_id: &str) -> String {
+    format!("{}/workers/subdomain", account(account_id))
+}
+
+pub fn subdomain_edge_preview(account_id: &str) -> String {
+    format!("{}/edge-preview", subdomain(account_id))
+}
+
+pub fn zone_edge_preview(zone_id: &str) -> String {
+    format!("{}/zones/{}/workers/edge-preview", api_base_url(), zone_id)
+}
+
+pub fn routes(zone_id: &str) -> String {
+    format!("{}/zones/{}/workers/routes", api_base_url(), zone_id)
+}
+
+pub fn durable_object_namespaces(account_id: &str) -> String {
+    format!("{}/workers/durable_objects/namespaces", account(account_id))
+}
+
+pub fn durable_object_namespace(account_id: &str, namespace_id: &str) -> String {
+    format!("{}/{}", durable_object_namespaces(account_id), namespace_id)
+}
+
+pub fn kv_namespaces(account_id: &str) -> String {
+    format!("{}/storage/kv/namespaces", account(account_id))
+}
+
+pub fn kv_bulk(account_id: &str, namespace_id: &str) -> String {
+    format!("{}/{}/bulk", kv_namespaces(account_id), namespace_id)
+}
+
+/// The value of a key, which has to be URL encoded already.
+pub fn kv_value(account_id: &str, namespace_id: &str, encoded_key: &str) -> String {
+    format!(
+        "{}/{}/values/{}",
+        kv_namespaces(account_id),
+        namespace_id,
+        encoded_key
+    )
+}
+
+pub fn mtls_certificates(account_id: &str) -> String {
+    format!("{}/mtls_certificates", account(account_id))
+}
+
+pub fn email_routing_addresses(account_id: &str) -> String {
+    format!("{}/email/routing/addresses", account(account_id))
+}
+
+pub fn alerting_webhooks(account_id: &str) -> String {
+    format!("{}/alerting/v3/destinations/webhooks", account(account_id))
+}
+
+pub fn alerting_policies(account_id: &str) -> String {
+    format!("{}/alerting/v3/policies", account(account_id))
+}
+
+pub fn pages_projects(account_id: &str) -> String {
+    format!("{}/pages/projects", account(account_id))
+}
+
+/// The Pages asset endpoints, e.g. `check-missing` or `upload`.
+pub fn pages_assets(action: &str) -> String {
+    format!("{}/pages/assets/{}", api_base_url(), action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_urls_on_the_api_base_url() {
+        let base = api_base_url();
+        assert_eq!(
+            script("abc", "my-worker"),
+            format!("{}/accounts/abc/workers/scripts/my-worker", base)
+        );
+        assert_eq!(
+            durable_object_namespace("abc", "ns1"),
+            format!(
+                "{}/accounts/abc/workers/durable_objects/namespaces/ns1",
+                base
+            )
+        );
+        assert_eq!(
+            dispatch_namespace_script("abc", "staging", "my-worker"),
+            format!(
+                "{}/accounts/abc/workers/dispatch/namespaces/staging/scripts/my-worker",
+                base
+            )
+        );
+        assert_eq!(routes("z1"), format!("{}/zones/z1/workers/routes", base));
+    }
+}
@@ -0,0 +1,180 @@
+use std::cmp;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use cloudflare::framework::response::ApiFailure;
+use lazy_static::lazy_static;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::terminal::summary;
+
+// requests in flight at once, across all of wrangler's threads
+const MAX_IN_FLIGHT: usize = 6;
+// times a rate limited request is sent again before its 429 is returned
+const MAX_RETRIES: u32 = 4;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+struct State {
+    in_flight: usize,
+    limit: usize,
+    // no request is sent before this, after the API rate limited one
+    paused_until: Option<Instant>,
+}
+
+lazy_static! {
+    static ref STATE: Mutex<State> = Mutex::new(State {
+        in_flight: 0,
+        limit: MAX_IN_FLIGHT,
+        paused_until: None,
+    });
+    static ref RELEASED: Condvar = Condvar::new();
+}
+
+/// What one attempt at a request came to.
+pub enum Outcome<T> {
+    Done(T),
+    /// The API answered 429 and the request can be sent again, after `retry_after` if it
+    /// said how long to wait.
+    RateLimited {
+        result: T,
+        retry_after: Option<Duration>,
+    },
+}
+
+/// Sends a request with `attempt` once there's room for it, so bulk uploads, Sites and
+/// publishes to several environments at once don't trip the account's rate limit. When
+/// the API rate limits a request, every request backs off and fewer are let through at
+/// once, and the request is sent again a few times before its result is returned.
+pub fn governed<T>(mut attempt: impl FnMut() -> Outcome<T>) -> T {
+    let mut retries = 0;
+    loop {
+        let permit = Permit::acquire();
+        summary::api_call();
+        let outcome = attempt();
+        drop(permit);
+
+        match outcome {
+            Outcome::Done(result) => {
+                succeeded();
+                return result;
+            }
+            Outcome::RateLimited {
+                result,
+                retry_after,
+            } => {
+                let delay = backoff(retries, retry_after);
+                rate_limited(delay);
+                if retries == MAX_RETRIES {
+                    return result;
+                }
+                log::info!("Rate limited by the API, sending again in {:?}", delay);
+                retries += 1;
+            }
+        }
+    }
+}
+
+/// Sends a request made with the cloudflare-rs client through the governor.
+pub fn governed_request<T>(
+    mut request: impl FnMut() -> Result<T, ApiFailure>,
+) -> Result<T, ApiFailure> {
+    governed(|| {
+        let result = request();
+        match &result {
+            Err(ApiFailure::Error(status, _)) if status.as_u16() == 429 => Outcome::RateLimited {
+                result,
+                retry_after: None,
+            },
+            _ => Outcome::Done(result),
+        }
+    })
+}
+
+/// How long the API asked to wait, from a Retry-After header in seconds.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+struct Permit;
+
+impl Permit {
+    fn acquire() -> Self {
+        let mut state = STATE.lock().unwrap();
+        loop {
+            match state.paused_until {
+                Some(until) if until > Instant::now() => {
+                    let wait = until.saturating_duration_since(Instant::now());
+                    state = RELEASED.wait_timeout(state, wait).unwrap().0;
+                }
+                _ if state.in_flight >= state.limit => {
+                    state = RELEASED.wait(state).unwrap();
+                }
+                _ => break,
+            }
+        }
+        state.in_flight += 1;
+        Permit
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        STATE.lock().unwrap().in_flight -= 1;
+        RELEASED.notify_one();
+    }
+}
+
+fn rate_limited(delay: Duration) {
+    let mut state = STATE.lock().unwrap();
+    state.limit = cmp::max(1, state.limit / 2);
+    let until = Instant::now() + delay;
+    match state.paused_until {
+        Some(paused) if paused >= until => (),
+        _ => state.paused_until = Some(until),
+    }
+}
+
+// let one more request through at once after each success, up to the maximum
+fn succeeded() {
+    let mut state = STATE.lock().unwrap();
+    if state.limit < MAX_IN_FLIGHT {
+        state.limit += 1;
+        RELEASED.notify_one();
+    }
+}
+
+// Retry-After when the API sent it, otherwise 1s, 2s, 4s... for each retry
+fn backoff(retries: u32, retry_after: Option<Duration>) -> Duration {
+    let delay = retry_after.unwrap_or_else(|| Duration::from_secs(1 << cmp::min(retries, 6)));
+    cmp::min(delay, MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn it_backs_off_for_as_long_as_the_api_asks() {
+        assert_eq!(backoff(0, None), Duration::from_secs(1));
+        assert_eq!(backoff(3, None), Duration::from_secs(8));
+        assert_eq!(
+            backoff(0, Some(Duration::from_secs(5))),
+            Duration::from_secs(5)
+        );
+        assert_eq!(backoff(0, Some(Duration::from_secs(600))), MAX_BACKOFF);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(30)));
+    }
+}
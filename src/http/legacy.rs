@@ -22,6 +22,21 @@ pub fn featured_legacy_auth_client(user: &GlobalUser, feature: Feature) -> Clien
     get_client(user, Some(feature))
 }
 
+/// The async counterpart of `legacy_auth_client`, for requests made from inside a tokio
+/// runtime, where the blocking client can't be used.
+pub fn legacy_auth_client_async(user: &GlobalUser) -> reqwest::Client {
+    let mut headers = headers(None);
+    add_auth_headers(&mut headers, user);
+
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .redirect(Policy::none())
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS))
+        .build()
+        .expect("could not create authenticated http client")
+}
+
 fn get_client(user: &GlobalUser, feature: Option<Feature>) -> Client {
     let mut headers = headers(feature);
     add_auth_headers(&mut headers, user);
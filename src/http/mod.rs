@@ -1,8 +1,22 @@
+pub mod cache;
 pub(self) mod cf;
+pub mod compress;
+pub mod endpoints;
 pub(crate) mod feature;
+pub(self) mod governor;
 pub(self) mod legacy;
+pub mod network;
+pub(self) mod preflight;
+pub mod trace;
 
 pub const DEFAULT_HTTP_TIMEOUT_SECONDS: u64 = 60;
-pub use cf::{cf_v4_api_client_async, cf_v4_client, featured_cf_v4_client, format_error};
+pub use cf::{
+    api_base_url, api_base_url_overridden, api_environment, api_error, cf_v4_api_client_async,
+    cf_v4_client, featured_cf_v4_client, format_error, read_result, status_error,
+};
 pub use feature::Feature;
-pub use legacy::{client, featured_legacy_auth_client, legacy_auth_client};
+pub use legacy::{
+    client, featured_legacy_auth_client, legacy_auth_client, legacy_auth_client_async,
+};
+pub use preflight::{require_scopes, Scope};
+pub use trace::{send_traced_async, Traced, TracedClient};
@@ -0,0 +1,78 @@
+use std::env;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::error::WranglerError;
+use crate::http::api_base_url_overridden;
+use crate::settings::env_flag;
+
+const API_HOST: &str = "api.cloudflare.com";
+// much shorter than the request timeouts, so an offline machine is caught quickly
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const PROXY_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"];
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Keeps wrangler off the network, for `--offline`.
+pub fn go_offline() {
+    OFFLINE.store(true, Ordering::SeqCst);
+}
+
+/// Whether wrangler should stay off the network, for `--offline` or `WRANGLER_OFFLINE=1`.
+pub fn offline() -> bool {
+    OFFLINE.load(Ordering::SeqCst) || env_flag("WRANGLER_OFFLINE")
+}
+
+/// Checks that the Cloudflare API can be reached before a command that needs it, so that
+/// without a network the command fails in a few seconds rather than after the request
+/// timeouts. The check is skipped behind a proxy, as the API may only be reachable
+/// through it, and when `$WRANGLER_API_BASE_URL` points wrangler elsewhere.
+pub fn require(command: &str) -> Result<(), failure::Error> {
+    if offline() {
+        return Err(WranglerError::Network(format!(
+            "`wrangler {}` needs the Cloudflare API, but wrangler is running offline (--offline or WRANGLER_OFFLINE)",
+            command
+        ))
+        .into());
+    }
+    if api_base_url_overridden() || PROXY_VARS.iter().any(|var| env::var_os(var).is_some()) {
+        return Ok(());
+    }
+
+    match probe(API_HOST) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(WranglerError::Network(format!(
+            "The network is unavailable: {} could not be reached ({}). `wrangler {}` needs the Cloudflare API, while commands like build, env, types, init and `kv:bulk … --dry-run` work offline.",
+            API_HOST, e, command
+        ))
+        .into()),
+    }
+}
+
+// Connects to `host` at each of its addresses in turn, as the HTTP client does, so that
+// e.g. a broken IPv6 route doesn't fail the check while IPv4 works.
+fn probe(host: &str) -> Result<(), String> {
+    let addrs = (host, 443).to_socket_addrs().map_err(|e| e.to_string())?;
+    let mut failure = "no address found".to_string();
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, PROBE_TIMEOUT) {
+            Ok(_) => return Ok(()),
+            Err(e) => failure = format!("{}: {}", addr, e),
+        }
+    }
+    Err(failure)
+}
+
+/// Fails with a network error when wrangler is running offline, for a step that would
+/// otherwise download `what`.
+pub fn require_download(what: &str) -> Result<(), failure::Error> {
+    if offline() {
+        return Err(WranglerError::Network(format!(
+            "{} has to be downloaded, but wrangler is running offline. Run `wrangler tools vendor` while online, and set WRANGLER_TOOLS_DIR to the directory it creates.",
+            what
+        ))
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::error::WranglerError;
+use crate::http::{endpoints, legacy_auth_client, Traced};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
+
+/// A permission group an API token needs for a command to succeed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scope {
+    WorkersScriptsWrite,
+    WorkersRoutesWrite,
+    WorkersKvStorageWrite,
+}
+
+impl Scope {
+    // the permission group name as it appears in the API and the dashboard
+    fn permission_group(self) -> &'static str {
+        match self {
+            Scope::WorkersScriptsWrite => "Workers Scripts Write",
+            Scope::WorkersRoutesWrite => "Workers Routes Write",
+            Scope::WorkersKvStorageWrite => "Workers KV Storage Write",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.permission_group())
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct VerifiedToken {
+    id: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct TokenDetails {
+    policies: Vec<Policy>,
+}
+
+#[derive(Deserialize)]
+struct Policy {
+    effect: String,
+    permission_groups: Vec<PermissionGroup>,
+}
+
+#[derive(Deserialize)]
+struct PermissionGroup {
+    name: String,
+}
+
+/// Checks that the user's API token has every one of `scopes` before a long running
+/// command starts, so a missing permission fails up front instead of with a 403 halfway
+/// through. Global API keys have every permission, so they are not checked.
+pub fn require_scopes(user: &GlobalUser, scopes: &[Scope]) -> Result<(), failure::Error> {
+    if let GlobalUser::GlobalKeyAuth { .. } = user {
+        return Ok(());
+    }
+
+    let client = legacy_auth_client(user);
+    let res = client.get(&endpoints::verify_token()).send_traced()?;
+    if !res.status().is_success() {
+        return Err(WranglerError::Auth(
+            "Your API token could not be verified. Run `wrangler login` or `wrangler config` to authenticate again.".to_string(),
+        )
+        .into());
+    }
+    let token: Response<VerifiedToken> = serde_json::from_str(&res.text()?)?;
+    if token.result.status != "active" {
+        return Err(WranglerError::Auth(format!(
+            "Your API token is {}. Run `wrangler login` or `wrangler config` to authenticate again.",
+            token.result.status
+        ))
+        .into());
+    }
+
+    // reading a token's policies needs the "API Tokens Read" permission, which most
+    // tokens don't have; in that case let the command find out the hard way, but say so
+    let res = client
+        .get(&endpoints::token(&token.result.id))
+        .send_traced()?;
+    if !res.status().is_success() {
+        StdErr::info(&format!(
+            "The permissions of your API token could not be read ({}), so they were not checked before starting. Add the 'API Tokens Read' permission to your token to have them checked.",
+            res.status()
+        ));
+        return Ok(());
+    }
+    let details: Response<TokenDetails> = serde_json::from_str(&res.text()?)?;
+
+    let missing = missing_scopes(&details.result.policies, scopes);
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing
+            .iter()
+            .map(|scope| format!("'{}'", styles::highlight(scope.to_string())))
+            .collect();
+        return Err(WranglerError::Auth(format!(
+            "Your API token is missing the {} permission(s).\nCreate a token that has them at {} and authenticate with `wrangler config`.",
+            missing.join(", "),
+            styles::url("https://dash.cloudflare.com/profile/api-tokens")
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+// A scope is missing unless a policy allows it, and it is missing all the same when
+// another policy denies it, as a deny wins over any allow.
+fn missing_scopes(policies: &[Policy], scopes: &[Scope]) -> Vec<Scope> {
+    let has = |effect: &str, scope: &Scope| {
+        policies.iter().any(|policy| {
+            policy.effect == effect
+                && policy
+                    .permission_groups
+                    .iter()
+                    .any(|group| group.name == scope.permission_group())
+        })
+    };
+    scopes
+        .iter()
+        .filter(|scope| !has("allow", scope) || has("deny", scope))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(effect: &str, groups: &[&str]) -> Policy {
+        Policy {
+            effect: effect.to_string(),
+            permission_groups: groups
+                .iter()
+                .map(|name| PermissionGroup {
+                    name: name.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn it_finds_missing_scopes() {
+        let policies = vec![
+            policy("allow", &["Workers Scripts Write"]),
+            policy("deny", &["Workers Routes Write"]),
+        ];
+        let missing = missing_scopes(
+            &policies,
+            &[Scope::WorkersScriptsWrite, Scope::WorkersRoutesWrite],
+        );
+        assert_eq!(missing, vec![Scope::WorkersRoutesWrite]);
+    }
+
+    #[test]
+    fn it_subtracts_denied_scopes() {
+        let policies = vec![
+            policy(
+                "allow",
+                &["Workers Scripts Write", "Workers KV Storage Write"],
+            ),
+            policy("deny", &["Workers KV Storage Write"]),
+        ];
+        let missing = missing_scopes(
+            &policies,
+            &[Scope::WorkersScriptsWrite, Scope::WorkersKvStorageWrite],
+        );
+        assert_eq!(missing, vec![Scope::WorkersKvStorageWrite]);
+    }
+}
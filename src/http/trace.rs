@@ -0,0 +1,352 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::framework::endpoint::Endpoint;
+use cloudflare::framework::response::{ApiFailure, ApiResponse, ApiResult};
+use lazy_static::lazy_static;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode, Url};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::http::cf::api_base_url;
+use crate::http::governor::{governed, governed_request, retry_after, Outcome};
+use crate::settings::get_wrangler_home_dir;
+use crate::support::run_log;
+use crate::terminal::summary;
+
+const LOG_FILE_NAME: &str = "wrangler.log";
+const REDACTED_HEADERS: &[&str] = &["authorization", "x-auth-key", "x-auth-email", "cookie"];
+// how many exchanges are kept for a support bundle, whether or not tracing is on
+const RECENT_EXCHANGES: usize = 20;
+
+lazy_static! {
+    static ref LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+    static ref RECENT: Mutex<VecDeque<Exchange>> = Mutex::new(VecDeque::new());
+    // path -> sunset date, for the endpoints the API said are deprecated
+    static ref DEPRECATED: Mutex<BTreeMap<String, Option<String>>> = Mutex::new(BTreeMap::new());
+}
+
+/// A request sent to the API and what came of it, as a line of the log file.
+#[derive(Clone, Debug)]
+pub struct Exchange {
+    pub line: String,
+    /// The API answered with an error status, or didn't answer at all.
+    pub failed: bool,
+}
+
+/// Turns on API request tracing for `--log-level debug` or `WRANGLER_LOG=debug`.
+/// Returns the path of the log file when tracing was turned on.
+pub fn init(level: &str) -> Result<Option<PathBuf>, failure::Error> {
+    match level.to_lowercase().as_str() {
+        "debug" | "trace" => (),
+        "error" | "warn" | "info" => return Ok(None),
+        other => failure::bail!(
+            "Unknown log level \"{}\". Use one of error, warn, info, debug or trace",
+            other
+        ),
+    }
+
+    let path = log_path()?;
+    fs::create_dir_all(path.parent().unwrap())?;
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    *LOG_FILE.lock().unwrap() = Some(file);
+
+    Ok(Some(path))
+}
+
+/// Where API requests are logged when tracing is on.
+pub fn log_path() -> Result<PathBuf, failure::Error> {
+    Ok(get_wrangler_home_dir()?.join("logs").join(LOG_FILE_NAME))
+}
+
+pub trait Traced {
+    /// Sends the request like `send` through the rate limit governor, and logs it when
+    /// tracing is on. The last few requests are kept for a support bundle either way.
+    fn send_traced(self) -> reqwest::Result<Response>;
+}
+
+impl Traced for RequestBuilder {
+    fn send_traced(self) -> reqwest::Result<Response> {
+        let mut next = Some(self);
+        governed(|| {
+            let request = next
+                .take()
+                .expect("a request is only sent again when it was cloned");
+            // streaming bodies (files, multipart forms) can't be cloned, so those are
+            // never sent again
+            next = request.try_clone();
+            let result = send_logged(request);
+
+            let rate_limited = match &result {
+                Ok(res) if res.status() == StatusCode::TOO_MANY_REQUESTS && next.is_some() => {
+                    Some(retry_after(res.headers()))
+                }
+                _ => None,
+            };
+            match rate_limited {
+                Some(retry_after) => Outcome::RateLimited {
+                    result,
+                    retry_after,
+                },
+                None => Outcome::Done(result),
+            }
+        })
+    }
+}
+
+/// Sends requests with the cloudflare-rs clients through the rate limit governor, and
+/// logs them like `Traced::send_traced`.
+pub trait TracedClient {
+    fn request_traced<ResultType, QueryType, BodyType>(
+        &self,
+        endpoint: &dyn Endpoint<ResultType, QueryType, BodyType>,
+    ) -> ApiResponse<ResultType>
+    where
+        ResultType: ApiResult,
+        QueryType: Serialize,
+        BodyType: Serialize;
+}
+
+impl<C: ApiClient> TracedClient for C {
+    fn request_traced<ResultType, QueryType, BodyType>(
+        &self,
+        endpoint: &dyn Endpoint<ResultType, QueryType, BodyType>,
+    ) -> ApiResponse<ResultType>
+    where
+        ResultType: ApiResult,
+        QueryType: Serialize,
+        BodyType: Serialize,
+    {
+        let method = format!("{:?}", endpoint.method()).to_uppercase();
+        let url = format!("{}/{}", api_base_url(), endpoint.path());
+        governed_request(|| {
+            let start = Instant::now();
+            let result = self.request(endpoint);
+            // the client doesn't say which status a request that succeeded was answered with
+            let (outcome, failed) = match &result {
+                Ok(_) => ("ok".to_string(), false),
+                Err(ApiFailure::Error(status, _)) => (status.as_u16().to_string(), true),
+                Err(ApiFailure::Invalid(e)) => (format!("error: {}", e), true),
+            };
+            let line = format!(
+                "{} {} {} {} {}ms",
+                timestamp(),
+                method,
+                url,
+                outcome,
+                start.elapsed().as_millis()
+            );
+            record(line, failed);
+            result
+        })
+    }
+}
+
+/// Sends a request with the async client like `send`, and logs it like
+/// `Traced::send_traced`. Only tail sends those, one at a time, so they don't go through
+/// the rate limit governor.
+pub async fn send_traced_async(
+    request_builder: reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    let request = request_builder.try_clone().and_then(|r| r.build().ok());
+    summary::api_call();
+    let start = Instant::now();
+    let result = request_builder.send().await;
+
+    let sent = request.as_ref().map(|r| (r.method(), r.url(), r.headers()));
+    let answer = match &result {
+        Ok(res) => Ok((res.url(), res.status(), res.headers())),
+        Err(e) => Err(e.to_string()),
+    };
+    log_exchange(sent, answer, start.elapsed());
+    result
+}
+
+/// An endpoint the API answered with a Deprecation or Sunset header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deprecation {
+    pub path: String,
+    /// When the endpoint goes away, if the API said.
+    pub sunset: Option<String>,
+}
+
+/// The endpoints used during this run that the API said are deprecated.
+pub fn deprecations() -> Vec<Deprecation> {
+    DEPRECATED
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(path, sunset)| Deprecation {
+            path: path.clone(),
+            sunset: sunset.clone(),
+        })
+        .collect()
+}
+
+/// The last few requests sent to the API, oldest first.
+pub fn recent_exchanges() -> Vec<Exchange> {
+    RECENT.lock().unwrap().iter().cloned().collect()
+}
+
+fn send_logged(request_builder: RequestBuilder) -> reqwest::Result<Response> {
+    // streaming bodies (files, multipart forms) can't be cloned, so for
+    // those only the response side of the exchange is logged
+    let request = request_builder.try_clone().and_then(|r| r.build().ok());
+    // streamed bodies are counted for the summary as they're read instead
+    if let Some(body) = request.as_ref().and_then(|r| r.body()?.as_bytes()) {
+        summary::uploaded(body.len() as u64);
+    }
+    let start = Instant::now();
+    let result = request_builder.send();
+
+    let sent = request.as_ref().map(|r| (r.method(), r.url(), r.headers()));
+    let answer = match &result {
+        Ok(res) => Ok((res.url(), res.status(), res.headers())),
+        Err(e) => Err(e.to_string()),
+    };
+    log_exchange(sent, answer, start.elapsed());
+    result
+}
+
+// Logs a request: when it was sent, its method and URL, the status it was answered with
+// or why it failed, how long it took, its ray ID and its (redacted) headers. Only the
+// response side is known of requests that couldn't be built ahead of sending them.
+fn log_exchange(
+    sent: Option<(&Method, &Url, &HeaderMap)>,
+    answer: Result<(&Url, StatusCode, &HeaderMap), String>,
+    elapsed: Duration,
+) {
+    let mut line = format!("{} ", timestamp());
+    match (&sent, &answer) {
+        (Some((method, url, _)), _) => line.push_str(&format!("{} {}", method, url)),
+        (None, Ok((url, _, _))) => line.push_str(&format!("- {}", url)),
+        (None, Err(_)) => line.push('-'),
+    }
+    match &answer {
+        Ok((_, status, _)) => line.push_str(&format!(" {}", status.as_u16())),
+        Err(e) => line.push_str(&format!(" error: {}", e)),
+    }
+    line.push_str(&format!(" {}ms", elapsed.as_millis()));
+    // the ray ID lets Cloudflare support find the request on their side
+    if let Some(ray) = answer
+        .as_ref()
+        .ok()
+        .and_then(|(_, _, headers)| headers.get("cf-ray"))
+        .and_then(|ray| ray.to_str().ok())
+    {
+        line.push_str(&format!(" cf-ray: {}", ray));
+    }
+    if let Some((_, _, headers)) = &sent {
+        line.push_str(&format!(" headers: {}", redact(headers)));
+    }
+
+    if let Ok((url, _, headers)) = &answer {
+        note_deprecation(url.path(), headers);
+    }
+    let failed = match &answer {
+        Ok((_, status, _)) => !status.is_success(),
+        Err(_) => true,
+    };
+    record(line, failed);
+}
+
+// Writes a request's line to the log file and the log of this run, and keeps it for a
+// support bundle
+fn record(line: String, failed: bool) {
+    write_line(&line);
+    run_log::write("api", json!({ "request": &line, "failed": failed }));
+    keep(Exchange { line, failed });
+}
+
+fn note_deprecation(path: &str, headers: &HeaderMap) {
+    let sunset = headers
+        .get("sunset")
+        .and_then(|sunset| sunset.to_str().ok())
+        .map(String::from);
+    if headers.contains_key("deprecation") || sunset.is_some() {
+        DEPRECATED.lock().unwrap().insert(path.to_string(), sunset);
+    }
+}
+
+fn keep(exchange: Exchange) {
+    let mut recent = RECENT.lock().unwrap();
+    if recent.len() == RECENT_EXCHANGES {
+        recent.pop_front();
+    }
+    recent.push_back(exchange);
+}
+
+fn write_line(line: &str) {
+    if let Some(file) = LOG_FILE.lock().unwrap().as_mut() {
+        // tracing should never be the reason a command fails
+        if let Err(e) = writeln!(file, "{}", line) {
+            log::warn!("Could not write to the log file: {}", e);
+        }
+    }
+}
+
+fn redact(headers: &HeaderMap) -> String {
+    let headers: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| {
+            if REDACTED_HEADERS.contains(&name.as_str()) {
+                format!("{}: [redacted]", name)
+            } else {
+                format!("{}: {}", name, value.to_str().unwrap_or("[binary]"))
+            }
+        })
+        .collect();
+    format!("{{{}}}", headers.join(", "))
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn it_notes_deprecated_endpoints() {
+        let path = "/client/v4/accounts/1/workers/scripts/worker/deprecated-test";
+        note_deprecation(path, &HeaderMap::new());
+        assert!(deprecations().iter().all(|d| d.path != path));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Deprecation", HeaderValue::from_static("true"));
+        headers.insert(
+            "Sunset",
+            HeaderValue::from_static("Wed, 11 Nov 2026 23:59:59 GMT"),
+        );
+        note_deprecation(path, &headers);
+        assert!(deprecations().contains(&Deprecation {
+            path: path.to_string(),
+            sunset: Some("Wed, 11 Nov 2026 23:59:59 GMT".to_string()),
+        }));
+    }
+
+    #[test]
+    fn it_redacts_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", HeaderValue::from_static("Bearer secret"));
+        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+
+        let redacted = redact(&headers);
+        assert!(!redacted.contains("secret"));
+        assert!(redacted.contains("authorization: [redacted]"));
+        assert!(redacted.contains("content-type: application/json"));
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use ring::digest::{digest, SHA256};
+use tempfile::TempDir;
+
+use super::dependencies::{Checksum, CHECKSUMS};
+use crate::http;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::Unit;
+
+// smaller downloads are over before progress would be worth showing
+const PROGRESS_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Downloads `url`, the prebuilt `tool` at `version` for `target`, into a temporary
+/// directory, and checks it against the checksum pinned for it in `CHECKSUMS`. A download
+/// that doesn't match is rejected. One with no pinned checksum, like a version pinned in
+/// wrangler.toml that this wrangler doesn't know, is installed with a warning.
+pub fn download_verified(
+    url: &str,
+    tool: &str,
+    version: &str,
+    target: &str,
+) -> Result<(TempDir, PathBuf), failure::Error> {
+    let client = http::client();
+    let mut res = client.get(url).send()?;
+    if !res.status().is_success() {
+        failure::bail!("could not download {} (status {})", url, res.status())
+    }
+    let bytes = match res.content_length() {
+        Some(length) if length >= PROGRESS_MIN_BYTES => {
+            let file_name = url.rsplit('/').next().unwrap_or(url);
+            let progress =
+                StdErr::progress(&format!("Downloading {}", file_name), length, Unit::Bytes);
+            let mut bytes = Vec::with_capacity(length as usize);
+            let mut chunk = [0; 64 * 1024];
+            loop {
+                let read = res.read(&mut chunk)?;
+                if read == 0 {
+                    break;
+                }
+                bytes.extend_from_slice(&chunk[..read]);
+                progress.inc(read as u64);
+            }
+            progress.finish_and_clear();
+            bytes
+        }
+        _ => res.bytes()?.to_vec(),
+    };
+
+    let actual = sha256_hex(&bytes);
+    match pinned_checksum(CHECKSUMS, tool, version, target) {
+        Some(expected) if actual != expected => failure::bail!(
+            "checksum mismatch for {}\nexpected: {}\nactual:   {}\nThe download may have been tampered with, so it will not be installed.",
+            url,
+            expected,
+            actual
+        ),
+        Some(_) => log::info!("verified {} with checksum {}", url, actual),
+        None => StdErr::warn(&format!(
+            "{} v{} has no checksum pinned in this version of wrangler, so the download from {} could not be verified (SHA-256 {}).",
+            tool, version, url, actual
+        )),
+    }
+
+    let dir = tempfile::tempdir()?;
+    let file_name = url.rsplit('/').next().unwrap_or("download.tar.gz");
+    let path = dir.path().join(file_name);
+    fs::write(&path, &bytes)?;
+
+    Ok((dir, path))
+}
+
+fn pinned_checksum<'a>(
+    checksums: &'a [Checksum],
+    tool: &str,
+    version: &str,
+    target: &str,
+) -> Option<&'a str> {
+    checksums
+        .iter()
+        .find(|pin| pin.tool == tool && pin.version == version && pin.target == target)
+        .map(|pin| pin.sha256)
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    digest(&SHA256, bytes)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_hashes_with_sha256() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn it_finds_pinned_checksums() {
+        let digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let checksums = [Checksum {
+            tool: "wasm-pack",
+            version: "0.9.1",
+            target: "x86_64-apple-darwin",
+            sha256: digest,
+        }];
+        assert_eq!(
+            pinned_checksum(&checksums, "wasm-pack", "0.9.1", "x86_64-apple-darwin"),
+            Some(digest)
+        );
+        assert_eq!(
+            pinned_checksum(&checksums, "wasm-pack", "0.9.1", "x86_64-pc-windows-msvc"),
+            None
+        );
+        assert_eq!(
+            pinned_checksum(&checksums, "wasm-pack", "0.10.0", "x86_64-apple-darwin"),
+            None
+        );
+    }
+
+    #[test]
+    fn it_pins_well_formed_checksums() {
+        for pin in CHECKSUMS {
+            assert_eq!(pin.sha256.len(), 64, "{} v{}", pin.tool, pin.version);
+            assert!(pin
+                .sha256
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+        }
+    }
+}
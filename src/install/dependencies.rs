@@ -1,2 +1,18 @@
 pub const WASM_PACK_VERSION: &str = "0.9.1";
 pub const GENERATE_VERSION: &str = "0.5.0";
+
+/// The SHA-256 checksum of a prebuilt tool wrangler downloads.
+pub struct Checksum {
+    pub tool: &'static str,
+    pub version: &'static str,
+    /// The target triple, or "" for a tool that is the same on every platform, like
+    /// wranglerjs
+    pub target: &'static str,
+    pub sha256: &'static str,
+}
+
+/// Checksums of the prebuilt tools, pinned here rather than fetched from the host the
+/// tools are downloaded from, so a host or mirror that serves a tampered tool can't serve
+/// a checksum to match. When bumping a version above, add the checksums of each of its
+/// targets from the tool's release.
+pub const CHECKSUMS: &[Checksum] = &[];
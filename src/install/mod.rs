@@ -1,18 +1,25 @@
+pub(crate) mod checksum;
 pub mod dependencies;
 pub mod target;
 
+use crate::http::network;
 use crate::terminal::emoji;
+use crate::terminal::message::{Message, StdOut};
 
 use binary_install::{Cache, Download};
 use log::info;
 use semver::Version;
+use url::Url;
 
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use lazy_static::lazy_static;
 
+const DEFAULT_TOOLS_HOST: &str = "https://workers.cloudflare.com";
+
 lazy_static! {
     static ref CACHE: Cache = get_wrangler_cache().expect("Could not get Wrangler cache location");
 }
@@ -30,11 +37,10 @@ pub fn install_cargo_generate() -> Result<PathBuf, failure::Error> {
     install(tool_name, tool_author, is_binary, version)?.binary(tool_name)
 }
 
-pub fn install_wasm_pack() -> Result<PathBuf, failure::Error> {
+pub fn install_wasm_pack(version: Version) -> Result<PathBuf, failure::Error> {
     let tool_name = "wasm-pack";
     let tool_author = "rustwasm";
     let is_binary = true;
-    let version = Version::parse(dependencies::WASM_PACK_VERSION)?;
     install(tool_name, tool_author, is_binary, version)?.binary(tool_name)
 }
 
@@ -44,12 +50,33 @@ pub fn install(
     is_binary: bool,
     version: Version,
 ) -> Result<Download, failure::Error> {
+    if let Ok(tools_dir) = env::var("WRANGLER_TOOLS_DIR") {
+        return vendored(Path::new(&tools_dir), tool_name, &version);
+    }
+
+    if is_binary && prefer_system_tools() {
+        if let Some(download) = system_tool(tool_name, &version) {
+            return Ok(download);
+        }
+    }
+
     let download = match tool_needs_update(tool_name, version)? {
         ToolDownload::NeedsInstall(version) => {
-            println!("{}  Installing {} v{}...", emoji::DOWN, tool_name, version);
+            network::require_download(&format!("{} v{}", tool_name, version))?;
+            StdOut::message(&format!(
+                "{}  Installing {} v{}...",
+                emoji::DOWN,
+                tool_name,
+                version
+            ));
             let binaries: Vec<&str> = if is_binary { vec![tool_name] } else { vec![] };
-            let download =
-                download_prebuilt(tool_name, owner, &version.to_string(), binaries.as_ref());
+            let download = download_prebuilt(
+                &CACHE,
+                tool_name,
+                owner,
+                &version.to_string(),
+                binaries.as_ref(),
+            );
             match download {
                 Ok(download) => Ok(download),
                 Err(e) => Err(failure::format_err!(
@@ -65,6 +92,91 @@ pub fn install(
     Ok(download)
 }
 
+/// The directory downloaded tools are installed in.
+pub fn cache_dir() -> PathBuf {
+    CACHE.destination.clone()
+}
+
+/// Downloads a tool into `dir` so it can be installed from there with `WRANGLER_TOOLS_DIR`
+/// on a machine without network access.
+pub fn vendor(
+    tool_name: &str,
+    owner: &str,
+    is_binary: bool,
+    version: Version,
+    dir: &Path,
+) -> Result<Download, failure::Error> {
+    fs::create_dir_all(dir)?;
+    let cache = Cache::at(dir);
+    let binaries: Vec<&str> = if is_binary { vec![tool_name] } else { vec![] };
+    download_prebuilt(
+        &cache,
+        tool_name,
+        owner,
+        &version.to_string(),
+        binaries.as_ref(),
+    )
+}
+
+// With WRANGLER_TOOLS_DIR set nothing is downloaded, so builds can run without network access
+fn vendored(
+    tools_dir: &Path,
+    tool_name: &str,
+    version: &Version,
+) -> Result<Download, failure::Error> {
+    let path = tools_dir.join(format!("{}-{}", tool_name, version));
+    if path.exists() {
+        log::debug!("tool {} located at {:?}", tool_name, path);
+        Ok(Download::at(&path))
+    } else {
+        failure::bail!(
+            "{} v{} was not found in $WRANGLER_TOOLS_DIR ({}). Run `wrangler tools vendor` somewhere with network access to download it.",
+            tool_name,
+            version,
+            tools_dir.display()
+        )
+    }
+}
+
+// Opt in with WRANGLER_SYSTEM_TOOLS=true to use tools installed by a package manager
+fn prefer_system_tools() -> bool {
+    match env::var("WRANGLER_SYSTEM_TOOLS") {
+        Ok(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+        Err(_) => false,
+    }
+}
+
+// Finds `tool_name` on $PATH, and uses it if `<tool_name> --version` reports exactly
+// `target_version`, as cached tools must.
+fn system_tool(tool_name: &str, target_version: &Version) -> Option<Download> {
+    let path = which::which(tool_name).ok()?;
+    let output = Command::new(&path).arg("--version").output().ok()?;
+    let installed_version = parse_version_output(&String::from_utf8_lossy(&output.stdout))?;
+
+    if &installed_version == target_version {
+        info!(
+            "using {} v{} from {}",
+            tool_name,
+            installed_version,
+            path.display()
+        );
+        Some(Download::at(path.parent()?))
+    } else {
+        info!(
+            "{} v{} on $PATH is not v{}, downloading it instead",
+            tool_name, installed_version, target_version
+        );
+        None
+    }
+}
+
+// `wasm-pack --version` prints `wasm-pack 0.9.1`, `cargo generate --version` `cargo-generate 0.5.0`
+fn parse_version_output(output: &str) -> Option<Version> {
+    output
+        .split_whitespace()
+        .find_map(|word| Version::parse(word.trim_start_matches('v')).ok())
+}
+
 fn tool_needs_update(
     tool_name: &str,
     target_version: Version,
@@ -110,26 +222,36 @@ fn get_installation(
 }
 
 fn download_prebuilt(
+    cache: &Cache,
     tool_name: &str,
     owner: &str,
     version: &str,
     binaries: &[&str],
 ) -> Result<Download, failure::Error> {
-    let url = match prebuilt_url(tool_name, owner, version) {
-        Some(url) => url,
+    let target = match prebuilt_target(tool_name) {
+        Some(target) => target,
         None => failure::bail!(format!(
             "no prebuilt {} binaries are available for this platform",
             tool_name
         )),
     };
+    let url = prebuilt_url(tool_name, owner, version, target);
 
     info!("prebuilt artifact {}", url);
 
+    // verify the download before it is unpacked into the cache, then
+    // hand the verified copy to the cache instead of the remote url
+    let (_dir, verified_path) = checksum::download_verified(&url, tool_name, version, target)?;
+    let verified_url = match Url::from_file_path(&verified_path) {
+        Ok(url) => url.to_string(),
+        Err(_) => failure::bail!("could not read {}", verified_path.display()),
+    };
+
     // no binaries are expected; downloading it as an artifact
     let res = if !binaries.is_empty() {
-        CACHE.download_version(true, tool_name, binaries, &url, version)?
+        cache.download_version(true, tool_name, binaries, &verified_url, version)?
     } else {
-        CACHE.download_artifact_version(tool_name, &url, version)?
+        cache.download_artifact_version(tool_name, &verified_url, version)?
     };
 
     match res {
@@ -138,35 +260,68 @@ fn download_prebuilt(
     }
 }
 
-fn prebuilt_url(tool_name: &str, owner: &str, version: &str) -> Option<String> {
+// Tools can be downloaded from a mirror of workers.cloudflare.com set with $WRANGLER_TOOLS_HOST,
+// for networks that block the default host. Proxies are picked up from $HTTPS_PROXY.
+fn tools_host() -> String {
+    match env::var("WRANGLER_TOOLS_HOST") {
+        Ok(host) if !host.is_empty() => host.trim_end_matches('/').to_string(),
+        _ => DEFAULT_TOOLS_HOST.to_string(),
+    }
+}
+
+// The target a prebuilt tool is downloaded for, "" for wranglerjs which runs anywhere,
+// or None when there are no prebuilt binaries for this platform
+fn prebuilt_target(tool_name: &str) -> Option<&'static str> {
     if tool_name == "wranglerjs" {
-        Some(format!(
-            "https://workers.cloudflare.com/get-wranglerjs-binary/{0}/v{1}.tar.gz",
-            tool_name, version
-        ))
+        Some("")
+    } else if target::LINUX && target::x86_64 {
+        Some("x86_64-unknown-linux-musl")
+    } else if target::MACOS && target::x86_64 {
+        Some("x86_64-apple-darwin")
+    } else if target::WINDOWS && target::x86_64 {
+        Some("x86_64-pc-windows-msvc")
     } else {
-        let target = if target::LINUX && target::x86_64 {
-            "x86_64-unknown-linux-musl"
-        } else if target::MACOS && target::x86_64 {
-            "x86_64-apple-darwin"
-        } else if target::WINDOWS && target::x86_64 {
-            "x86_64-pc-windows-msvc"
-        } else {
-            return None;
-        };
-
-        let url = format!(
-            "https://workers.cloudflare.com/get-binary/{0}/{1}/v{2}/{3}.tar.gz",
-            owner, tool_name, version, target
-        );
-        Some(url)
+        None
+    }
+}
+
+fn prebuilt_url(tool_name: &str, owner: &str, version: &str, target: &str) -> String {
+    let host = tools_host();
+    if tool_name == "wranglerjs" {
+        format!(
+            "{0}/get-wranglerjs-binary/{1}/v{2}.tar.gz",
+            host, tool_name, version
+        )
+    } else {
+        format!(
+            "{0}/get-binary/{1}/{2}/v{3}/{4}.tar.gz",
+            host, owner, tool_name, version, target
+        )
     }
 }
 
+// Installed tools go in $WRANGLER_CACHE, then $WRANGLER_HOME/cache, and otherwise the
+// platform's cache directory, which is $XDG_CACHE_HOME/.wrangler on Linux.
 fn get_wrangler_cache() -> Result<Cache, failure::Error> {
     if let Ok(path) = env::var("WRANGLER_CACHE") {
         Ok(Cache::at(Path::new(&path)))
+    } else if let Ok(path) = env::var("WRANGLER_HOME") {
+        Ok(Cache::at(&Path::new(&path).join("cache")))
     } else {
         Cache::new("wrangler")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_tool_versions() {
+        assert_eq!(
+            parse_version_output("wasm-pack 0.9.1\n"),
+            Some(Version::parse("0.9.1").unwrap())
+        );
+        assert_eq!(parse_version_output("command not found"), None);
+    }
+}
@@ -1,7 +1,8 @@
 //! Self-installation of `wrangler`
 //!
 //! This module contains one public function which will self-install the
-//! currently running executable as `wrangler`. Our goal is to install this in
+//! currently running executable as `wrangler`, and returns where it was installed.
+//! Reporting the outcome and exiting is left to the binary. Our goal is to install this in
 //! a place that's already in `PATH`, ideally in an idiomatic location. To that
 //! end we place `wrangler` next to the `rustup` executable in `PATH`.
 //!
@@ -18,34 +19,13 @@
 
 use std::env;
 use std::fs;
-use std::io;
-use std::path::Path;
-use std::process;
+use std::path::{Path, PathBuf};
 
 use failure::{self, bail, ResultExt};
 
-pub fn install() -> ! {
-    if let Err(e) = do_install() {
-        eprintln!("{}", e);
-        for cause in e.iter_causes() {
-            eprintln!("Caused by: {}", cause);
-        }
-    }
-
-    // On Windows we likely popped up a console for the installation. If we were
-    // to exit here immediately then the user wouldn't see any error that
-    // happened above or any successful message. Let's wait for them to say
-    // they've read everything and then continue.
-    if cfg!(windows) {
-        println!("Press enter to close this window...");
-        let mut line = String::new();
-        drop(io::stdin().read_line(&mut line));
-    }
+use crate::terminal::interactive;
 
-    process::exit(0);
-}
-
-fn do_install() -> Result<(), failure::Error> {
+pub fn install() -> Result<PathBuf, failure::Error> {
     // Find `rustup.exe` in PATH, we'll be using its installation directory as
     // our installation directory.
     let rustup = match which::which("rustup") {
@@ -73,14 +53,10 @@ fn do_install() -> Result<(), failure::Error> {
     let me = env::current_exe()?;
     fs::copy(&me, &destination)
         .with_context(|_| format!("failed to copy executable to `{}`", destination.display()))?;
-    println!(
-        "info: successfully installed wrangler to `{}`",
-        destination.display()
-    );
 
     // ... and that's it!
 
-    Ok(())
+    Ok(destination)
 }
 
 fn confirm_can_overwrite(dst: &Path) -> Result<(), failure::Error> {
@@ -102,17 +78,11 @@ fn confirm_can_overwrite(dst: &Path) -> Result<(), failure::Error> {
 
     // It looks like we're at an interactive prompt, so ask the user if they'd
     // like to overwrite the previous installation.
-    eprintln!(
-        "info: existing wrangler installation found at `{}`",
+    let prompt = format!(
+        "An existing wrangler installation was found at `{}`. Would you like to overwrite it?",
         dst.display()
     );
-    eprint!("info: would you like to overwrite this file? [y/N]: ");
-    let mut line = String::new();
-    io::stdin()
-        .read_line(&mut line)
-        .with_context(|_| "failed to read stdin")?;
-
-    if line.starts_with('y') || line.starts_with('Y') {
+    if interactive::confirm(&prompt)? {
         return Ok(());
     }
 
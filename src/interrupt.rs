@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::terminal::message::{Message, StdErr};
+
+/// The exit code shells report for a process stopped by SIGINT.
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+pub const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+struct Cleanup {
+    next_id: usize,
+    children: HashMap<usize, Child>,
+    temp_files: Vec<PathBuf>,
+    operation: Option<String>,
+}
+
+lazy_static! {
+    static ref CLEANUP: Mutex<Cleanup> = Mutex::new(Cleanup::default());
+}
+
+static HANDLED_BY_COMMAND: AtomicBool = AtomicBool::new(false);
+
+/// Listens for Ctrl-C in the background. When it arrives, child processes started with
+/// `spawn` or `status` are killed and registered temporary files are removed, so no
+/// orphaned node processes are left behind, and then `on_interrupt` is called. The CLI
+/// exits with `INTERRUPTED_EXIT_CODE` there; a tool embedding wrangler decides for itself.
+pub fn install_handler<F>(on_interrupt: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut runtime = match TokioRuntime::new() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                log::warn!("Could not listen for Ctrl-C: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = runtime.block_on(tokio::signal::ctrl_c()) {
+            log::warn!("Could not listen for Ctrl-C: {}", e);
+            return;
+        }
+        if HANDLED_BY_COMMAND.load(Ordering::SeqCst) {
+            return;
+        }
+
+        match clean_up() {
+            Some(operation) => StdErr::warn(&format!(
+                "Interrupted while {}, which may not have finished. Run the command again to complete it.",
+                operation
+            )),
+            None => StdErr::warn("Interrupted"),
+        }
+        on_interrupt();
+    });
+}
+
+/// For commands like `wrangler tail` that shut down gracefully on Ctrl-C by themselves.
+pub fn handled_by_command() {
+    HANDLED_BY_COMMAND.store(true, Ordering::SeqCst);
+}
+
+// kills children and removes temp files, returning what was in progress
+fn clean_up() -> Option<String> {
+    let mut cleanup = CLEANUP.lock().unwrap();
+    for (_, mut child) in cleanup.children.drain() {
+        log::info!("Killing child process {}", child.id());
+        if let Err(e) = child.kill() {
+            log::warn!("Could not kill child process {}: {}", child.id(), e);
+        }
+        let _ = child.wait();
+    }
+    for path in cleanup.temp_files.drain(..) {
+        log::info!("Removing {}", path.display());
+        let _ = fs::remove_file(&path);
+    }
+    cleanup.operation.take()
+}
+
+/// A child process that is killed when it is dropped or when wrangler is interrupted.
+pub struct GuardedChild {
+    id: usize,
+}
+
+impl GuardedChild {
+    /// Waits for the child to exit, like `Child::wait`.
+    pub fn wait(self) -> io::Result<ExitStatus> {
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(status);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Returns the exit status if the child has exited, like `Child::try_wait`.
+    pub fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
+        let mut cleanup = CLEANUP.lock().unwrap();
+        match cleanup.children.get_mut(&self.id) {
+            Some(child) => {
+                let status = child.try_wait()?;
+                if status.is_some() {
+                    cleanup.children.remove(&self.id);
+                }
+                Ok(status)
+            }
+            // the interrupt handler killed it and is about to exit
+            None => Err(io::Error::from(io::ErrorKind::Interrupted)),
+        }
+    }
+}
+
+impl Drop for GuardedChild {
+    fn drop(&mut self) {
+        if let Some(mut child) = CLEANUP.lock().unwrap().children.remove(&self.id) {
+            if let Err(e) = child.kill() {
+                log::warn!("Could not kill child process {}: {}", child.id(), e);
+            }
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Spawns `command` so it is killed if wrangler is interrupted.
+pub fn spawn(command: &mut Command) -> io::Result<GuardedChild> {
+    let child = command.spawn()?;
+    let mut cleanup = CLEANUP.lock().unwrap();
+    let id = cleanup.next_id;
+    cleanup.next_id += 1;
+    cleanup.children.insert(id, child);
+    Ok(GuardedChild { id })
+}
+
+/// Runs `command` to completion like `Command::status`, killing it if wrangler is interrupted.
+pub fn status(command: &mut Command) -> io::Result<ExitStatus> {
+    spawn(command)?.wait()
+}
+
+/// Removes `path` if wrangler is interrupted before `forget_temp_file` is called.
+pub fn remove_on_interrupt(path: &Path) {
+    CLEANUP.lock().unwrap().temp_files.push(path.to_path_buf());
+}
+
+pub fn forget_temp_file(path: &Path) {
+    CLEANUP.lock().unwrap().temp_files.retain(|p| p != path);
+}
+
+/// Describes what wrangler is doing until the returned guard is dropped, so an interrupt
+/// can say what may have been left half done (e.g. "publishing my-worker").
+pub fn operation(description: String) -> OperationGuard {
+    CLEANUP.lock().unwrap().operation = Some(description);
+    OperationGuard
+}
+
+pub struct OperationGuard;
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        CLEANUP.lock().unwrap().operation = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn it_kills_guarded_children_on_drop() {
+        let child = spawn(Command::new("sleep").arg("30")).unwrap();
+        let id = child.id;
+        drop(child);
+        assert!(!CLEANUP.lock().unwrap().children.contains_key(&id));
+    }
+}
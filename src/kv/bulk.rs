@@ -1,18 +1,23 @@
 use std::time::Duration;
 
-use indicatif::ProgressBar;
-
 use cloudflare::endpoints::workerskv::delete_bulk::DeleteBulk;
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
 use cloudflare::endpoints::workerskv::write_bulk::WriteBulk;
-use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::auth::Credentials;
-use cloudflare::framework::{Environment, HttpApiClient, HttpApiClientConfig};
+use cloudflare::framework::response::{ApiErrors, ApiFailure};
+use cloudflare::framework::{HttpApiClient, HttpApiClientConfig};
+
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::StatusCode;
 
-use crate::commands::kv::format_error;
+use crate::commands::kv::api_error;
 use crate::http::feature::headers;
+use crate::http::{self, compress, Traced, TracedClient};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::{Progress, Unit};
+use crate::terminal::summary;
 
 const API_MAX_PAIRS: usize = 10000;
 // The consts below are halved from the API's true capacity to help avoid
@@ -20,69 +25,145 @@ const API_MAX_PAIRS: usize = 10000;
 pub const BATCH_KEY_MAX: usize = API_MAX_PAIRS / 2;
 const UPLOAD_MAX_SIZE: usize = 50 * 1024 * 1024;
 
+// KV operations can be lengthy if payloads are large.
+const BULK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 // Create a special API client that has a longer timeout than usual, given that KV operations
 // can be lengthy if payloads are large.
 fn bulk_api_client(user: &GlobalUser) -> Result<HttpApiClient, failure::Error> {
     let config = HttpApiClientConfig {
-        http_timeout: Duration::from_secs(5 * 60),
+        http_timeout: BULK_TIMEOUT,
         default_headers: headers(None),
     };
 
     HttpApiClient::new(
         Credentials::from(user.to_owned()),
         config,
-        Environment::Production,
+        http::api_environment()?,
     )
 }
 
+/// A progress bar counting the bytes `put` sends, when the pairs take more than one
+/// request to upload.
+pub fn progress_bar(pairs: &[KeyValuePair]) -> Option<Progress> {
+    let total: usize = pairs.iter().map(pair_size).sum();
+    if pairs.len() > BATCH_KEY_MAX || total > UPLOAD_MAX_SIZE {
+        Some(StdErr::progress("Uploading", total as u64, Unit::Bytes))
+    } else {
+        None
+    }
+}
+
+fn pair_size(pair: &KeyValuePair) -> usize {
+    pair.key.len() + pair.value.len()
+}
+
 pub fn put(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
     pairs: Vec<KeyValuePair>,
-    progress_bar: &Option<ProgressBar>,
+    progress_bar: &Option<Progress>,
 ) -> Result<(), failure::Error> {
     let client = bulk_api_client(user)?;
 
     for b in batch_keys_values(pairs) {
-        match client.request(&WriteBulk {
+        let batch_size: usize = b.iter().map(pair_size).sum();
+        if compress::enabled() && put_compressed(target, user, namespace_id, &b)? {
+            if let Some(pb) = &progress_bar {
+                pb.inc(batch_size as u64);
+            }
+            continue;
+        }
+
+        match client.request_traced(&WriteBulk {
             account_identifier: &target.account_id,
             namespace_identifier: namespace_id,
-            bulk_key_value_pairs: b.to_owned(),
+            bulk_key_value_pairs: b,
         }) {
-            Ok(_) => {}
-            Err(e) => failure::bail!("{}", format_error(e)),
+            Ok(_) => summary::uploaded(batch_size as u64),
+            Err(e) => return Err(api_error(e)),
         }
 
         if let Some(pb) = &progress_bar {
-            pb.inc(b.len() as u64);
+            pb.inc(batch_size as u64);
         }
     }
 
     Ok(())
 }
 
+// Sends a batch with a gzipped body, which is much smaller for the text files Sites
+// upload. Returns false, and turns compression off, if the API turns the body down.
+fn put_compressed(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+    pairs: &[KeyValuePair],
+) -> Result<bool, failure::Error> {
+    let url = http::endpoints::kv_bulk(&target.account_id, namespace_id);
+    let body = compress::gzip(serde_json::to_string(pairs)?.as_bytes())?;
+
+    let res = http::legacy_auth_client(user)
+        .put(&url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(CONTENT_ENCODING, "gzip")
+        .timeout(BULK_TIMEOUT)
+        .body(body)
+        .send_traced()?;
+
+    let status = res.status();
+    if rejects_compression(status) {
+        log::info!(
+            "the KV bulk API turned a gzipped body down with {}, sending them uncompressed",
+            status
+        );
+        compress::disable();
+        return Ok(false);
+    }
+    if !status.is_success() {
+        let errors: ApiErrors = res.json().unwrap_or_default();
+        return Err(api_error(ApiFailure::Error(status, errors)));
+    }
+    Ok(true)
+}
+
+// The API may turn a gzipped body down with a 400 as well as a 415, so any client error
+// is worth sending again uncompressed, except those the uncompressed body would get too:
+// bad credentials, and rate or size limits.
+fn rejects_compression(status: StatusCode) -> bool {
+    status.is_client_error()
+        && !matches!(
+            status,
+            StatusCode::UNAUTHORIZED
+                | StatusCode::FORBIDDEN
+                | StatusCode::PAYLOAD_TOO_LARGE
+                | StatusCode::TOO_MANY_REQUESTS
+        )
+}
+
 pub fn delete(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
     keys: Vec<String>,
-    progress_bar: &Option<ProgressBar>,
+    progress_bar: &Option<Progress>,
 ) -> Result<(), failure::Error> {
     let client = bulk_api_client(user)?;
 
     for b in batch_keys(keys) {
-        match client.request(&DeleteBulk {
+        let batch_len = b.len();
+        match client.request_traced(&DeleteBulk {
             account_identifier: &target.account_id,
             namespace_identifier: namespace_id,
-            bulk_keys: b.to_owned(),
+            bulk_keys: b,
         }) {
             Ok(_) => {}
-            Err(e) => failure::bail!("{}", format_error(e)),
+            Err(e) => return Err(api_error(e)),
         }
 
         if let Some(pb) = &progress_bar {
-            pb.inc(b.len() as u64);
+            pb.inc(batch_len as u64);
         }
     }
 
@@ -109,7 +190,7 @@ fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
                 let pair = pairs.pop().unwrap();
                 if key_count + 1 > BATCH_KEY_MAX
                 // Keep upload size small to keep KV bulk API happy
-                || key_pair_bytes + pair.key.len() + pair.value.len() > UPLOAD_MAX_SIZE
+                || key_pair_bytes + pair_size(&pair) > UPLOAD_MAX_SIZE
                 {
                     batches.push(key_value_batch.to_vec());
                     key_count = 0;
@@ -119,7 +200,7 @@ fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
 
                 // Add the popped key-value pair to the running batch of key-value pair uploads
                 key_count += 1;
-                key_pair_bytes = key_pair_bytes + pair.key.len() + pair.value.len();
+                key_pair_bytes += pair_size(&pair);
                 key_value_batch.push(pair);
             }
         }
@@ -142,3 +223,17 @@ fn batch_keys(mut keys: Vec<String>) -> Vec<Vec<String>> {
 
     batches
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_falls_back_to_uncompressed_bodies_on_client_errors() {
+        assert!(rejects_compression(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        assert!(rejects_compression(StatusCode::BAD_REQUEST));
+        assert!(!rejects_compression(StatusCode::FORBIDDEN));
+        assert!(!rejects_compression(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!rejects_compression(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+}
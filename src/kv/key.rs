@@ -3,12 +3,16 @@ use serde_json::value::Value as JsonValue;
 use cloudflare::endpoints::workerskv::list_namespace_keys::ListNamespaceKeys;
 use cloudflare::endpoints::workerskv::list_namespace_keys::ListNamespaceKeysParams;
 use cloudflare::endpoints::workerskv::Key;
-use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 use cloudflare::framework::HttpApiClient;
 
+use crate::http::TracedClient;
 use crate::settings::toml::Target;
 
+const MAX_KEYS_PER_PAGE: usize = 1000;
+// the API lists no fewer keys at a time
+const MIN_KEYS_PER_PAGE: usize = 10;
+
 pub struct KeyList {
     keys_result: Option<Vec<Key>>,
     prefix: Option<String>,
@@ -17,6 +21,8 @@ pub struct KeyList {
     namespace_id: String,
     cursor: Option<String>,
     init_fetch: bool,
+    // keys left to return when the list is limited
+    remaining: Option<usize>,
 }
 
 impl KeyList {
@@ -34,13 +40,34 @@ impl KeyList {
             namespace_id: namespace_id.to_string(),
             cursor: None,
             init_fetch: false,
+            remaining: None,
         };
         Ok(iter)
     }
 
+    /// Lists the keys after `cursor`, as returned by `cursor()` of an earlier list.
+    pub fn starting_at(mut self, cursor: Option<&str>) -> Self {
+        self.cursor = cursor.map(str::to_string);
+        self
+    }
+
+    /// Stops after `limit` keys, which must be at least 10. Pages are sized so that
+    /// `cursor()` then points right after the last key returned.
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.remaining = limit;
+        self
+    }
+
+    /// Where to pick the list up again when it was limited, or None when every key
+    /// was listed.
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
     fn request_params(&self) -> ListNamespaceKeys {
         let params = ListNamespaceKeysParams {
-            limit: None, // Defaults to 1000 (the maximum)
+            // Defaults to 1000 (the maximum)
+            limit: self.remaining.map(|remaining| page_size(remaining) as u16),
             cursor: self.cursor.to_owned(),
             prefix: self.prefix.to_owned(),
         };
@@ -53,7 +80,7 @@ impl KeyList {
     }
 
     fn get_batch(&mut self) -> Result<Vec<Key>, ApiFailure> {
-        let response = self.client.request(&self.request_params());
+        let response = self.client.request_traced(&self.request_params());
 
         match response {
             Ok(success) => {
@@ -64,12 +91,8 @@ impl KeyList {
             Err(e) => Err(e),
         }
     }
-}
-
-impl Iterator for KeyList {
-    type Item = Result<Key, ApiFailure>;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_key(&mut self) -> Option<Result<Key, ApiFailure>> {
         // Attempt to extract next key from vector of keys in KeyList.
         // If no key vector or no keys left, go to fallback case below to
         // attempt to fetch the next page of keys from the Workers KV API.
@@ -104,6 +127,32 @@ impl Iterator for KeyList {
     }
 }
 
+impl Iterator for KeyList {
+    type Item = Result<Key, ApiFailure>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+        let key = self.next_key();
+        if let (Some(Ok(_)), Some(remaining)) = (&key, &mut self.remaining) {
+            *remaining -= 1;
+        }
+        key
+    }
+}
+
+// How many keys to ask for when `remaining` are left to list, so the last page holds
+// exactly the last of them.
+fn page_size(remaining: usize) -> usize {
+    let size = remaining.min(MAX_KEYS_PER_PAGE);
+    // never leave less than a page for the last request
+    if remaining > size && remaining - size < MIN_KEYS_PER_PAGE {
+        return remaining - MIN_KEYS_PER_PAGE;
+    }
+    size.max(MIN_KEYS_PER_PAGE)
+}
+
 // Returns Some(cursor) if cursor is non-empty, otherwise returns None.
 fn extract_cursor(result_info: Option<JsonValue>) -> Option<String> {
     let result_info = result_info.unwrap();
@@ -115,3 +164,17 @@ fn extract_cursor(result_info: Option<JsonValue>) -> Option<String> {
         Some(returned_cursor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_sizes_pages_to_end_on_the_limit() {
+        assert_eq!(page_size(25), 25);
+        assert_eq!(page_size(2500), 1000);
+        // 1005 keys can't be listed as 1000 and then 5
+        assert_eq!(page_size(1005), 995);
+        assert_eq!(page_size(10), 10);
+    }
+}
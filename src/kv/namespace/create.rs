@@ -4,15 +4,21 @@ use cloudflare::endpoints::workerskv::WorkersKvNamespace;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::{ApiFailure, ApiSuccess};
 
+use crate::http::{self, TracedClient};
+
 pub fn create(
     client: &impl ApiClient,
     account_id: &str,
     title: &str,
 ) -> Result<ApiSuccess<WorkersKvNamespace>, ApiFailure> {
-    client.request(&CreateNamespace {
+    let response = client.request_traced(&CreateNamespace {
         account_identifier: account_id,
         params: CreateNamespaceParams {
             title: title.to_string(),
         },
-    })
+    });
+    if response.is_ok() {
+        http::cache::invalidate(&http::endpoints::kv_namespaces(account_id));
+    }
+    response
 }
@@ -3,6 +3,7 @@ use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::{ApiFailure, ApiSuccess};
 use cloudflare::framework::HttpApiClient;
 
+use crate::http::{self, TracedClient};
 use crate::settings::toml::Target;
 
 pub fn delete(
@@ -10,8 +11,12 @@ pub fn delete(
     target: &Target,
     id: &str,
 ) -> Result<ApiSuccess<()>, ApiFailure> {
-    client.request(&RemoveNamespace {
+    let response = client.request_traced(&RemoveNamespace {
         account_identifier: &target.account_id,
         namespace_identifier: id,
-    })
+    });
+    if response.is_ok() {
+        http::cache::invalidate(&http::endpoints::kv_namespaces(&target.account_id));
+    }
+    response
 }
@@ -1,9 +1,11 @@
 mod create;
 mod delete;
 mod list;
+mod rename;
 mod upsert;
 
 pub use create::create;
 pub use delete::delete;
 pub use list::list;
+pub use rename::rename;
 pub use upsert::{upsert, UpsertedNamespace};
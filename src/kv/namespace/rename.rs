@@ -0,0 +1,25 @@
+use cloudflare::endpoints::workerskv::rename_namespace::RenameNamespace;
+use cloudflare::endpoints::workerskv::rename_namespace::RenameNamespaceParams;
+use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::framework::response::{ApiFailure, ApiSuccess};
+
+use crate::http::{self, TracedClient};
+
+pub fn rename(
+    client: &impl ApiClient,
+    account_id: &str,
+    id: &str,
+    title: &str,
+) -> Result<ApiSuccess<()>, ApiFailure> {
+    let response = client.request_traced(&RenameNamespace {
+        account_identifier: account_id,
+        namespace_identifier: id,
+        params: RenameNamespaceParams {
+            title: title.to_string(),
+        },
+    });
+    if response.is_ok() {
+        http::cache::invalidate(&http::endpoints::kv_namespaces(account_id));
+    }
+    response
+}
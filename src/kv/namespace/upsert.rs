@@ -28,17 +28,19 @@ pub fn upsert(
                 if api_errors.errors.iter().any(|e| e.code == 10014) {
                     log::info!("Namespace {} already exists.", title);
 
-                    match list(&client, target)?
+                    // the namespace may have been created since the list was cached
+                    http::cache::invalidate(&http::endpoints::kv_namespaces(&target.account_id));
+                    match list(user, target)?
                         .iter()
                         .find(|ns| ns.title == title) {
                         Some(namespace) => Ok(UpsertedNamespace::Reused(namespace.to_owned())),
                         None => failure::bail!("namespace already exists, but could not be found in the API's listed namespaces"),
                     }
                 } else {
-                    failure::bail!("{}", http::format_error(e, Some(&error_suggestions)))
+                    Err(http::api_error(e, Some(&error_suggestions)))
                 }
             }
-            _ => failure::bail!("{}", http::format_error(e, Some(&error_suggestions))),
+            _ => Err(http::api_error(e, Some(&error_suggestions))),
         },
     }
 }
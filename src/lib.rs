@@ -8,14 +8,18 @@ pub mod preview;
 pub use build::build_target;
 pub mod commands;
 pub mod deploy;
+pub mod error;
 pub mod http;
 pub mod install;
 pub mod installer;
+pub mod interrupt;
 pub mod kv;
 pub mod login;
 pub mod settings;
 pub mod sites;
+pub mod support;
 pub mod tail;
+pub mod telemetry;
 pub mod terminal;
 pub mod upload;
 pub mod version;
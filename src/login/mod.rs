@@ -1,5 +1,4 @@
 use eventual::Timer;
-use indicatif::{ProgressBar, ProgressStyle};
 use openssl::base64;
 use openssl::rsa::{Padding, Rsa};
 use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
@@ -8,8 +7,9 @@ use std::collections::HashMap;
 use std::str;
 
 use crate::commands::config::global_config;
+use crate::http::{self, Traced};
 use crate::settings::global_user::GlobalUser;
-use crate::terminal::{interactive, open_browser};
+use crate::terminal::{interactive, open_browser, progress};
 
 pub fn run() -> Result<(), failure::Error> {
     let rsa = Rsa::generate(1024)?;
@@ -65,16 +65,14 @@ fn poll_token(token_id: String) -> Result<String, failure::Error> {
     let client = reqwest::blocking::Client::new();
     let timer = Timer::new().interval_ms(1000).iter();
 
-    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
-    let spinner = ProgressBar::new_spinner().with_style(style);
-    spinner.set_message("Waiting for API token...");
+    let spinner = progress::spinner("Waiting for API token...");
     spinner.enable_steady_tick(20);
 
     for (seconds, _) in timer.enumerate() {
         let res = client
-            .get("https://api.cloudflare.com/client/v4/workers/token")
+            .get(&http::endpoints::workers_token())
             .json(&request_params)
-            .send()?;
+            .send_traced()?;
 
         if res.status().is_success() {
             let body: TokenResponse = res.json()?;
@@ -5,25 +5,36 @@ extern crate tokio;
 
 use std::convert::TryFrom;
 use std::env;
-use std::path::Path;
+use std::io;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::str::FromStr;
+use std::time::Instant;
 
-use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
-use exitfailure::ExitFailure;
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, ErrorKind, SubCommand};
 use url::Url;
 
 use wrangler::commands;
 use wrangler::commands::kv::key::KVMetaData;
+use wrangler::error::{self, WranglerError};
+use wrangler::http;
 use wrangler::installer;
+use wrangler::interrupt;
 use wrangler::preview::{HttpMethod, PreviewOpt};
 use wrangler::settings;
 use wrangler::settings::global_user::GlobalUser;
+use wrangler::settings::metadata::Annotations;
+use wrangler::settings::preferences::Preferences;
 use wrangler::settings::toml::TargetType;
-use wrangler::terminal::message::{Message, Output, StdOut};
-use wrangler::terminal::{emoji, interactive, styles};
+use wrangler::support;
+use wrangler::telemetry;
+use wrangler::terminal::github::{self, Location};
+use wrangler::terminal::message::{self, LogFormat, Message, Output, StdErr, StdOut};
+use wrangler::terminal::{emoji, interactive, styles, summary, table};
 use wrangler::version::background_check_for_updates;
 
-fn main() -> Result<(), ExitFailure> {
+fn main() {
     env_logger::init();
     let latest_version_receiver = background_check_for_updates();
     if let Ok(me) = env::current_exe() {
@@ -35,27 +46,202 @@ fn main() -> Result<(), ExitFailure> {
             .expect("executable should have a filename")
             .starts_with("wrangler-init")
         {
-            installer::install();
+            install();
         }
     }
-    run()?;
-    if let Ok(latest_version) = latest_version_receiver.try_recv() {
-        let latest_version = styles::highlight(latest_version.to_string());
+    interrupt::install_handler(|| process::exit(interrupt::INTERRUPTED_EXIT_CODE));
+    let default_panic_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_panic_hook(info);
+        support::offer_bundle(&format!("wrangler {}", info));
+    }));
+    let started = Instant::now();
+    let result = run();
+
+    let update_docs_url =
+        styles::url("https://developers.cloudflare.com/workers/cli-wrangler/install-update#update");
+    for deprecation in http::trace::deprecations() {
+        let sunset = match &deprecation.sunset {
+            Some(sunset) => format!(" and will be removed on {}", sunset),
+            None => String::new(),
+        };
+        StdErr::warn(&format!(
+            "The Cloudflare API reports that {}, which wrangler {} uses, is deprecated{}. A newer wrangler may use its replacement: {}",
+            deprecation.path,
+            env!("CARGO_PKG_VERSION"),
+            sunset,
+            update_docs_url
+        ));
+    }
+    let update = latest_version_receiver.try_recv().ok();
+    if let Some(update) = update.as_ref().filter(|update| update.far_behind) {
+        StdErr::warn(&format!(
+            "Wrangler {} is far behind the latest version ({}), and some features or the API endpoints it uses may no longer work. Update wrangler: {}",
+            env!("CARGO_PKG_VERSION"),
+            styles::highlight(update.latest.to_string()),
+            update_docs_url
+        ));
+    }
+    // the warnings printed once the command is done count towards --strict too
+    let result = result.and_then(|()| message::check_strict());
+
+    telemetry::record(result.is_ok(), started.elapsed());
+    if let Err(e) = &result {
+        support::run_log::error(e);
+    }
+    support::run_log::finish(result.is_ok(), started.elapsed());
+    if Preferences::load().summary {
+        summary::print(started.elapsed());
+    }
+
+    if let Err(e) = result {
+        let code = e.downcast_ref::<WranglerError>().map(|e| e.code());
+        if message::log_format() == LogFormat::Ndjson {
+            message::emit_event("error", code, "error", &e.to_string());
+        } else if github::enabled() {
+            // configuration errors are about the file, even though wrangler can't say which line
+            let location = match code {
+                Some("config") => Some(Location {
+                    file: support::config_path().display().to_string(),
+                    ..Location::default()
+                }),
+                _ => None,
+            };
+            let mut problem = e.to_string();
+            for cause in e.iter_causes() {
+                problem.push_str(&format!("\ncaused by {}", cause));
+            }
+            github::annotate("error", location.as_ref(), &problem);
+        } else {
+            match code {
+                Some(code) => eprintln!("Error [{}]: {}", code, e),
+                None => eprintln!("Error: {}", e),
+            }
+            for cause in e.iter_causes() {
+                eprintln!("Info: caused by {}", cause);
+            }
+        }
+        if support::api_failed() {
+            let mut problem = e.to_string();
+            for cause in e.iter_causes() {
+                problem.push_str(&format!("\ncaused by {}", cause));
+            }
+            support::offer_bundle(&problem);
+        }
+        process::exit(error::exit_code(&e));
+    }
+    if let Some(update) = update.filter(|update| !update.far_behind) {
         let new_version_available = format!(
             "A new version of Wrangler ({}) is available!",
-            latest_version
+            styles::highlight(update.latest.to_string())
         );
         let update_message = "You can learn more about updating here:".to_string();
-        let update_docs_url = styles::url(
-            "https://developers.cloudflare.com/workers/cli-wrangler/install-update#update",
-        );
 
         StdOut::billboard(&format!(
             "{}\n{}\n{}",
             new_version_available, update_message, update_docs_url
         ));
     }
-    Ok(())
+}
+
+// Commands that never talk to the Cloudflare API. Every other command checks that the
+// API can be reached before it starts.
+// Self-installs wrangler, for the `wrangler-init` executable the install script downloads.
+fn install() -> ! {
+    match installer::install() {
+        Ok(destination) => println!(
+            "info: successfully installed wrangler to `{}`",
+            destination.display()
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            for cause in e.iter_causes() {
+                eprintln!("Caused by: {}", cause);
+            }
+        }
+    }
+
+    // On Windows we likely popped up a console for the installation. If we were
+    // to exit here immediately then the user wouldn't see any error that
+    // happened above or any successful message. Let's wait for them to say
+    // they've read everything and then continue.
+    if cfg!(windows) {
+        println!("Press enter to close this window...");
+        let mut line = String::new();
+        drop(io::stdin().read_line(&mut line));
+    }
+
+    process::exit(0);
+}
+
+// Commands that never call the Cloudflare API, so they skip the check that it can be
+// reached. Some download from elsewhere, e.g. `generate` and `artifact push`.
+fn works_offline(matches: &ArgMatches) -> bool {
+    match matches.subcommand() {
+        ("build", _)
+        | ("env", _)
+        | ("types", _)
+        | ("init", _)
+        | ("telemetry", _)
+        | ("logs", _)
+        | ("generate", _)
+        | ("artifact", _)
+        | ("tools", _) => true,
+        ("kv:bulk", Some(bulk)) => bulk
+            .subcommand()
+            .1
+            .map_or(false, |command| command.is_present("dry-run")),
+        ("config", Some(config)) => config.is_present("no-verify"),
+        ("dev", Some(dev)) => dev.subcommand_name() == Some("replay"),
+        _ => false,
+    }
+}
+
+// The subcommands run, e.g. "kv:namespace list", without any of their arguments.
+fn command_path(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let (name, Some(subcommand)) = matches.subcommand() {
+        names.push(name);
+        matches = subcommand;
+    }
+    names.join(" ")
+}
+
+// The value of `arg` for the innermost subcommand run, e.g. --config for `wrangler kv:key put`.
+fn subcommand_value(matches: &ArgMatches, arg: &str) -> Option<String> {
+    let mut matches = matches;
+    while let (_, Some(subcommand)) = matches.subcommand() {
+        matches = subcommand;
+    }
+    matches.value_of(arg).map(str::to_string)
+}
+
+// An argument clap doesn't know may name a plugin, a `wrangler-<name>` executable on the
+// PATH. Anything else, or a plugin that can't be found, is clap's error to report.
+fn run_plugin(error: clap::Error) -> Result<(), failure::Error> {
+    let name = match error.kind {
+        ErrorKind::UnknownArgument
+        | ErrorKind::UnrecognizedSubcommand
+        | ErrorKind::InvalidSubcommand => error.info.as_ref().and_then(|info| info.first()),
+        _ => None,
+    };
+    let args: Vec<String> = env::args().skip(1).collect();
+    // only global flags can come before the plugin's name
+    let position = name
+        .and_then(|name| args.iter().position(|arg| arg == name))
+        .filter(|&i| args[..i].iter().all(|arg| arg.starts_with('-')));
+
+    match (name.and_then(|name| commands::plugin::find(name)), position) {
+        (Some(plugin), Some(i)) => {
+            let code = commands::plugin::run(&plugin, &args[i + 1..])?;
+            if code != 0 {
+                process::exit(code);
+            }
+            Ok(())
+        }
+        _ => error.exit(),
+    }
 }
 
 #[allow(clippy::cognitive_complexity)]
@@ -112,12 +298,98 @@ fn run() -> Result<(), failure::Error> {
 
     let silent_verbose_arg = verbose_arg.clone().hidden(true);
 
-    let matches = App::new(format!("{}{} wrangler", emoji::WORKER, emoji::SPARKLES))
+    let pages_account_arg = Arg::with_name("account-id")
+        .help("the account the project belongs to. defaults to the account_id in your configuration file")
+        .long("account-id")
+        .takes_value(true);
+
+    let app_matches = App::new(format!("{}{} wrangler", emoji::WORKER, emoji::SPARKLES))
         .version(env!("CARGO_PKG_VERSION"))
         .author("The Wrangler Team <wrangler@cloudflare.com>")
         .setting(AppSettings::ArgRequiredElseHelp)
         .setting(AppSettings::DeriveDisplayOrder)
         .setting(AppSettings::VersionlessSubcommands)
+        .arg(
+            Arg::with_name("strict")
+                .help("treat warnings, such as deprecations, as errors. useful in CI")
+                .long("strict")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("log-format")
+                .help("print messages as human readable text, or as one JSON event per line on stderr")
+                .long("log-format")
+                .value_name("FORMAT")
+                .possible_values(&["text", "ndjson"])
+                .takes_value(true)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("plain")
+                .help("print messages without emoji, spinners or borders, for screen readers and minimal terminals. `plain = true` in preferences.toml does the same")
+                .long("plain")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("ci")
+                .help("never wait on a terminal for an answer, and print without spinners or color. answers piped to stdin are still read. on by default when $CI is set")
+                .long("ci")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("yes")
+                .help("answer yes to confirmations, such as before deleting something. needed for those in CI mode")
+                .long("yes")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("wide")
+                .help("print tables at their full width instead of cutting long cells short to fit the terminal")
+                .long("wide")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("no-header")
+                .help("leave the header row out of tables")
+                .long("no-header")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("no-compress")
+                .help("send uploads to the API uncompressed. by default large KV and Sites uploads are gzipped")
+                .long("no-compress")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("keep-temp")
+                .help("keep the files webpack builds produce, and the configuration and stats behind them, in .wrangler/debug")
+                .long("keep-temp")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("offline")
+                .help("never use the network. also set by WRANGLER_OFFLINE. commands that need the Cloudflare API fail straight away")
+                .long("offline")
+                .takes_value(false)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .help("log API requests at debug level to a file in the wrangler config directory. also set by WRANGLER_LOG")
+                .long("log-level")
+                .value_name("LEVEL")
+                .possible_values(&["error", "warn", "info", "debug", "trace"])
+                .takes_value(true)
+                .global(true)
+        )
         .subcommand(
             SubCommand::with_name("kv:namespace")
                 .about(&*format!(
@@ -152,7 +424,39 @@ fn run() -> Result<(), failure::Error> {
                 )
                 .subcommand(
                     SubCommand::with_name("list")
-                        .about("List all namespaces on your Cloudflare account")
+                        .about("List all namespaces on your Cloudflare account (a table, or json when piped)")
+                        .arg(silent_verbose_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("rename")
+                        .about("Change the title of a namespace")
+                        .arg(
+                            Arg::with_name("namespace-id")
+                            .help("The id of the namespace to rename")
+                            .required(true)
+                            .index(1)
+                        )
+                        .arg(
+                            Arg::with_name("title")
+                            .help("The new title")
+                            .required(true)
+                            .index(2)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(silent_verbose_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("info")
+                        .about("Show a namespace's title, an estimate of its key count, and when wrangler created it")
+                        .arg(
+                            Arg::with_name("namespace-id")
+                            .help("The id of the namespace")
+                            .required(true)
+                            .index(1)
+                        )
+                        .arg(environment_arg.clone())
                         .arg(silent_verbose_arg.clone())
                         .arg(wrangler_file.clone())
                 )
@@ -226,6 +530,14 @@ fn run() -> Result<(), failure::Error> {
                             .required(true)
                             .index(1)
                         )
+                        .arg(
+                            Arg::with_name("out")
+                            .help("Write the value to this file instead of stdout")
+                            .short("o")
+                            .long("out")
+                            .value_name("FILE")
+                            .takes_value(true)
+                        )
                         .arg(silent_verbose_arg.clone())
                         .arg(wrangler_file.clone())
                 )
@@ -262,6 +574,25 @@ fn run() -> Result<(), failure::Error> {
                             .value_name("STRING")
                             .takes_value(true),
                         )
+                        .arg(
+                            Arg::with_name("limit")
+                            .help("List at most this many keys, and print the cursor to list the rest with")
+                            .long("limit")
+                            .value_name("COUNT")
+                            .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("cursor")
+                            .help("Continue a list cut short by --limit")
+                            .long("cursor")
+                            .value_name("CURSOR")
+                            .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("show-values")
+                            .help("Also show the start of each key's value")
+                            .long("show-values"),
+                        )
                         .arg(silent_verbose_arg.clone())
                         .arg(wrangler_file.clone())
                 )
@@ -288,6 +619,12 @@ fn run() -> Result<(), failure::Error> {
                             .required(true)
                             .index(1)
                         )
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .help("check the file without uploading anything. works offline")
+                                .long("dry-run")
+                                .takes_value(false)
+                        )
                         .arg(wrangler_file.clone())
                         .arg(silent_verbose_arg.clone())
                 )
@@ -305,10 +642,48 @@ fn run() -> Result<(), failure::Error> {
                             .required(true)
                             .index(1)
                         )
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .help("check the file without deleting anything. works offline")
+                                .long("dry-run")
+                                .takes_value(false)
+                        )
                         .arg(wrangler_file.clone())
                         .arg(silent_verbose_arg.clone())
                 )
         )
+        .subcommand(
+            SubCommand::with_name("deployments")
+                .about(&*format!(
+                    "{} List the versions of your worker that have been published",
+                    emoji::ROUTE
+                ))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List the uploaded versions of your worker, with their messages and tags")
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("diff")
+                        .about("Show how the code, bindings and settings of two versions differ")
+                        .arg(
+                            Arg::with_name("from")
+                                .help("the older version's id (find it with `wrangler deployments list`)")
+                                .required(true)
+                                .index(1)
+                        )
+                        .arg(
+                            Arg::with_name("to")
+                                .help("the newer version's id")
+                                .required(true)
+                                .index(2)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+        )
         .subcommand(
             SubCommand::with_name("route")
                 .about(&*format!(
@@ -319,7 +694,7 @@ fn run() -> Result<(), failure::Error> {
                 .setting(AppSettings::SubcommandRequiredElseHelp)
                 .subcommand(
                     SubCommand::with_name("list")
-                        .about("List all routes associated with a zone (outputs json)")
+                        .about("List all routes associated with a zone (a table, or json when piped)")
                         .arg(environment_arg.clone())
                         .arg(wrangler_file.clone())
                         .arg(silent_verbose_arg.clone())
@@ -369,6 +744,270 @@ fn run() -> Result<(), failure::Error> {
                         .arg(wrangler_file.clone())
                         .arg(silent_verbose_arg.clone())
                 )
+                .subcommand(
+                    SubCommand::with_name("diff")
+                        .about("Compare the secrets set on a script with the variables in a .env file")
+                        .arg(
+                            Arg::with_name("from")
+                                .help("the .env file listing the secrets the script should have")
+                                .long("from")
+                                .value_name("FILE")
+                                .required(true)
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("env")
+                                .help("environments to compare. repeat to compare several")
+                                .short("e")
+                                .long("env")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                        )
+                        .arg(wrangler_file.clone())
+                        .arg(silent_verbose_arg.clone())
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("mtls-certificate")
+                .about(&*format!(
+                    "{} Manage client certificates for origins that require mTLS",
+                    emoji::KEY
+                ))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("upload")
+                        .about("Upload a client certificate and its private key")
+                        .arg(
+                            Arg::with_name("cert")
+                                .help("path to the PEM encoded certificate (chain)")
+                                .long("cert")
+                                .value_name("FILE")
+                                .required(true)
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("key")
+                                .help("path to the PEM encoded private key")
+                                .long("key")
+                                .value_name("FILE")
+                                .required(true)
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .help("a name to recognize the certificate by")
+                                .long("name")
+                                .takes_value(true)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List the mTLS certificates in your account")
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("delete")
+                        .about("Delete an mTLS certificate from your account")
+                        .arg(
+                            Arg::with_name("id")
+                                .help("id of the certificate to delete")
+                                .required(true)
+                                .index(1)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("alert")
+                .about(&*format!(
+                    "{} Manage notifications about your worker's errors",
+                    emoji::WARN
+                ))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Notify a webhook when too many requests to your worker fail")
+                        .arg(
+                            Arg::with_name("error-rate")
+                                .help("the share of requests that have to fail, e.g. 5%")
+                                .long("error-rate")
+                                .value_name("PERCENT")
+                                .required(true)
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("window")
+                                .help("how long the error rate is measured over, e.g. 5m")
+                                .long("window")
+                                .default_value("5m")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("webhook")
+                                .help("the URL to notify")
+                                .long("webhook")
+                                .value_name("URL")
+                                .required(true)
+                                .takes_value(true)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List the alerts for your worker")
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("delete")
+                        .about("Delete an alert")
+                        .arg(
+                            Arg::with_name("id")
+                                .help("id of the alert to delete")
+                                .required(true)
+                                .index(1)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("dispatch-namespace")
+                .about(&*format!(
+                    "{} Manage Workers for Platforms dispatch namespaces",
+                    emoji::FILES
+                ))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Create a dispatch namespace")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("name of the dispatch namespace")
+                                .required(true)
+                                .index(1)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List the dispatch namespaces in your account")
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("delete")
+                        .about("Delete a dispatch namespace and the scripts in it")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("name of the dispatch namespace")
+                                .required(true)
+                                .index(1)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("email:address")
+                .about(&*format!(
+                    "{} Manage the destination addresses your worker can send email to",
+                    emoji::INBOX
+                ))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List destination addresses and whether they are verified")
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("verify")
+                        .about("Send a verification email to a destination address, or to every address in [email] that hasn't been added yet")
+                        .arg(
+                            Arg::with_name("address")
+                                .help("the email address to verify")
+                                .index(1)
+                        )
+                        .arg(environment_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("pages")
+                .about(&*format!(
+                    "{} Deploy static sites to Cloudflare Pages",
+                    emoji::FILES
+                ))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("publish")
+                        .about("Upload a directory of static assets as a new deployment of a Pages project")
+                        .arg(
+                            Arg::with_name("directory")
+                                .help("the directory of static assets to deploy")
+                                .required(true)
+                                .index(1)
+                        )
+                        .arg(
+                            Arg::with_name("project-name")
+                                .help("the Pages project to deploy to")
+                                .long("project-name")
+                                .required(true)
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("branch")
+                                .help("the branch to deploy as. defaults to the project's production branch")
+                                .long("branch")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("commit-message")
+                                .help("a message to show with the deployment")
+                                .long("commit-message")
+                                .takes_value(true)
+                        )
+                        .arg(pages_account_arg.clone())
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("project")
+                        .about("Manage Pages projects")
+                        .setting(AppSettings::SubcommandRequiredElseHelp)
+                        .subcommand(
+                            SubCommand::with_name("list")
+                                .about("List the Pages projects in your account")
+                                .arg(pages_account_arg.clone())
+                                .arg(wrangler_file.clone())
+                        )
+                        .subcommand(
+                            SubCommand::with_name("create")
+                                .about("Create a Pages project for direct uploads")
+                                .arg(
+                                    Arg::with_name("name")
+                                        .help("name of the project")
+                                        .required(true)
+                                        .index(1)
+                                )
+                                .arg(
+                                    Arg::with_name("production-branch")
+                                        .help("deployments to this branch are served from the project's pages.dev subdomain")
+                                        .long("production-branch")
+                                        .takes_value(true)
+                                        .default_value("main")
+                                )
+                                .arg(pages_account_arg.clone())
+                                .arg(wrangler_file.clone())
+                        )
+                )
         )
         .subcommand(
             SubCommand::with_name("generate")
@@ -442,6 +1081,18 @@ fn run() -> Result<(), failure::Error> {
                         .long("env")
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::with_name("out-dir")
+                        .help("also write what publish would upload to this directory, for `wrangler publish --from-artifact`")
+                        .long("out-dir")
+                        .value_name("DIR")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("license-report")
+                        .help("list the licenses of the packages bundled into your worker, and fail on those denied in [build.licenses]")
+                        .long("license-report")
+                )
                 .arg(wrangler_file.clone())
                 .arg(silent_verbose_arg.clone()),
         )
@@ -488,7 +1139,32 @@ fn run() -> Result<(), failure::Error> {
                         .long("watch")
                         .takes_value(false),
                 )
-                .arg(verbose_arg.clone()),
+                .arg(verbose_arg.clone())
+                .subcommand(
+                    SubCommand::with_name("cleanup")
+                        .about("Delete stale branch previews published with `wrangler publish --preview-branch`")
+                        .arg(wrangler_file.clone())
+                        .arg(
+                            Arg::with_name("older-than")
+                                .help("only delete previews that haven't been published in this many days. defaults to 7")
+                                .long("older-than")
+                                .value_name("DAYS")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("all")
+                                .help("delete every branch preview")
+                                .long("all")
+                                .takes_value(false)
+                                .conflicts_with("older-than")
+                        )
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .help("print what would be deleted without deleting anything")
+                                .long("dry-run")
+                                .takes_value(false)
+                        )
+                ),
         )
         .subcommand(
             SubCommand::with_name("dev")
@@ -536,20 +1212,109 @@ fn run() -> Result<(), failure::Error> {
                         .long("upstream-protocol")
                         .takes_value(true)
                 )
-        )
-        .subcommand(
-            SubCommand::with_name("publish")
-                .about(&*format!(
+                .arg(
+                    Arg::with_name("env-file")
+                        .help("load variables from a .env file into vars")
+                        .long("env-file")
+                        .value_name("FILE")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("local-upstream")
+                        .help("origin that requests your worker doesn't handle, or passes to fetch, are sent to, like https://example.com")
+                        .long("local-upstream")
+                        .value_name("URL")
+                        .takes_value(true)
+                        .conflicts_with("host")
+                )
+                .arg(
+                    Arg::with_name("timings")
+                        .help("log how long each request took, and the Server-Timing metrics of its response")
+                        .long("timings")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("inspector-port")
+                        .help("port for Chrome DevTools to debug the worker on. defaults to 9229")
+                        .long("inspector-port")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("record")
+                        .help("record the requests the server handles to a file, for `wrangler dev replay`")
+                        .long("record")
+                        .value_name("FILE")
+                        .takes_value(true)
+                )
+                .subcommand(
+                    SubCommand::with_name("replay")
+                        .about("Send the requests recorded with `wrangler dev --record` to a running dev server")
+                        .arg(
+                            Arg::with_name("file")
+                                .help("the file the requests were recorded to")
+                                .required(true)
+                                .index(1)
+                        )
+                        .arg(
+                            Arg::with_name("url")
+                                .help("the dev server to send them to. defaults to http://127.0.0.1:8787")
+                                .long("url")
+                                .takes_value(true)
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("test")
+                .about(&*format!(
+                    "{} Run a test command against your worker served like `wrangler dev`",
+                    emoji::MICROSCOPE
+                ))
+                .arg(wrangler_file.clone())
+                .arg(environment_arg.clone())
+                .arg(
+                    Arg::with_name("port")
+                        .help("port to serve the worker on. defaults to 8787")
+                        .short("p")
+                        .long("port")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("env-file")
+                        .help("load variables from a .env file into vars")
+                        .long("env-file")
+                        .value_name("FILE")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("command")
+                        .help("the test command, which finds the worker at $WRANGLER_DEV_URL, e.g. `wrangler test -- npm test`")
+                        .required(true)
+                        .multiple(true)
+                        .last(true)
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("publish")
+                .about(&*format!(
                     "{} Publish your worker to the orange cloud",
                     emoji::UP
                 ))
                 .arg(wrangler_file.clone())
                 .arg(
                     Arg::with_name("env")
-                        .help("environments to publish to")
+                        .help("environments to publish to. repeat to publish several at once")
                         .short("e")
                         .long("env")
                         .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                )
+                .arg(
+                    Arg::with_name("all-envs")
+                        .help("publish every environment in your configuration file at once")
+                        .long("all-envs")
+                        .takes_value(false)
+                        .conflicts_with_all(&["env", "preview-branch", "dispatch-namespace"])
                 )
                 .arg(silent_verbose_arg.clone())
                 .arg(
@@ -565,8 +1330,160 @@ fn run() -> Result<(), failure::Error> {
                     .long("output")
                     .takes_value(true)
                     .possible_value("json")
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .help("publish even if nothing has changed since the last publish")
+                        .long("force")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("preview-branch")
+                        .help("publish a preview of a branch to workers.dev under its own script name")
+                        .long("preview-branch")
+                        .value_name("BRANCH")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("dispatch-namespace")
+                        .help("upload the worker as a user script in a Workers for Platforms dispatch namespace")
+                        .long("dispatch-namespace")
+                        .value_name("NAMESPACE")
+                        .takes_value(true)
+                        .conflicts_with("preview-branch")
+                )
+                .arg(
+                    Arg::with_name("message")
+                        .help("a note to record with the uploaded version, shown by `wrangler deployments list`")
+                        .long("message")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .help("a tag to record with the uploaded version, like a release or commit")
+                        .long("tag")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("remote-lock")
+                        .help("also mark the script as being published, so publishes from other machines wait their turn")
+                        .long("remote-lock")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("create-missing-bindings")
+                        .help("create the KV namespaces that have no id in your configuration file, and save their ids to it")
+                        .long("create-missing-bindings")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("env-file")
+                        .help("publish the variables in a .env file as plain-text bindings, after asking for confirmation")
+                        .long("env-file")
+                        .value_name("FILE")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("from-artifact")
+                        .help("publish a directory written by `wrangler build --out-dir` instead of building the project")
+                        .long("from-artifact")
+                        .value_name("DIR")
+                        .takes_value(true)
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("types")
+                .about(&*format!(
+                    "{} Generate TypeScript declarations, or a Rust module, for the bindings in your configuration file",
+                    emoji::SPARKLES
+                ))
+                .arg(wrangler_file.clone())
+                .arg(environment_arg.clone())
+                .arg(
+                    Arg::with_name("output")
+                        .help("the file to write the declarations to. defaults to worker-configuration.d.ts, or src/bindings.rs for Rust projects")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .takes_value(true)
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about(&*format!(
+                    "{} Compare your configuration file with what is deployed",
+                    emoji::MICROSCOPE
+                ))
+                .arg(wrangler_file.clone())
+                .arg(environment_arg.clone())
+                .arg(silent_verbose_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("metrics")
+                .about(&*format!(
+                    "{} Show request, error and CPU time metrics for your worker",
+                    emoji::MICROSCOPE
+                ))
+                .arg(wrangler_file.clone())
+                .arg(environment_arg.clone())
+                .arg(
+                    Arg::with_name("since")
+                        .help("how far back to look, e.g. 30m, 1h or 7d. defaults to 1h")
+                        .long("since")
+                        .takes_value(true)
+                        .default_value("1h")
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .help("print the metrics as JSON")
+                        .long("json")
+                        .takes_value(false)
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("usage")
+                .about(&*format!(
+                    "{} Estimate your worker's monthly cost under each usage model",
+                    emoji::MICROSCOPE
+                ))
+                .arg(wrangler_file.clone())
+                .arg(environment_arg.clone())
+                .arg(
+                    Arg::with_name("since")
+                        .help("how much recent traffic to base the estimate on, e.g. 1d or 30d. defaults to 7d")
+                        .long("since")
+                        .takes_value(true)
+                        .default_value("7d")
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("destroy")
+                .about(&*format!(
+                    "{} Delete a deployed worker and the resources wrangler created for it",
+                    emoji::WARN
+                ))
+                .arg(wrangler_file.clone())
+                .arg(environment_arg.clone())
+                .arg(
+                    Arg::with_name("kv-namespaces")
+                        .help("also delete the production KV namespaces wrangler created")
+                        .long("kv-namespaces")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("durable-objects")
+                        .help("also delete Durable Object namespaces that belong to the worker")
+                        .long("durable-objects")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("dry-run")
+                        .help("print what would be deleted without deleting anything")
+                        .long("dry-run")
+                        .takes_value(false)
+                )
+                .arg(silent_verbose_arg.clone()),
+        )
         .subcommand(
             SubCommand::with_name("config")
                 .about(&*format!(
@@ -621,25 +1538,219 @@ fn run() -> Result<(), failure::Error> {
                         .takes_value(true)
                 )
                 .arg(
+                    // logs no longer come through cloudflared, so these ports are unused
                     Arg::with_name("tunnel_port")
-                        .help("port to accept tail log requests")
                         .short("p")
                         .long("port")
                         .takes_value(true)
+                        .hidden(true)
                 )
                 .arg(
                     Arg::with_name("metrics_port")
-                        .help("provides endpoint for cloudflared metrics. used to retrieve tunnel url")
                         .long("metrics")
                         .takes_value(true)
+                        .hidden(true)
+                )
+                .arg(
+                    Arg::with_name("stats")
+                        .help("show a live panel of the request rate, error rate, top paths and top exceptions instead of the logs, which are still printed when stdout is redirected")
+                        .long("stats")
+                )
+                .arg(
+                    Arg::with_name("errors_only")
+                        .help("only print exceptions and console.error logs, each distinct one once, with stack traces mapped through the script's source map when it has one")
+                        .long("errors-only")
                 )
                 .arg(verbose_arg.clone())
         )
         .subcommand(
             SubCommand::with_name("login")
                 .about(&*format!("{} Authenticate Wrangler with your Cloudflare username and password", emoji::UNLOCKED)))
-        .get_matches();
+        .subcommand(
+            SubCommand::with_name("tools")
+                .about(&*format!("{} Manage the tools wrangler downloads to build your project", emoji::DOWN))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("vendor")
+                        .about("Download the tools your project needs, to build it later without network access")
+                        .arg(wrangler_file.clone())
+                        .arg(
+                            Arg::with_name("dir")
+                                .help("directory to download the tools to. defaults to $WRANGLER_TOOLS_DIR, or ./wrangler-tools")
+                                .long("dir")
+                                .value_name("DIR")
+                                .takes_value(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("clean")
+                        .about("List the tools wrangler has downloaded and remove old versions")
+                        .arg(
+                            Arg::with_name("all")
+                                .help("remove every downloaded tool, not only old versions")
+                                .long("all")
+                                .takes_value(false)
+                        )
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .help("list what would be removed without removing anything")
+                                .long("dry-run")
+                                .takes_value(false)
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("env")
+                .about(&*format!("{} Inspect the environments in your configuration file", emoji::SLEUTH))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List your environments and the worker each one publishes")
+                        .arg(wrangler_file.clone())
+                )
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about("Show the settings an environment resolves to, and where each one comes from")
+                        .arg(wrangler_file.clone())
+                        .arg(
+                            Arg::with_name("name")
+                                .help("the environment to show. shows the top level settings if omitted")
+                                .index(1)
+                                .value_name("ENVIRONMENT NAME")
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("artifact")
+                .about(&*format!("{} Push and pull artifacts written by `wrangler build --out-dir`", emoji::UP))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("push")
+                        .about("Push an artifact to an OCI registry or S3-compatible bucket, keyed by the digest of its contents")
+                        .arg(
+                            Arg::with_name("dir")
+                                .help("the directory written by `wrangler build --out-dir`")
+                                .index(1)
+                                .value_name("DIR")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("destination")
+                                .help("oci://<registry>/<repository> or s3://<bucket>/<prefix>, with :<tag> to tag the artifact")
+                                .index(2)
+                                .value_name("DESTINATION")
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("pull")
+                        .about("Pull an artifact by tag or digest, checking every file against its digest")
+                        .arg(
+                            Arg::with_name("source")
+                                .help("oci://<registry>/<repository> or s3://<bucket>/<prefix>, with :<tag> or @sha256:<digest>")
+                                .index(1)
+                                .value_name("SOURCE")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("out-dir")
+                                .help("the empty or new directory to pull the artifact into")
+                                .long("out-dir")
+                                .value_name("DIR")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("logs")
+                .about(&*format!("{} Look at the logs wrangler keeps of the last few commands it ran", emoji::MICROSCOPE))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("show-last")
+                        .about("Print the log of the last command: its arguments, configuration, environment, API requests and error, one JSON object per line")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("telemetry")
+                .about(&*format!("{} Choose whether wrangler sends anonymous usage data", emoji::MICROSCOPE))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("enable")
+                        .about("Send the name of each command, how long it took, whether it succeeded and the wrangler version")
+                )
+                .subcommand(
+                    SubCommand::with_name("disable")
+                        .about("Stop sending usage data. telemetry is off until enabled")
+                )
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about("Show whether telemetry is enabled and exactly what has been sent")
+                )
+        )
+        .get_matches_safe();
+    let matches = match app_matches {
+        Ok(matches) => matches,
+        Err(e) => return run_plugin(e),
+    };
+
+    let command = command_path(&matches);
+    let config_path = subcommand_value(&matches, "config").map(PathBuf::from);
+    let env_name = subcommand_value(&matches, "env");
+    telemetry::set_command(command.clone());
+    // a log of looking at the logs would push out the one asked for
+    if !command.starts_with("logs") {
+        support::run_log::start(&command, config_path.as_deref(), env_name.as_deref());
+    }
+    support::set_context(command, config_path, env_name);
+
+    if matches.value_of("log-format") == Some("ndjson") {
+        message::set_log_format(LogFormat::Ndjson);
+    }
+    if matches.is_present("plain") || Preferences::load().plain {
+        message::set_plain();
+    }
+    if matches.is_present("ci") || interactive::ci_detected() {
+        interactive::set_ci();
+    }
+    if matches.is_present("yes") {
+        interactive::assume_yes();
+    }
 
+    let log_level = matches
+        .value_of("log-level")
+        .map(str::to_string)
+        .or_else(|| env::var("WRANGLER_LOG").ok());
+    if let Some(log_level) = log_level {
+        if let Some(log_path) = http::trace::init(&log_level)? {
+            StdErr::info(&format!("Logging API requests to {}", log_path.display()));
+        }
+    }
+
+    if matches.is_present("wide") {
+        table::set_wide();
+    }
+    if matches.is_present("no-header") {
+        table::hide_header();
+    }
+    if matches.is_present("no-compress") {
+        http::compress::disable();
+    }
+    if matches.is_present("keep-temp") {
+        wrangler::wranglerjs::keep_temp_files();
+    }
+    if matches.is_present("offline") {
+        http::network::go_offline();
+    }
+    if let Some(command) = matches.subcommand_name() {
+        if !works_offline(&matches) {
+            http::network::require(command)?;
+        }
+    }
+
+    if matches.is_present("strict") {
+        message::set_strict();
+    }
     let mut is_preview = false;
 
     let not_recommended_msg = styles::warning("(Not Recommended)");
@@ -657,12 +1768,12 @@ fn run() -> Result<(), failure::Error> {
         let user: GlobalUser = if default {
             // API Tokens are the default
             StdOut::billboard(&format!("To find your API Token, go to {}\nand create it using the \"Edit Cloudflare Workers\" template.\n\nConsider using {} which only requires your Cloudflare username and password.\n\nIf you are trying to use your Global API Key instead of an API Token\n{}, run {}.", api_token_url, wrangler_login_msg, not_recommended_msg, recommended_cmd_msg));
-            let api_token: String = interactive::get_user_input("Enter API Token: ");
+            let api_token: String = interactive::get_user_input("Enter API Token: ")?;
             GlobalUser::TokenAuth { api_token }
         } else {
             StdOut::billboard(&format!("We don't recommend using your Global API Key!\nPlease consider using an API Token instead.\n\n{}", token_support_url));
-            let email: String = interactive::get_user_input("Enter Email: ");
-            let api_key: String = interactive::get_user_input("Enter Global API Key: ");
+            let email: String = interactive::get_user_input("Enter Email: ")?;
+            let api_key: String = interactive::get_user_input("Enter Global API Key: ")?;
 
             GlobalUser::GlobalKeyAuth { email, api_key }
         };
@@ -719,6 +1830,36 @@ fn run() -> Result<(), failure::Error> {
         commands::init(name, target_type, site)?;
     } else if let Some(matches) = matches.subcommand_matches("build") {
         commands::build(matches)?;
+    } else if let Some(cleanup_matches) = matches
+        .subcommand_matches("preview")
+        .and_then(|m| m.subcommand_matches("cleanup"))
+    {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            cleanup_matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let manifest = settings::toml::Manifest::new(config_path)?;
+        let target = manifest.get_target(None, is_preview)?;
+
+        let older_than_days = if cleanup_matches.is_present("all") {
+            None
+        } else {
+            match cleanup_matches.value_of("older-than") {
+                Some(days) => match days.parse() {
+                    Ok(days) => Some(days),
+                    Err(_) => failure::bail!("--older-than expects a number of days"),
+                },
+                None => Some(7),
+            }
+        };
+        let dry_run = cleanup_matches.is_present("dry-run");
+
+        commands::preview_branch::cleanup(&user, &target, older_than_days, dry_run)?;
     } else if let Some(matches) = matches.subcommand_matches("preview") {
         log::info!("Getting project settings");
         let config_path = Path::new(
@@ -770,6 +1911,13 @@ fn run() -> Result<(), failure::Error> {
         };
 
         commands::preview(target, user, options, verbose)?;
+    } else if let Some(matches) = matches
+        .subcommand_matches("dev")
+        .and_then(|matches| matches.subcommand_matches("replay"))
+    {
+        let file = Path::new(matches.value_of("file").unwrap());
+        let url = matches.value_of("url").unwrap_or("http://127.0.0.1:8787");
+        commands::dev::replay(file, url)?;
     } else if let Some(matches) = matches.subcommand_matches("dev") {
         use commands::dev::Protocol;
 
@@ -803,14 +1951,31 @@ fn run() -> Result<(), failure::Error> {
         let env = matches.value_of("env");
         let deployments = manifest.get_deployments(env)?;
         is_preview = true;
-        let target = manifest.get_target(env, is_preview)?;
+        let mut target = manifest.get_target(env, is_preview)?;
+        if let Some(env_file) = matches.value_of("env-file") {
+            let vars = settings::env_file::load(Path::new(env_file))?;
+            settings::env_file::apply(&mut target, vars);
+        }
         let user = settings::global_user::GlobalUser::new().ok();
         let verbose = matches.is_present("verbose");
 
         let local_protocol = Protocol::try_from(local_protocol_str.unwrap_or("http"))?;
         let upstream_protocol = Protocol::try_from(upstream_protocol_str.unwrap_or("https"))?;
 
-        let server_config = commands::dev::ServerConfig::new(host, ip, port, upstream_protocol)?;
+        let mut server_config =
+            commands::dev::ServerConfig::new(host, ip, port, upstream_protocol)?;
+        server_config.timings = matches.is_present("timings");
+        if let Some(local_upstream) = matches.value_of("local-upstream") {
+            server_config.set_local_upstream(local_upstream)?;
+        }
+        if let Some(inspector_port) = matches.value_of("inspector-port") {
+            server_config.inspector_port = inspector_port
+                .parse()
+                .expect("--inspector-port expects a number");
+        }
+        if let Some(record) = matches.value_of("record") {
+            server_config.recorder = Some(commands::dev::Recorder::new(Path::new(record))?);
+        }
 
         commands::dev::dev(
             target,
@@ -821,6 +1986,40 @@ fn run() -> Result<(), failure::Error> {
             upstream_protocol,
             verbose,
         )?;
+    } else if let Some(matches) = matches.subcommand_matches("test") {
+        log::info!("Running tests");
+
+        let config_path = Path::new(
+            matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let manifest = settings::toml::Manifest::new(config_path)?;
+        let env = matches.value_of("env");
+        let deployments = manifest.get_deployments(env)?;
+        is_preview = true;
+        let mut target = manifest.get_target(env, is_preview)?;
+        if let Some(env_file) = matches.value_of("env-file") {
+            let vars = settings::env_file::load(Path::new(env_file))?;
+            settings::env_file::apply(&mut target, vars);
+        }
+        let user = settings::global_user::GlobalUser::new().ok();
+
+        let port: Option<u16> = matches
+            .value_of("port")
+            .map(|p| p.parse().expect("--port expects a number"));
+        let server_config =
+            commands::dev::ServerConfig::new(None, None, port, commands::dev::Protocol::Https)?;
+        let command: Vec<&str> = matches.values_of("command").unwrap().collect();
+
+        commands::test(
+            target,
+            deployments,
+            user,
+            server_config,
+            &command,
+            matches.is_present("verbose"),
+        )?;
     } else if matches.subcommand_matches("whoami").is_some() {
         log::info!("Getting User settings");
         let user = settings::global_user::GlobalUser::new()?;
@@ -848,16 +2047,131 @@ fn run() -> Result<(), failure::Error> {
                 .value_of("config")
                 .unwrap_or(commands::DEFAULT_CONFIG_PATH),
         );
-        let manifest = settings::toml::Manifest::new(config_path)?;
-        let env = matches.value_of("env");
-        let mut target = manifest.get_target(env, is_preview)?;
-        let deploy_config = manifest.get_deployments(env)?;
-        if matches.is_present("output") && matches.value_of("output") == Some("json") {
-            commands::publish(&user, &mut target, deploy_config, Output::Json)?;
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let mut envs: Vec<String> = if matches.is_present("all-envs") {
+            match &manifest.env {
+                Some(environments) if !environments.is_empty() => {
+                    environments.keys().cloned().collect()
+                }
+                _ => failure::bail!("There are no [env] sections in {}", config_path.display()),
+            }
         } else {
-            commands::publish(&user, &mut target, deploy_config, Output::PlainText)?;
+            matches
+                .values_of("env")
+                .map(|envs| envs.map(String::from).collect())
+                .unwrap_or_default()
+        };
+        envs.sort();
+        envs.dedup();
+        if envs.len() > 1
+            && (matches.is_present("preview-branch") || matches.is_present("dispatch-namespace"))
+        {
+            failure::bail!(
+                "--preview-branch and --dispatch-namespace publish one environment at a time"
+            )
         }
-    } else if let Some(matches) = matches.subcommand_matches("subdomain") {
+
+        let mut env_vars = None;
+        if let Some(env_file) = matches.value_of("env-file") {
+            let vars = settings::env_file::load(Path::new(env_file))?;
+            if !vars.is_empty() {
+                let mut names: Vec<&String> = vars.keys().collect();
+                names.sort();
+                StdOut::info(&format!(
+                    "{} sets {}",
+                    env_file,
+                    names
+                        .iter()
+                        .map(|name| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+                let prompt = "Publish these as plain-text bindings? Their values will be readable by anyone with access to your worker, so use `wrangler secret put` for anything sensitive.";
+                if !interactive::confirm(prompt)? {
+                    failure::bail!(
+                        "Not publishing. Remove --env-file to publish without these variables."
+                    )
+                }
+                env_vars = Some(vars);
+            }
+        }
+        if matches.is_present("remote-lock") {
+            wrangler::deploy::lock::use_remote_lock();
+        }
+        let mut annotations = Annotations {
+            message: matches.value_of("message").map(String::from),
+            tag: matches.value_of("tag").map(String::from),
+            ..Default::default()
+        };
+        if manifest.git_metadata.unwrap_or(true) {
+            wrangler::upload::git::annotate(&mut annotations);
+        }
+        let artifact = matches.value_of("from-artifact").map(PathBuf::from);
+        if let Some(dir) = &artifact {
+            // fail before anything is published, rather than once the upload is built
+            wrangler::upload::form::artifact::Artifact::read(dir)?;
+        }
+        let force = matches.is_present("force");
+        let out = if matches.value_of("output") == Some("json") {
+            Output::Json
+        } else {
+            Output::PlainText
+        };
+
+        if envs.len() > 1 {
+            let mut environments = Vec::new();
+            for env in envs {
+                commands::account::ensure_account_id(
+                    &user,
+                    &mut manifest,
+                    config_path,
+                    Some(&env),
+                )?;
+                if matches.is_present("create-missing-bindings") {
+                    commands::kv::namespace::create_missing(
+                        &user,
+                        &mut manifest,
+                        config_path,
+                        Some(&env),
+                    )?;
+                }
+                let mut target = manifest.get_target(Some(&env), is_preview)?;
+                target.annotations = annotations.clone();
+                target.artifact = artifact.clone();
+                if let Some(vars) = &env_vars {
+                    settings::env_file::apply(&mut target, vars.clone());
+                }
+                let deployments = manifest.get_deployments(Some(&env))?;
+                environments.push(commands::publish::Environment {
+                    name: env,
+                    target,
+                    deployments,
+                });
+            }
+            commands::publish::publish_environments(&user, environments, force, out)?;
+        } else {
+            let env = envs.first().map(String::as_str);
+            commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+            if matches.is_present("create-missing-bindings") {
+                commands::kv::namespace::create_missing(&user, &mut manifest, config_path, env)?;
+            }
+            let mut target = manifest.get_target(env, is_preview)?;
+            target.annotations = annotations;
+            target.artifact = artifact;
+            if let Some(vars) = env_vars {
+                settings::env_file::apply(&mut target, vars);
+            }
+            let deploy_config = manifest.get_deployments(env)?;
+
+            if let Some(branch) = matches.value_of("preview-branch") {
+                commands::preview_branch::publish(&user, &mut target, branch, force, out)?;
+            } else if let Some(namespace) = matches.value_of("dispatch-namespace") {
+                commands::dispatch_namespace::publish(&user, &mut target, namespace, force, out)?;
+            } else {
+                commands::publish(&user, &mut target, deploy_config, env, force, out)?;
+            }
+        }
+    } else if let Some(matches) = matches.subcommand_matches("types") {
         log::info!("Getting project settings");
         let config_path = Path::new(
             matches
@@ -865,12 +2179,127 @@ fn run() -> Result<(), failure::Error> {
                 .unwrap_or(commands::DEFAULT_CONFIG_PATH),
         );
         let manifest = settings::toml::Manifest::new(config_path)?;
+        let target = manifest.get_target(matches.value_of("env"), is_preview)?;
+
+        let output = matches.value_of("output").map(Path::new);
+        commands::types::types(&target, output)?;
+    } else if let Some(matches) = matches.subcommand_matches("diff") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+        let deployments = manifest.get_deployments(env)?;
+
+        commands::diff(&target, &deployments, &user)?;
+    } else if let Some(deployments_matches) = matches.subcommand_matches("deployments") {
+        let (subcommand, subcommand_matches) = deployments_matches.subcommand();
+        let matches = subcommand_matches.unwrap();
+
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
+        match subcommand {
+            "list" => commands::deployments::list(&target, &user)?,
+            "diff" => commands::deployments::diff(
+                &target,
+                &user,
+                matches.value_of("from").unwrap(),
+                matches.value_of("to").unwrap(),
+            )?,
+            _ => unreachable!(),
+        }
+    } else if let Some(matches) = matches.subcommand_matches("metrics") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
+        let since = matches.value_of("since").unwrap_or("1h");
+        commands::metrics(&target, &user, since, matches.is_present("json"))?;
+    } else if let Some(matches) = matches.subcommand_matches("usage") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
+        commands::usage(&target, &user, matches.value_of("since").unwrap_or("7d"))?;
+    } else if let Some(matches) = matches.subcommand_matches("destroy") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
         let env = matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
         let target = manifest.get_target(env, is_preview)?;
 
+        let opt = commands::destroy::DestroyOpt {
+            kv_namespaces: matches.is_present("kv-namespaces"),
+            durable_objects: matches.is_present("durable-objects"),
+            dry_run: matches.is_present("dry-run"),
+            yes: matches.is_present("yes"),
+        };
+
+        commands::destroy(&target, &user, env, opt)?;
+    } else if let Some(matches) = matches.subcommand_matches("subdomain") {
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = matches.value_of("env");
+
         log::info!("Getting User settings");
         let user = settings::global_user::GlobalUser::new()?;
 
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
         let name = matches.value_of("name");
 
         if let Some(name) = name {
@@ -951,6 +2380,180 @@ fn run() -> Result<(), failure::Error> {
                 let target = manifest.get_target(env, is_preview)?;
                 commands::secret::list_secrets(&user, &target)?;
             }
+            ("diff", Some(diff_matches)) => {
+                let targets = match diff_matches.values_of("env") {
+                    Some(envs) => envs
+                        .map(|env| manifest.get_target(Some(env), is_preview))
+                        .collect::<Result<Vec<_>, _>>()?,
+                    None => vec![manifest.get_target(None, is_preview)?],
+                };
+                let env_file = Path::new(diff_matches.value_of("from").unwrap());
+                commands::secret::diff_secrets(&user, &targets, env_file)?;
+            }
+            _ => unreachable!(),
+        }
+    } else if let Some(mtls_matches) = matches.subcommand_matches("mtls-certificate") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let (subcommand, subcommand_matches) = mtls_matches.subcommand();
+        let subcommand_matches = subcommand_matches.unwrap();
+        let config_path = Path::new(
+            subcommand_matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = subcommand_matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
+        match subcommand {
+            "upload" => commands::mtls_certificate::upload(
+                &target,
+                &user,
+                Path::new(subcommand_matches.value_of("cert").unwrap()),
+                Path::new(subcommand_matches.value_of("key").unwrap()),
+                subcommand_matches.value_of("name"),
+            )?,
+            "list" => commands::mtls_certificate::list(&target, &user)?,
+            "delete" => commands::mtls_certificate::delete(
+                &target,
+                &user,
+                subcommand_matches.value_of("id").unwrap(),
+            )?,
+            _ => unreachable!(),
+        }
+    } else if let Some(alert_matches) = matches.subcommand_matches("alert") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let (subcommand, subcommand_matches) = alert_matches.subcommand();
+        let subcommand_matches = subcommand_matches.unwrap();
+        let config_path = Path::new(
+            subcommand_matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = subcommand_matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
+        match subcommand {
+            "add" => commands::alert::add(
+                &target,
+                &user,
+                subcommand_matches.value_of("error-rate").unwrap(),
+                subcommand_matches.value_of("window").unwrap(),
+                subcommand_matches.value_of("webhook").unwrap(),
+            )?,
+            "list" => commands::alert::list(&target, &user)?,
+            "delete" => {
+                commands::alert::delete(&target, &user, subcommand_matches.value_of("id").unwrap())?
+            }
+            _ => unreachable!(),
+        }
+    } else if let Some(dispatch_matches) = matches.subcommand_matches("dispatch-namespace") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let (subcommand, subcommand_matches) = dispatch_matches.subcommand();
+        let subcommand_matches = subcommand_matches.unwrap();
+        let config_path = Path::new(
+            subcommand_matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = subcommand_matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
+        match subcommand {
+            "create" => commands::dispatch_namespace::create(
+                &target,
+                &user,
+                subcommand_matches.value_of("name").unwrap(),
+            )?,
+            "list" => commands::dispatch_namespace::list(&target, &user)?,
+            "delete" => commands::dispatch_namespace::delete(
+                &target,
+                &user,
+                subcommand_matches.value_of("name").unwrap(),
+            )?,
+            _ => unreachable!(),
+        }
+    } else if let Some(email_matches) = matches.subcommand_matches("email:address") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        log::info!("Getting project settings");
+        let (subcommand, subcommand_matches) = email_matches.subcommand();
+        let subcommand_matches = subcommand_matches.unwrap();
+        let config_path = Path::new(
+            subcommand_matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let mut manifest = settings::toml::Manifest::new(config_path)?;
+        let env = subcommand_matches.value_of("env");
+        commands::account::ensure_account_id(&user, &mut manifest, config_path, env)?;
+        let target = manifest.get_target(env, is_preview)?;
+
+        match subcommand {
+            "list" => commands::email::list_addresses(&target, &user)?,
+            "verify" => commands::email::verify_address(
+                &target,
+                &user,
+                subcommand_matches.value_of("address"),
+            )?,
+            _ => unreachable!(),
+        }
+    } else if let Some(pages_matches) = matches.subcommand_matches("pages") {
+        log::info!("Getting User settings");
+        let user = settings::global_user::GlobalUser::new()?;
+
+        let account_id = |matches: &clap::ArgMatches| -> Result<String, failure::Error> {
+            match matches.value_of("account-id") {
+                Some(account_id) => Ok(account_id.to_string()),
+                None => {
+                    let config_path = Path::new(
+                        matches
+                            .value_of("config")
+                            .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+                    );
+                    commands::account::account_id(&user, config_path)
+                }
+            }
+        };
+
+        match pages_matches.subcommand() {
+            ("publish", Some(publish_matches)) => commands::pages::publish(
+                &user,
+                &account_id(publish_matches)?,
+                publish_matches.value_of("project-name").unwrap(),
+                Path::new(publish_matches.value_of("directory").unwrap()),
+                publish_matches.value_of("branch"),
+                publish_matches.value_of("commit-message"),
+            )?,
+            ("project", Some(project_matches)) => match project_matches.subcommand() {
+                ("list", Some(list_matches)) => {
+                    commands::pages::list_projects(&user, &account_id(list_matches)?)?
+                }
+                ("create", Some(create_matches)) => commands::pages::create_project(
+                    &user,
+                    &account_id(create_matches)?,
+                    create_matches.value_of("name").unwrap(),
+                    create_matches
+                        .value_of("production-branch")
+                        .unwrap_or("main"),
+                )?,
+                _ => unreachable!(),
+            },
             _ => unreachable!(),
         }
     } else if let Some(kv_matches) = matches.subcommand_matches("kv:namespace") {
@@ -971,7 +2574,14 @@ fn run() -> Result<(), failure::Error> {
                 is_preview = create_matches.is_present("preview");
                 let env = create_matches.value_of("env");
                 let binding = create_matches.value_of("binding").unwrap();
-                commands::kv::namespace::create(&manifest, is_preview, env, &user, binding)?;
+                commands::kv::namespace::create(
+                    &manifest,
+                    config_path,
+                    is_preview,
+                    env,
+                    &user,
+                    binding,
+                )?;
             }
             ("delete", Some(delete_matches)) => {
                 is_preview = delete_matches.is_present("preview");
@@ -993,6 +2603,26 @@ fn run() -> Result<(), failure::Error> {
                 let target = manifest.get_target(env, is_preview)?;
                 commands::kv::namespace::list(&target, &user)?;
             }
+            ("rename", Some(rename_matches)) => {
+                let env = rename_matches.value_of("env");
+                let target = manifest.get_target(env, is_preview)?;
+                commands::kv::namespace::rename(
+                    &target,
+                    &user,
+                    rename_matches.value_of("namespace-id").unwrap(),
+                    rename_matches.value_of("title").unwrap(),
+                )?;
+            }
+            ("info", Some(info_matches)) => {
+                let env = info_matches.value_of("env");
+                let target = manifest.get_target(env, is_preview)?;
+                commands::kv::namespace::info(
+                    &target,
+                    &user,
+                    env,
+                    info_matches.value_of("namespace-id").unwrap(),
+                )?;
+            }
             _ => unreachable!(),
         }
     } else if let Some(kv_matches) = matches.subcommand_matches("kv:key") {
@@ -1030,7 +2660,8 @@ fn run() -> Result<(), failure::Error> {
         match (subcommand, subcommand_matches) {
             ("get", Some(get_key_matches)) => {
                 let key = get_key_matches.value_of("key").unwrap();
-                commands::kv::key::get(&target, &user, &namespace_id, key)?
+                let out = get_key_matches.value_of("out").map(Path::new);
+                commands::kv::key::get(&target, &user, &namespace_id, key, out)?
             }
             ("put", Some(put_key_matches)) => {
                 let key = put_key_matches.value_of("key").unwrap().to_string();
@@ -1060,8 +2691,20 @@ fn run() -> Result<(), failure::Error> {
                 commands::kv::key::delete(&target, &user, &namespace_id, key)?
             }
             ("list", Some(list_key_matches)) => {
-                let prefix = list_key_matches.value_of("prefix");
-                commands::kv::key::list(&target, &user, &namespace_id, prefix)?
+                let limit = match list_key_matches.value_of("limit") {
+                    Some(limit) => match limit.parse() {
+                        Ok(limit) => Some(limit),
+                        Err(_) => failure::bail!("--limit expects a number of keys"),
+                    },
+                    None => None,
+                };
+                let options = commands::kv::key::ListOptions {
+                    prefix: list_key_matches.value_of("prefix"),
+                    limit,
+                    cursor: list_key_matches.value_of("cursor"),
+                    show_values: list_key_matches.is_present("show-values"),
+                };
+                commands::kv::key::list(&target, &user, &namespace_id, &options, &mut io::stdout())?
             }
             _ => unreachable!(),
         }
@@ -1098,11 +2741,13 @@ fn run() -> Result<(), failure::Error> {
         match (subcommand, subcommand_matches) {
             ("put", Some(put_bulk_matches)) => {
                 let path = put_bulk_matches.value_of("path").unwrap();
-                commands::kv::bulk::put(&target, &user, &namespace_id, Path::new(path))?
+                let dry_run = put_bulk_matches.is_present("dry-run");
+                commands::kv::bulk::put(&target, &user, &namespace_id, Path::new(path), dry_run)?
             }
             ("delete", Some(delete_bulk_matches)) => {
                 let path = delete_bulk_matches.value_of("path").unwrap();
-                commands::kv::bulk::delete(&target, &user, &namespace_id, Path::new(path))?
+                let dry_run = delete_bulk_matches.is_present("dry-run");
+                commands::kv::bulk::delete(&target, &user, &namespace_id, Path::new(path), dry_run)?
             }
             _ => unreachable!(),
         }
@@ -1117,18 +2762,85 @@ fn run() -> Result<(), failure::Error> {
         let target = manifest.get_target(env, is_preview)?;
         let user = settings::global_user::GlobalUser::new()?;
 
-        let tunnel_port: Option<u16> = matches
-            .value_of("tunnel_port")
-            .map(|p| p.parse().expect("--port expects a number"));
-        let metrics_port: Option<u16> = matches
-            .value_of("metrics_port")
-            .map(|p| p.parse().expect("--metrics expects a number"));
+        if matches.is_present("tunnel_port") || matches.is_present("metrics_port") {
+            // on stderr, as the logs are printed on stdout
+            StdErr::warn(
+                "--port and --metrics are no longer used, as tail no longer runs cloudflared",
+            );
+        }
 
         let verbose = matches.is_present("verbose");
+        let stats = matches.is_present("stats");
+        let errors_only = matches.is_present("errors_only");
 
-        commands::tail::start(&target, &user, tunnel_port, metrics_port, verbose)?;
+        commands::tail::start(&target, &user, verbose, stats, errors_only)?;
     } else if matches.subcommand_matches("login").is_some() {
         commands::login::run()?;
+    } else if let Some(tools_matches) = matches.subcommand_matches("tools") {
+        if let Some(vendor_matches) = tools_matches.subcommand_matches("vendor") {
+            log::info!("Getting project settings");
+            let config_path = Path::new(
+                vendor_matches
+                    .value_of("config")
+                    .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+            );
+            let manifest = settings::toml::Manifest::new(config_path)?;
+            let target = manifest.get_target(None, is_preview)?;
+
+            let dir = match vendor_matches.value_of("dir") {
+                Some(dir) => PathBuf::from(dir),
+                None => env::var("WRANGLER_TOOLS_DIR")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| PathBuf::from("wrangler-tools")),
+            };
+
+            commands::tools::vendor(&target, &dir)?;
+        } else if let Some(clean_matches) = tools_matches.subcommand_matches("clean") {
+            let all = clean_matches.is_present("all");
+            let dry_run = clean_matches.is_present("dry-run");
+            commands::tools::clean(all, dry_run)?;
+        }
+    } else if let Some(env_matches) = matches.subcommand_matches("env") {
+        let (subcommand, subcommand_matches) = env_matches.subcommand();
+        let subcommand_matches = subcommand_matches.unwrap();
+
+        log::info!("Getting project settings");
+        let config_path = Path::new(
+            subcommand_matches
+                .value_of("config")
+                .unwrap_or(commands::DEFAULT_CONFIG_PATH),
+        );
+        let manifest = settings::toml::Manifest::new(config_path)?;
+
+        match subcommand {
+            "list" => commands::env::list(&manifest)?,
+            "show" => commands::env::show(&manifest, subcommand_matches.value_of("name"))?,
+            _ => unreachable!(),
+        }
+    } else if let Some(artifact_matches) = matches.subcommand_matches("artifact") {
+        match artifact_matches.subcommand() {
+            ("push", Some(push_matches)) => commands::artifact::push(
+                Path::new(push_matches.value_of("dir").unwrap()),
+                push_matches.value_of("destination").unwrap(),
+            )?,
+            ("pull", Some(pull_matches)) => commands::artifact::pull(
+                pull_matches.value_of("source").unwrap(),
+                Path::new(pull_matches.value_of("out-dir").unwrap()),
+            )?,
+            _ => unreachable!(),
+        }
+    } else if let Some(logs_matches) = matches.subcommand_matches("logs") {
+        match logs_matches.subcommand_name() {
+            Some("show-last") => commands::logs::show_last()?,
+            _ => unreachable!(),
+        }
+    } else if let Some(telemetry_matches) = matches.subcommand_matches("telemetry") {
+        match telemetry_matches.subcommand_name() {
+            Some("enable") => commands::telemetry::enable()?,
+            Some("disable") => commands::telemetry::disable()?,
+            Some("show") => commands::telemetry::show()?,
+            _ => unreachable!(),
+        }
     }
     Ok(())
 }
@@ -3,7 +3,7 @@ use std::path::Path;
 use reqwest::blocking::Client;
 use serde::Deserialize;
 
-use crate::http;
+use crate::http::{self, Traced};
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
@@ -147,10 +147,7 @@ fn authenticated_upload(
     target: &Target,
     asset_manifest: Option<AssetManifest>,
 ) -> Result<Preview, failure::Error> {
-    let create_address = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/preview",
-        target.account_id, target.name
-    );
+    let create_address = http::endpoints::script_preview(&target.account_id, &target.name);
     log::info!("address: {}", create_address);
 
     let script_upload_form = upload::form::build(target, asset_manifest, None)?;
@@ -158,14 +155,10 @@ fn authenticated_upload(
     let res = client
         .post(&create_address)
         .multipart(script_upload_form)
-        .send()?;
+        .send_traced()?;
 
     if !res.status().is_success() {
-        failure::bail!(
-            "Something went wrong! Status: {}, Details {}",
-            res.status(),
-            res.text()?
-        )
+        return Err(http::status_error(res.status(), &res.text()?));
     }
 
     let text = &res.text()?;
@@ -204,14 +197,10 @@ fn unauthenticated_upload(target: &Target) -> Result<Preview, failure::Error> {
     let res = client
         .post(create_address)
         .multipart(script_upload_form)
-        .send()?;
+        .send_traced()?;
 
     if !res.status().is_success() {
-        failure::bail!(
-            "Something went wrong! Status: {}, Details {}",
-            res.status(),
-            res.text()?
-        )
+        return Err(http::status_error(res.status(), &res.text()?));
     }
 
     let text = &res.text()?;
@@ -4,10 +4,41 @@ use serde::Serialize;
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum Binding {
-    WasmModule { name: String, part: String },
-    KvNamespace { name: String, namespace_id: String },
-    TextBlob { name: String, part: String },
-    PlainText { name: String, text: String },
+    WasmModule {
+        name: String,
+        part: String,
+    },
+    KvNamespace {
+        name: String,
+        namespace_id: String,
+    },
+    TextBlob {
+        name: String,
+        part: String,
+    },
+    DataBlob {
+        name: String,
+        part: String,
+    },
+    PlainText {
+        name: String,
+        text: String,
+    },
+    Json {
+        name: String,
+        json: serde_json::Value,
+    },
+    MtlsCertificate {
+        name: String,
+        certificate_id: String,
+    },
+    SendEmail {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        destination_address: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        allowed_destination_addresses: Option<Vec<String>>,
+    },
 }
 
 impl Binding {
@@ -23,7 +54,34 @@ impl Binding {
         Binding::TextBlob { name, part }
     }
 
+    pub fn new_data_blob(name: String, part: String) -> Binding {
+        Binding::DataBlob { name, part }
+    }
+
     pub fn new_plain_text(name: String, text: String) -> Binding {
         Binding::PlainText { name, text }
     }
+
+    pub fn new_json(name: String, json: serde_json::Value) -> Binding {
+        Binding::Json { name, json }
+    }
+
+    pub fn new_mtls_certificate(name: String, certificate_id: String) -> Binding {
+        Binding::MtlsCertificate {
+            name,
+            certificate_id,
+        }
+    }
+
+    pub fn new_send_email(
+        name: String,
+        destination_address: Option<String>,
+        allowed_destination_addresses: Option<Vec<String>>,
+    ) -> Binding {
+        Binding::SendEmail {
+            name,
+            destination_address,
+            allowed_destination_addresses,
+        }
+    }
 }
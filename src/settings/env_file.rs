@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::settings::toml::Target;
+
+/// Reads a dotenv file (e.g. `.env.production`) into a map of variable names to values.
+pub fn load(path: &Path) -> Result<HashMap<String, String>, failure::Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => failure::bail!("Could not read {}: {}", path.display(), e),
+    };
+    match parse(&contents) {
+        Ok(vars) => Ok(vars),
+        Err(e) => failure::bail!("{} is not a valid .env file: {}", path.display(), e),
+    }
+}
+
+/// Adds the variables to the target's `vars`, replacing any with the same name.
+pub fn apply(target: &mut Target, vars: HashMap<String, String>) {
+    let target_vars = target.vars.get_or_insert_with(HashMap::new);
+    for (name, value) in vars {
+        if target_vars.contains_key(&name) {
+            log::info!("{} from the .env file replaces the one in vars", name);
+        }
+        target_vars.insert(name, serde_json::Value::String(value));
+    }
+}
+
+// Supports the subset of dotenv most projects use: `KEY=value` lines, an optional
+// `export ` prefix, `#` comments, and single or double quoted values. Double quoted
+// values may contain `\n` escapes.
+fn parse(contents: &str) -> Result<HashMap<String, String>, failure::Error> {
+    let mut vars = HashMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = match line.find('=') {
+            Some(eq) => (line[..eq].trim(), line[eq + 1..].trim()),
+            None => failure::bail!("line {} is not of the form KEY=value", i + 1),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            failure::bail!("line {} has an invalid name {:?}", i + 1, name)
+        }
+        vars.insert(name.to_string(), parse_value(value));
+    }
+    Ok(vars)
+}
+
+// A quoted value ends at its closing quote, so a comment can follow it.
+fn parse_value(value: &str) -> String {
+    if value.starts_with('"') {
+        if let Some(end) = closing_quote(&value[1..], '"') {
+            return value[1..=end].replace("\\n", "\n").replace("\\\"", "\"");
+        }
+    } else if value.starts_with('\'') {
+        if let Some(end) = closing_quote(&value[1..], '\'') {
+            return value[1..=end].to_string();
+        }
+    }
+    // unquoted values end at a comment
+    match value.find(" #") {
+        Some(comment) => value[..comment].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+// The index in `value` of the quote that closes it. Double quotes can be escaped.
+fn closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        if c == quote && !escaped {
+            return Some(i);
+        }
+        escaped = quote == '"' && c == '\\' && !escaped;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_dotenv_files() {
+        let vars = parse(
+            "# production settings\nAPI_URL=https://api.example.com # the API\nexport MODE = production\nGREETING=\"hello\\nworld\"\nRAW='a \\n b'\nEMPTY=\nQUOTED=\"say \\\"hi\\\" # twice\" # a comment\nSINGLE='x' # another\n",
+        )
+        .unwrap();
+
+        assert_eq!(vars["API_URL"], "https://api.example.com");
+        assert_eq!(vars["MODE"], "production");
+        assert_eq!(vars["GREETING"], "hello\nworld");
+        assert_eq!(vars["RAW"], "a \\n b");
+        assert_eq!(vars["EMPTY"], "");
+        assert_eq!(vars["QUOTED"], "say \"hi\" # twice");
+        assert_eq!(vars["SINGLE"], "x");
+        assert_eq!(vars.len(), 7);
+
+        assert!(parse("NOT A VARIABLE").is_err());
+        assert!(parse("BAD-NAME=1").is_err());
+    }
+}
@@ -11,6 +11,20 @@ pub trait QueryEnvironment {
     fn empty(&self) -> Result<bool, failure::Error>;
 }
 
+/// Whether an environment variable turning something on or off is set, e.g.
+/// `WRANGLER_OFFLINE=1` or `CI=true`: it has a value other than "", "0", "false", "no"
+/// or "off".
+pub fn env_flag(var: &str) -> bool {
+    env::var(var).map_or(false, |value| is_on(&value))
+}
+
+fn is_on(value: &str) -> bool {
+    !matches!(
+        value.trim().to_lowercase().as_str(),
+        "" | "0" | "false" | "no" | "off"
+    )
+}
+
 #[derive(Clone, Debug)]
 pub struct Environment {
     whitelist: Vec<&'static str>,
@@ -145,4 +159,16 @@ mod tests {
 
         assert_eq!(environment.collect().unwrap(), expected_env_vars);
     }
+
+    #[test]
+    fn it_reads_flags() {
+        assert!(is_on("1"));
+        assert!(is_on("true"));
+        assert!(is_on("TRUE"));
+        assert!(!is_on("0"));
+        assert!(!is_on("False"));
+        assert!(!is_on(" no "));
+        assert!(!is_on("off"));
+        assert!(!is_on(""));
+    }
 }
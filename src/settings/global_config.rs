@@ -1,19 +1,68 @@
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 pub const DEFAULT_CONFIG_FILE_NAME: &str = "default.toml";
 
+// Where wrangler kept its configuration before it followed the XDG base directory spec
+const LEGACY_HOME_DIR: &str = ".wrangler";
+const HOME_DIR_NAME: &str = "wrangler";
+
+/// The directory wrangler keeps its configuration in: `$WRANGLER_HOME` if set, otherwise
+/// `$XDG_CONFIG_HOME/wrangler` (or the platform's config directory). A legacy `~/.wrangler`
+/// directory is moved there the first time it is found.
 pub fn get_wrangler_home_dir() -> Result<PathBuf, failure::Error> {
-    let config_dir = if let Ok(value) = env::var("WRANGLER_HOME") {
+    if let Ok(value) = env::var("WRANGLER_HOME") {
         log::info!("Using $WRANGLER_HOME: {}", value);
-        Path::new(&value).to_path_buf()
+        return Ok(Path::new(&value).to_path_buf());
+    }
+
+    let home_dir = dirs::home_dir().expect("Could not find home directory");
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(value) if !value.is_empty() => PathBuf::from(value),
+        _ => dirs::config_dir().unwrap_or_else(|| home_dir.join(".config")),
+    };
+    Ok(resolve_home_dir(
+        config_dir.join(HOME_DIR_NAME),
+        home_dir.join(LEGACY_HOME_DIR),
+    ))
+}
+
+// The XDG home, after moving the legacy home there if only the legacy one exists
+fn resolve_home_dir(config_dir: PathBuf, legacy_dir: PathBuf) -> PathBuf {
+    if legacy_dir.exists() && !config_dir.exists() {
+        migrate_legacy_dir(&legacy_dir, &config_dir);
+    }
+
+    if config_dir.exists() || !legacy_dir.exists() {
+        log::info!("Using wrangler home {}", config_dir.display());
+        config_dir
     } else {
-        log::info!("No $WRANGLER_HOME detected, using $HOME");
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".wrangler")
+        // the migration failed, keep using the old location rather than losing the config
+        log::info!("Using legacy wrangler home {}", legacy_dir.display());
+        legacy_dir
+    }
+}
+
+fn migrate_legacy_dir(legacy_dir: &Path, config_dir: &Path) {
+    log::info!(
+        "Moving {} to {}",
+        legacy_dir.display(),
+        config_dir.display()
+    );
+    let moved = match config_dir.parent() {
+        Some(parent) => fs::create_dir_all(parent).and_then(|_| fs::rename(legacy_dir, config_dir)),
+        None => fs::rename(legacy_dir, config_dir),
     };
-    Ok(config_dir)
+    if let Err(e) = moved {
+        // e.g. the two directories are on different file systems
+        log::warn!(
+            "Could not move {} to {}: {}",
+            legacy_dir.display(),
+            config_dir.display(),
+            e
+        );
+    }
 }
 
 pub fn get_global_config_path() -> Result<PathBuf, failure::Error> {
@@ -22,3 +71,72 @@ pub fn get_global_config_path() -> Result<PathBuf, failure::Error> {
     log::info!("Using global config file: {}", global_config_file.display());
     Ok(global_config_file)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the XDG wrangler home and the legacy one in `home`, neither of which exists yet
+    fn home_dirs(home: &Path) -> (PathBuf, PathBuf) {
+        (
+            home.join(".config").join(HOME_DIR_NAME),
+            home.join(LEGACY_HOME_DIR),
+        )
+    }
+
+    #[test]
+    fn it_uses_the_xdg_home_on_a_fresh_install() {
+        let home = tempfile::tempdir().unwrap();
+        let (config_dir, legacy_dir) = home_dirs(home.path());
+
+        assert_eq!(
+            resolve_home_dir(config_dir.clone(), legacy_dir.clone()),
+            config_dir
+        );
+        assert!(!legacy_dir.exists());
+    }
+
+    #[test]
+    fn it_moves_the_legacy_home() {
+        let home = tempfile::tempdir().unwrap();
+        let (config_dir, legacy_dir) = home_dirs(home.path());
+        fs::create_dir_all(legacy_dir.join("config")).unwrap();
+        fs::write(legacy_dir.join("config").join(DEFAULT_CONFIG_FILE_NAME), "").unwrap();
+
+        assert_eq!(
+            resolve_home_dir(config_dir.clone(), legacy_dir.clone()),
+            config_dir
+        );
+        assert!(config_dir
+            .join("config")
+            .join(DEFAULT_CONFIG_FILE_NAME)
+            .exists());
+        assert!(!legacy_dir.exists());
+    }
+
+    #[test]
+    fn it_leaves_the_legacy_home_alone_when_both_exist() {
+        let home = tempfile::tempdir().unwrap();
+        let (config_dir, legacy_dir) = home_dirs(home.path());
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::create_dir_all(&legacy_dir).unwrap();
+        fs::write(legacy_dir.join("kept"), "").unwrap();
+
+        assert_eq!(
+            resolve_home_dir(config_dir.clone(), legacy_dir.clone()),
+            config_dir
+        );
+        assert!(legacy_dir.join("kept").exists());
+        assert!(!config_dir.join("kept").exists());
+    }
+
+    #[test]
+    fn it_uses_wrangler_home_when_set() {
+        let home = tempfile::tempdir().unwrap();
+        env::set_var("WRANGLER_HOME", home.path());
+        let wrangler_home = get_wrangler_home_dir();
+        env::remove_var("WRANGLER_HOME");
+
+        assert_eq!(wrangler_home.unwrap(), home.path());
+    }
+}
@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 use cloudflare::framework::auth::Credentials;
 use serde::{Deserialize, Serialize};
 
+use crate::error::WranglerError;
 use crate::settings::{get_global_config_path, Environment, QueryEnvironment};
 use crate::terminal::{emoji, styles};
 
@@ -80,10 +81,11 @@ impl GlobalUser {
             );
             s.merge(config::File::with_name(config_str))?;
         } else {
-            failure::bail!(
+            return Err(WranglerError::Auth(format!(
                 "config path does not exist {}. Try running `wrangler login` or `wrangler config`",
                 config_str
-            );
+            ))
+            .into());
         }
 
         GlobalUser::from_config(s)
@@ -114,7 +116,7 @@ impl GlobalUser {
                     vars_msg
                 );
                 log::info!("{:?}", config);
-                failure::bail!(msg)
+                Err(WranglerError::Auth(msg).into())
             }
         }
     }
@@ -1,9 +1,41 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::settings::binding::Binding;
+use crate::settings::toml::TailConsumer;
 
 #[derive(Serialize, Debug)]
 pub struct Metadata {
     pub body_part: String,
     pub bindings: Vec<Binding>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tail_consumers: Vec<TailConsumer>,
+    #[serde(skip_serializing_if = "Annotations::is_empty")]
+    pub annotations: Annotations,
+}
+
+/// Notes attached to an uploaded version of a script, from `wrangler publish --message`
+/// and `--tag` and the git checkout it was published from, which `wrangler deployments
+/// list` shows.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Annotations {
+    #[serde(rename = "workers/message", skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(rename = "workers/tag", skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(rename = "git/commit", skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    #[serde(rename = "git/branch", skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// whether there were uncommitted changes
+    #[serde(rename = "git/dirty", skip_serializing_if = "Option::is_none")]
+    pub git_dirty: Option<bool>,
+    /// the digest of the SBOM written by the build, with `[build.sbom] attach = true`
+    #[serde(rename = "wrangler/sbom", skip_serializing_if = "Option::is_none")]
+    pub sbom: Option<String>,
+}
+
+impl Annotations {
+    pub fn is_empty(&self) -> bool {
+        self == &Annotations::default()
+    }
 }
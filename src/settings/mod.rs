@@ -1,9 +1,11 @@
 pub mod binding;
+pub mod env_file;
 mod environment;
 mod global_config;
 pub mod global_user;
 pub mod metadata;
+pub mod preferences;
 pub mod toml;
 
-pub use environment::{Environment, QueryEnvironment};
+pub use environment::{env_flag, Environment, QueryEnvironment};
 pub use global_config::{get_global_config_path, get_wrangler_home_dir, DEFAULT_CONFIG_FILE_NAME};
@@ -0,0 +1,49 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::settings::get_wrangler_home_dir;
+
+const PREFERENCES_FILE_NAME: &str = "preferences.toml";
+
+/// How wrangler behaves on this machine, from `preferences.toml` in the wrangler home
+/// directory. Unlike the global config, it holds no credentials, and wrangler never
+/// writes to it.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Preferences {
+    /// The language to print messages in, e.g. "fr" or "pt-BR"
+    pub locale: Option<String>,
+    /// Print without emoji, spinners or borders, like `--plain`
+    pub plain: bool,
+    /// Print how long the command took, what it uploaded and how many API calls it made
+    pub summary: bool,
+    /// How many runs to keep logs of in the `logs` directory, 0 for none. 20 by default
+    pub keep_logs: Option<usize>,
+}
+
+impl Preferences {
+    /// The preferences, or the defaults when there's no file. A file that can't be read
+    /// is logged and ignored, as preferences never stop a command.
+    pub fn load() -> Preferences {
+        let path = match preferences_path() {
+            Ok(path) if path.exists() => path,
+            _ => return Preferences::default(),
+        };
+        let preferences = fs::read_to_string(&path)
+            .map_err(failure::Error::from)
+            .and_then(|text| Ok(toml::from_str(&text)?));
+        match preferences {
+            Ok(preferences) => preferences,
+            Err(e) => {
+                log::warn!("Could not read {}: {}", path.display(), e);
+                Preferences::default()
+            }
+        }
+    }
+}
+
+pub fn preferences_path() -> Result<PathBuf, failure::Error> {
+    Ok(get_wrangler_home_dir()?.join(PREFERENCES_FILE_NAME))
+}
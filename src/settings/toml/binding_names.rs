@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use crate::error::WranglerError;
+use crate::settings::toml::{Target, TargetType};
+use crate::terminal::emoji;
+use crate::upload::form::module_file;
+
+// can't be declared as globals
+const RESERVED_WORDS: &[&str] = &[
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+// globals of the Workers runtime that a binding would shadow
+const RESERVED_GLOBALS: &[&str] = &[
+    "addEventListener",
+    "Array",
+    "atob",
+    "btoa",
+    "caches",
+    "clearInterval",
+    "clearTimeout",
+    "console",
+    "crypto",
+    "Date",
+    "Error",
+    "fetch",
+    "FormData",
+    "globalThis",
+    "Headers",
+    "HTMLRewriter",
+    "Infinity",
+    "JSON",
+    "Map",
+    "Math",
+    "NaN",
+    "navigator",
+    "Number",
+    "Object",
+    "Promise",
+    "queueMicrotask",
+    "ReadableStream",
+    "Request",
+    "Response",
+    "scheduler",
+    "self",
+    "Set",
+    "setInterval",
+    "setTimeout",
+    "String",
+    "structuredClone",
+    "Symbol",
+    "TextDecoder",
+    "TextEncoder",
+    "undefined",
+    "URL",
+    "URLSearchParams",
+    "WebAssembly",
+    "WebSocket",
+];
+
+/// Checks that no two bindings of the target share a name, and that no binding is named
+/// after a reserved word or a runtime global, naming the configuration entries at fault.
+/// Names that aren't JavaScript identifiers only get a warning, which is returned, as the
+/// worker can still reach them through `globalThis`.
+pub fn validate(
+    target: &Target,
+    environment_name: Option<&str>,
+) -> Result<Vec<String>, failure::Error> {
+    let table = match environment_name {
+        Some(env) => format!("[env.{}] ", env),
+        None => String::new(),
+    };
+
+    let mut entries: Vec<(&str, String)> = Vec::new();
+    for kv in &target.kv_namespaces {
+        entries.push((&kv.binding, format!("{}kv_namespaces binding", table)));
+    }
+    for certificate in &target.mtls_certificates {
+        entries.push((
+            &certificate.binding,
+            format!("{}mtls_certificates binding", table),
+        ));
+    }
+    if let Some(email) = &target.email {
+        for send_email in &email.send_email {
+            entries.push((&send_email.name, format!("{}email.send_email name", table)));
+        }
+    }
+    if let Some(vars) = &target.vars {
+        for name in vars.keys() {
+            entries.push((name, format!("{}vars", table)));
+        }
+    }
+    if let Some(text_blobs) = &target.text_blobs {
+        for name in text_blobs.keys() {
+            // only set at the top level
+            entries.push((name, "text_blobs".to_string()));
+        }
+    }
+    if target.site.is_some() {
+        entries.push(("__STATIC_CONTENT", "[site]".to_string()));
+        entries.push(("__STATIC_CONTENT_MANIFEST", "[site]".to_string()));
+    }
+    if target.target_type == TargetType::Rust {
+        entries.push(("wasm", "the Rust build's Wasm module".to_string()));
+    }
+    // a [[rules]] glob that can't be walked fails the build, which says why
+    let rule_files = module_file::from_target(target).unwrap_or_default();
+    let rule_names: Vec<String> = rule_files.iter().map(|file| file.name()).collect();
+    for (name, file) in rule_names.iter().zip(&rule_files) {
+        // only set at the top level
+        entries.push((name, format!("[[rules]] file {}", file.path().display())));
+    }
+
+    let mut problems = Vec::new();
+    let mut warnings = Vec::new();
+    let mut seen: BTreeMap<&str, &str> = BTreeMap::new();
+    // sorted so the messages don't depend on HashMap order
+    entries.sort();
+    for (name, entry) in &entries {
+        if let Some(first) = seen.insert(name, entry) {
+            problems.push(format!(
+                "\"{}\" is bound by both {} and {}",
+                name, first, entry
+            ));
+        }
+        if RESERVED_WORDS.contains(name) {
+            problems.push(format!(
+                "\"{}\" in {} is a reserved word in JavaScript",
+                name, entry
+            ));
+        } else if RESERVED_GLOBALS.contains(name) {
+            problems.push(format!(
+                "\"{}\" in {} would hide the runtime's global of the same name",
+                name, entry
+            ));
+        } else if !is_identifier(name) {
+            warnings.push(format!(
+                "\"{}\" in {} is not a valid JavaScript identifier, so your worker can only reach it as globalThis[\"{}\"]",
+                name, entry, name
+            ));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(WranglerError::Config(format!(
+            "{} Rename these bindings in your configuration file:\n  {}",
+            emoji::WARN,
+            problems.join("\n  ")
+        ))
+        .into());
+    }
+    Ok(warnings)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => (),
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use semver::{Compat, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// Settings for the external build process, from the `[build]` table.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Build {
+    /// Seconds to let the build run before it is killed; no limit when unset or 0.
+    pub timeout: Option<u64>,
+    /// The Node.js versions webpack builds run on, as an npm range like ">=14 <17".
+    pub node_version: Option<String>,
+    /// Commands run before and after every build, from `[build.hooks]`.
+    pub hooks: Option<BuildHooks>,
+    /// The software bill of materials written on every build, from `[build.sbom]`.
+    pub sbom: Option<Sbom>,
+    /// The licenses `wrangler build --license-report` fails on, from `[build.licenses]`.
+    pub licenses: Option<Licenses>,
+}
+
+/// Shell commands run around the build, whatever the target type. Each runs to
+/// completion in order, and the first to fail fails the build.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct BuildHooks {
+    #[serde(default)]
+    pub pre: Vec<String>,
+    #[serde(default)]
+    pub post: Vec<String>,
+}
+
+/// Writes a software bill of materials of the packages bundled into the worker on every
+/// build: `sbom.cdx.json` for CycloneDX, or `sbom.spdx.json` for SPDX.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Sbom {
+    #[serde(default)]
+    pub format: SbomFormat,
+    /// Record the SBOM's digest with the uploaded version when publishing
+    #[serde(default)]
+    pub attach: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SbomFormat {
+    CycloneDx,
+    Spdx,
+}
+
+impl Default for SbomFormat {
+    fn default() -> Self {
+        SbomFormat::CycloneDx
+    }
+}
+
+/// SPDX license identifiers no bundled package may need, like "GPL-3.0-only", or
+/// prefixes ending in `*`, like "AGPL-*". A package whose license expression offers a
+/// choice only fails when every choice needs a denied license.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Licenses {
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl Build {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+            .filter(|timeout| *timeout > 0)
+            .map(Duration::from_secs)
+    }
+
+    pub fn pre_hooks(&self) -> &[String] {
+        self.hooks
+            .as_ref()
+            .map_or(&[], |hooks| hooks.pre.as_slice())
+    }
+
+    pub fn post_hooks(&self) -> &[String] {
+        self.hooks
+            .as_ref()
+            .map_or(&[], |hooks| hooks.post.as_slice())
+    }
+
+    pub fn denied_licenses(&self) -> &[String] {
+        self.licenses
+            .as_ref()
+            .map_or(&[], |licenses| licenses.deny.as_slice())
+    }
+
+    pub fn node_version(&self) -> Result<Option<VersionReq>, failure::Error> {
+        match &self.node_version {
+            Some(range) => match VersionReq::parse_compat(range, Compat::Npm) {
+                Ok(req) => Ok(Some(req)),
+                Err(e) => failure::bail!(
+                    "node_version = \"{}\" in [build] is not a version range like \">=14 <17\": {}",
+                    range,
+                    e
+                ),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semver::Version;
+
+    #[test]
+    fn it_parses_npm_style_node_versions() {
+        let build = Build {
+            node_version: Some(">=14 <17".to_string()),
+            ..Default::default()
+        };
+        let req = build.node_version().unwrap().unwrap();
+        assert!(req.matches(&Version::parse("16.13.0").unwrap()));
+        assert!(!req.matches(&Version::parse("17.0.1").unwrap()));
+
+        let build = Build {
+            node_version: Some("fourteen".to_string()),
+            ..Default::default()
+        };
+        assert!(build.node_version().is_err());
+    }
+}
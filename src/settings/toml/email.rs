@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// The `[email]` table, for workers that send email through Email Routing.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Email {
+    #[serde(default)]
+    pub send_email: Vec<SendEmail>,
+}
+
+impl Email {
+    /// Every address the worker may send to, which must be verified destination
+    /// addresses in the account.
+    pub fn destination_addresses(&self) -> Vec<String> {
+        let mut addresses = Vec::new();
+        for binding in &self.send_email {
+            addresses.extend(binding.destination_address.iter().cloned());
+            if let Some(allowed) = &binding.allowed_destination_addresses {
+                addresses.extend(allowed.iter().cloned());
+            }
+        }
+        addresses.sort();
+        addresses.dedup();
+        addresses
+    }
+}
+
+/// A `send_email` binding. Without a destination address or an allow list, the worker
+/// may send to any verified destination address in the account.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SendEmail {
+    pub name: String,
+    pub destination_address: Option<String>,
+    pub allowed_destination_addresses: Option<Vec<String>>,
+}
+
+impl SendEmail {
+    pub fn binding(&self) -> Binding {
+        Binding::new_send_email(
+            self.name.clone(),
+            self.destination_address.clone(),
+            self.allowed_destination_addresses.clone(),
+        )
+    }
+}
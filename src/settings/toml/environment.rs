@@ -4,9 +4,12 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use serde_with::rust::string_empty_as_none;
 
+use crate::settings::toml::email::Email;
 use crate::settings::toml::kv_namespace::ConfigKvNamespace;
+use crate::settings::toml::mtls_certificate::MtlsCertificate;
 use crate::settings::toml::route::RouteConfig;
 use crate::settings::toml::site::Site;
+use crate::settings::toml::tail_consumer::TailConsumer;
 use crate::settings::toml::triggers::Triggers;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -21,15 +24,39 @@ pub struct Environment {
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
+    pub node_compat: Option<bool>,
+    pub minify: Option<bool>,
+    pub keep_names: Option<bool>,
+    pub drop_console: Option<bool>,
     pub private: Option<bool>,
     pub site: Option<Site>,
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
-    pub vars: Option<HashMap<String, String>>,
+    pub mtls_certificates: Option<Vec<MtlsCertificate>>,
+    pub email: Option<Email>,
+    pub tail_consumers: Option<Vec<TailConsumer>>,
+    pub vars: Option<HashMap<String, serde_json::Value>>,
+    pub vars_strategy: Option<VarsStrategy>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub triggers: Option<Triggers>,
 }
 
+/// How an environment's vars relate to the top level ones.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VarsStrategy {
+    /// Only the environment's own vars are used (the default)
+    Replace,
+    /// The environment's vars are merged into the top level ones, tables key by key
+    Merge,
+}
+
+impl Default for VarsStrategy {
+    fn default() -> Self {
+        VarsStrategy::Replace
+    }
+}
+
 impl Environment {
     pub fn route_config(
         &self,
@@ -11,12 +11,21 @@ use serde_with::rust::string_empty_as_none;
 
 use crate::commands::{validate_worker_name, DEFAULT_CONFIG_PATH};
 use crate::deploy::{self, DeployTarget, DeploymentSet};
+use crate::error::WranglerError;
+use crate::settings::metadata::Annotations;
+use crate::settings::toml::binding_names;
+use crate::settings::toml::build::Build;
 use crate::settings::toml::dev::Dev;
-use crate::settings::toml::environment::Environment;
+use crate::settings::toml::email::Email;
+use crate::settings::toml::environment::{Environment, VarsStrategy};
 use crate::settings::toml::kv_namespace::{ConfigKvNamespace, KvNamespace};
+use crate::settings::toml::mtls_certificate::MtlsCertificate;
 use crate::settings::toml::route::RouteConfig;
+use crate::settings::toml::rule::Rule;
 use crate::settings::toml::site::Site;
+use crate::settings::toml::tail_consumer::TailConsumer;
 use crate::settings::toml::target_type::TargetType;
+use crate::settings::toml::tools::Tools;
 use crate::settings::toml::triggers::Triggers;
 use crate::settings::toml::Target;
 use crate::terminal::{
@@ -40,6 +49,19 @@ pub struct Manifest {
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
+    /// Polyfill the Node APIs npm packages often assume, e.g. Buffer and events, when
+    /// bundling with webpack.
+    pub node_compat: Option<bool>,
+    /// Minify the script when bundling with webpack, e.g. off for a debuggable dev
+    /// environment. Defaults to webpack's own setting.
+    pub minify: Option<bool>,
+    /// Keep function and class names when minifying.
+    pub keep_names: Option<bool>,
+    /// Remove console calls when minifying.
+    pub drop_console: Option<bool>,
+    /// The entry point of a javascript project, e.g. "src/index.js". Defaults to the
+    /// `main` of package.json.
+    pub main: Option<PathBuf>,
     pub private: Option<bool>,
     // TODO: maybe one day, serde toml support will allow us to serialize sites
     // as a TOML inline table (this would prevent confusion with environments too!)
@@ -47,10 +69,21 @@ pub struct Manifest {
     pub dev: Option<Dev>,
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
+    pub mtls_certificates: Option<Vec<MtlsCertificate>>,
+    pub email: Option<Email>,
+    pub tail_consumers: Option<Vec<TailConsumer>>,
     pub env: Option<HashMap<String, Environment>>,
-    pub vars: Option<HashMap<String, String>>,
+    pub vars: Option<HashMap<String, serde_json::Value>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
+    /// Attach the files matching each rule's globs to the upload, as text, data or
+    /// compiled Wasm modules.
+    pub rules: Option<Vec<Rule>>,
     pub triggers: Option<Triggers>,
+    pub tools: Option<Tools>,
+    pub build: Option<Build>,
+    /// Record the git commit, branch and whether there were uncommitted changes with
+    /// each upload. On unless set to false.
+    pub git_metadata: Option<bool>,
 }
 
 impl Manifest {
@@ -60,17 +93,20 @@ impl Manifest {
         if config_path.to_str().unwrap() == DEFAULT_CONFIG_PATH {
             message.push_str("; run `wrangler init` to create one.");
         }
-        failure::ensure!(config_path.exists(), message);
+        if !config_path.exists() {
+            return Err(WranglerError::Config(message).into());
+        }
         let config = read_config(config_path)?;
 
         let manifest: Manifest = match config.try_into() {
             Ok(m) => m,
             Err(e) => {
-                if e.to_string().contains("unknown field `kv-namespaces`") {
-                    failure::bail!("kv-namespaces should not live under the [site] table in your configuration file; please move it above [site].")
+                let msg = if e.to_string().contains("unknown field `kv-namespaces`") {
+                    "kv-namespaces should not live under the [site] table in your configuration file; please move it above [site].".to_string()
                 } else {
-                    failure::bail!(e)
-                }
+                    e.to_string()
+                };
+                return Err(WranglerError::Config(msg).into());
             }
         };
 
@@ -261,7 +297,7 @@ impl Manifest {
             if let Some(environment_name) = environment_name {
                 msg.push_str(&format!(" in [env.{}]", environment_name));
             }
-            failure::bail!("{}", &msg)
+            Err(WranglerError::Config(msg).into())
         } else {
             Ok(result)
         }
@@ -287,21 +323,46 @@ impl Manifest {
         Not inherited: Must be defined for every environment individually.
         */
         let mut target = Target {
-            target_type,                                 // Top level
-            account_id: self.account_id.clone(),         // Inherited
-            webpack_config: self.webpack_config.clone(), // Inherited
+            target_type,                                         // Top level
+            account_id: self.account_id.clone(),                 // Inherited
+            webpack_config: self.webpack_config.clone(),         // Inherited
+            node_compat: self.node_compat.unwrap_or_default(),   // Inherited
+            minify: self.minify,                                 // Inherited
+            keep_names: self.keep_names.unwrap_or_default(),     // Inherited
+            drop_console: self.drop_console.unwrap_or_default(), // Inherited
+            main: self.main.clone(),                             // Top level
             // importantly, the top level name will be modified
             // to include the name of the environment
             name: self.name.clone(), // Inherited
             kv_namespaces: get_namespaces(self.kv_namespaces.clone(), preview)?, // Not inherited
+            mtls_certificates: self.mtls_certificates.clone().unwrap_or_default(), // Not inherited
+            email: self.email.clone(), // Not inherited
+            tail_consumers: self.tail_consumers.clone().unwrap_or_default(), // Not inherited
             site: self.site.clone(), // Inherited
             vars: self.vars.clone(), // Not inherited
             text_blobs: self.text_blobs.clone(), // Inherited
+            rules: self.rules.clone().unwrap_or_default(), // Top level
+            tools: self.tools.clone().unwrap_or_default(), // Top level
+            build: self.build.clone().unwrap_or_default(), // Top level
+            dispatch_namespace: None,
+            annotations: Annotations::default(),
+            artifact: None,
+            warnings: Vec::new(),
         };
 
         let environment = self.get_environment(environment_name)?;
 
         if let Some(environment) = environment {
+            let dropped = self.non_inherited_keys(environment);
+            if !dropped.is_empty() {
+                let env_name = environment_name.unwrap_or_default();
+                target.warnings.push(format!(
+                    "Environments don't inherit {keys}, so the top level {keys} in your configuration file will not be used for [env.{env}]. Set {keys} under [env.{env}] to silence this warning, even if empty.",
+                    keys = dropped.join(", "),
+                    env = env_name
+                ));
+            }
+
             target.name = self.worker_name(environment_name);
             if let Some(account_id) = &environment.account_id {
                 target.account_id = account_id.clone();
@@ -309,21 +370,118 @@ impl Manifest {
             if let Some(webpack_config) = &environment.webpack_config {
                 target.webpack_config = Some(webpack_config.clone());
             }
+            if let Some(node_compat) = environment.node_compat {
+                target.node_compat = node_compat;
+            }
+            if let Some(minify) = environment.minify {
+                target.minify = Some(minify);
+            }
+            if let Some(keep_names) = environment.keep_names {
+                target.keep_names = keep_names;
+            }
+            if let Some(drop_console) = environment.drop_console {
+                target.drop_console = drop_console;
+            }
 
             // don't inherit kv namespaces because it is an anti-pattern to use the same namespaces across multiple environments
             target.kv_namespaces = get_namespaces(environment.kv_namespaces.clone(), preview)?;
+            target.mtls_certificates = environment.mtls_certificates.clone().unwrap_or_default();
+            target.email = environment.email.clone();
+            target.tail_consumers = environment.tail_consumers.clone().unwrap_or_default();
 
             if let Some(site) = &environment.site {
                 target.site = Some(site.clone());
             }
 
-            // don't inherit vars
-            target.vars = environment.vars.clone();
+            // don't inherit vars, unless the environment asks for them to be merged
+            target.vars = match environment.vars_strategy.unwrap_or_default() {
+                VarsStrategy::Replace => environment.vars.clone(),
+                VarsStrategy::Merge => merge_vars(self.vars.as_ref(), environment.vars.as_ref()),
+            };
         }
 
+        if target.main.is_some() && target.target_type != TargetType::JavaScript {
+            target.warnings.push(format!(
+                "`main` in your configuration file only applies when type = \"javascript\", so it will not be used for this {} project.",
+                target.target_type
+            ));
+        }
+
+        let webpack_only: Vec<&str> = vec![
+            ("node_compat", target.node_compat),
+            ("minify", target.minify.is_some()),
+            ("keep_names", target.keep_names),
+            ("drop_console", target.drop_console),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .map(|(key, _)| key)
+        .collect();
+        if !webpack_only.is_empty() && target.target_type != TargetType::Webpack {
+            target.warnings.push(format!(
+                "{} in your configuration file only apply when type = \"webpack\", so they will not be used for this {} project.",
+                webpack_only.join(", "),
+                target.target_type
+            ));
+        }
+
+        // fail before any API call rather than on the API's 400
+        validate_worker_name(&target.name)?;
+        let warnings = binding_names::validate(&target, environment_name)?;
+        target.warnings.extend(warnings);
         Ok(target)
     }
 
+    /// The non-inherited keys set at the top level but not in `environment`, whose top
+    /// level values would silently not apply to it.
+    pub fn non_inherited_keys(&self, environment: &Environment) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+        if self
+            .kv_namespaces
+            .as_ref()
+            .map_or(false, |ns| !ns.is_empty())
+            && environment.kv_namespaces.is_none()
+        {
+            keys.push("kv_namespaces");
+        }
+        if self
+            .mtls_certificates
+            .as_ref()
+            .map_or(false, |certs| !certs.is_empty())
+            && environment.mtls_certificates.is_none()
+        {
+            keys.push("mtls_certificates");
+        }
+        if self
+            .email
+            .as_ref()
+            .map_or(false, |email| !email.send_email.is_empty())
+            && environment.email.is_none()
+        {
+            keys.push("email");
+        }
+        if self
+            .tail_consumers
+            .as_ref()
+            .map_or(false, |consumers| !consumers.is_empty())
+            && environment.tail_consumers.is_none()
+        {
+            keys.push("tail_consumers");
+        }
+        if self.vars.as_ref().map_or(false, |vars| !vars.is_empty())
+            && environment.vars.is_none()
+            && environment.vars_strategy != Some(VarsStrategy::Merge)
+        {
+            keys.push("vars");
+        }
+        let has_routes =
+            self.route.is_some() || self.routes.as_ref().map_or(false, |r| !r.is_empty());
+        if has_routes && environment.route.is_none() && environment.routes.is_none() {
+            keys.push("routes");
+        }
+        keys
+    }
+
     pub fn get_environment(
         &self,
         environment_name: Option<&str>,
@@ -426,18 +584,18 @@ impl Manifest {
             if has_top_level_fields {
                 needs_new_line = true;
                 for top_level_field in top_level_fields {
-                    println!("- {}", top_level_field);
+                    StdOut::message(&format!("- {}", top_level_field));
                 }
             }
             if has_env_fields {
                 for (env_name, env_fields) in env_fields {
                     if needs_new_line {
-                        println!();
+                        StdOut::message("");
                     }
-                    println!("[env.{}]", env_name);
+                    StdOut::message(&format!("[env.{}]", env_name));
                     needs_new_line = true;
                     for env_field in env_fields {
-                        println!("  - {}", env_field);
+                        StdOut::message(&format!("  - {}", env_field));
                     }
                 }
             }
@@ -503,6 +661,43 @@ fn check_for_duplicate_names(manifest: &Manifest) -> Result<(), failure::Error>
     Ok(())
 }
 
+// Merges an environment's vars into the top level ones. Tables are merged key by key,
+// any other value from the environment replaces the top level one.
+fn merge_vars(
+    top_level: Option<&HashMap<String, serde_json::Value>>,
+    env: Option<&HashMap<String, serde_json::Value>>,
+) -> Option<HashMap<String, serde_json::Value>> {
+    let mut vars = top_level.cloned();
+    if let Some(env) = env {
+        let vars = vars.get_or_insert_with(HashMap::new);
+        for (name, value) in env {
+            match vars.get_mut(name) {
+                Some(existing) => merge_value(existing, value),
+                None => {
+                    vars.insert(name.clone(), value.clone());
+                }
+            }
+        }
+    }
+    vars
+}
+
+fn merge_value(base: &mut serde_json::Value, overlay: &serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_value(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
 fn get_namespaces(
     kv_namespaces: Option<Vec<ConfigKvNamespace>>,
     preview: bool,
@@ -529,7 +724,7 @@ fn get_namespaces(
                     binding: ns.binding,
                 })
             } else {
-                failure::bail!("You must specify the namespace ID in the id field for the namespace with binding \"{}\"", &ns.binding)
+                failure::bail!("You must specify the namespace ID in the id field for the namespace with binding \"{}\", or create it with `wrangler publish --create-missing-bindings`", &ns.binding)
             }
         }).collect()
     } else {
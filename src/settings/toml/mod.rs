@@ -1,20 +1,35 @@
+mod binding_names;
+mod build;
 mod dev;
+mod email;
 mod environment;
 mod kv_namespace;
 mod manifest;
+mod mtls_certificate;
 mod route;
+mod rule;
 mod site;
+mod tail_consumer;
 mod target;
 mod target_type;
+mod tools;
 mod triggers;
+mod writer;
 
-pub use environment::Environment;
+pub use build::{Build, BuildHooks, Licenses, Sbom, SbomFormat};
+pub use email::{Email, SendEmail};
+pub use environment::{Environment, VarsStrategy};
 pub use kv_namespace::{ConfigKvNamespace, KvNamespace};
 pub use manifest::Manifest;
+pub use mtls_certificate::MtlsCertificate;
 pub use route::{Route, RouteConfig};
+pub use rule::{ModuleType, Rule};
 pub use site::Site;
+pub use tail_consumer::TailConsumer;
 pub use target::Target;
 pub use target_type::TargetType;
+pub use tools::Tools;
+pub use writer::ConfigWriter;
 
 #[cfg(test)]
 mod tests;
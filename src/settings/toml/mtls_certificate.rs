@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// A client certificate uploaded with `wrangler mtls-certificate upload`, bound so the
+/// worker can present it to origins that require mTLS.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MtlsCertificate {
+    pub binding: String,
+    pub certificate_id: String,
+}
+
+impl MtlsCertificate {
+    pub fn binding(&self) -> Binding {
+        Binding::new_mtls_certificate(self.binding.clone(), self.certificate_id.clone())
+    }
+}
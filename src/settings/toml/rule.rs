@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// A `[[rules]]` entry, which attaches the files its globs match to the upload as
+/// modules of one type, e.g. `{ type = "Text", globs = ["templates/**/*.html"] }`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Rule {
+    #[serde(rename = "type")]
+    pub module_type: ModuleType,
+    pub globs: Vec<String>,
+}
+
+/// What a file attached by a rule is exposed to the worker as
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ModuleType {
+    /// a string
+    Text,
+    /// an ArrayBuffer
+    Data,
+    /// a WebAssembly.Module
+    CompiledWasm,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// A worker that receives the logs, exceptions and events of this one as trace events.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TailConsumer {
+    pub service: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+}
+
+impl TailConsumer {
+    /// The name of the consumer's script, which has the environment appended like
+    /// `wrangler publish --env` does.
+    pub fn script_name(&self) -> String {
+        match &self.environment {
+            Some(environment) => format!("{}-{}", self.service, environment),
+            None => self.service.clone(),
+        }
+    }
+}
@@ -1,6 +1,13 @@
+use super::build::Build;
+use super::email::Email;
 use super::kv_namespace::KvNamespace;
+use super::mtls_certificate::MtlsCertificate;
+use super::rule::Rule;
 use super::site::Site;
+use super::tail_consumer::TailConsumer;
 use super::target_type::TargetType;
+use super::tools::Tools;
+use crate::settings::metadata::Annotations;
 
 use std::collections::HashMap;
 use std::env;
@@ -11,12 +18,38 @@ use std::path::PathBuf;
 pub struct Target {
     pub account_id: String,
     pub kv_namespaces: Vec<KvNamespace>,
+    pub mtls_certificates: Vec<MtlsCertificate>,
+    pub email: Option<Email>,
+    pub tail_consumers: Vec<TailConsumer>,
     pub name: String,
     pub target_type: TargetType,
     pub webpack_config: Option<String>,
+    /// Polyfill Node's APIs when bundling with webpack
+    pub node_compat: bool,
+    /// Whether webpack minifies the script, webpack's default when None
+    pub minify: Option<bool>,
+    /// Keep function and class names when minifying
+    pub keep_names: bool,
+    /// Remove console calls when minifying
+    pub drop_console: bool,
+    /// The script a javascript target uploads, instead of the `main` of package.json
+    pub main: Option<PathBuf>,
     pub site: Option<Site>,
-    pub vars: Option<HashMap<String, String>>,
+    pub vars: Option<HashMap<String, serde_json::Value>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
+    pub rules: Vec<Rule>,
+    pub tools: Tools,
+    pub build: Build,
+    /// Set by `wrangler publish --dispatch-namespace`, never by the configuration file
+    pub dispatch_namespace: Option<String>,
+    /// Set by `wrangler publish --message` and `--tag`, never by the configuration file
+    pub annotations: Annotations,
+    /// Set by `wrangler publish --from-artifact`: a directory written by `wrangler build
+    /// --out-dir` to upload instead of building the project
+    pub artifact: Option<PathBuf>,
+    /// What in the configuration file won't be used or may not work, for the commands that
+    /// build the target to print
+    pub warnings: Vec<String>,
 }
 
 impl Target {
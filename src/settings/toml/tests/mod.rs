@@ -124,6 +124,136 @@ fn it_uses_env_name_when_provided() {
     assert_eq!(manifest.worker_name(Some(TEST_ENV_NAME)), custom_env_name);
 }
 
+#[test]
+fn it_finds_top_level_keys_environments_do_not_inherit() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        route = "example.com/*"
+        vars = { MODE = "top" }
+        kv_namespaces = [{ binding = "CACHE", id = "abc" }]
+
+        [env.staging]
+        route = "staging.example.com/*"
+        vars = {}
+    "#,
+    )
+    .unwrap();
+    let staging = manifest.get_environment(Some("staging")).unwrap().unwrap();
+
+    assert_eq!(manifest.non_inherited_keys(staging), vec!["kv_namespaces"]);
+    // returned rather than printed, for the commands that build the target
+    let target = manifest.get_target(Some("staging"), false).unwrap();
+    assert_eq!(target.warnings.len(), 1);
+    assert!(target.warnings[0].starts_with("Environments don't inherit kv_namespaces"));
+}
+
+#[test]
+fn it_merges_structured_vars_into_environments() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        workers_dev = true
+
+        [vars]
+        MODE = "production"
+        LIMITS = { requests = 100, burst = 10 }
+
+        [env.staging]
+        vars_strategy = "merge"
+        vars = { LIMITS = { requests = 5 }, HOSTS = ["a.example.com"] }
+    "#,
+    )
+    .unwrap();
+
+    let vars = manifest
+        .get_target(Some("staging"), false)
+        .unwrap()
+        .vars
+        .unwrap();
+    assert_eq!(vars["MODE"], serde_json::json!("production"));
+    assert_eq!(
+        vars["LIMITS"],
+        serde_json::json!({ "requests": 5, "burst": 10 })
+    );
+    assert_eq!(vars["HOSTS"], serde_json::json!(["a.example.com"]));
+}
+
+#[test]
+fn it_binds_mtls_certificates_per_environment() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        workers_dev = true
+
+        [[mtls_certificates]]
+        binding = "ORIGIN_CERT"
+        certificate_id = "top-level-cert"
+
+        [env.staging]
+        mtls_certificates = [{ binding = "ORIGIN_CERT", certificate_id = "staging-cert" }]
+    "#,
+    )
+    .unwrap();
+
+    let top_level = manifest.get_target(None, false).unwrap();
+    assert_eq!(
+        top_level.mtls_certificates[0].certificate_id,
+        "top-level-cert"
+    );
+    let staging = manifest.get_target(Some("staging"), false).unwrap();
+    assert_eq!(staging.mtls_certificates.len(), 1);
+    assert_eq!(staging.mtls_certificates[0].certificate_id, "staging-cert");
+}
+
+#[test]
+fn it_reads_send_email_bindings() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        workers_dev = true
+
+        [email]
+        send_email = [
+            { name = "ALERTS", destination_address = "alerts@example.com" },
+            { name = "TEAM", allowed_destination_addresses = ["ops@example.com", "alerts@example.com"] },
+        ]
+    "#,
+    )
+    .unwrap();
+
+    let email = manifest.get_target(None, false).unwrap().email.unwrap();
+    assert_eq!(email.send_email.len(), 2);
+    assert_eq!(
+        email.destination_addresses(),
+        vec![
+            "alerts@example.com".to_string(),
+            "ops@example.com".to_string()
+        ]
+    );
+}
+
+#[test]
+fn it_reads_tail_consumers() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        workers_dev = true
+        tail_consumers = [{ service = "log-forwarder" }, { service = "alerts", environment = "production" }]
+    "#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(None, false).unwrap();
+    assert_eq!(target.tail_consumers[0].script_name(), "log-forwarder");
+    assert_eq!(target.tail_consumers[1].script_name(), "alerts-production");
+}
+
 fn base_fixture_path() -> PathBuf {
     let current_dir = env::current_dir().unwrap();
 
@@ -138,3 +268,99 @@ fn base_fixture_path() -> PathBuf {
 fn toml_fixture_path(fixture: &str) -> PathBuf {
     base_fixture_path().join(fixture).with_extension("toml")
 }
+
+#[test]
+fn it_rejects_colliding_and_reserved_binding_names() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        workers_dev = true
+        kv_namespaces = [{ binding = "CACHE", id = "abc" }]
+
+        [env.staging]
+        vars = { CACHE = "off", fetch = "on" }
+        kv_namespaces = [{ binding = "CACHE", id = "def" }]
+
+        [env.production]
+        vars = { "api-url" = "https://example.com" }
+        kv_namespaces = [{ binding = "CACHE", id = "ghi" }]
+    "#,
+    )
+    .unwrap();
+
+    assert!(manifest.get_target(None, false).is_ok());
+    let err = manifest
+        .get_target(Some("staging"), false)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(
+        "\"CACHE\" is bound by both [env.staging] kv_namespaces binding and [env.staging] vars"
+    ));
+    assert!(err.contains("\"fetch\" in [env.staging] vars"));
+    // only a warning, as it can still be read through globalThis
+    assert!(manifest.get_target(Some("production"), false).is_ok());
+}
+
+#[test]
+fn it_inherits_node_compat() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "webpack"
+        workers_dev = true
+        node_compat = true
+
+        [env.staging]
+
+        [env.production]
+        node_compat = false
+    "#,
+    )
+    .unwrap();
+
+    assert!(manifest.get_target(None, false).unwrap().node_compat);
+    assert!(
+        manifest
+            .get_target(Some("staging"), false)
+            .unwrap()
+            .node_compat
+    );
+    assert!(
+        !manifest
+            .get_target(Some("production"), false)
+            .unwrap()
+            .node_compat
+    );
+}
+
+#[test]
+fn it_sets_minify_per_environment() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "webpack"
+        workers_dev = true
+        drop_console = true
+
+        [env.dev]
+        minify = false
+        drop_console = false
+
+        [env.production]
+        minify = true
+        keep_names = true
+    "#,
+    )
+    .unwrap();
+
+    let top_level = manifest.get_target(None, false).unwrap();
+    assert_eq!(top_level.minify, None);
+    assert!(top_level.drop_console);
+    let dev = manifest.get_target(Some("dev"), false).unwrap();
+    assert_eq!(dev.minify, Some(false));
+    assert!(!dev.drop_console);
+    let production = manifest.get_target(Some("production"), false).unwrap();
+    assert_eq!(production.minify, Some(true));
+    assert!(production.keep_names && production.drop_console);
+}
@@ -0,0 +1,80 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::install::dependencies;
+
+/// Exact tool versions pinned in the `[tools]` table, so everyone working
+/// on a project builds it with the same tools.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Tools {
+    pub wasm_pack: Option<String>,
+    pub wranglerjs: Option<String>,
+}
+
+impl Tools {
+    pub fn wasm_pack_version(&self) -> Result<Version, failure::Error> {
+        parse_version(
+            "wasm_pack",
+            self.wasm_pack.as_deref(),
+            dependencies::WASM_PACK_VERSION,
+        )
+    }
+
+    pub fn wranglerjs_version(&self) -> Result<Version, failure::Error> {
+        parse_version(
+            "wranglerjs",
+            self.wranglerjs.as_deref(),
+            env!("CARGO_PKG_VERSION"),
+        )
+    }
+}
+
+fn parse_version(
+    tool: &str,
+    pinned: Option<&str>,
+    default: &str,
+) -> Result<Version, failure::Error> {
+    match pinned {
+        // allow `wasm_pack = "v0.10.3"` as it is written in release notes
+        Some(pinned) => match Version::parse(pinned.trim_start_matches('v')) {
+            Ok(version) => Ok(version),
+            Err(e) => failure::bail!(
+                "[tools] {} = \"{}\" is not a valid version: {}",
+                tool,
+                pinned,
+                e
+            ),
+        },
+        None => Ok(Version::parse(default)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_uses_pinned_versions() {
+        let tools = Tools {
+            wasm_pack: Some("v0.10.3".to_string()),
+            wranglerjs: None,
+        };
+        assert_eq!(
+            tools.wasm_pack_version().unwrap(),
+            Version::parse("0.10.3").unwrap()
+        );
+        assert_eq!(
+            tools.wranglerjs_version().unwrap(),
+            Version::parse(env!("CARGO_PKG_VERSION")).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_versions() {
+        let tools = Tools {
+            wasm_pack: Some("latest".to_string()),
+            wranglerjs: None,
+        };
+        assert!(tools.wasm_pack_version().is_err());
+    }
+}
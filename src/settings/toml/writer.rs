@@ -0,0 +1,249 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+/// Edits the text of a configuration file in place, so the ids wrangler creates can be
+/// saved to it without reordering keys or dropping the user's comments.
+pub struct ConfigWriter {
+    lines: Vec<String>,
+    trailing_newline: bool,
+}
+
+// The lines of one table: the top level (named "") or the lines below a `[header]`.
+struct Section {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+impl Section {
+    fn env(&self) -> Option<&str> {
+        self.name
+            .strip_prefix("env.")
+            .map(|rest| rest.split('.').next().unwrap_or(rest))
+    }
+}
+
+impl ConfigWriter {
+    pub fn new(contents: &str) -> ConfigWriter {
+        ConfigWriter {
+            lines: contents.lines().map(str::to_string).collect(),
+            trailing_newline: contents.ends_with('\n'),
+        }
+    }
+
+    pub fn open(path: &Path) -> Result<ConfigWriter, failure::Error> {
+        Ok(ConfigWriter::new(&fs::read_to_string(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), failure::Error> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Sets `key = "value"` at the top level, or in `[env.<env>]`, adding the key (and
+    /// the environment table) when it isn't there yet.
+    pub fn set_string(&mut self, env: Option<&str>, key: &str, value: &str) {
+        let table = match env {
+            Some(env) => format!("env.{}", env),
+            None => String::new(),
+        };
+        let sections = self.sections();
+        let section = match sections.iter().find(|section| section.name == table) {
+            Some(section) => section,
+            None => {
+                self.lines.push(String::new());
+                self.lines.push(format!("[{}]", table));
+                self.lines.push(format!("{} = \"{}\"", key, value));
+                return;
+            }
+        };
+
+        for i in section.start..section.end {
+            if let Some(updated) = replace_value(&self.lines[i], key, value) {
+                self.lines[i] = updated;
+                return;
+            }
+        }
+        // new top level keys go below the first line, which is usually `name`
+        let at = match env {
+            Some(_) => section.start,
+            None => section.end.min(1),
+        };
+        self.lines.insert(at, format!("{} = \"{}\"", key, value));
+    }
+
+    /// Sets `field = "value"` on the KV namespace with `binding` for `env`, either in an
+    /// inline table or in a `[[kv_namespaces]]` table. Returns false if there is no such
+    /// namespace in the file.
+    pub fn set_kv_namespace(
+        &mut self,
+        env: Option<&str>,
+        binding: &str,
+        field: &str,
+        value: &str,
+    ) -> bool {
+        let binding_re =
+            Regex::new(&format!(r#"binding\s*=\s*"{}""#, regex::escape(binding))).unwrap();
+        let field_re = Regex::new(&format!(
+            r#"\b{}\s*=\s*("[^"]*"|'[^']*')"#,
+            regex::escape(field)
+        ))
+        .unwrap();
+        let assignment = format!("{} = \"{}\"", field, value);
+
+        for section in self.sections() {
+            if section.env() != env {
+                continue;
+            }
+            for i in section.start..section.end {
+                let line = self.lines[i].clone();
+                let m = match binding_re.find(&line) {
+                    Some(m) => m,
+                    None => continue,
+                };
+
+                if let Some(open) = line[..m.start()].rfind('{') {
+                    let close = line[m.end()..]
+                        .find('}')
+                        .map(|close| m.end() + close)
+                        .unwrap_or_else(|| line.len());
+                    self.lines[i] = match field_re.find(&line[open..close]) {
+                        Some(f) => format!(
+                            "{}{}{}",
+                            &line[..open + f.start()],
+                            assignment,
+                            &line[open + f.end()..]
+                        ),
+                        None => format!("{}, {}{}", &line[..m.end()], assignment, &line[m.end()..]),
+                    };
+                    return true;
+                }
+
+                for j in section.start..section.end {
+                    if let Some(updated) = replace_value(&self.lines[j], field, value) {
+                        self.lines[j] = updated;
+                        return true;
+                    }
+                }
+                let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+                self.lines
+                    .insert(i + 1, format!("{}{}", indent, assignment));
+                return true;
+            }
+        }
+        false
+    }
+
+    fn sections(&self) -> Vec<Section> {
+        let header = Regex::new(r"^\s*\[\[?\s*([A-Za-z0-9_.\-]+)\s*\]").unwrap();
+        let mut sections = vec![Section {
+            name: String::new(),
+            start: 0,
+            end: self.lines.len(),
+        }];
+        for (i, line) in self.lines.iter().enumerate() {
+            if let Some(captures) = header.captures(line) {
+                sections.last_mut().unwrap().end = i;
+                sections.push(Section {
+                    name: captures[1].to_string(),
+                    start: i + 1,
+                    end: self.lines.len(),
+                });
+            }
+        }
+        sections
+    }
+}
+
+impl fmt::Display for ConfigWriter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.lines.join("\n"))?;
+        if self.trailing_newline {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Replaces the value of `key = ...` on a line, keeping any comment after it.
+fn replace_value(line: &str, key: &str, value: &str) -> Option<String> {
+    let re = Regex::new(&format!(
+        r#"^(\s*{}\s*=\s*)("[^"]*"|'[^']*'|[^\s#]*)(.*)$"#,
+        regex::escape(key)
+    ))
+    .unwrap();
+    let captures = re.captures(line)?;
+    Some(format!("{}\"{}\"{}", &captures[1], value, &captures[3]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_string(contents: &str, env: Option<&str>, key: &str, value: &str) -> String {
+        let mut config = ConfigWriter::new(contents);
+        config.set_string(env, key, value);
+        config.to_string()
+    }
+
+    fn set_id(contents: &str, env: Option<&str>, binding: &str) -> Option<String> {
+        let mut config = ConfigWriter::new(contents);
+        if config.set_kv_namespace(env, binding, "id", "abc") {
+            Some(config.to_string())
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn it_replaces_values_and_keeps_comments() {
+        let contents = "name = \"worker\"\naccount_id = \"\" # from the dashboard\n\n[env.prod]\naccount_id = \"\"\n";
+        assert_eq!(
+            set_string(contents, None, "account_id", "abc"),
+            "name = \"worker\"\naccount_id = \"abc\" # from the dashboard\n\n[env.prod]\naccount_id = \"\"\n"
+        );
+        assert_eq!(
+            set_string(contents, Some("prod"), "account_id", "abc"),
+            "name = \"worker\"\naccount_id = \"\" # from the dashboard\n\n[env.prod]\naccount_id = \"abc\"\n"
+        );
+    }
+
+    #[test]
+    fn it_adds_missing_values() {
+        let contents = "name = \"worker\"\ntype = \"javascript\"\n";
+        assert_eq!(
+            set_string(contents, None, "account_id", "abc"),
+            "name = \"worker\"\naccount_id = \"abc\"\ntype = \"javascript\"\n"
+        );
+        assert_eq!(
+            set_string(contents, Some("prod"), "zone_id", "abc"),
+            "name = \"worker\"\ntype = \"javascript\"\n\n[env.prod]\nzone_id = \"abc\"\n"
+        );
+    }
+
+    #[test]
+    fn it_sets_ids_in_inline_tables() {
+        let contents = "name = \"worker\"\nkv_namespaces = [\n  { binding = \"FOO\", preview_id = \"p\" }\n]\n\n[env.prod]\nkv_namespaces = [{ binding = \"FOO\", id = \"\" }]\n";
+        assert_eq!(
+            set_id(contents, Some("prod"), "FOO").unwrap(),
+            "name = \"worker\"\nkv_namespaces = [\n  { binding = \"FOO\", preview_id = \"p\" }\n]\n\n[env.prod]\nkv_namespaces = [{ binding = \"FOO\", id = \"abc\" }]\n"
+        );
+        assert_eq!(
+            set_id(contents, None, "FOO").unwrap(),
+            "name = \"worker\"\nkv_namespaces = [\n  { binding = \"FOO\", id = \"abc\", preview_id = \"p\" }\n]\n\n[env.prod]\nkv_namespaces = [{ binding = \"FOO\", id = \"\" }]\n"
+        );
+    }
+
+    #[test]
+    fn it_sets_ids_in_array_tables() {
+        let contents = "name = \"worker\"\n\n[[kv_namespaces]]\n# cache\nbinding = \"FOO\"\n";
+        assert_eq!(
+            set_id(contents, None, "FOO").unwrap(),
+            "name = \"worker\"\n\n[[kv_namespaces]]\n# cache\nbinding = \"FOO\"\nid = \"abc\"\n"
+        );
+        assert!(set_id(contents, None, "BAR").is_none());
+    }
+}
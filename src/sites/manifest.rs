@@ -1,3 +1,5 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-pub type AssetManifest = HashMap<String, String>;
+// A BTreeMap keeps the serialized manifest in a stable order, so that the same
+// set of files always produces the same upload.
+pub type AssetManifest = BTreeMap<String, String>;
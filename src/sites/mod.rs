@@ -9,12 +9,12 @@ pub use sync::sync;
 use std::ffi::OsString;
 use std::fs;
 use std::hash::Hasher;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use failure::format_err;
 use ignore::overrides::{Override, OverrideBuilder};
 use ignore::{Walk, WalkBuilder};
-use indicatif::{ProgressBar, ProgressStyle};
+use number_prefix::NumberPrefix;
 use twox_hash::XxHash64;
 
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
@@ -23,6 +23,7 @@ use crate::kv::namespace::{upsert, UpsertedNamespace};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{KvNamespace, Target};
 use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress;
 pub const KEY_MAX_SIZE: usize = 512;
 // Oddly enough, metadata.len() returns a u64, not usize.
 pub const VALUE_MAX_SIZE: u64 = 25 * 1024 * 1024;
@@ -74,20 +75,18 @@ pub fn directory_keys_values(
             let mut upload_vec: Vec<KeyValuePair> = Vec::new();
             let mut asset_manifest = AssetManifest::new();
             let mut file_list: Vec<String> = Vec::new();
+            validate_file_sizes(target, directory)?;
             let dir_walker = get_dir_iterator(target, directory)?;
-            let spinner_style =
-                ProgressStyle::default_spinner().template("{spinner}   Preparing {msg}...");
-            let spinner = ProgressBar::new_spinner().with_style(spinner_style);
+            let spinner = progress::spinner("Preparing files...");
 
             for entry in dir_walker {
                 spinner.tick();
                 let entry = entry.unwrap();
                 let path = entry.path();
                 if path.is_file() {
-                    spinner.set_message(&format!("{}", path.display()));
+                    spinner.set_message(&format!("Preparing {}...", path.display()));
 
                     file_list.push(path.to_str().unwrap().to_string());
-                    validate_file_size(&path)?;
 
                     let value = std::fs::read(path)?;
 
@@ -125,20 +124,48 @@ pub fn directory_keys_values(
 // no partial uploads happen). I don't like this functionality (and the similar key length checking
 // logic in validate_key_size()) because it duplicates the size checking the API already does--but
 // doing a preemptive check like this (before calling the API) will prevent partial bucket uploads
-// from happening.
-fn validate_file_size(path: &Path) -> Result<(), failure::Error> {
-    let metadata = fs::metadata(path)?;
-    let file_len = metadata.len();
+// from happening. Every file over the limit is listed, so they can all be dealt with at once.
+fn validate_file_sizes(target: &Target, directory: &Path) -> Result<(), failure::Error> {
+    let oversized = oversized_files(target, directory)?;
+    if oversized.is_empty() {
+        return Ok(());
+    }
 
-    if file_len > VALUE_MAX_SIZE {
-        failure::bail!(
-            "File `{}` of {} bytes exceeds the maximum value size limit of {} bytes",
-            path.display(),
-            file_len,
-            VALUE_MAX_SIZE
-        );
+    let files: Vec<String> = oversized
+        .iter()
+        .map(|(path, len)| format!("{} ({})", path.display(), human_size(*len)))
+        .collect();
+    failure::bail!(
+        "{} of the files in your bucket exceed the maximum value size limit of {} and can't be uploaded to Workers KV. Remove them or add them to `exclude` in the [site] section of your configuration file:\n  {}",
+        files.len(),
+        human_size(VALUE_MAX_SIZE),
+        files.join("\n  ")
+    )
+}
+
+fn oversized_files(
+    target: &Target,
+    directory: &Path,
+) -> Result<Vec<(PathBuf, u64)>, failure::Error> {
+    let mut oversized = Vec::new();
+    for entry in get_dir_iterator(target, directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_file() {
+            let file_len = fs::metadata(path)?.len();
+            if file_len > VALUE_MAX_SIZE {
+                oversized.push((path.to_path_buf(), file_len));
+            }
+        }
+    }
+    Ok(oversized)
+}
+
+fn human_size(bytes: u64) -> String {
+    match NumberPrefix::binary(bytes as f64) {
+        NumberPrefix::Standalone(bytes) => format!("{} bytes", bytes),
+        NumberPrefix::Prefixed(prefix, n) => format!("{:.1} {}B", n, prefix),
     }
-    Ok(())
 }
 
 fn validate_key_size(key: &str) -> Result<(), failure::Error> {
@@ -309,18 +336,34 @@ mod tests {
     use std::io::Write;
     use std::path::{Path, PathBuf};
 
+    use crate::settings::metadata::Annotations;
     use crate::settings::toml::{Site, Target, TargetType};
 
     fn make_target(site: Site) -> Target {
         Target {
             account_id: "".to_string(),
             kv_namespaces: Vec::new(),
+            mtls_certificates: Vec::new(),
+            email: None,
+            tail_consumers: Vec::new(),
             name: "".to_string(),
             target_type: TargetType::JavaScript,
             webpack_config: None,
+            node_compat: false,
+            minify: None,
+            keep_names: false,
+            drop_console: false,
+            main: None,
             site: Some(site),
             vars: None,
             text_blobs: None,
+            rules: Vec::new(),
+            tools: Default::default(),
+            build: Default::default(),
+            dispatch_namespace: None,
+            annotations: Annotations::default(),
+            artifact: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -538,6 +581,48 @@ mod tests {
         fs::remove_dir_all(test_dir).unwrap();
     }
 
+    #[test]
+    fn it_lists_every_file_over_the_size_limit() {
+        let mut site = Site::default();
+        site.bucket = PathBuf::from("public");
+        let target = make_target(site);
+
+        let test_dir = "test8";
+        // If test dir already exists, delete it.
+        if fs::metadata(test_dir).is_ok() {
+            fs::remove_dir_all(test_dir).unwrap();
+        }
+        fs::create_dir(test_dir).unwrap();
+
+        for (name, len) in &[
+            ("small.txt", 1),
+            ("video.mp4", VALUE_MAX_SIZE + 1),
+            ("archive.zip", 2 * VALUE_MAX_SIZE),
+        ] {
+            let file = fs::File::create(format!("{}/{}", test_dir, name)).unwrap();
+            file.set_len(*len).unwrap();
+        }
+
+        let mut oversized = oversized_files(&target, Path::new(test_dir)).unwrap();
+        oversized.sort();
+        assert_eq!(
+            oversized,
+            vec![
+                (PathBuf::from("test8/archive.zip"), 2 * VALUE_MAX_SIZE),
+                (PathBuf::from("test8/video.mp4"), VALUE_MAX_SIZE + 1),
+            ]
+        );
+
+        let err = validate_file_sizes(&target, Path::new(test_dir)).unwrap_err();
+        let message = err.to_string();
+        assert!(message.starts_with(
+            "2 of the files in your bucket exceed the maximum value size limit of 25.0 MiB"
+        ));
+        assert!(message.contains("test8/archive.zip (50.0 MiB)"));
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
     #[test]
     fn it_inserts_hash_before_extension() {
         let value = "<h1>Hello World!</h1>";
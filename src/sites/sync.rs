@@ -34,15 +34,13 @@ pub fn sync(
             Ok(remote_key) => {
                 remote_keys.insert(remote_key.name);
             }
-            Err(e) => failure::bail!(kv::format_error(e)),
+            Err(e) => return Err(kv::api_error(e)),
         }
     }
 
     let (pairs, asset_manifest, _): (Vec<KeyValuePair>, AssetManifest, _) =
         directory_keys_values(target, path)?;
 
-    let to_upload = filter_files(pairs.clone(), &remote_keys);
-
     // Now delete files from Workers KV that exist in remote but no longer exist locally.
     // Get local keys
     let mut local_keys: HashSet<_> = HashSet::new();
@@ -50,6 +48,9 @@ pub fn sync(
         local_keys.insert(pair.key.clone());
     }
 
+    // the file contents are only kept once, for the files that are uploaded
+    let to_upload = filter_files(pairs, &remote_keys);
+
     // Find keys that are present in remote but not present in local, and
     // stage them for deletion.
     let to_delete: Vec<_> = remote_keys
@@ -0,0 +1,231 @@
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::preferences::Preferences;
+
+// how many runs are logged before the oldest log is deleted, unless preferences.toml
+// says otherwise
+const KEEP_LOGS: usize = 20;
+const LOG_PREFIX: &str = "run-";
+const LOG_EXTENSION: &str = ".jsonl";
+// environment variables that could hold credentials are logged by name only, as are the
+// values given on the command line, like the value of `wrangler kv:key put <key> <value>`
+const REDACTED: &str = "[redacted]";
+const SECRET_VARS: &[&str] = &["TOKEN", "KEY", "SECRET", "PASSWORD", "EMAIL", "AUTH"];
+
+lazy_static! {
+    static ref LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+}
+
+/// Starts a log of this run in the `logs` directory of the wrangler home directory:
+/// the arguments, the configuration and environment it resolves, each API request and
+/// any error, one JSON object per line. Only the newest logs are kept. A log that can't
+/// be written never stops the command.
+pub fn start(command: &str, config_path: Option<&Path>, env: Option<&str>) {
+    let keep = Preferences::load().keep_logs.unwrap_or(KEEP_LOGS);
+    if keep == 0 {
+        return;
+    }
+    match open(keep) {
+        Ok(file) => *LOG_FILE.lock().unwrap() = Some(file),
+        Err(e) => {
+            log::debug!("could not start a log of this run: {}", e);
+            return;
+        }
+    }
+
+    write(
+        "invocation",
+        json!({
+            "args": arguments(env::args().skip(1), command),
+            "command": command,
+            "config": config_path.map(|path| path.display().to_string()),
+            "env": env,
+            "cwd": env::current_dir().ok().map(|dir| dir.display().to_string()),
+            "version": env!("CARGO_PKG_VERSION"),
+            "os": format!("{} {}", env::consts::OS, env::consts::ARCH),
+        }),
+    );
+    write("environment", environment(env::vars()));
+}
+
+/// Adds an entry to the log of this run, if there is one.
+pub fn write(kind: &str, fields: Value) {
+    let mut log_file = LOG_FILE.lock().unwrap();
+    let file = match log_file.as_mut() {
+        Some(file) => file,
+        None => return,
+    };
+    let entry = json!({
+        "time": Utc::now().to_rfc3339(),
+        "kind": kind,
+        "fields": fields,
+    });
+    if let Err(e) = writeln!(file, "{}", entry) {
+        log::debug!("could not write to the log of this run: {}", e);
+    }
+}
+
+/// Logs the error the run failed with, along with its causes.
+pub fn error(e: &failure::Error) {
+    let causes: Vec<String> = e.iter_causes().map(|cause| cause.to_string()).collect();
+    write(
+        "error",
+        json!({ "message": e.to_string(), "causes": causes }),
+    );
+}
+
+/// Ends the log with how the run went.
+pub fn finish(success: bool, duration: Duration) {
+    write(
+        "finished",
+        json!({ "success": success, "duration_ms": duration.as_millis() as u64 }),
+    );
+}
+
+/// The logs kept of the last few runs, oldest first.
+pub fn logs() -> Result<Vec<PathBuf>, failure::Error> {
+    let dir = logs_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut logs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.starts_with(LOG_PREFIX) && name.ends_with(LOG_EXTENSION)
+                })
+        })
+        .collect();
+    // names start with the time of the run, so they sort in the order the runs started
+    logs.sort();
+    Ok(logs)
+}
+
+// Deletes the oldest logs, so that with this run's there are `keep`, and opens a new one.
+fn open(keep: usize) -> Result<File, failure::Error> {
+    let logs = logs()?;
+    for old in &logs[..logs.len().saturating_sub(keep - 1)] {
+        fs::remove_file(old)?;
+    }
+
+    let dir = logs_dir()?;
+    fs::create_dir_all(&dir)?;
+    let name = format!(
+        "{}{}-{}{}",
+        LOG_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+        process::id(),
+        LOG_EXTENSION
+    );
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(name))?;
+    Ok(file)
+}
+
+// The arguments without the values in them: subcommand names and flags are logged, while
+// positional values and the values of `--flag=value` are redacted.
+fn arguments(args: impl Iterator<Item = String>, command: &str) -> Vec<String> {
+    let subcommands: Vec<&str> = command.split_whitespace().collect();
+    args.map(|arg| {
+        if subcommands.contains(&arg.as_str()) {
+            arg
+        } else if arg.starts_with('-') {
+            match arg.find('=') {
+                Some(i) => format!("{}={}", &arg[..i], REDACTED),
+                None => arg,
+            }
+        } else {
+            REDACTED.to_string()
+        }
+    })
+    .collect()
+}
+
+// The variables that change what wrangler does, without the values of any that could be
+// credentials
+fn environment(vars: impl Iterator<Item = (String, String)>) -> Value {
+    let mut environment = serde_json::Map::new();
+    for (name, value) in vars {
+        if !name.starts_with("WRANGLER_") && !name.starts_with("CF_") && name != "CI" {
+            continue;
+        }
+        let value = if SECRET_VARS.iter().any(|secret| name.contains(secret)) {
+            REDACTED.to_string()
+        } else {
+            value
+        };
+        environment.insert(name, Value::String(value));
+    }
+    Value::Object(environment)
+}
+
+fn logs_dir() -> Result<PathBuf, failure::Error> {
+    Ok(get_wrangler_home_dir()?.join("logs"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_logs_the_environment_without_credentials() {
+        let vars = vec![
+            ("CF_API_TOKEN", "secret-token"),
+            ("CF_ACCOUNT_ID", "abc123"),
+            ("WRANGLER_LOG", "debug"),
+            ("HOME", "/home/user"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        assert_eq!(
+            environment(vars),
+            json!({
+                "CF_API_TOKEN": "[redacted]",
+                "CF_ACCOUNT_ID": "abc123",
+                "WRANGLER_LOG": "debug",
+            })
+        );
+    }
+
+    #[test]
+    fn it_logs_the_arguments_without_their_values() {
+        let args = vec![
+            "kv:key",
+            "put",
+            "--binding=CACHE",
+            "api-key",
+            "s3cr3t",
+            "--ttl",
+        ]
+        .into_iter()
+        .map(str::to_string);
+
+        assert_eq!(
+            arguments(args, "kv:key put"),
+            vec![
+                "kv:key",
+                "put",
+                "--binding=[redacted]",
+                "[redacted]",
+                "[redacted]",
+                "--ttl"
+            ]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::convert::TryFrom;
+use std::io::Write;
+
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+// 2.0, the first version with deflate
+const VERSION: u16 = 20;
+const DEFLATED: u16 = 8;
+// 1980-01-01, the earliest date a zip can hold, so bundles don't depend on the clock
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Writes `files`, as (name, contents) pairs, as a zip archive of deflated files. This
+/// is just what a support bundle needs: no directories, timestamps or zip64, so each
+/// file and the whole archive have to stay under 4GB.
+pub fn write(out: &mut impl Write, files: &[(String, Vec<u8>)]) -> Result<(), failure::Error> {
+    let mut central_directory = Vec::new();
+    let mut offset: u32 = 0;
+    for (name, contents) in files {
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+        let fields = Fields {
+            crc: crc.sum(),
+            compressed_size: u32_len(compressed.len())?,
+            size: u32_len(contents.len())?,
+            name,
+        };
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&LOCAL_FILE_HEADER.to_le_bytes());
+        fields.write(&mut header)?;
+        header.extend_from_slice(name.as_bytes());
+        out.write_all(&header)?;
+        out.write_all(&compressed)?;
+
+        central_directory.extend_from_slice(&CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+        // made by: 2.0 on MS-DOS, which leaves file attributes unset
+        central_directory.extend_from_slice(&VERSION.to_le_bytes());
+        fields.write(&mut central_directory)?;
+        // comment length, disk number, internal and external attributes
+        central_directory.extend_from_slice(&[0; 10]);
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+
+        offset = u32_len(offset as usize + header.len() + compressed.len())?;
+    }
+
+    let count =
+        u16::try_from(files.len()).map_err(|_| failure::format_err!("too many files for a zip"))?;
+    let mut end = Vec::new();
+    end.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    // this disk, and the disk the central directory starts on
+    end.extend_from_slice(&[0; 4]);
+    end.extend_from_slice(&count.to_le_bytes());
+    end.extend_from_slice(&count.to_le_bytes());
+    end.extend_from_slice(&u32_len(central_directory.len())?.to_le_bytes());
+    end.extend_from_slice(&offset.to_le_bytes());
+    // comment length
+    end.extend_from_slice(&[0; 2]);
+
+    out.write_all(&central_directory)?;
+    out.write_all(&end)?;
+    Ok(())
+}
+
+// the fields local file headers and central directory headers share
+struct Fields<'a> {
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    name: &'a str,
+}
+
+impl Fields<'_> {
+    fn write(&self, out: &mut Vec<u8>) -> Result<(), failure::Error> {
+        let name_length = u16::try_from(self.name.len())
+            .map_err(|_| failure::format_err!("{} is too long a name for a zip", self.name))?;
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        // flags
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&DEFLATED.to_le_bytes());
+        // time, then date
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&DOS_DATE.to_le_bytes());
+        out.extend_from_slice(&self.crc.to_le_bytes());
+        out.extend_from_slice(&self.compressed_size.to_le_bytes());
+        out.extend_from_slice(&self.size.to_le_bytes());
+        out.extend_from_slice(&name_length.to_le_bytes());
+        // extra field length
+        out.extend_from_slice(&0u16.to_le_bytes());
+        Ok(())
+    }
+}
+
+fn u32_len(len: usize) -> Result<u32, failure::Error> {
+    u32::try_from(len).map_err(|_| failure::format_err!("too large for a zip"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_writes_a_zip_archive() {
+        let files = vec![
+            (
+                "problem.txt".to_string(),
+                b"wrangler publish failed".to_vec(),
+            ),
+            ("versions.txt".to_string(), b"wrangler 1.19.0".to_vec()),
+        ];
+        let mut archive = Vec::new();
+        write(&mut archive, &files).unwrap();
+
+        assert_eq!(&archive[..4], &LOCAL_FILE_HEADER.to_le_bytes());
+        // the end of central directory record is the last 22 bytes, and counts both files
+        let end = &archive[archive.len() - 22..];
+        assert_eq!(&end[..4], &END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        assert_eq!(&end[10..12], &2u16.to_le_bytes());
+        let central_directory_offset = u32::from_le_bytes([end[16], end[17], end[18], end[19]]);
+        assert_eq!(
+            &archive[central_directory_offset as usize..][..4],
+            &CENTRAL_DIRECTORY_HEADER.to_le_bytes()
+        );
+    }
+}
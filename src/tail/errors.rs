@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::build::SourceMap;
+use crate::terminal::message::{Message, StdOut};
+
+lazy_static! {
+    // the file, line and column of a frame, like `at handle (worker.js:1:2345)`
+    static ref FRAME: Regex = Regex::new(r"([^\s()]+?):(\d+):(\d+)").unwrap();
+}
+
+/// Picks the exceptions and `console.error` logs out of tail events, and prints each
+/// distinct error once in full, with its stack mapped to the original source when the
+/// script has a source map. An error seen before is printed as one line with a count.
+pub struct ErrorFilter {
+    source_map: Option<SourceMap>,
+    seen: HashMap<String, usize>,
+}
+
+impl ErrorFilter {
+    pub fn new(source_map: Option<SourceMap>) -> Self {
+        ErrorFilter {
+            source_map,
+            seen: HashMap::new(),
+        }
+    }
+
+    pub fn print(&mut self, event: &str) {
+        for error in self.errors(event) {
+            StdOut::message(&error);
+        }
+    }
+
+    // What to print for the errors in `event`
+    fn errors(&mut self, event: &str) -> Vec<String> {
+        let event: Value = serde_json::from_str(event).unwrap_or_default();
+        let mut errors = Vec::new();
+        for error in errors_in(&event) {
+            let error = match &self.source_map {
+                Some(source_map) => map_stack(&error, source_map),
+                None => error,
+            };
+            let count = self.seen.entry(error.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                errors.push(error);
+            } else {
+                let first_line = error.lines().next().unwrap_or_default();
+                errors.push(format!("(seen {} times) {}", count, first_line));
+            }
+        }
+        errors
+    }
+}
+
+// The exceptions an event threw, with their stacks when the runtime sent one, and what
+// it logged with console.error
+fn errors_in(event: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    for exception in event["exceptions"].as_array().into_iter().flatten() {
+        let mut error = format!(
+            "{}: {}",
+            exception["name"].as_str().unwrap_or("Error"),
+            exception["message"].as_str().unwrap_or_default()
+        );
+        if let Some(stack) = exception["stack"].as_str() {
+            error.push('\n');
+            error.push_str(stack);
+        }
+        errors.push(error);
+    }
+    for log in event["logs"].as_array().into_iter().flatten() {
+        if log["level"].as_str() != Some("error") {
+            continue;
+        }
+        let parts: Vec<String> = log["message"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|part| match part {
+                Value::String(part) => part.clone(),
+                part => part.to_string(),
+            })
+            .collect();
+        errors.push(parts.join(" "));
+    }
+    errors
+}
+
+// Replaces the generated positions in the frames of a stack with where they came from.
+// Frames the source map doesn't cover are left as they were.
+fn map_stack(error: &str, source_map: &SourceMap) -> String {
+    let lines: Vec<String> = error
+        .lines()
+        .map(|line| {
+            if !line.trim_start().starts_with("at ") {
+                return line.to_string();
+            }
+            FRAME
+                .replace_all(line, |frame: &Captures| {
+                    // stacks count lines and columns from 1, source maps from 0
+                    let line: usize = frame[2].parse().unwrap_or(0);
+                    let column: usize = frame[3].parse().unwrap_or(0);
+                    let original = if line > 0 && column > 0 {
+                        source_map.original(line - 1, column - 1)
+                    } else {
+                        None
+                    };
+                    match original {
+                        Some((file, line, column)) => {
+                            format!("{}:{}:{}", file, line + 1, column + 1)
+                        }
+                        None => frame[0].to_string(),
+                    }
+                })
+                .into_owned()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_prints_each_error_once_with_its_stack_mapped_to_the_source() {
+        // line 1 comes from src/index.ts line 1, and line 2 from columns 1 and 5 of line 3
+        let source_map = SourceMap::parse(
+            r#"{"version":3,"sources":["index.ts"],"sourceRoot":"src/","mappings":"AAAA;AAEA,IAAI"}"#,
+        )
+        .unwrap();
+        let mut filter = ErrorFilter::new(Some(source_map));
+        let event = serde_json::json!({
+            "outcome": "exception",
+            "exceptions": [{
+                "name": "TypeError",
+                "message": "x is undefined",
+                "stack": "    at handle (worker.js:2:6)\n    at worker.js:9:1",
+            }],
+            "logs": [
+                { "level": "log", "message": ["fine"] },
+                { "level": "error", "message": ["failed:", { "status": 500 }] },
+            ],
+        })
+        .to_string();
+
+        assert_eq!(
+            filter.errors(&event),
+            vec![
+                "TypeError: x is undefined\n    at handle (src/index.ts:3:5)\n    at worker.js:9:1",
+                "failed: {\"status\":500}",
+            ]
+        );
+        assert_eq!(
+            filter.errors(&event),
+            vec![
+                "(seen 2 times) TypeError: x is undefined",
+                "(seen 2 times) failed: {\"status\":500}",
+            ]
+        );
+        assert!(filter.errors(r#"{"outcome":"ok","logs":[]}"#).is_empty());
+    }
+}
@@ -1,213 +1,243 @@
-use std::str;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use indicatif::{ProgressBar, ProgressStyle};
-use regex::Regex;
-use tokio::sync::oneshot::{Receiver, Sender};
-use tokio::time::{delay_for, Delay};
-
-use cloudflare::endpoints::workers::{CreateTail, CreateTailParams, DeleteTail, SendTailHeartbeat};
-use cloudflare::framework::HttpApiClientConfig;
-use cloudflare::framework::{async_api, async_api::ApiClient};
+use cloudflare::framework::response::{ApiErrors, ApiFailure};
+use futures_util::stream::StreamExt;
+use indicatif::ProgressBar;
+use serde::Deserialize;
+use tokio::time::{delay_for, interval};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::Message;
 
+use super::errors::ErrorFilter;
+use super::stats::Panel;
+use crate::build::SourceMap;
 use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::progress;
 
-const KEEP_ALIVE_INTERVAL: u64 = 60;
+// the protocol the runtime sends events over a tail's WebSocket with
+const TRACE_PROTOCOL: &str = "trace-v1";
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+const PANEL_REFRESH: Duration = Duration::from_secs(1);
 
 pub struct Session {
+    client: reqwest::Client,
+    target: Target,
     tail_id: Option<String>,
+    // the `--stats` panel, when it was asked for
+    panel: Option<Panel>,
+    // what `--errors-only` prints instead of whole events
+    errors: Option<ErrorFilter>,
+}
+
+#[derive(Deserialize)]
+struct TailResponse {
+    result: Option<CreatedTail>,
+}
+
+#[derive(Deserialize)]
+struct CreatedTail {
+    id: String,
+    url: String,
 }
 
 /// Session is responsible for interacting with the Workers API to establish and maintain the tail
-/// connection; once the tail session is established, it uses the returned tail_id to send periodic
-/// "keepalive" heartbeats to the service. If the service does not receive a heartbeat for ~10
-/// minutes, it will kill the tail session by removing the trace worker.
+/// connection; once the tail is created, it connects to the tail's WebSocket and prints every
+/// event the runtime sends over it. Pings on the socket keep the tail alive, so no heartbeats
+/// are needed. When the socket drops, or the tail expires, the session creates a new tail,
+/// backing off exponentially while the network or the API is down, and marks the gap in
+/// the logs on stderr.
 impl Session {
     pub async fn run(
         target: Target,
         user: GlobalUser,
-        shutdown_rx: Receiver<()>,
-        tx: Sender<()>,
-        metrics_port: u16,
         verbose: bool,
+        stats: bool,
+        errors_only: bool,
     ) -> Result<(), failure::Error> {
+        let errors = if errors_only {
+            Some(ErrorFilter::new(SourceMap::for_target(&target)))
+        } else {
+            None
+        };
         // During the start process we'll populate the tail with the response from the API.
-        let mut session = Session { tail_id: None };
-        // We need to exit on a shutdown command without waiting for API calls to complete.
-        tokio::select! {
-            _ = shutdown_rx => { session.close(&user, &target).await }
-            result = session.start(&target, &user, tx, metrics_port, verbose) => { result }
-        }
+        let mut session = Session {
+            client: http::legacy_auth_client_async(&user),
+            target,
+            tail_id: None,
+            panel: if stats { Some(Panel::new()) } else { None },
+            errors,
+        };
+        // We need to exit on Ctrl-C without waiting for API calls to complete.
+        let result = tokio::select! {
+            _ = tokio::signal::ctrl_c() => { Ok(()) }
+            result = session.start(verbose) => { result }
+        };
+        StdErr::message("Closing tail session...");
+        session.close().await?;
+        result
     }
 
-    async fn close(&self, user: &GlobalUser, target: &Target) -> Result<(), failure::Error> {
-        // The API will clean up tails after about 10 minutes of inactivity, or 24 hours of
-        // activity but since we limit the number of tails allowed on a single script we should at
-        // least try to delete them as we go.
-        if let Some(tail_id) = &self.tail_id {
-            let client = http::cf_v4_api_client_async(&user, HttpApiClientConfig::default())?;
-            client
-                .request(&DeleteTail {
-                    account_identifier: &target.account_id,
-                    script_name: &target.name,
-                    tail_id: &tail_id,
-                })
-                .await?;
+    async fn close(&mut self) -> Result<(), failure::Error> {
+        // The API will clean up tails after they expire, but since we limit the number of tails
+        // allowed on a single script we should at least try to delete them as we go.
+        if let Some(tail_id) = self.tail_id.take() {
+            let addr =
+                http::endpoints::script_tail(&self.target.account_id, &self.target.name, &tail_id);
+            http::send_traced_async(self.client.delete(&addr)).await?;
         }
 
         Ok(())
     }
 
-    async fn start(
-        &mut self,
-        target: &Target,
-        user: &GlobalUser,
-        tx: Sender<()>,
-        metrics_port: u16,
-        verbose: bool,
-    ) -> Result<(), failure::Error> {
-        let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
-        let spinner = ProgressBar::new_spinner().with_style(style);
-
+    async fn start(&mut self, verbose: bool) -> Result<(), failure::Error> {
         // Verbose output and the spinner don't play well together.
-        if verbose {
-            eprintln!("This may take a few seconds...");
+        let spinner = if verbose {
+            StdErr::message("This may take a few seconds...");
+            ProgressBar::hidden()
         } else {
-            spinner.set_message("This may take a few seconds...");
+            let spinner = progress::spinner("This may take a few seconds...");
             spinner.enable_steady_tick(20);
-        }
+            spinner
+        };
 
-        let client = http::cf_v4_api_client_async(user, HttpApiClientConfig::default())?;
-
-        // TODO: make Tunnel struct responsible for getting its own port!
-        let url = get_tunnel_url(metrics_port).await?;
-        let response = client
-            .request(&CreateTail {
-                account_identifier: &target.account_id,
-                script_name: &target.name,
-                params: CreateTailParams { url },
-            })
-            .await;
-
-        match response {
-            Ok(success) => {
-                spinner.abandon_with_message("Now prepared to stream logs.");
-
-                let tail_id = success.result.id;
-                self.tail_id = Some(tail_id.clone());
-
-                // Loop indefinitely to send "heartbeat" to API and keep log streaming alive.
-                // This should loop forever until SIGINT is issued or Wrangler process is killed
-                // through other means.
-                let duration = Duration::from_millis(1000 * KEEP_ALIVE_INTERVAL);
-                let mut delay = delay_for(duration);
-
-                loop {
-                    delay.await;
-                    let heartbeat_result = send_heartbeat(&target, &client, &tail_id).await;
-                    if heartbeat_result.is_err() {
-                        return heartbeat_result;
-                    }
-                    delay = delay_for(duration);
-                }
-            }
-            Err(e) => {
-                tx.send(()).unwrap();
-                failure::bail!(http::format_error(e, Some(&tail_help)))
+        let mut url = self.create().await?;
+        spinner.finish_and_clear();
+        StdErr::message("Now prepared to stream logs.");
+
+        // Loop indefinitely to print events and keep log streaming alive.
+        // This should loop forever until SIGINT is issued or Wrangler process is killed
+        // through other means.
+        loop {
+            let lost = match print_events(&url, self.panel.as_mut(), self.errors.as_mut()).await {
+                Ok(()) => "the tail was closed".to_string(),
+                Err(e) => e.to_string(),
+            };
+            let dropped_at = Instant::now();
+            if let Some(panel) = &mut self.panel {
+                panel.detach();
             }
+            StdErr::message(&format!("Lost the tail session: {}. Reconnecting...", lost));
+            url = self.reconnect().await?;
+            // logs sent while the session was down are gone, so say where they would be
+            StdErr::message(&format!(
+                "--- Reconnected after {}s. Logs from that time were not received. ---",
+                dropped_at.elapsed().as_secs()
+            ));
         }
     }
-}
 
-async fn get_tunnel_url(metrics_port: u16) -> Result<String, failure::Error> {
-    // regex for extracting url from cloudflared metrics port.
-    let url_regex = Regex::new("userHostname=\"(https://[a-z.-]+)\"").unwrap();
+    // Creates a tail, and returns the URL of its WebSocket.
+    async fn create(&mut self) -> Result<String, failure::Error> {
+        let res = self.request_tail().await?;
+        self.created(res).await
+    }
 
-    struct RetryDelay {
-        delay: Delay,
-        attempt: u64,
-        max_attempts: u64,
+    async fn request_tail(&self) -> reqwest::Result<reqwest::Response> {
+        let addr = http::endpoints::script_tails(&self.target.account_id, &self.target.name);
+        http::send_traced_async(self.client.post(&addr).json(&serde_json::json!({}))).await
     }
 
-    // This retry loop retries retrieving the cloudflared endpoint url from the cloudflared /metrics
-    // until it gets the URL or has tried retrieving the URL over 5 times.
-    impl RetryDelay {
-        fn new(max_attempts: u64) -> RetryDelay {
-            RetryDelay {
-                delay: delay_for(Duration::from_millis(0)),
-                attempt: 0,
-                max_attempts,
-            }
+    // Reads the tail the API created from its response, or the error it answered with.
+    async fn created(&mut self, res: reqwest::Response) -> Result<String, failure::Error> {
+        let status = res.status();
+        if !status.is_success() {
+            let errors: ApiErrors = res.json().await.unwrap_or_default();
+            return Err(http::api_error(
+                ApiFailure::Error(status, errors),
+                Some(&tail_help),
+            ));
         }
-
-        // our retry delay is an [exponential backoff](https://en.wikipedia.org/wiki/Exponential_backoff),
-        // which simply waits twice as long between each attempt to avoid hammering the LogServer.
-        fn reset(self) -> RetryDelay {
-            let attempt = self.attempt + 1;
-            let delay = delay_for(Duration::from_millis(attempt * attempt * 1000));
-            let max_attempts = self.max_attempts;
-
-            RetryDelay {
-                attempt,
-                delay,
-                max_attempts,
+        let response: TailResponse = res.json().await?;
+        match response.result {
+            Some(tail) => {
+                self.tail_id = Some(tail.id);
+                Ok(tail.url)
             }
+            None => failure::bail!("The Workers API did not return the tail it created"),
         }
+    }
 
-        fn expired(&self) -> bool {
-            self.attempt > self.max_attempts
-        }
-
-        fn is_elapsed(&self) -> bool {
-            self.delay.is_elapsed()
+    // Creates a new tail, waiting twice as long after each failed attempt, until one
+    // succeeds. Returns the URL of its WebSocket. Only network errors and errors on the
+    // API's side are waited out: one like the script having been deleted, the token
+    // no longer being allowed to tail it, or the limit of tails on the script being
+    // reached won't go away by trying again, so it ends the session.
+    async fn reconnect(&mut self) -> Result<String, failure::Error> {
+        let mut delay = Duration::from_secs(1);
+        loop {
+            // the old tail counts toward the script's limit until it expires
+            self.close().await.ok();
+            let failure = match self.request_tail().await {
+                Ok(res) if !res.status().is_server_error() => return self.created(res).await,
+                Ok(res) => format!("the API answered with {}", res.status()),
+                Err(e) => e.to_string(),
+            };
+            StdErr::message(&format!(
+                "Could not reconnect: {}\nTrying again in {}s...",
+                failure,
+                delay.as_secs()
+            ));
+            delay_for(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
         }
     }
+}
 
-    let mut delay = RetryDelay::new(5);
-
-    while !delay.expired() {
-        if delay.is_elapsed() {
-            let metrics_url = format!("http://localhost:{}/metrics", metrics_port);
-            if let Ok(resp) = reqwest::get(&metrics_url).await {
-                let body = resp.text().await?;
+// Prints every event sent over the tail's WebSocket as its own line, until the socket
+// is closed. With a stats panel, the events are counted and the panel redrawn every
+// second instead, and only printed when stdout isn't the terminal the panel is on.
+// With an error filter, only the errors in each event are printed.
+async fn print_events(
+    url: &str,
+    mut panel: Option<&mut Panel>,
+    mut errors: Option<&mut ErrorFilter>,
+) -> Result<(), failure::Error> {
+    let request = ::http::Request::builder()
+        .uri(url)
+        .header("Sec-WebSocket-Protocol", TRACE_PROTOCOL)
+        .body(())?;
+    let (mut socket, _) = connect_async(request).await?;
+    let print = panel.is_none() || !atty::is(atty::Stream::Stdout);
+    let mut refresh = interval(PANEL_REFRESH);
 
-                if let Some(capture) = url_regex.captures(&body) {
-                    if let Some(url) = capture.get(1) {
-                        return Ok(url.as_str().to_string());
-                    }
+    loop {
+        let message = tokio::select! {
+            message = socket.next() => message,
+            _ = refresh.tick() => {
+                if let Some(panel) = panel.as_mut() {
+                    panel.draw()?;
                 }
+                continue;
+            }
+        };
+        let event = match message {
+            Some(message) => match message? {
+                Message::Text(event) => event,
+                Message::Binary(event) => String::from_utf8_lossy(&event).into_owned(),
+                Message::Close(_) => break,
+                // pings are answered by tungstenite
+                _ => continue,
+            },
+            None => break,
+        };
+        if let Some(panel) = panel.as_mut() {
+            panel.record(&event);
+        }
+        if print {
+            match errors.as_mut() {
+                Some(errors) => errors.print(&event),
+                None => StdOut::message(&event),
             }
-
-            delay = delay.reset();
         }
     }
 
-    failure::bail!("Could not extract tunnel url from cloudflared")
-}
-
-async fn send_heartbeat(
-    target: &Target,
-    client: &async_api::Client,
-    tail_id: &str,
-) -> Result<(), failure::Error> {
-    let response = client
-        .request(&SendTailHeartbeat {
-            account_identifier: &target.account_id,
-            script_name: &target.name,
-            tail_id,
-        })
-        .await;
-
-    match response {
-        Ok(_) => Ok(()),
-        Err(e) => failure::bail!(http::format_error(e, Some(&tail_help))),
-    }
+    Ok(())
 }
 
-// tail_help() provides more detailed explanations of Workers KV API error codes.
-// See https://api.cloudflare.com/#workers-kv-namespace-errors for details.
+// tail_help() provides more detailed explanations of Workers tail API error codes.
 fn tail_help(error_code: u16) -> &'static str {
     match error_code {
         7003 | 7000 => {
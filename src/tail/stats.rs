@@ -0,0 +1,221 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
+
+use console::Term;
+use serde_json::Value;
+use url::Url;
+
+// how far back the counts go
+const WINDOW: Duration = Duration::from_secs(60);
+// how many paths and exceptions the panel lists
+const TOP: usize = 5;
+const MAX_EXCEPTION_CHARS: usize = 80;
+
+/// Rolling counts of the events a tail received in the last minute.
+pub struct Stats {
+    started: Instant,
+    samples: VecDeque<Sample>,
+}
+
+struct Sample {
+    at: Instant,
+    path: String,
+    failed: bool,
+    exceptions: Vec<String>,
+}
+
+impl Stats {
+    pub fn new(now: Instant) -> Self {
+        Stats {
+            started: now,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Counts a `trace-v1` event, the JSON the runtime sends for every invocation.
+    pub fn record(&mut self, event: &str, now: Instant) {
+        let event: Value = serde_json::from_str(event).unwrap_or_default();
+        let path = match event["event"]["request"]["url"].as_str() {
+            Some(url) => Url::parse(url)
+                .map(|url| url.path().to_string())
+                .unwrap_or_else(|_| url.to_string()),
+            None if event["event"]["cron"].is_string() => "(scheduled)".to_string(),
+            None => "(unknown)".to_string(),
+        };
+        let exceptions: Vec<String> = event["exceptions"]
+            .as_array()
+            .map(|exceptions| exceptions.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|exception| {
+                let exception = format!(
+                    "{}: {}",
+                    exception["name"].as_str().unwrap_or("Error"),
+                    exception["message"].as_str().unwrap_or_default()
+                );
+                exception.chars().take(MAX_EXCEPTION_CHARS).collect()
+            })
+            .collect();
+        let status = event["event"]["response"]["status"].as_u64().unwrap_or(0);
+        let failed = event["outcome"]
+            .as_str()
+            .map_or(false, |outcome| outcome != "ok")
+            || !exceptions.is_empty()
+            || status >= 500;
+
+        self.samples.push_back(Sample {
+            at: now,
+            path,
+            failed,
+            exceptions,
+        });
+        self.expire(now);
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(sample) = self.samples.front() {
+            if now.duration_since(sample.at) < WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// The lines of the panel: the request rate, error rate, and the most common paths
+    /// and exceptions.
+    pub fn render(&mut self, now: Instant) -> Vec<String> {
+        self.expire(now);
+        let requests = self.samples.len();
+        let failed = self.samples.iter().filter(|sample| sample.failed).count();
+        // until a whole window has gone by, the rate is over the time tail has run
+        let seconds = now.duration_since(self.started).min(WINDOW).as_secs_f64();
+        let rate = if seconds > 0.0 {
+            requests as f64 / seconds
+        } else {
+            0.0
+        };
+        let error_rate = if requests > 0 {
+            failed as f64 * 100.0 / requests as f64
+        } else {
+            0.0
+        };
+
+        let mut lines = vec![format!(
+            "Last {}s: {} requests, {:.1} req/s, {:.1}% errors",
+            WINDOW.as_secs(),
+            requests,
+            rate,
+            error_rate
+        )];
+        lines.push("Top paths:".to_string());
+        lines.extend(top(self.samples.iter().map(|sample| sample.path.as_str())));
+        lines.push("Top exceptions:".to_string());
+        let exceptions = self.samples.iter().flat_map(|sample| &sample.exceptions);
+        lines.extend(top(exceptions.map(String::as_str)));
+        lines
+    }
+}
+
+// The most common of `items`, with how often each occurs, ties in alphabetical order
+fn top<'a>(items: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for item in items {
+        *counts.entry(item).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    if counts.is_empty() {
+        return vec!["  (none)".to_string()];
+    }
+    counts
+        .into_iter()
+        .take(TOP)
+        .map(|(item, count)| format!("  {:>6}  {}", count, item))
+        .collect()
+}
+
+/// The stats, drawn on stderr over the last time they were drawn.
+pub struct Panel {
+    stats: Stats,
+    term: Term,
+    drawn: usize,
+}
+
+impl Panel {
+    pub fn new() -> Self {
+        Panel {
+            stats: Stats::new(Instant::now()),
+            term: Term::stderr(),
+            drawn: 0,
+        }
+    }
+
+    pub fn record(&mut self, event: &str) {
+        self.stats.record(event, Instant::now());
+    }
+
+    pub fn draw(&mut self) -> io::Result<()> {
+        if self.drawn > 0 {
+            self.term.clear_last_lines(self.drawn)?;
+        }
+        let lines = self.stats.render(Instant::now());
+        for line in &lines {
+            self.term.write_line(line)?;
+        }
+        self.drawn = lines.len();
+        Ok(())
+    }
+
+    /// Leaves the panel as it was drawn, so messages printed after it aren't drawn over.
+    pub fn detach(&mut self) {
+        self.drawn = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_requests_errors_paths_and_exceptions_over_the_last_minute() {
+        let start = Instant::now();
+        let mut stats = Stats::new(start);
+        let request = |path: &str, status: u16| {
+            serde_json::json!({
+                "outcome": "ok",
+                "exceptions": [],
+                "event": {
+                    "request": { "url": format!("https://example.com{}?q=1", path) },
+                    "response": { "status": status },
+                },
+            })
+            .to_string()
+        };
+        // an hour before the others, so it has expired
+        stats.record(&request("/old", 200), start);
+        let now = start + Duration::from_secs(3600);
+        stats.record(&request("/", 200), now);
+        stats.record(&request("/api", 200), now);
+        stats.record(&request("/api", 503), now);
+        let thrown = serde_json::json!({
+            "outcome": "exception",
+            "exceptions": [{ "name": "TypeError", "message": "x is undefined" }],
+            "event": { "request": { "url": "https://example.com/api" } },
+        });
+        stats.record(&thrown.to_string(), now);
+
+        let lines = stats.render(now + Duration::from_secs(20));
+        assert_eq!(
+            lines,
+            vec![
+                "Last 60s: 4 requests, 0.1 req/s, 50.0% errors",
+                "Top paths:",
+                "       3  /api",
+                "       1  /",
+                "Top exceptions:",
+                "       1  TypeError: x is undefined",
+            ]
+        );
+    }
+}
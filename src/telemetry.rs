@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::http::network;
+use crate::settings::get_wrangler_home_dir;
+
+pub const TELEMETRY_URL: &str = "https://sparrow.cloudflare.com/api/v1/event";
+// how many of the events sent `wrangler telemetry show` keeps to list
+const KEEP_EVENTS: usize = 20;
+// the event is sent after the command has finished, so don't hold the terminal for long
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    static ref COMMAND: Mutex<Option<String>> = Mutex::new(None);
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Consent {
+    enabled: bool,
+}
+
+/// What is sent about a command once telemetry is enabled. It holds nothing that could
+/// identify the user, their account or their project: no arguments, names, paths or ids.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    /// The subcommand run, e.g. `kv:namespace list`.
+    pub command: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub wrangler_version: String,
+}
+
+/// Whether the user has opted in to telemetry with `wrangler telemetry enable`. It is
+/// off until they do.
+pub fn enabled() -> bool {
+    consent_path()
+        .and_then(|path| Ok(fs::read_to_string(path)?))
+        .ok()
+        .and_then(|consent| toml::from_str::<Consent>(&consent).ok())
+        .unwrap_or_default()
+        .enabled
+}
+
+/// Records the user's choice. Turning telemetry off also drops the events kept for
+/// `wrangler telemetry show`.
+pub fn set_enabled(enabled: bool) -> Result<(), failure::Error> {
+    fs::create_dir_all(get_wrangler_home_dir()?)?;
+    fs::write(consent_path()?, toml::to_string(&Consent { enabled })?)?;
+    if !enabled {
+        let _ = fs::remove_file(events_path()?);
+    }
+    Ok(())
+}
+
+/// Sets the command the event is about, once the arguments have been parsed.
+pub fn set_command(command: String) {
+    *COMMAND.lock().unwrap() = Some(command);
+}
+
+/// The event for the command being run, if it got as far as parsing its arguments.
+pub fn event(success: bool, duration: Duration) -> Option<Event> {
+    let command = COMMAND.lock().unwrap().clone()?;
+    Some(Event {
+        command,
+        duration_ms: duration.as_millis() as u64,
+        success,
+        wrangler_version: env!("CARGO_PKG_VERSION").to_string(),
+    })
+}
+
+/// Sends the event for the command that just finished when telemetry is enabled, and
+/// once it was sent keeps a copy for `wrangler telemetry show`, which lists only what
+/// actually left the machine. Telemetry never fails a command, so errors are only
+/// logged.
+pub fn record(success: bool, duration: Duration) {
+    if !enabled() {
+        return;
+    }
+    let event = match event(success, duration) {
+        // changing the setting isn't worth reporting
+        Some(event) if !event.command.starts_with("telemetry") => event,
+        _ => return,
+    };
+    if network::offline() {
+        return;
+    }
+    if let Err(e) = send(&event) {
+        log::debug!("could not send the telemetry event: {}", e);
+        return;
+    }
+    if let Err(e) = keep(&event) {
+        log::debug!("could not keep the telemetry event: {}", e);
+    }
+}
+
+/// The last few events sent, oldest first.
+pub fn sent_events() -> Vec<Event> {
+    events_path()
+        .and_then(|path| Ok(fs::read_to_string(path)?))
+        .map(|events| {
+            events
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn keep(event: &Event) -> Result<(), failure::Error> {
+    let mut events = sent_events();
+    events.push(event.clone());
+    let skip = events.len().saturating_sub(KEEP_EVENTS);
+
+    let mut lines = String::new();
+    for event in &events[skip..] {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    fs::create_dir_all(get_wrangler_home_dir()?)?;
+    fs::write(events_path()?, lines)?;
+    Ok(())
+}
+
+fn send(event: &Event) -> Result<(), failure::Error> {
+    reqwest::blocking::Client::builder()
+        .timeout(SEND_TIMEOUT)
+        .build()?
+        .post(TELEMETRY_URL)
+        .json(event)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+fn consent_path() -> Result<PathBuf, failure::Error> {
+    Ok(get_wrangler_home_dir()?.join("telemetry.toml"))
+}
+
+fn events_path() -> Result<PathBuf, failure::Error> {
+    Ok(get_wrangler_home_dir()?.join("telemetry-events.jsonl"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_sends_only_the_command_and_how_it_went() {
+        set_command("kv:namespace list".to_string());
+        let event = event(true, Duration::from_millis(1500)).unwrap();
+
+        let sent: serde_json::Value = serde_json::to_value(&event).unwrap();
+        let mut fields: Vec<&String> = sent.as_object().unwrap().keys().collect();
+        fields.sort();
+        assert_eq!(
+            fields,
+            vec!["command", "duration_ms", "success", "wrangler_version"]
+        );
+        assert_eq!(sent["command"], "kv:namespace list");
+        assert_eq!(sent["duration_ms"], 1500);
+    }
+}
@@ -0,0 +1,91 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// Where in the user's files a problem is, for GitHub to show it on that line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub line: Option<u64>,
+    pub column: Option<u64>,
+}
+
+/// Whether wrangler runs in a GitHub Actions workflow, which reads the workflow commands
+/// wrangler prints to annotate the run with its errors and warnings.
+pub fn enabled() -> bool {
+    env::var("GITHUB_ACTIONS").map_or(false, |actions| actions == "true")
+}
+
+/// Prints an `error` or `warning` workflow command, which GitHub shows on the summary of
+/// the run, and on the line of the file when there's a location.
+pub fn annotate(level: &str, location: Option<&Location>, msg: &str) {
+    eprintln!("{}", command(level, location, msg));
+}
+
+/// Sets an output of the step running wrangler, e.g. `url`, for later steps to read as
+/// `steps.<id>.outputs.url`.
+pub fn set_output(name: &str, value: &str) -> Result<(), failure::Error> {
+    let path = match env::var_os("GITHUB_OUTPUT") {
+        Some(path) => path,
+        None => failure::bail!("GITHUB_OUTPUT is not set, so step outputs can't be set"),
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    // values are only ever one line, which is all `name=value` can hold
+    writeln!(file, "{}={}", name, value.replace(&['\r', '\n'][..], " "))?;
+    Ok(())
+}
+
+fn command(level: &str, location: Option<&Location>, msg: &str) -> String {
+    let mut properties = Vec::new();
+    if let Some(location) = location {
+        properties.push(format!("file={}", escape_property(&location.file)));
+        if let Some(line) = location.line {
+            properties.push(format!("line={}", line));
+        }
+        if let Some(column) = location.column {
+            properties.push(format!("col={}", column));
+        }
+    }
+    let properties = if properties.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", properties.join(","))
+    };
+    let msg = console::strip_ansi_codes(msg);
+    format!("::{}{}::{}", level, properties, escape_data(msg.trim()))
+}
+
+// the escaping the runner undoes, so a message can span lines
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_prints_workflow_commands() {
+        let location = Location {
+            file: "src/index.js".to_string(),
+            line: Some(12),
+            column: Some(5),
+        };
+        assert_eq!(
+            command("warning", Some(&location), "unused variable\n x"),
+            "::warning file=src/index.js,line=12,col=5::unused variable%0A x"
+        );
+        assert_eq!(
+            command("error", None, "100% of requests failed"),
+            "::error::100%25 of requests failed"
+        );
+    }
+}
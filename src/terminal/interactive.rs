@@ -1,11 +1,70 @@
 use atty::Stream;
 use std::io::{self, Read};
-// For interactively handling reading in a string
-pub fn get_user_input(prompt_string: &str) -> String {
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::settings::env_flag;
+
+// set by `--ci`, or when `$CI` says wrangler runs in CI
+static CI: AtomicBool = AtomicBool::new(false);
+// set by `--yes`
+static YES: AtomicBool = AtomicBool::new(false);
+
+/// Whether wrangler runs in CI, where no one can answer a prompt: `CI` is set, as CI
+/// services do. Piping stdin or stdout alone isn't CI, e.g. `echo y | wrangler …`.
+pub fn ci_detected() -> bool {
+    env_flag("CI")
+}
+
+/// CI mode: prompts fail instead of waiting on a terminal for an answer, unless `--yes`
+/// answers them or the answer is piped to stdin, and there are no spinners or colors in
+/// the logs.
+pub fn set_ci() {
+    CI.store(true, Ordering::SeqCst);
+    console::set_colors_enabled(false);
+    console::set_colors_enabled_stderr(false);
+}
+
+pub fn is_ci() -> bool {
+    CI.load(Ordering::SeqCst)
+}
+
+/// Answers yes to every confirmation, for `--yes`.
+pub fn assume_yes() {
+    YES.store(true, Ordering::SeqCst);
+}
+
+// For interactively handling reading in a string, typed or piped to stdin, e.g.
+// `echo "$CF_API_TOKEN" | wrangler config`. Fails when there's nothing to read.
+pub fn get_user_input(prompt_string: &str) -> Result<String, failure::Error> {
+    if waits_on_terminal() {
+        failure::bail!(
+            "wrangler can't ask for input in CI mode: {} Pipe the answer to stdin instead.",
+            prompt_string.trim_end()
+        )
+    }
     println!("{}", prompt_string);
-    let mut input: String = read!("{}\n");
-    input = strip_trailing_whitespace(input);
-    input
+    match read_line()? {
+        Some(input) => Ok(input),
+        None => failure::bail!(
+            "No answer to \"{}\" could be read from stdin",
+            prompt_string.trim_end()
+        ),
+    }
+}
+
+// In CI mode wrangler doesn't wait for someone to type at a terminal that may never
+// answer; a pipe has its answer ready, or ends.
+fn waits_on_terminal() -> bool {
+    is_ci() && atty::is(Stream::Stdin)
+}
+
+// A line from stdin without its line ending, or None when stdin has ended.
+fn read_line() -> Result<Option<String>, failure::Error> {
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(strip_trailing_whitespace(line)))
 }
 
 pub fn get_user_input_multi_line(prompt_string: &str) -> String {
@@ -37,8 +96,24 @@ const NO: &str = "n";
 // and lowercasing logic below.
 // TODO: loop this to retry until valid input is received.
 pub fn confirm(prompt_string: &str) -> Result<bool, failure::Error> {
+    if YES.load(Ordering::SeqCst) {
+        println!("{} [y/n] y (--yes)", prompt_string);
+        return Ok(true);
+    }
+    if waits_on_terminal() {
+        failure::bail!(
+            "{} wrangler can't ask in CI mode, so pass --yes to go ahead.",
+            prompt_string
+        )
+    }
     println!("{} [y/n]", prompt_string);
-    let mut response: String = read!("{}\n");
+    let mut response = match read_line()? {
+        Some(response) => response,
+        None => failure::bail!(
+            "{} No answer could be read from stdin, so pass --yes to go ahead.",
+            prompt_string
+        ),
+    };
     response = response.split_whitespace().collect(); // remove whitespace
     response.make_ascii_lowercase(); // ensure response is all lowercase
     response.truncate(INTERACTIVE_RESPONSE_LEN); // at this point, all valid input will be "y" or "n"
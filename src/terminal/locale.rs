@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::preferences::Preferences;
+
+// Translations that ship with wrangler, by locale. A community translation is added as
+// `("fr", include_str!("locales/fr.toml"))`.
+const BUNDLED: &[(&str, &str)] = &[];
+
+lazy_static! {
+    static ref CATALOG: Catalog = load();
+    // `{}`, or `{0}`, `{1}`… in a translation that puts the parts in another order
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{(\d*)\}").unwrap();
+}
+
+/// The translation of a message into the user's language, or the message itself when
+/// there's none. Messages are looked up by their English text, so modules print them
+/// with `StdOut` and `StdErr` as they always have.
+pub fn translate(msg: &str) -> String {
+    CATALOG.translate(msg)
+}
+
+#[derive(Default, Deserialize)]
+struct CatalogFile {
+    #[serde(default)]
+    messages: HashMap<String, String>,
+}
+
+/// The messages of one language. A message with parts that change from one run to the
+/// next, like a worker's name, has `{}` in its place in the English text:
+///
+/// ```toml
+/// [messages]
+/// "Success" = "Succès"
+/// "Built successfully, built project size is {}" = "Compilé, le projet fait {}"
+/// ```
+#[derive(Default)]
+struct Catalog {
+    exact: HashMap<String, String>,
+    patterns: Vec<(Regex, String)>,
+}
+
+impl Catalog {
+    fn parse(text: &str) -> Result<Catalog, failure::Error> {
+        let file: CatalogFile = toml::from_str(text)?;
+        let mut catalog = Catalog::default();
+        for (english, translation) in file.messages {
+            if !english.contains("{}") {
+                catalog.exact.insert(english, translation);
+                continue;
+            }
+            let parts: Vec<String> = english.split("{}").map(regex::escape).collect();
+            let pattern = Regex::new(&format!("^(?s){}$", parts.join("(.*?)")))?;
+            catalog.patterns.push((pattern, translation));
+        }
+        // the longest English text is the most specific
+        catalog
+            .patterns
+            .sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.as_str().len()));
+        Ok(catalog)
+    }
+
+    fn translate(&self, msg: &str) -> String {
+        if let Some(translation) = self.exact.get(msg) {
+            return translation.clone();
+        }
+        for (pattern, translation) in &self.patterns {
+            if let Some(captures) = pattern.captures(msg) {
+                let mut next = 0;
+                return PLACEHOLDER
+                    .replace_all(translation, |placeholder: &regex::Captures| {
+                        let index = match placeholder[1].parse::<usize>() {
+                            Ok(index) => index,
+                            Err(_) => {
+                                next += 1;
+                                next - 1
+                            }
+                        };
+                        captures
+                            .get(index + 1)
+                            .map_or("", |part| part.as_str())
+                            .to_string()
+                    })
+                    .into_owned();
+            }
+        }
+        msg.to_string()
+    }
+}
+
+// The locale comes from `WRANGLER_LOCALE`, then `locale` in preferences.toml, then the
+// system's. A catalog in `locales/` in the wrangler home directory wins over a bundled
+// one, so translations can be tried out without building wrangler.
+fn load() -> Catalog {
+    let locale = env::var("WRANGLER_LOCALE")
+        .ok()
+        .or_else(|| Preferences::load().locale)
+        .or_else(system_locale);
+    let locale = match locale {
+        Some(locale) => locale,
+        None => return Catalog::default(),
+    };
+
+    for candidate in candidates(&locale) {
+        let user_catalog = get_wrangler_home_dir()
+            .map(|home| home.join("locales").join(format!("{}.toml", candidate)));
+        let text = match user_catalog {
+            Ok(path) if path.exists() => fs::read_to_string(&path).ok(),
+            _ => BUNDLED
+                .iter()
+                .find(|(bundled, _)| *bundled == candidate)
+                .map(|(_, text)| text.to_string()),
+        };
+        if let Some(text) = text {
+            match Catalog::parse(&text) {
+                Ok(catalog) => return catalog,
+                Err(e) => log::warn!("Could not read the {} translation: {}", candidate, e),
+            }
+        }
+    }
+    Catalog::default()
+}
+
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|locale| !locale.is_empty())
+}
+
+// The catalogs to look for, most specific first: "pt_BR.UTF-8" is "pt-BR", then "pt".
+// English, and the C locale, need none.
+fn candidates(locale: &str) -> Vec<String> {
+    let locale = locale.split(&['.', '@'][..]).next().unwrap_or_default();
+    let locale = locale.replace('_', "-");
+    let language = locale.split('-').next().unwrap_or_default().to_string();
+    if language.is_empty() || language == "en" || language == "C" || language == "POSIX" {
+        return Vec::new();
+    }
+    let mut candidates = vec![locale.clone()];
+    if language != locale {
+        candidates.push(language);
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_translates_messages_with_the_parts_that_vary() {
+        let catalog = Catalog::parse(
+            r#"
+            [messages]
+            "Success" = "Succès"
+            "Deleted {} keys from {}" = "{1} : {0} clés supprimées"
+            "Deleted {}" = "{} supprimé"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(catalog.translate("Success"), "Succès");
+        assert_eq!(
+            catalog.translate("Deleted 12 keys from CACHE"),
+            "CACHE : 12 clés supprimées"
+        );
+        assert_eq!(catalog.translate("Deleted my-worker"), "my-worker supprimé");
+        assert_eq!(catalog.translate("Not translated"), "Not translated");
+        assert_eq!(candidates("pt_BR.UTF-8"), vec!["pt-BR", "pt"]);
+        assert!(candidates("en_US.UTF-8").is_empty());
+    }
+}
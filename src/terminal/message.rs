@@ -1,51 +1,204 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::error::WranglerError;
+
 use super::emoji;
+use super::github::{self, Location};
+use super::locale;
+use super::progress::{Progress, Unit};
 
 use billboard::{Billboard, BorderColor, BorderStyle};
+use console::Emoji;
 use serde::Serialize;
 
+// Counts warnings so `--strict` can fail the command
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+static STRICT: AtomicBool = AtomicBool::new(false);
+static NDJSON: AtomicBool = AtomicBool::new(false);
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// The number of warnings printed so far.
+pub fn warning_count() -> usize {
+    WARNINGS.load(Ordering::SeqCst)
+}
+
+/// With `--strict`, any warning fails the command.
+pub fn set_strict() {
+    STRICT.store(true, Ordering::SeqCst);
+}
+
+/// Fails with a strict error when warnings were printed with `--strict`. Commands check
+/// before they change anything, e.g. publish once the build is done and before it
+/// uploads, and wrangler checks again with the warnings printed after the command.
+pub fn check_strict() -> Result<(), failure::Error> {
+    let warnings = warning_count();
+    if STRICT.load(Ordering::SeqCst) && warnings > 0 {
+        return Err(WranglerError::Strict(format!(
+            "{} warning(s) were printed and --strict is set",
+            warnings
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LogFormat {
+    Text,
+    Ndjson,
+}
+
+/// With `LogFormat::Ndjson`, every message is written to stderr as a
+/// single line JSON event instead of being printed for humans.
+pub fn set_log_format(format: LogFormat) {
+    NDJSON.store(format == LogFormat::Ndjson, Ordering::SeqCst);
+}
+
+pub fn log_format() -> LogFormat {
+    if NDJSON.load(Ordering::SeqCst) {
+        LogFormat::Ndjson
+    } else {
+        LogFormat::Text
+    }
+}
+
+/// Plain mode prints messages without emoji, spinners or borders, for screen readers
+/// and terminals that can't draw them.
+pub fn set_plain() {
+    PLAIN.store(true, Ordering::SeqCst);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::SeqCst)
+}
+
+// A message in the user's language, marked with an emoji, or in plain mode with a word
+// when the emoji says something the message doesn't
+fn decorate(emoji: &Emoji, label: Option<&str>, msg: &str) -> String {
+    let msg = locale::translate(msg);
+    match label {
+        _ if !is_plain() => format!("{} {}", emoji, msg),
+        Some(label) => format!("{}: {}", locale::translate(label), msg),
+        None => msg,
+    }
+}
+
+#[derive(Serialize)]
+struct Event<'a> {
+    level: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'a str>,
+    message: String,
+    fields: BTreeMap<&'a str, &'a str>,
+}
+
+fn event_json(level: &str, code: Option<&str>, kind: &str, msg: &str) -> String {
+    let mut fields = BTreeMap::new();
+    fields.insert("kind", kind);
+    let event = Event {
+        level,
+        code,
+        message: console::strip_ansi_codes(msg).trim().to_string(),
+        fields,
+    };
+    serde_json::to_string(&event).unwrap()
+}
+
+/// Writes an event to stderr; only used with `LogFormat::Ndjson`.
+pub fn emit_event(level: &str, code: Option<&str>, kind: &str, msg: &str) {
+    eprintln!("{}", event_json(level, code, kind, msg));
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Output {
     Json,
     PlainText,
 }
+
+/// Messages are printed in the user's language when there's a translation for them, and
+/// written to NDJSON events in English, the same for every user.
 pub trait Message {
     fn message(msg: &str);
 
     fn info(msg: &str) {
-        let msg = format!("{} {}", emoji::INFO, msg);
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("info", None, "info", msg);
+        }
+        let msg = decorate(&emoji::INFO, None, msg);
         Self::message(&msg);
     }
 
     fn warn(msg: &str) {
-        let msg = format!("{} {}", emoji::WARN, msg);
+        Self::warn_at(None, msg);
+    }
+
+    /// A warning about a place in the user's files, which GitHub Actions shows on that
+    /// line when wrangler runs in a workflow.
+    fn warn_at(location: Option<&Location>, msg: &str) {
+        WARNINGS.fetch_add(1, Ordering::SeqCst);
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("warn", None, "warn", msg);
+        }
+        if github::enabled() {
+            return github::annotate("warning", location, msg);
+        }
+        let msg = decorate(&emoji::WARN, Some("Warning"), msg);
         Self::message(&msg);
     }
 
     fn success(msg: &str) {
-        let msg = format!("{} {}", emoji::SPARKLES, msg);
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("info", None, "success", msg);
+        }
+        let msg = decorate(&emoji::SPARKLES, None, msg);
         Self::message(&msg);
     }
 
     fn user_error(msg: &str) {
-        let msg = format!("{} {}", emoji::EYES, msg);
+        Self::user_error_at(None, msg);
+    }
+
+    /// Like `warn_at`, for an error.
+    fn user_error_at(location: Option<&Location>, msg: &str) {
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("error", None, "user_error", msg);
+        }
+        if github::enabled() {
+            return github::annotate("error", location, msg);
+        }
+        let msg = decorate(&emoji::EYES, Some("Error"), msg);
         Self::message(&msg);
     }
 
     fn working(msg: &str) {
-        let msg = format!("{} {}", emoji::SWIRL, msg);
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("info", None, "working", msg);
+        }
+        let msg = decorate(&emoji::SWIRL, None, msg);
         Self::message(&msg);
     }
 
     fn preview(msg: &str) {
-        let msg = format!("{} {}", emoji::WORKER, msg);
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("info", None, "preview", msg);
+        }
+        let msg = decorate(&emoji::WORKER, None, msg);
         Self::message(&msg);
     }
 
     fn help(msg: &str) {
-        let msg = format!("{} {}", emoji::SLEUTH, msg);
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("info", None, "help", msg);
+        }
+        let msg = decorate(&emoji::SLEUTH, Some("Help"), msg);
         Self::message(&msg);
     }
 
+    /// Counts towards `total`, drawn as a bar where the messages go when that's a
+    /// terminal, and printed as a line every few seconds when it isn't.
+    fn progress(msg: &str, total: u64, unit: Unit) -> Progress;
+
     fn billboard(msg: &str);
     fn deprecation_warning(msg: &str);
     fn as_json<T>(value: &T)
@@ -60,22 +213,39 @@ impl Message for StdOut {
         println!("{}", msg);
     }
 
+    fn progress(msg: &str, total: u64, unit: Unit) -> Progress {
+        Progress::new(msg, total, unit, true)
+    }
+
     fn billboard(msg: &str) {
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("info", None, "billboard", msg);
+        }
+        if is_plain() {
+            return Self::message(&locale::translate(msg));
+        }
         let billboard = Billboard::builder()
             .border_style(BorderStyle::Round)
             .border_color(BorderColor::Cyan)
             .margin(1)
             .build();
-        billboard.display(msg);
+        billboard.display(&locale::translate(msg));
     }
 
     fn deprecation_warning(msg: &str) {
+        WARNINGS.fetch_add(1, Ordering::SeqCst);
+        if log_format() == LogFormat::Ndjson {
+            return emit_event("warn", None, "deprecation", msg);
+        }
+        if is_plain() {
+            return Self::message(&decorate(&emoji::WARN, Some("Warning"), msg));
+        }
         let bb = Billboard::builder()
             .border_style(BorderStyle::Round)
             .border_color(BorderColor::Red)
             .margin(1)
             .build();
-        bb.display(msg);
+        bb.display(&locale::translate(msg));
     }
 
     fn as_json<T>(value: &T)
@@ -93,6 +263,10 @@ impl Message for StdErr {
         eprintln!("{}", msg);
     }
 
+    fn progress(msg: &str, total: u64, unit: Unit) -> Progress {
+        Progress::new(msg, total, unit, false)
+    }
+
     fn billboard(_msg: &str) {
         panic!("Can't display billboard to stderr.")
     }
@@ -107,3 +281,18 @@ impl Message for StdErr {
         panic!("Json output intended for stdout, not stderr.")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_serializes_events_without_styling() {
+        let msg = format!("publishing {}", console::style("my-worker").yellow().bold());
+        let json = event_json("warn", Some("config"), "warn", &msg);
+        assert_eq!(
+            json,
+            r#"{"level":"warn","code":"config","message":"publishing my-worker","fields":{"kind":"warn"}}"#
+        );
+    }
+}
@@ -1,6 +1,11 @@
 mod browser;
 pub mod emoji;
+pub mod github;
 pub mod interactive;
+pub mod locale;
 pub mod message;
+pub mod progress;
 pub mod styles;
+pub mod summary;
+pub mod table;
 pub use browser::open_browser;
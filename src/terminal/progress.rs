@@ -0,0 +1,167 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use indicatif::{HumanBytes, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use super::interactive;
+use super::message::{self, LogFormat};
+
+// how often progress is printed when it can't be drawn as a bar
+const LINE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What a `Progress` counts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Bytes,
+    /// Things, e.g. `Items("keys")`
+    Items(&'static str),
+}
+
+/// How far along an upload, a download or a bulk KV operation is. On a terminal it's a
+/// progress bar; elsewhere, like in CI logs, it's a line of text every few seconds.
+/// Clones count towards the same total, so it can be handed to the reader of a request body.
+#[derive(Clone)]
+pub struct Progress(Kind);
+
+#[derive(Clone)]
+enum Kind {
+    Bar(ProgressBar),
+    Lines(Arc<Mutex<Lines>>),
+}
+
+struct Lines {
+    msg: String,
+    unit: Unit,
+    total: u64,
+    position: u64,
+    stdout: bool,
+    printed_at: Instant,
+}
+
+impl Progress {
+    /// Use `Message::progress` instead, which draws where that stream's messages go.
+    pub(super) fn new(msg: &str, total: u64, unit: Unit, stdout: bool) -> Self {
+        let is_terminal = atty::is(if stdout {
+            atty::Stream::Stdout
+        } else {
+            atty::Stream::Stderr
+        });
+        let drawn = !message::is_plain() && !interactive::is_ci();
+        if is_terminal && message::log_format() == LogFormat::Text && drawn {
+            let target = if stdout {
+                ProgressDrawTarget::stdout()
+            } else {
+                ProgressDrawTarget::stderr()
+            };
+            let bar = ProgressBar::with_draw_target(total, target);
+            let template = match unit {
+                Unit::Bytes => "{wide_bar} {bytes}/{total_bytes} ({eta} left)\n{msg}",
+                Unit::Items(_) => "{wide_bar} {pos}/{len}\n{msg}",
+            };
+            bar.set_style(ProgressStyle::default_bar().template(template));
+            bar.set_message(msg);
+            return Progress(Kind::Bar(bar));
+        }
+        Progress(Kind::Lines(Arc::new(Mutex::new(Lines {
+            msg: msg.to_string(),
+            unit,
+            total,
+            position: 0,
+            stdout,
+            printed_at: Instant::now(),
+        }))))
+    }
+
+    pub fn inc(&self, delta: u64) {
+        match &self.0 {
+            Kind::Bar(bar) => bar.inc(delta),
+            Kind::Lines(lines) => {
+                let mut lines = lines.lock().unwrap();
+                lines.position += delta;
+                if lines.printed_at.elapsed() >= LINE_INTERVAL {
+                    lines.print(&lines.describe());
+                    lines.printed_at = Instant::now();
+                }
+            }
+        }
+    }
+
+    /// Ends the progress with a message saying what was done.
+    pub fn finish(&self, msg: &str) {
+        match &self.0 {
+            Kind::Bar(bar) => bar.finish_with_message(msg),
+            Kind::Lines(lines) => lines.lock().unwrap().print(msg),
+        }
+    }
+
+    /// Ends the progress, leaving nothing of it behind.
+    pub fn finish_and_clear(&self) {
+        if let Kind::Bar(bar) = &self.0 {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+/// A spinner saying what's going on while something that can't be counted runs. In plain
+/// and CI mode there's no spinner, and `msg` is printed once instead.
+pub fn spinner(msg: &str) -> ProgressBar {
+    if message::is_plain() || interactive::is_ci() {
+        eprintln!("{}", msg);
+        return ProgressBar::hidden();
+    }
+    let spinner = ProgressBar::new_spinner()
+        .with_style(ProgressStyle::default_spinner().template("{spinner}   {msg}"));
+    spinner.set_message(msg);
+    spinner
+}
+
+impl Lines {
+    fn describe(&self) -> String {
+        let (position, total) = match self.unit {
+            Unit::Bytes => (
+                HumanBytes(self.position).to_string(),
+                HumanBytes(self.total).to_string(),
+            ),
+            Unit::Items(items) => (
+                self.position.to_string(),
+                format!("{} {}", self.total, items),
+            ),
+        };
+        let percent = if self.total > 0 {
+            self.position * 100 / self.total
+        } else {
+            100
+        };
+        format!("{}: {} of {} ({}%)", self.msg, position, total, percent)
+    }
+
+    fn print(&self, line: &str) {
+        if message::log_format() == LogFormat::Ndjson {
+            message::emit_event("info", None, "progress", line);
+        } else if self.stdout {
+            println!("{}", line);
+        } else {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_describes_progress_in_plain_text() {
+        let mut lines = Lines {
+            msg: "Deleting".to_string(),
+            unit: Unit::Items("keys"),
+            total: 5000,
+            position: 0,
+            stdout: false,
+            printed_at: Instant::now(),
+        };
+        assert_eq!(lines.describe(), "Deleting: 0 of 5000 keys (0%)");
+        lines.position = 1250;
+        assert_eq!(lines.describe(), "Deleting: 1250 of 5000 keys (25%)");
+    }
+}
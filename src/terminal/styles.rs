@@ -11,3 +11,15 @@ pub fn warning<D>(msg: D) -> StyledObject<D> {
 pub fn highlight<D>(msg: D) -> StyledObject<D> {
     style(msg).yellow().bold()
 }
+
+pub fn addition<D>(msg: D) -> StyledObject<D> {
+    style(msg).green()
+}
+
+pub fn deletion<D>(msg: D) -> StyledObject<D> {
+    style(msg).red()
+}
+
+pub fn modification<D>(msg: D) -> StyledObject<D> {
+    style(msg).yellow()
+}
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use indicatif::HumanBytes;
+
+use super::message::{self, Message, StdErr};
+
+// counted as the command runs, for the footer printed when it's done
+static API_CALLS: AtomicUsize = AtomicUsize::new(0);
+static BYTES_UPLOADED: AtomicU64 = AtomicU64::new(0);
+
+/// Counts a request sent to the API, including each time a rate limited one is sent again.
+pub fn api_call() {
+    API_CALLS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Counts bytes sent to the API in request bodies.
+pub fn uploaded(bytes: u64) {
+    BYTES_UPLOADED.fetch_add(bytes, Ordering::SeqCst);
+}
+
+/// Prints what the command took, with `summary = true` in preferences.toml, which helps
+/// tell why a publish in CI is slow.
+pub fn print(elapsed: Duration) {
+    StdErr::info(&describe(
+        elapsed,
+        BYTES_UPLOADED.load(Ordering::SeqCst),
+        API_CALLS.load(Ordering::SeqCst),
+        message::warning_count(),
+    ));
+}
+
+fn describe(elapsed: Duration, bytes: u64, api_calls: usize, warnings: usize) -> String {
+    format!(
+        "Done in {:.1}s: {} uploaded, {} API {}, {} {}",
+        elapsed.as_secs_f64(),
+        HumanBytes(bytes),
+        api_calls,
+        plural(api_calls, "call"),
+        warnings,
+        plural(warnings, "warning")
+    )
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_describes_what_the_command_took() {
+        assert_eq!(
+            describe(Duration::from_millis(12_340), 3 * 1024 * 1024, 14, 1),
+            "Done in 12.3s: 3.00MB uploaded, 14 API calls, 1 warning"
+        );
+    }
+}
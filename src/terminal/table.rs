@@ -0,0 +1,133 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use console::Term;
+
+// set by the global `--wide` and `--no-header` flags
+static WIDE: AtomicBool = AtomicBool::new(false);
+static NO_HEADER: AtomicBool = AtomicBool::new(false);
+
+// columns are never truncated to fewer characters than this
+const MIN_COLUMN_WIDTH: usize = 8;
+const GAP: &str = "  ";
+
+/// Prints tables at their full width, even when that's wider than the terminal.
+pub fn set_wide() {
+    WIDE.store(true, Ordering::SeqCst);
+}
+
+/// Leaves the header out of tables, for scripts that read them.
+pub fn hide_header() {
+    NO_HEADER.store(true, Ordering::SeqCst);
+}
+
+/// The columns of what a list command lists, sized to fit the terminal. Cells that don't
+/// fit are cut short with `…`, the widest column first.
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: &[&str]) -> Self {
+        Table {
+            header: header.iter().map(|column| column.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row<S: Into<String>>(&mut self, row: Vec<S>) {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn print(&self) {
+        // only a terminal has a width to fit
+        let width = if WIDE.load(Ordering::SeqCst) {
+            None
+        } else {
+            Term::stdout()
+                .size_checked()
+                .map(|(_, columns)| columns as usize)
+        };
+        print!("{}", self.render(width, !NO_HEADER.load(Ordering::SeqCst)));
+    }
+
+    fn render(&self, width: Option<usize>, header: bool) -> String {
+        let mut lines: Vec<&Vec<String>> = Vec::new();
+        if header {
+            lines.push(&self.header);
+        }
+        lines.extend(&self.rows);
+
+        let mut widths: Vec<usize> = (0..self.header.len())
+            .map(|column| {
+                lines
+                    .iter()
+                    .map(|line| line.get(column).map_or(0, |cell| cell.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        if let Some(width) = width {
+            let gaps = GAP.len() * widths.len().saturating_sub(1);
+            while widths.iter().sum::<usize>() + gaps > width {
+                let widest = (0..widths.len()).max_by_key(|&column| widths[column]);
+                match widest {
+                    Some(column) if widths[column] > MIN_COLUMN_WIDTH => widths[column] -= 1,
+                    _ => break,
+                }
+            }
+        }
+
+        let mut table = String::new();
+        for line in lines {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, &width)| {
+                    let cell = line.get(column).map_or("", String::as_str);
+                    format!("{:width$}", truncate(cell, width), width = width)
+                })
+                .collect();
+            table.push_str(cells.join(GAP).trim_end());
+            table.push('\n');
+        }
+        table
+    }
+}
+
+fn truncate(cell: &str, width: usize) -> String {
+    if cell.chars().count() <= width {
+        return cell.to_string();
+    }
+    let mut truncated: String = cell.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_cuts_the_widest_column_to_fit() {
+        let mut table = Table::new(&["Id", "Pattern", "Script"]);
+        table.add_row(vec!["1", "example.com/*", "worker"]);
+        table.add_row(vec!["2", "example.com/a-very-long-path/*", "other-worker"]);
+
+        assert_eq!(
+            table.render(None, true),
+            "Id  Pattern                         Script\n\
+             1   example.com/*                   worker\n\
+             2   example.com/a-very-long-path/*  other-worker\n"
+        );
+        assert_eq!(
+            table.render(Some(36), false),
+            "1  example.com/*        worker\n\
+             2  example.com/a-very…  other-worker\n"
+        );
+    }
+}
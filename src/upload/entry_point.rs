@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::upload::package::Package;
+
+// the events a service worker can handle
+const HANDLER_EVENTS: &[&str] = &["fetch", "scheduled", "email"];
+
+/// The script a JavaScript target uploads: `main` from the configuration file when it is
+/// set, otherwise the `main` of package.json.
+pub fn script_path(target: &Target) -> Result<PathBuf, failure::Error> {
+    let main = match &target.main {
+        Some(main) => main,
+        None => {
+            let build_dir = target.build_dir()?;
+            return Package::new(&build_dir)?.main(&build_dir);
+        }
+    };
+
+    if !main.is_file() {
+        failure::bail!(
+            "The entrypoint of your Worker ({}), set by `main` in your configuration file, could not be found.",
+            main.display()
+        )
+    }
+    check_handlers(main, &fs::read_to_string(main)?)?;
+    Ok(main.clone())
+}
+
+// Scripts are uploaded in the service worker format, so the entry point has to register
+// its handlers with addEventListener rather than export them.
+fn check_handlers(path: &Path, source: &str) -> Result<(), failure::Error> {
+    let events = events(source);
+    let exports = exports(source);
+
+    if events.is_empty() && !exports.is_empty() {
+        failure::bail!(
+            "{} is an ES module (it exports {}), but wrangler uploads service worker scripts. Register your handlers with `addEventListener(\"fetch\", ...)` instead.",
+            path.display(),
+            exports.join(", ")
+        )
+    }
+    if !events.iter().any(|event| HANDLER_EVENTS.contains(event)) {
+        StdOut::warn(&format!(
+            "{} doesn't seem to register a handler for any of the {} events, so your Worker may never run.",
+            path.display(),
+            HANDLER_EVENTS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+// the events passed to addEventListener, e.g. `addEventListener("fetch", ...)`
+fn events(source: &str) -> Vec<&str> {
+    let re = Regex::new(r#"addEventListener\(\s*["'`](\w+)["'`]"#).unwrap();
+    re.captures_iter(source)
+        .filter_map(|captures| captures.get(1))
+        .map(|event| event.as_str())
+        .collect()
+}
+
+// the names the module exports, "default" included
+fn exports(source: &str) -> Vec<String> {
+    let declaration = Regex::new(
+        r"(?m)^\s*export\s+(?:async\s+)?(?:function\*?|class|const|let|var)\s+([A-Za-z_$][\w$]*)",
+    )
+    .unwrap();
+    let default = Regex::new(r"(?m)^\s*export\s+default\b").unwrap();
+    let list = Regex::new(r"(?m)^\s*export\s*\{([^}]*)\}").unwrap();
+
+    let mut exports: Vec<String> = Vec::new();
+    if default.is_match(source) {
+        exports.push("default".to_string());
+    }
+    for captures in declaration.captures_iter(source) {
+        exports.push(captures[1].to_string());
+    }
+    for captures in list.captures_iter(source) {
+        for specifier in captures[1].split(',') {
+            // `handler as default` exports "default"
+            if let Some(name) = specifier.split_whitespace().last() {
+                exports.push(name.to_string());
+            }
+        }
+    }
+    exports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_handlers_and_exports() {
+        let service_worker = r#"
+addEventListener("fetch", (event) => event.respondWith(handle(event.request)));
+addEventListener('scheduled', (event) => event.waitUntil(refresh()));
+"#;
+        assert_eq!(events(service_worker), vec!["fetch", "scheduled"]);
+        assert!(exports(service_worker).is_empty());
+        assert!(check_handlers(Path::new("src/index.js"), service_worker).is_ok());
+
+        let module = r#"
+export const VERSION = "1";
+export async function handle(request) {}
+export { handle as default, VERSION as version };
+"#;
+        assert_eq!(
+            exports(module),
+            vec!["VERSION", "handle", "default", "version"]
+        );
+        let err = check_handlers(Path::new("src/index.ts"), module).unwrap_err();
+        assert!(err.to_string().contains("is an ES module"));
+    }
+}
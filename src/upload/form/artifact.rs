@@ -0,0 +1,389 @@
+//! An artifact is a directory holding everything the code of a worker contributes to its
+//! upload, written by `wrangler build --out-dir` and uploaded by `wrangler publish
+//! --from-artifact`, so the machine that builds a worker doesn't have to be the one
+//! that publishes it:
+//!
+//! - `artifact.json`: what the files below are and how they are bound
+//! - the script, with its sourcemap when the build left one next to it
+//! - `wasm/`, `modules/` and `text_blobs/`: the Wasm modules, `[[rules]]` files and
+//!   `[text_blobs]` the script is bound to
+//! - `metadata.json`: the metadata the upload would have had when the artifact was built
+//! - the SBOM of the build, when `[build.sbom]` asked for one
+//!
+//! Bindings that come from the configuration file, like KV namespaces and `[vars]`, are
+//! taken from the configuration of the environment the artifact is published to, so an
+//! artifact built once can be published to staging and then production. Workers Sites
+//! buckets are not part of an artifact, and are uploaded from the publishing machine.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::module_file::ModuleFile;
+use super::project_assets::ProjectAssets;
+use super::text_blob::TextBlob;
+use super::wasm_module::WasmModule;
+use crate::settings::toml::{ModuleType, TargetType};
+
+pub const MANIFEST: &str = "artifact.json";
+// changes when fields are removed or change meaning
+const VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Artifact {
+    pub version: u32,
+    pub wrangler_version: String,
+    /// The worker it was built for
+    pub name: String,
+    #[serde(rename = "type")]
+    pub target_type: TargetType,
+    /// The script, relative to the artifact directory like every other file
+    pub script: String,
+    pub wasm_modules: Vec<Part>,
+    pub module_files: Vec<Part>,
+    pub text_blobs: Vec<Part>,
+    pub sourcemaps: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sbom: Option<String>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+/// A file the script is bound to
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Part {
+    pub binding: String,
+    pub file: String,
+    /// for `[[rules]]` files only
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub module_type: Option<ModuleType>,
+}
+
+impl Artifact {
+    /// Reads the artifact written to `dir`.
+    pub fn read(dir: &Path) -> Result<Self, failure::Error> {
+        let manifest = fs::read_to_string(dir.join(MANIFEST)).map_err(|e| {
+            failure::format_err!(
+                "{} is not an artifact written by `wrangler build --out-dir`: {}",
+                dir.display(),
+                e
+            )
+        })?;
+        let mut artifact: Artifact = serde_json::from_str(&manifest)?;
+        if artifact.version > VERSION {
+            failure::bail!(
+                "{} was written by wrangler {}, which is newer than this one. Update wrangler to publish it.",
+                dir.display(),
+                artifact.wrangler_version
+            )
+        }
+        // artifact.json may come from a store, so its files must stay inside the artifact
+        safe_path(&artifact.script)?;
+        for file in artifact
+            .sourcemaps
+            .iter()
+            .chain(artifact.sbom.iter())
+            .chain(
+                artifact
+                    .wasm_modules
+                    .iter()
+                    .chain(&artifact.module_files)
+                    .chain(&artifact.text_blobs)
+                    .map(|part| &part.file),
+            )
+        {
+            safe_path(file)?;
+        }
+        artifact.dir = dir.to_path_buf();
+        Ok(artifact)
+    }
+
+    /// Copies the code in `assets` and the SBOM, if there is one, into `dir`, which must
+    /// be empty or hold an earlier artifact, and writes `metadata` next to it.
+    pub fn write(
+        dir: &Path,
+        name: &str,
+        target_type: &TargetType,
+        assets: &ProjectAssets,
+        sbom: Option<&Path>,
+        metadata: &serde_json::Value,
+    ) -> Result<Self, failure::Error> {
+        if dir.is_dir() {
+            let is_empty = fs::read_dir(dir)?.next().is_none();
+            if !is_empty && !dir.join(MANIFEST).is_file() {
+                failure::bail!(
+                    "{} already exists and does not hold an artifact. Pass an empty or new directory to --out-dir.",
+                    dir.display()
+                )
+            }
+            fs::remove_dir_all(dir)?;
+        }
+        fs::create_dir_all(dir)?;
+
+        let script_path = assets.script_path();
+        let script = copy(&script_path, dir, Path::new(""))?;
+        let mut sourcemaps = Vec::new();
+        let sourcemap = PathBuf::from(format!("{}.map", script_path.display()));
+        if sourcemap.is_file() {
+            sourcemaps.push(copy(&sourcemap, dir, Path::new(""))?);
+        }
+
+        let mut wasm_modules = Vec::new();
+        for wasm_module in &assets.wasm_modules {
+            wasm_modules.push(Part {
+                binding: wasm_module.binding_name(),
+                file: copy(&wasm_module.path(), dir, Path::new("wasm"))?,
+                module_type: None,
+            });
+        }
+        let mut module_files = Vec::new();
+        for module_file in &assets.module_files {
+            // file names can repeat across directories, bindings can't
+            let subdir = Path::new("modules").join(module_file.name());
+            module_files.push(Part {
+                binding: module_file.name(),
+                file: copy(&module_file.path(), dir, &subdir)?,
+                module_type: Some(module_file.module_type()),
+            });
+        }
+        let mut text_blobs = Vec::new();
+        fs::create_dir_all(dir.join("text_blobs"))?;
+        for text_blob in &assets.text_blobs {
+            let file = format!("text_blobs/{}", text_blob.binding);
+            fs::write(dir.join(&file), &text_blob.data)?;
+            text_blobs.push(Part {
+                binding: text_blob.binding.clone(),
+                file,
+                module_type: None,
+            });
+        }
+
+        let sbom = match sbom {
+            Some(sbom) => Some(copy(sbom, dir, Path::new(""))?),
+            None => None,
+        };
+
+        let artifact = Artifact {
+            version: VERSION,
+            wrangler_version: env!("CARGO_PKG_VERSION").to_string(),
+            name: name.to_string(),
+            target_type: target_type.clone(),
+            script,
+            wasm_modules,
+            module_files,
+            text_blobs,
+            sourcemaps,
+            sbom,
+            dir: dir.to_path_buf(),
+        };
+        fs::write(
+            dir.join("metadata.json"),
+            serde_json::to_string_pretty(metadata)?,
+        )?;
+        fs::write(dir.join(MANIFEST), serde_json::to_string_pretty(&artifact)?)?;
+        Ok(artifact)
+    }
+
+    pub fn script_path(&self) -> PathBuf {
+        self.dir.join(&self.script)
+    }
+
+    pub fn sbom_path(&self) -> Option<PathBuf> {
+        self.sbom.as_ref().map(|sbom| self.dir.join(sbom))
+    }
+
+    pub fn wasm_modules(&self) -> Result<Vec<WasmModule>, failure::Error> {
+        self.wasm_modules
+            .iter()
+            .map(|part| WasmModule::new(self.dir.join(&part.file), part.binding.clone()))
+            .collect()
+    }
+
+    pub fn module_files(&self) -> Result<Vec<ModuleFile>, failure::Error> {
+        self.module_files
+            .iter()
+            .map(|part| match part.module_type {
+                Some(module_type) => Ok(ModuleFile::new(
+                    self.dir.join(&part.file),
+                    part.binding.clone(),
+                    module_type,
+                )),
+                None => failure::bail!("{} in {} has no type", part.file, MANIFEST),
+            })
+            .collect()
+    }
+
+    pub fn text_blobs(&self) -> Result<Vec<TextBlob>, failure::Error> {
+        self.text_blobs
+            .iter()
+            .map(|part| {
+                let data = fs::read_to_string(self.dir.join(&part.file))?;
+                TextBlob::new(data, part.binding.clone())
+            })
+            .collect()
+    }
+}
+
+// Copies `path` into `subdir` of the artifact, keeping its file name so the upload gets
+// the same part names and content types, and returns where it went.
+fn copy(path: &Path, dir: &Path, subdir: &Path) -> Result<String, failure::Error> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| failure::format_err!("{} is not a file", path.display()))?;
+    fs::create_dir_all(dir.join(subdir))?;
+    let file = subdir.join(file_name);
+    fs::copy(path, dir.join(&file)).map_err(|e| {
+        failure::format_err!("could not copy {} into the artifact: {}", path.display(), e)
+    })?;
+    // forward slashes, so an artifact built on Windows can be published elsewhere
+    Ok(file.to_string_lossy().replace('\\', "/"))
+}
+
+/// A file name from an artifact must stay inside the directory the artifact is in.
+pub fn safe_path(file: &str) -> Result<PathBuf, failure::Error> {
+    let path = PathBuf::from(file);
+    if file.is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        failure::bail!("{} is not a file name an artifact can hold", file)
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_back_the_artifact_it_writes() {
+        let project = tempfile::tempdir().unwrap();
+        let script = project.path().join("worker.js");
+        fs::write(&script, "addEventListener('fetch', () => {})").unwrap();
+        fs::write(project.path().join("worker.js.map"), "{}").unwrap();
+        let wasm = project.path().join("module.wasm");
+        fs::write(&wasm, b"\0asm").unwrap();
+        let html = project.path().join("index.html");
+        fs::write(&html, "<p>hi</p>").unwrap();
+
+        let mut assets = ProjectAssets::new(
+            script,
+            vec![WasmModule::new(wasm, "wasm".to_string()).unwrap()],
+            Vec::new(),
+            vec![TextBlob::new("hello".to_string(), "GREETING".to_string()).unwrap()],
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        )
+        .unwrap();
+        assets.module_files = vec![ModuleFile::new(
+            html,
+            "index_html".to_string(),
+            ModuleType::Text,
+        )];
+
+        let out = tempfile::tempdir().unwrap();
+        let dir = out.path().join("artifact");
+        let metadata = serde_json::json!({ "body_part": "worker" });
+        Artifact::write(
+            &dir,
+            "worker",
+            &TargetType::Webpack,
+            &assets,
+            None,
+            &metadata,
+        )
+        .unwrap();
+        // an earlier artifact is replaced
+        let sbom = project.path().join("sbom.cdx.json");
+        fs::write(&sbom, "{}").unwrap();
+        Artifact::write(
+            &dir,
+            "worker",
+            &TargetType::Webpack,
+            &assets,
+            Some(&sbom),
+            &metadata,
+        )
+        .unwrap();
+
+        let artifact = Artifact::read(&dir).unwrap();
+        assert_eq!(artifact.name, "worker");
+        assert_eq!(artifact.sourcemaps, vec!["worker.js.map"]);
+        assert_eq!(
+            fs::read_to_string(artifact.script_path()).unwrap(),
+            "addEventListener('fetch', () => {})"
+        );
+        let wasm_modules = artifact.wasm_modules().unwrap();
+        assert_eq!(wasm_modules[0].filename(), "module");
+        assert_eq!(wasm_modules[0].binding_name(), "wasm");
+        let module_files = artifact.module_files().unwrap();
+        assert_eq!(module_files[0].name(), "index_html");
+        assert!(module_files[0]
+            .path()
+            .ends_with("modules/index_html/index.html"));
+        assert_eq!(artifact.text_blobs().unwrap()[0].data, "hello");
+        assert!(dir.join("metadata.json").is_file());
+        assert_eq!(artifact.sbom_path(), Some(dir.join("sbom.cdx.json")));
+
+        assert!(Artifact::write(
+            project.path(),
+            "worker",
+            &TargetType::Webpack,
+            &assets,
+            None,
+            &metadata
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_keeps_artifact_files_inside_the_artifact() {
+        assert_eq!(
+            safe_path("wasm/module.wasm").unwrap(),
+            PathBuf::from("wasm/module.wasm")
+        );
+        assert!(safe_path("../wrangler.toml").is_err());
+        assert!(safe_path("/etc/passwd").is_err());
+        assert!(safe_path("").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = |script: &str, sbom: &str, wasm: &str| {
+            serde_json::json!({
+                "version": VERSION,
+                "wrangler_version": "1.19.0",
+                "name": "worker",
+                "type": "webpack",
+                "script": script,
+                "wasm_modules": [{ "binding": "wasm", "file": wasm }],
+                "module_files": [],
+                "text_blobs": [],
+                "sourcemaps": [],
+                "sbom": sbom,
+            })
+            .to_string()
+        };
+        let write = |manifest: String| fs::write(dir.path().join(MANIFEST), manifest).unwrap();
+
+        write(manifest("worker.js", "sbom.cdx.json", "wasm/module.wasm"));
+        assert!(Artifact::read(dir.path()).is_ok());
+        write(manifest(
+            "../../.ssh/id_rsa",
+            "sbom.cdx.json",
+            "wasm/module.wasm",
+        ));
+        assert!(Artifact::read(dir.path()).is_err());
+        write(manifest("worker.js", "/etc/passwd", "wasm/module.wasm"));
+        assert!(Artifact::read(dir.path()).is_err());
+        write(manifest(
+            "worker.js",
+            "sbom.cdx.json",
+            "wasm/../../module.wasm",
+        ));
+        assert!(Artifact::read(dir.path()).is_err());
+    }
+}
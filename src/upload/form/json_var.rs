@@ -0,0 +1,20 @@
+use super::binding::Binding;
+use serde::{Deserialize, Serialize};
+
+/// A var that isn't a string (a table, array, number or boolean), bound as JSON so
+/// the worker gets it with its structure intact.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JsonVar {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+impl JsonVar {
+    pub fn new(name: String, value: serde_json::Value) -> Result<Self, failure::Error> {
+        Ok(Self { name, value })
+    }
+
+    pub fn binding(&self) -> Binding {
+        Binding::new_json(self.name.clone(), self.value.clone())
+    }
+}
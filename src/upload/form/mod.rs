@@ -1,126 +1,249 @@
+pub mod artifact;
+mod json_var;
+pub mod module_file;
 mod plain_text;
 mod project_assets;
 mod text_blob;
 mod wasm_module;
 
 use reqwest::blocking::multipart::{Form, Part};
-use std::fs;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read};
 use std::path::Path;
 use std::path::PathBuf;
 
+use twox_hash::XxHash64;
+
 use crate::settings::binding;
 use crate::settings::metadata::Metadata;
 use crate::settings::toml::{Target, TargetType};
 use crate::sites::AssetManifest;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::{Progress, Unit};
+use crate::terminal::summary;
 use crate::wranglerjs;
 
+use artifact::Artifact;
+use json_var::JsonVar;
 use plain_text::PlainText;
 use project_assets::ProjectAssets;
 use text_blob::TextBlob;
 use wasm_module::WasmModule;
 
 // TODO: https://github.com/cloudflare/wrangler/issues/1083
-use super::{krate, Package};
+use super::{entry_point, krate};
+
+// uploads smaller than this finish too quickly for a progress bar to help
+const PROGRESS_MIN_BYTES: u64 = 1024 * 1024;
 
 pub fn build(
     target: &Target,
     asset_manifest: Option<AssetManifest>,
     session_config: Option<serde_json::Value>,
 ) -> Result<Form, failure::Error> {
+    let assets = project_assets(target, asset_manifest)?;
+
+    build_form(&assets, session_config, None)
+}
+
+// Like `build`, but for large uploads also returns a progress bar that counts the bytes
+// of the script and Wasm modules as they are streamed from disk into the request.
+pub fn build_with_progress(
+    target: &Target,
+    asset_manifest: Option<AssetManifest>,
+) -> Result<(Form, Option<Progress>), failure::Error> {
+    let assets = project_assets(target, asset_manifest)?;
+
+    let mut total = fs::metadata(assets.script_path())?.len();
+    for wasm_module in &assets.wasm_modules {
+        total += fs::metadata(wasm_module.path())?.len();
+    }
+    for module_file in &assets.module_files {
+        total += fs::metadata(module_file.path())?.len();
+    }
+    if total < PROGRESS_MIN_BYTES {
+        return Ok((build_form(&assets, None, None)?, None));
+    }
+
+    let progress_bar = StdErr::progress("Uploading the script", total, Unit::Bytes);
+    let form = build_form(&assets, None, Some(&progress_bar))?;
+    Ok((form, Some(progress_bar)))
+}
+
+// Hashes everything that `build` would put into the upload form, so callers can
+// tell whether a script has changed since it was last uploaded.
+pub fn fingerprint(
+    target: &Target,
+    asset_manifest: Option<AssetManifest>,
+) -> Result<u64, failure::Error> {
+    let assets = project_assets(target, asset_manifest)?;
+    let mut hasher = XxHash64::default();
+
+    // bindings are partly collected from HashMaps, so sort them
+    // to keep the hash stable between runs
+    let mut bindings = Vec::new();
+    for binding in assets.bindings() {
+        bindings.push(serde_json::to_string(&binding)?);
+    }
+    bindings.sort();
+    for binding in bindings {
+        hasher.write(binding.as_bytes());
+    }
+    if !assets.tail_consumers.is_empty() {
+        hasher.write(serde_json::to_string(&assets.tail_consumers)?.as_bytes());
+    }
+
+    hasher.write(&fs::read(assets.script_path())?);
+
+    for wasm_module in &assets.wasm_modules {
+        hasher.write(&fs::read(wasm_module.path())?);
+    }
+
+    // sorted by binding already
+    for module_file in &assets.module_files {
+        hasher.write(&fs::read(module_file.path())?);
+    }
+
+    let mut text_blobs: Vec<&TextBlob> = assets.text_blobs.iter().collect();
+    text_blobs.sort_by(|a, b| a.binding.cmp(&b.binding));
+    for text_blob in text_blobs {
+        hasher.write(text_blob.data.as_bytes());
+    }
+
+    Ok(hasher.finish())
+}
+
+/// Writes the code `publish` would upload for `target` to `dir`, for `wrangler publish
+/// --from-artifact` to upload later, maybe from another machine.
+pub fn write_artifact(
+    target: &Target,
+    dir: &Path,
+    sbom: Option<&Path>,
+) -> Result<Artifact, failure::Error> {
+    let assets = project_assets(target, None)?;
+    Artifact::write(
+        dir,
+        &target.name,
+        &target.target_type,
+        &assets,
+        sbom,
+        &metadata(&assets),
+    )
+}
+
+fn project_assets(
+    target: &Target,
+    asset_manifest: Option<AssetManifest>,
+) -> Result<ProjectAssets, failure::Error> {
     let target_type = &target.target_type;
     let kv_namespaces = &target.kv_namespaces;
     let mut text_blobs: Vec<TextBlob> = Vec::new();
     let mut plain_texts: Vec<PlainText> = Vec::new();
+    let mut json_vars: Vec<JsonVar> = Vec::new();
     let mut wasm_modules: Vec<WasmModule> = Vec::new();
-
-    if let Some(blobs) = &target.text_blobs {
-        for (key, blob_path) in blobs.iter() {
-            let blob = fs::read_to_string(blob_path)?;
-            text_blobs.push(TextBlob::new(blob, key.clone())?);
+    let send_emails = match &target.email {
+        Some(email) => email.send_email.clone(),
+        None => Vec::new(),
+    };
+
+    let artifact = match &target.artifact {
+        Some(dir) => Some(Artifact::read(dir)?),
+        None => None,
+    };
+
+    match &artifact {
+        // the text blobs were read when the artifact was built
+        Some(artifact) => text_blobs.extend(artifact.text_blobs()?),
+        None => {
+            if let Some(blobs) = &target.text_blobs {
+                for (key, blob_path) in blobs.iter() {
+                    let blob = fs::read_to_string(blob_path)?;
+                    text_blobs.push(TextBlob::new(blob, key.clone())?);
+                }
+            }
         }
     }
 
     if let Some(vars) = &target.vars {
         for (key, value) in vars.iter() {
-            plain_texts.push(PlainText::new(key.clone(), value.clone())?)
+            match value {
+                serde_json::Value::String(text) => {
+                    plain_texts.push(PlainText::new(key.clone(), text.clone())?)
+                }
+                value => json_vars.push(JsonVar::new(key.clone(), value.clone())?),
+            }
         }
     }
 
-    match target_type {
-        TargetType::Rust => {
-            log::info!("Rust project detected. Publishing...");
-            let name = krate::Krate::new("./")?.name.replace("-", "_");
-            // TODO: move into build?
-            build_generated_dir()?;
-            concat_js(&name)?;
-
-            let path = PathBuf::from(format!("./pkg/{}_bg.wasm", name));
-            let binding = "wasm".to_string();
-            let wasm_module = WasmModule::new(path, binding)?;
-            wasm_modules.push(wasm_module);
-            let script_path = PathBuf::from("./worker/generated/script.js");
-
-            let assets = ProjectAssets::new(
-                script_path,
-                wasm_modules,
-                kv_namespaces.to_vec(),
-                text_blobs,
-                plain_texts,
-            )?;
-
-            build_form(&assets, session_config)
-        }
-        TargetType::JavaScript => {
-            log::info!("JavaScript project detected. Publishing...");
-            let build_dir = target.build_dir()?;
-            let package = Package::new(&build_dir)?;
-
-            let script_path = package.main(&build_dir)?;
-
-            let assets = ProjectAssets::new(
-                script_path,
-                wasm_modules,
-                kv_namespaces.to_vec(),
-                text_blobs,
-                plain_texts,
-            )?;
-
-            build_form(&assets, session_config)
-        }
-        TargetType::Webpack => {
-            log::info!("webpack project detected. Publishing...");
-            // TODO: https://github.com/cloudflare/wrangler/issues/850
-            let build_dir = target.build_dir()?;
-            let bundle = wranglerjs::Bundle::new(&build_dir);
-
-            let script_path = bundle.script_path();
+    // The manifest is bound rather than built into the script, so that a large site
+    // doesn't count towards the script size limit, whatever the type of project.
+    if let Some(asset_manifest) = asset_manifest {
+        log::info!("adding __STATIC_CONTENT_MANIFEST");
+        let binding = "__STATIC_CONTENT_MANIFEST".to_string();
+        let asset_manifest_blob = get_asset_manifest_blob(asset_manifest)?;
+        let text_blob = TextBlob::new(asset_manifest_blob, binding)?;
+        text_blobs.push(text_blob);
+    }
 
-            if bundle.has_wasm() {
-                let path = bundle.wasm_path();
-                let binding = bundle.get_wasm_binding();
+    let script_path = match &artifact {
+        Some(artifact) => {
+            log::info!("Publishing {} from an artifact", artifact.script);
+            wasm_modules = artifact.wasm_modules()?;
+            artifact.script_path()
+        }
+        None => match target_type {
+            TargetType::Rust => {
+                log::info!("Rust project detected. Publishing...");
+                let name = krate::Krate::new("./")?.name.replace("-", "_");
+                // TODO: move into build?
+                build_generated_dir()?;
+                concat_js(&name)?;
+
+                let path = PathBuf::from(format!("./pkg/{}_bg.wasm", name));
+                let binding = "wasm".to_string();
                 let wasm_module = WasmModule::new(path, binding)?;
                 wasm_modules.push(wasm_module);
+                PathBuf::from("./worker/generated/script.js")
             }
-
-            if let Some(asset_manifest) = asset_manifest {
-                log::info!("adding __STATIC_CONTENT_MANIFEST");
-                let binding = "__STATIC_CONTENT_MANIFEST".to_string();
-                let asset_manifest_blob = get_asset_manifest_blob(asset_manifest)?;
-                let text_blob = TextBlob::new(asset_manifest_blob, binding)?;
-                text_blobs.push(text_blob);
+            TargetType::JavaScript => {
+                log::info!("JavaScript project detected. Publishing...");
+                entry_point::script_path(target)?
             }
-
-            let assets = ProjectAssets::new(
-                script_path,
-                wasm_modules,
-                kv_namespaces.to_vec(),
-                text_blobs,
-                plain_texts,
-            )?;
-
-            build_form(&assets, session_config)
-        }
-    }
+            TargetType::Webpack => {
+                log::info!("webpack project detected. Publishing...");
+                // TODO: https://github.com/cloudflare/wrangler/issues/850
+                let build_dir = target.build_dir()?;
+                let bundle = wranglerjs::Bundle::new(&build_dir);
+
+                if bundle.has_wasm() {
+                    let path = bundle.wasm_path();
+                    let binding = bundle.get_wasm_binding();
+                    let wasm_module = WasmModule::new(path, binding)?;
+                    wasm_modules.push(wasm_module);
+                }
+                bundle.script_path()
+            }
+        },
+    };
+
+    let mut assets = ProjectAssets::new(
+        script_path,
+        wasm_modules,
+        kv_namespaces.to_vec(),
+        text_blobs,
+        plain_texts,
+        json_vars,
+        target.mtls_certificates.to_vec(),
+        send_emails,
+        target.tail_consumers.clone(),
+    )?;
+    assets.annotations = target.annotations.clone();
+    assets.module_files = match &artifact {
+        Some(artifact) => artifact.module_files()?,
+        None => module_file::from_target(target)?,
+    };
+    Ok(assets)
 }
 
 fn get_asset_manifest_blob(asset_manifest: AssetManifest) -> Result<String, failure::Error> {
@@ -131,13 +254,14 @@ fn get_asset_manifest_blob(asset_manifest: AssetManifest) -> Result<String, fail
 fn build_form(
     assets: &ProjectAssets,
     session_config: Option<serde_json::Value>,
+    progress_bar: Option<&Progress>,
 ) -> Result<Form, failure::Error> {
     let mut form = Form::new();
 
     // The preview service in particular streams the request form, and requires that the
     // "metadata" part be set first, so this order is important.
     form = add_metadata(form, assets)?;
-    form = add_files(form, assets)?;
+    form = add_files(form, assets, progress_bar)?;
     if let Some(session_config) = session_config {
         form = add_session_config(form, session_config)?
     }
@@ -148,11 +272,29 @@ fn build_form(
     Ok(form)
 }
 
-fn add_files(mut form: Form, assets: &ProjectAssets) -> Result<Form, failure::Error> {
-    form = form.file(assets.script_name(), assets.script_path())?;
+fn add_files(
+    mut form: Form,
+    assets: &ProjectAssets,
+    progress_bar: Option<&Progress>,
+) -> Result<Form, failure::Error> {
+    form = add_file(
+        form,
+        assets.script_name(),
+        assets.script_path(),
+        progress_bar,
+    )?;
 
     for wasm_module in &assets.wasm_modules {
-        form = form.file(wasm_module.filename(), wasm_module.path())?;
+        form = add_file(
+            form,
+            wasm_module.filename(),
+            wasm_module.path(),
+            progress_bar,
+        )?;
+    }
+
+    for module_file in &assets.module_files {
+        form = add_file(form, module_file.name(), module_file.path(), progress_bar)?;
     }
 
     for text_blob in &assets.text_blobs {
@@ -166,11 +308,53 @@ fn add_files(mut form: Form, assets: &ProjectAssets) -> Result<Form, failure::Er
     Ok(form)
 }
 
-fn add_metadata(mut form: Form, assets: &ProjectAssets) -> Result<Form, failure::Error> {
-    let metadata_json = serde_json::json!(&Metadata {
+// Files are read while the request is sent rather than up front, like `Form::file` does.
+fn add_file(
+    form: Form,
+    name: String,
+    path: PathBuf,
+    progress_bar: Option<&Progress>,
+) -> Result<Form, failure::Error> {
+    let progress_bar = progress_bar.cloned();
+    let file = File::open(&path)?;
+    let length = file.metadata()?.len();
+    let mime = mime_guess::from_path(&path).first_or_octet_stream();
+    let mut part = Part::reader_with_length(ProgressReader { file, progress_bar }, length)
+        .mime_str(mime.as_ref())?;
+    if let Some(file_name) = path.file_name() {
+        part = part.file_name(file_name.to_string_lossy().to_string());
+    }
+    Ok(form.part(name, part))
+}
+
+// Counts the bytes of a file as they're sent
+struct ProgressReader {
+    file: File,
+    progress_bar: Option<Progress>,
+}
+
+impl Read for ProgressReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.file.read(buf)?;
+        if let Some(progress_bar) = &self.progress_bar {
+            progress_bar.inc(read as u64);
+        }
+        summary::uploaded(read as u64);
+        Ok(read)
+    }
+}
+
+fn metadata(assets: &ProjectAssets) -> serde_json::Value {
+    serde_json::json!(&Metadata {
         body_part: assets.script_name(),
         bindings: assets.bindings(),
-    });
+        tail_consumers: assets.tail_consumers.clone(),
+        annotations: assets.annotations.clone(),
+    })
+}
+
+fn add_metadata(mut form: Form, assets: &ProjectAssets) -> Result<Form, failure::Error> {
+    let metadata_json = metadata(assets);
 
     let metadata = Part::text((metadata_json).to_string())
         .file_name("metadata.json")
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+
+use super::binding::Binding;
+use crate::settings::toml::{ModuleType, Rule, Target};
+
+/// A file attached to the upload by a `[[rules]]` entry. It is bound under a name made
+/// from its path, e.g. `templates/index.html` as `templates_index_html`, and `404.html`
+/// as `_404_html` so the name is a JavaScript identifier.
+#[derive(Debug)]
+pub struct ModuleFile {
+    path: PathBuf,
+    binding: String,
+    module_type: ModuleType,
+}
+
+impl ModuleFile {
+    pub fn new(path: PathBuf, binding: String, module_type: ModuleType) -> Self {
+        ModuleFile {
+            path,
+            binding,
+            module_type,
+        }
+    }
+
+    // the binding is also the name of the file's part in the upload form, as file names
+    // can repeat across directories
+    pub fn binding(&self) -> Binding {
+        let (name, part) = (self.binding.clone(), self.binding.clone());
+        match self.module_type {
+            ModuleType::Text => Binding::new_text_blob(name, part),
+            ModuleType::Data => Binding::new_data_blob(name, part),
+            ModuleType::CompiledWasm => Binding::new_wasm_module(name, part),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.binding.clone()
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    pub fn module_type(&self) -> ModuleType {
+        self.module_type
+    }
+}
+
+/// The files the target's `[[rules]]` attach to its upload.
+pub fn from_target(target: &Target) -> Result<Vec<ModuleFile>, failure::Error> {
+    if target.rules.is_empty() {
+        return Ok(Vec::new());
+    }
+    from_rules(&target.rules, &target.build_dir()?)
+}
+
+/// The files under `dir` that the rules match, outside of node_modules. A file matched
+/// by more than one rule gets the type of the first.
+pub fn from_rules(rules: &[Rule], dir: &Path) -> Result<Vec<ModuleFile>, failure::Error> {
+    let mut files: Vec<ModuleFile> = Vec::new();
+    let mut bound: HashMap<String, PathBuf> = HashMap::new();
+
+    for rule in rules {
+        let mut globs = OverrideBuilder::new(dir);
+        for glob in &rule.globs {
+            globs.add(glob)?;
+        }
+        globs.add("!node_modules/")?;
+        let walker = WalkBuilder::new(dir)
+            .standard_filters(false)
+            .overrides(globs.build()?)
+            .build();
+
+        for entry in walker {
+            let entry = entry?;
+            if !entry
+                .file_type()
+                .map_or(false, |file_type| file_type.is_file())
+            {
+                continue;
+            }
+            let path = entry.into_path();
+            if files.iter().any(|file| file.path == path) {
+                continue;
+            }
+
+            let binding = binding_name(path.strip_prefix(dir).unwrap_or(&path));
+            if let Some(other) = bound.insert(binding.clone(), path.clone()) {
+                failure::bail!(
+                    "{} and {} would both be bound as {}. Rename one of them, or narrow the globs of your [[rules]].",
+                    other.display(),
+                    path.display(),
+                    binding
+                )
+            }
+            log::info!("Attaching {} as {}", path.display(), binding);
+            files.push(ModuleFile {
+                path,
+                binding,
+                module_type: rule.module_type,
+            });
+        }
+    }
+
+    files.sort_by(|a, b| a.binding.cmp(&b.binding));
+    Ok(files)
+}
+
+fn binding_name(path: &Path) -> String {
+    let name: String = path
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn it_attaches_files_by_the_first_matching_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("templates")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        for file in &[
+            "templates/index.html",
+            "templates/raw.html",
+            "404.html",
+            "logo.png",
+            "node_modules/pkg/readme.html",
+        ] {
+            fs::write(dir.path().join(file), "x").unwrap();
+        }
+
+        let rules = vec![
+            Rule {
+                module_type: ModuleType::Data,
+                globs: vec!["**/raw.html".to_string(), "*.png".to_string()],
+            },
+            Rule {
+                module_type: ModuleType::Text,
+                globs: vec!["**/*.html".to_string()],
+            },
+        ];
+        let files = from_rules(&rules, dir.path()).unwrap();
+        let bound: Vec<(String, ModuleType)> = files
+            .iter()
+            .map(|file| (file.name(), file.module_type))
+            .collect();
+        assert_eq!(
+            bound,
+            vec![
+                ("_404_html".to_string(), ModuleType::Text),
+                ("logo_png".to_string(), ModuleType::Data),
+                ("templates_index_html".to_string(), ModuleType::Text),
+                ("templates_raw_html".to_string(), ModuleType::Data),
+            ]
+        );
+    }
+}
@@ -4,11 +4,14 @@ use failure::format_err;
 
 use super::binding::Binding;
 use super::filename_from_path;
+use super::json_var::JsonVar;
+use super::module_file::ModuleFile;
 use super::plain_text::PlainText;
 use super::text_blob::TextBlob;
 use super::wasm_module::WasmModule;
 
-use crate::settings::toml::KvNamespace;
+use crate::settings::metadata::Annotations;
+use crate::settings::toml::{KvNamespace, MtlsCertificate, SendEmail, TailConsumer};
 
 #[derive(Debug)]
 pub struct ProjectAssets {
@@ -18,6 +21,13 @@ pub struct ProjectAssets {
     pub kv_namespaces: Vec<KvNamespace>,
     pub text_blobs: Vec<TextBlob>,
     pub plain_texts: Vec<PlainText>,
+    pub json_vars: Vec<JsonVar>,
+    pub mtls_certificates: Vec<MtlsCertificate>,
+    pub send_emails: Vec<SendEmail>,
+    pub tail_consumers: Vec<TailConsumer>,
+    pub annotations: Annotations,
+    /// Files attached by `[[rules]]`
+    pub module_files: Vec<ModuleFile>,
 }
 
 impl ProjectAssets {
@@ -27,6 +37,10 @@ impl ProjectAssets {
         kv_namespaces: Vec<KvNamespace>,
         text_blobs: Vec<TextBlob>,
         plain_texts: Vec<PlainText>,
+        json_vars: Vec<JsonVar>,
+        mtls_certificates: Vec<MtlsCertificate>,
+        send_emails: Vec<SendEmail>,
+        tail_consumers: Vec<TailConsumer>,
     ) -> Result<Self, failure::Error> {
         let script_name = filename_from_path(&script_path).ok_or_else(|| {
             format_err!("filename should not be empty: {}", script_path.display())
@@ -39,6 +53,12 @@ impl ProjectAssets {
             kv_namespaces,
             text_blobs,
             plain_texts,
+            json_vars,
+            mtls_certificates,
+            send_emails,
+            tail_consumers,
+            annotations: Annotations::default(),
+            module_files: Vec::new(),
         })
     }
 
@@ -61,6 +81,22 @@ impl ProjectAssets {
             let binding = plain_text.binding();
             bindings.push(binding);
         }
+        for json_var in &self.json_vars {
+            let binding = json_var.binding();
+            bindings.push(binding);
+        }
+        for certificate in &self.mtls_certificates {
+            let binding = certificate.binding();
+            bindings.push(binding);
+        }
+        for send_email in &self.send_emails {
+            let binding = send_email.binding();
+            bindings.push(binding);
+        }
+        for module_file in &self.module_files {
+            let binding = module_file.binding();
+            bindings.push(binding);
+        }
 
         bindings
     }
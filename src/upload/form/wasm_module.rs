@@ -30,6 +30,10 @@ impl WasmModule {
         Binding::new_wasm_module(self.binding.clone(), self.filename.clone())
     }
 
+    pub fn binding_name(&self) -> String {
+        self.binding.clone()
+    }
+
     pub fn path(&self) -> PathBuf {
         self.path.clone()
     }
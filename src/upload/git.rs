@@ -0,0 +1,29 @@
+use std::process::Command;
+
+use crate::settings::metadata::Annotations;
+
+/// Records the commit, branch and uncommitted changes of the git checkout in the current
+/// directory, so the API can tell which code a version was uploaded from. Outside of a
+/// git repository, or without git installed, nothing is recorded.
+pub fn annotate(annotations: &mut Annotations) {
+    let commit = match git(&["rev-parse", "HEAD"]) {
+        Some(commit) => commit,
+        None => {
+            log::info!("Not recording git metadata, this isn't a git checkout");
+            return;
+        }
+    };
+    annotations.git_commit = Some(commit);
+    // a detached HEAD, like most CI checkouts, has no branch
+    annotations.git_branch = git(&["rev-parse", "--abbrev-ref", "HEAD"]).filter(|b| b != "HEAD");
+    annotations.git_dirty = git(&["status", "--porcelain"]).map(|status| !status.is_empty());
+}
+
+// the trimmed stdout of a git command that succeeded
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -1,4 +1,6 @@
+mod entry_point;
 pub mod form;
+pub mod git;
 mod krate;
 pub mod package;
 
@@ -6,6 +8,8 @@ pub use package::Package;
 
 use reqwest::blocking::Client;
 
+use crate::error::WranglerError;
+use crate::http::{self, Traced};
 use crate::settings::toml::Target;
 use crate::sites::AssetManifest;
 
@@ -14,23 +18,29 @@ pub fn script(
     target: &Target,
     asset_manifest: Option<AssetManifest>,
 ) -> Result<(), failure::Error> {
-    let worker_addr = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
-        target.account_id, target.name,
-    );
+    let worker_addr = match &target.dispatch_namespace {
+        Some(namespace) => {
+            http::endpoints::dispatch_namespace_script(&target.account_id, namespace, &target.name)
+        }
+        None => http::endpoints::script(&target.account_id, &target.name),
+    };
 
-    let script_upload_form = form::build(target, asset_manifest, None)?;
+    let (script_upload_form, progress_bar) = form::build_with_progress(target, asset_manifest)?;
 
     let res = client
         .put(&worker_addr)
         .multipart(script_upload_form)
-        .send()?;
+        .send_traced();
+    if let Some(progress_bar) = progress_bar {
+        progress_bar.finish_and_clear();
+    }
+    let res = res?;
 
     let res_status = res.status();
 
     if !res_status.is_success() {
         let res_text = res.text()?;
-        failure::bail!(error_msg(res_status, res_text))
+        return Err(WranglerError::Api(error_msg(res_status, res_text)).into());
     }
 
     Ok(())
@@ -5,23 +5,44 @@ use std::sync::mpsc;
 use std::thread;
 use std::time::SystemTime;
 
-use crate::settings::get_wrangler_home_dir;
+use crate::http::network;
+use crate::settings::{env_flag, get_wrangler_home_dir};
 
 use reqwest::header::USER_AGENT;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
 const ONE_DAY: u64 = 60 * 60 * 24;
+// a newer major version, or this many minor versions, is far enough behind to warn about
+const MINOR_VERSIONS_BEHIND: u64 = 3;
 
-pub fn background_check_for_updates() -> mpsc::Receiver<Version> {
+/// A newer version of wrangler than the one running.
+pub struct Update {
+    pub latest: Version,
+    /// The running version is far enough behind that features, or the API endpoints it
+    /// uses, may no longer work.
+    pub far_behind: bool,
+}
+
+/// Checks for a newer version of wrangler on a thread of its own, asking crates.io at
+/// most once a day. Set `WRANGLER_NO_UPDATE_CHECK=1` to turn the check off.
+pub fn background_check_for_updates() -> mpsc::Receiver<Update> {
     let (sender, receiver) = mpsc::channel();
+    if env_flag("WRANGLER_NO_UPDATE_CHECK") {
+        return receiver;
+    }
 
     let _detached_thread = thread::spawn(move || match check_wrangler_versions() {
         Ok(wrangler_versions) => {
             // If the wrangler version has not been checked within the last day and the versions
-            // are different, print out an update message
-            if wrangler_versions.is_outdated() {
-                let _ = sender.send(wrangler_versions.latest);
+            // are different, print out an update message. One that is far behind is
+            // warned about every time.
+            let far_behind = wrangler_versions.is_far_behind();
+            if wrangler_versions.is_outdated() || far_behind {
+                let _ = sender.send(Update {
+                    latest: wrangler_versions.latest,
+                    far_behind,
+                });
             }
         }
         Err(e) => log::debug!("could not determine if update is needed:\n{}", e),
@@ -46,6 +67,12 @@ impl WranglerVersion {
     pub fn is_outdated(&self) -> bool {
         !self.checked && (self.current != self.latest)
     }
+
+    pub fn is_far_behind(&self) -> bool {
+        self.latest.major > self.current.major
+            || (self.latest.major == self.current.major
+                && self.latest.minor >= self.current.minor + MINOR_VERSIONS_BEHIND)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -128,6 +155,9 @@ fn get_latest_version(
 }
 
 fn get_latest_version_from_api(installed_version: &str) -> Result<Version, failure::Error> {
+    if network::offline() {
+        failure::bail!("not checking for updates while offline");
+    }
     let url = "https://crates.io/api/v1/crates/wrangler";
     let user_agent = format!(
         "wrangler/{} ({})",
@@ -155,3 +185,23 @@ struct ApiResponse {
 struct CrateInformation {
     max_version: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_warns_when_far_behind() {
+        let version = |current: &str, latest: &str| WranglerVersion {
+            current: Version::parse(current).unwrap(),
+            latest: Version::parse(latest).unwrap(),
+            checked: true,
+        };
+
+        assert!(!version("1.19.0", "1.19.0").is_far_behind());
+        assert!(!version("1.17.1", "1.19.0").is_far_behind());
+        assert!(version("1.16.0", "1.19.0").is_far_behind());
+        assert!(version("1.19.0", "2.0.0").is_far_behind());
+        assert!(!version("2.0.0", "1.19.0").is_far_behind());
+    }
+}
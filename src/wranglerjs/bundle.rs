@@ -85,8 +85,11 @@ mod tests {
         let out = create_temp_dir("it_writes_the_bundle_script");
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
+            warnings: vec![],
             script: "foo".to_string(),
             wasm: None,
+            unminified_size: None,
+            modules: vec![],
         };
         let bundle = Bundle::new_at(out.clone());
 
@@ -102,8 +105,11 @@ mod tests {
         let out = create_temp_dir("it_writes_the_bundle_wasm");
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
+            warnings: vec![],
             script: "".to_string(),
             wasm: Some("abc".to_string()),
+            unminified_size: None,
+            modules: vec![],
         };
         let bundle = Bundle::new_at(out.clone());
 
@@ -117,9 +123,12 @@ mod tests {
     #[test]
     fn it_has_errors() {
         let wranglerjs_output = WranglerjsOutput {
-            errors: vec!["a".to_string(), "b".to_string()],
+            errors: vec!["a".to_string().into(), "b".to_string().into()],
+            warnings: vec![],
             script: "".to_string(),
             wasm: None,
+            unminified_size: None,
+            modules: vec![],
         };
         assert!(wranglerjs_output.has_errors());
         assert!(wranglerjs_output.get_errors() == "a\nb");
@@ -1,5 +1,4 @@
 mod bundle;
-mod guarded_command;
 pub mod output;
 
 pub use bundle::Bundle;
@@ -10,6 +9,7 @@ use std::fs::File;
 use std::iter;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Sender};
 use std::thread;
 use std::time::Duration;
@@ -21,13 +21,34 @@ use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use semver::Version;
 
+use crate::build;
+use crate::http::network;
 use crate::install;
-use crate::settings::toml::Target;
+use crate::interrupt;
+use crate::settings::toml::{Build, Target};
 use crate::terminal::message::{Message, StdErr, StdOut};
 use crate::upload::package::Package;
 use crate::watch::{wait_for_changes, COOLDOWN_PERIOD};
 
-use guarded_command::GuardedCommand;
+pub const TOOL_NAME: &str = "wranglerjs";
+pub const TOOL_AUTHOR: &str = "cloudflare";
+const DEBUG_DIR: &str = ".wrangler/debug";
+
+static KEEP_TEMP: AtomicBool = AtomicBool::new(false);
+
+/// Turns on `--keep-temp`: builds keep the files {wranglerjs} produces, and the webpack
+/// configuration and stats it used, in `.wrangler/debug/`.
+pub fn keep_temp_files() {
+    KEEP_TEMP.store(true, Ordering::SeqCst);
+}
+
+fn debug_dir() -> Option<PathBuf> {
+    if KEEP_TEMP.load(Ordering::SeqCst) {
+        Some(PathBuf::from(DEBUG_DIR))
+    } else {
+        None
+    }
+}
 
 // Run the underlying {wranglerjs} executable.
 
@@ -38,13 +59,24 @@ use guarded_command::GuardedCommand;
 pub fn run_build(target: &Target) -> Result<WranglerjsOutput, failure::Error> {
     let (mut command, temp_file, bundle) = setup_build(target)?;
 
-    log::info!("Running {:?}", command);
-
-    let status = command.status()?;
+    let status = build::run_command(&mut command, target.build.timeout())?;
+
+    // the files are kept for failed builds too, so a failure to keep them must not hide
+    // how the build went
+    if let Some(debug_dir) = debug_dir() {
+        if let Err(e) = keep_debug_files(&debug_dir, &temp_file) {
+            StdErr::warn(&format!(
+                "Could not keep the build files in {}: {}",
+                debug_dir.display(),
+                e
+            ));
+        }
+    }
 
     if status.success() {
         let output = fs::read_to_string(&temp_file).expect("could not retrieve output");
-        fs::remove_file(temp_file)?;
+        fs::remove_file(&temp_file)?;
+        interrupt::forget_temp_file(&temp_file);
         let wranglerjs_output: WranglerjsOutput =
             serde_json::from_str(&output).expect("could not parse wranglerjs output");
 
@@ -67,7 +99,8 @@ pub fn run_build_and_watch(target: &Target, tx: Option<Sender<()>>) -> Result<()
 
     // Turbofish the result of the closure so we can use ?
     thread::spawn::<_, Result<(), failure::Error>>(move || {
-        let _command_guard = GuardedCommand::spawn(command);
+        let _command_guard =
+            interrupt::spawn(&mut command).expect("failed to execute child command");
 
         let (watcher_tx, watcher_rx) = channel();
         let mut watcher = notify::watcher(watcher_tx, Duration::from_secs(1))?;
@@ -120,13 +153,33 @@ pub fn run_build_and_watch(target: &Target, tx: Option<Sender<()>>) -> Result<()
     Ok(())
 }
 
+// copies the output of {wranglerjs} next to the configuration and stats it wrote to the
+// debug directory, and lists them
+fn keep_debug_files(debug_dir: &Path, temp_file: &Path) -> Result<(), failure::Error> {
+    let output = debug_dir.join("wranglerjs-output.json");
+    fs::copy(temp_file, &output)?;
+
+    let mut kept = vec![output];
+    for name in &["webpack.config.json", "webpack-stats.json"] {
+        let path = debug_dir.join(name);
+        if path.exists() {
+            kept.push(path);
+        }
+    }
+    let kept: Vec<String> = kept.iter().map(|path| path.display().to_string()).collect();
+    StdErr::info(&format!("Kept the build files:\n{}", kept.join("\n")));
+    Ok(())
+}
+
 fn write_wranglerjs_output(
     bundle: &Bundle,
     output: &WranglerjsOutput,
     custom_webpack: bool,
 ) -> Result<(), failure::Error> {
     if output.has_errors() {
-        StdErr::user_error(output.get_errors().as_str());
+        for error in &output.errors {
+            StdErr::user_error_at(error.github_location().as_ref(), &error.render());
+        }
         if custom_webpack {
             failure::bail!(
             "webpack returned an error. Try configuring `entry` in your webpack config relative to the current working directory, or setting `context = __dirname` in your webpack config."
@@ -138,6 +191,10 @@ fn write_wranglerjs_output(
         }
     }
 
+    for warning in &output.warnings {
+        StdErr::warn_at(warning.github_location().as_ref(), &warning.render());
+    }
+
     bundle.write(output)?;
 
     log::info!(
@@ -152,6 +209,7 @@ fn setup_build(target: &Target) -> Result<(Command, PathBuf, Bundle), failure::E
     for tool in &["node", "npm"] {
         env_dep_installed(tool)?;
     }
+    check_node_version(&target.build)?;
 
     let build_dir = target.build_dir()?;
 
@@ -163,22 +221,41 @@ fn setup_build(target: &Target) -> Result<(Command, PathBuf, Bundle), failure::E
 
     let node = which::which("node").unwrap();
     let mut command = Command::new(node);
-    let wranglerjs_path = install().expect("could not install wranglerjs");
+    let wranglerjs_path = install(target.tools.wranglerjs_version()?)?;
     command.arg(wranglerjs_path);
 
     // create a temp file for IPC with the wranglerjs process
     let mut temp_file = env::temp_dir();
     temp_file.push(format!(".wranglerjs_output{}", random_chars(5)));
     File::create(temp_file.clone())?;
+    interrupt::remove_on_interrupt(&temp_file);
 
     command.arg(format!(
         "--output-file={}",
         temp_file.to_str().unwrap().to_string()
     ));
 
+    if let Some(debug_dir) = debug_dir() {
+        fs::create_dir_all(&debug_dir)?;
+        let debug_dir = fs::canonicalize(&debug_dir)?;
+        command.arg(format!("--debug-dir={}", debug_dir.display()));
+    }
+
     let bundle = Bundle::new(&build_dir);
 
     command.arg(format!("--wasm-binding={}", bundle.get_wasm_binding()));
+    if target.node_compat {
+        command.arg("--node-compat=1");
+    }
+    if let Some(minify) = target.minify {
+        command.arg(format!("--minify={}", minify as u8));
+    }
+    if target.keep_names {
+        command.arg("--keep-names=1");
+    }
+    if target.drop_console {
+        command.arg("--drop-console=1");
+    }
 
     let custom_webpack_config_path = match &target.webpack_config {
         Some(webpack_config) => Some(PathBuf::from(&webpack_config)),
@@ -226,7 +303,7 @@ fn build_with_default_webpack(
 
 // Run {npm install} in the specified directory. Skips the install if a
 // {node_modules} is found in the directory.
-fn run_npm_install(dir: &PathBuf) -> Result<(), failure::Error> {
+pub fn run_npm_install(dir: &PathBuf) -> Result<(), failure::Error> {
     let flock_path = dir.join(&".install.lock");
     let flock = File::create(&flock_path)?;
     // avoid running multiple {npm install} at the same time (eg. in tests)
@@ -236,9 +313,13 @@ fn run_npm_install(dir: &PathBuf) -> Result<(), failure::Error> {
         let mut command = build_npm_command();
         command.current_dir(dir.clone());
         command.arg("install");
+        // only from npm's cache
+        if network::offline() {
+            command.arg("--offline");
+        }
         log::info!("Running {:?} in directory {:?}", command, dir);
 
-        let status = command.status()?;
+        let status = interrupt::status(&mut command)?;
 
         if !status.success() {
             failure::bail!("failed to execute `{:?}`: exited with {}", command, status)
@@ -281,6 +362,37 @@ fn env_dep_installed(tool: &str) -> Result<(), failure::Error> {
     Ok(())
 }
 
+// webpack fails in obscure ways on Node.js versions it doesn't support, so the version
+// is checked before the build starts
+fn check_node_version(build: &Build) -> Result<(), failure::Error> {
+    let output = Command::new(which::which("node")?)
+        .arg("--version")
+        .output()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let version = match Version::parse(output.trim().trim_start_matches('v')) {
+        Ok(version) => version,
+        Err(_) => {
+            log::info!("could not parse node version {:?}", output);
+            return Ok(());
+        }
+    };
+
+    match build.node_version()? {
+        Some(req) if !req.matches(&version) => failure::bail!(
+            "Node.js {} doesn't match node_version = \"{}\" in the [build] section of your configuration file. Switch to a matching version, e.g. with `nvm install`, or change node_version.",
+            version,
+            build.node_version.as_deref().unwrap_or_default()
+        ),
+        // webpack 4 uses a hash OpenSSL 3 dropped
+        None if version.major >= 17 => StdErr::warn(&format!(
+            "Node.js {} may fail to build with webpack 4 with \"error:0308010C:digital envelope routines::unsupported\". If it does, set NODE_OPTIONS=--openssl-legacy-provider or use Node.js 16, and pin it with node_version in [build].",
+            version
+        )),
+        _ => (),
+    }
+    Ok(())
+}
+
 // Use the env-provided source directory and remove the quotes
 fn get_source_dir() -> PathBuf {
     let mut dir = install::target::SOURCE_DIR.to_string();
@@ -290,18 +402,14 @@ fn get_source_dir() -> PathBuf {
 }
 
 // Install {wranglerjs} from our GitHub releases
-fn install() -> Result<PathBuf, failure::Error> {
+fn install(version: Version) -> Result<PathBuf, failure::Error> {
     let wranglerjs_path = if install::target::DEBUG {
         let source_path = get_source_dir();
         let wranglerjs_path = source_path.join("wranglerjs");
         log::info!("wranglerjs at: {:?}", wranglerjs_path);
         wranglerjs_path
     } else {
-        let tool_name = "wranglerjs";
-        let tool_author = "cloudflare";
-        let is_binary = false;
-        let version = Version::parse(env!("CARGO_PKG_VERSION"))?;
-        let wranglerjs_path = install::install(tool_name, tool_author, is_binary, version)?;
+        let wranglerjs_path = install::install(TOOL_NAME, TOOL_AUTHOR, false, version)?;
         log::info!("wranglerjs downloaded at: {:?}", wranglerjs_path.path());
         wranglerjs_path.path()
     };
@@ -1,8 +1,12 @@
-use crate::terminal::emoji;
+use crate::terminal::github::Location;
+use crate::terminal::{emoji, styles};
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
+use lazy_static::lazy_static;
 use number_prefix::NumberPrefix;
-use serde::Deserialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::io::prelude::*;
 
 // This structure represents the communication between {wranglerjs} and
@@ -13,7 +17,126 @@ pub struct WranglerjsOutput {
     pub wasm: Option<String>,
     pub script: String,
     // Errors emitted by {wranglerjs}, if any
-    pub errors: Vec<String>,
+    pub errors: Vec<Diagnostic>,
+    // Warnings emitted by {wranglerjs}, older versions don't send them
+    #[serde(default)]
+    pub warnings: Vec<Diagnostic>,
+    // Bytes in the script before it was minified, when it was
+    #[serde(default)]
+    pub unminified_size: Option<u64>,
+    // The files bundled into the script, older versions don't send them
+    #[serde(default)]
+    pub modules: Vec<String>,
+}
+
+/// A webpack error or warning, with the place in the source it points to when webpack
+/// knows it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "RawDiagnostic")]
+pub struct Diagnostic {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u64>,
+    pub message: String,
+}
+
+// older versions of {wranglerjs} send webpack's formatted text instead of an object
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDiagnostic {
+    Text(String),
+    Structured {
+        file: Option<String>,
+        line: Option<u64>,
+        column: Option<u64>,
+        message: String,
+    },
+}
+
+impl From<RawDiagnostic> for Diagnostic {
+    fn from(raw: RawDiagnostic) -> Self {
+        match raw {
+            RawDiagnostic::Text(text) => Diagnostic::from(text),
+            RawDiagnostic::Structured {
+                file,
+                line,
+                column,
+                message,
+            } => Diagnostic {
+                file,
+                line,
+                column,
+                message,
+            },
+        }
+    }
+}
+
+impl From<String> for Diagnostic {
+    // webpack puts the module, and the position in it, on the first line:
+    // "./src/index.js 12:4-10\nModule parse failed: Unexpected token"
+    fn from(text: String) -> Self {
+        lazy_static! {
+            static ref LOCATION: Regex =
+                Regex::new(r"^(\S*[./]\S*)(?: (\d+):(\d+)(?:-\d+)?)?$").unwrap();
+        }
+        let mut lines = text.splitn(2, '\n');
+        let first = lines.next().unwrap_or_default();
+        if let (Some(caps), Some(message)) = (LOCATION.captures(first), lines.next()) {
+            return Diagnostic {
+                file: Some(caps[1].to_string()),
+                line: caps.get(2).and_then(|line| line.as_str().parse().ok()),
+                column: caps.get(3).and_then(|column| column.as_str().parse().ok()),
+                message: message.trim().to_string(),
+            };
+        }
+        Diagnostic {
+            file: None,
+            line: None,
+            column: None,
+            message: text,
+        }
+    }
+}
+
+impl Diagnostic {
+    fn location(&self) -> Option<String> {
+        let file = self.file.as_ref()?;
+        Some(match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", file, line, column),
+            (Some(line), None) => format!("{}:{}", file, line),
+            _ => file.to_string(),
+        })
+    }
+
+    /// Where the diagnostic points, for GitHub Actions to annotate the line
+    pub fn github_location(&self) -> Option<Location> {
+        Some(Location {
+            file: self.file.clone()?,
+            line: self.line,
+            column: self.column,
+        })
+    }
+
+    /// The diagnostic for the terminal, with its location highlighted
+    pub fn render(&self) -> String {
+        match self.location() {
+            Some(location) => format!("{} {}", styles::highlight(location), self.message),
+            None => self.message.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location() {
+            Some(location) => write!(f, "{} {}", location, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
 }
 
 impl WranglerjsOutput {
@@ -22,7 +145,11 @@ impl WranglerjsOutput {
     }
 
     pub fn get_errors(&self) -> String {
-        self.errors.join("\n")
+        self.errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
     fn project_size_bytes(&self) -> u64 {
@@ -72,6 +199,24 @@ impl WranglerjsOutput {
     pub fn project_size(&self) -> String {
         Self::project_size_message(self.project_size_bytes())
     }
+
+    /// What minifying did to the size of the script, e.g. "Minified the script from
+    /// 120 KiB to 45 KiB", when it was minified.
+    pub fn minified_size(&self) -> Option<String> {
+        let before = self.unminified_size?;
+        Some(format!(
+            "Minified the script from {} to {}",
+            human_size(before),
+            human_size(self.script.len() as u64)
+        ))
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    match NumberPrefix::binary(bytes as f64) {
+        NumberPrefix::Standalone(bytes) => format!("{} bytes", bytes),
+        NumberPrefix::Prefixed(prefix, n) => format!("{:.0} {}B", n, prefix),
+    }
 }
 
 #[cfg(test)]
@@ -88,23 +233,83 @@ mod tests {
         assert!(WranglerjsOutput::project_size_message((1 << 20) - 4096).contains("reaching"));
     }
 
+    #[test]
+    fn it_reads_structured_and_text_diagnostics() {
+        let output: WranglerjsOutput = serde_json::from_str(
+            r#"{
+                "script": "",
+                "errors": [
+                    "./src/index.js 12:4-10\nModule parse failed: Unexpected token",
+                    "./src/index.js\nModule not found: Error: Can't resolve 'lodash'",
+                    "Entry module not found"
+                ],
+                "warnings": [{"file": "/app/src/util.js", "line": 3, "column": 1, "message": "export 'x' was not found"}]
+            }"#,
+        )
+        .unwrap();
+
+        let location = |d: &Diagnostic| (d.file.clone(), d.line, d.column);
+        assert_eq!(
+            location(&output.errors[0]),
+            (Some("./src/index.js".to_string()), Some(12), Some(4))
+        );
+        assert_eq!(
+            output.errors[0].message,
+            "Module parse failed: Unexpected token"
+        );
+        assert_eq!(
+            location(&output.errors[1]),
+            (Some("./src/index.js".to_string()), None, None)
+        );
+        assert_eq!(location(&output.errors[2]), (None, None, None));
+        assert_eq!(
+            output.warnings[0].to_string(),
+            "/app/src/util.js:3:1 export 'x' was not found"
+        );
+    }
+
     #[test]
     fn it_returns_project_size_with_wasm() {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
+            warnings: vec![],
             script: "abcdefg".to_string(),
             wasm: Some("123456".to_string()),
+            unminified_size: None,
+            modules: vec![],
         };
 
         assert_eq!(wranglerjs_output.project_size_bytes(), 21);
     }
 
+    #[test]
+    fn it_reports_what_minifying_saved() {
+        let mut wranglerjs_output = WranglerjsOutput {
+            errors: vec![],
+            warnings: vec![],
+            script: "a".repeat(2048),
+            wasm: None,
+            unminified_size: Some(6 * 1024),
+            modules: vec![],
+        };
+        assert_eq!(
+            wranglerjs_output.minified_size().unwrap(),
+            "Minified the script from 6 KiB to 2 KiB"
+        );
+
+        wranglerjs_output.unminified_size = None;
+        assert_eq!(wranglerjs_output.minified_size(), None);
+    }
+
     #[test]
     fn it_returns_project_size_without_wasm() {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
+            warnings: vec![],
             script: "abcdefg".to_string(),
             wasm: None,
+            unminified_size: None,
+            modules: vec![],
         };
 
         assert_eq!(wranglerjs_output.project_size_bytes(), 15);
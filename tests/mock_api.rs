@@ -0,0 +1,41 @@
+use std::process::Command;
+use std::str;
+
+use assert_cmd::prelude::*;
+use serde_json::json;
+use tempfile::TempDir;
+use wrangler::fixtures::{Fixture, MockApi, WranglerToml};
+
+const ACCOUNT_ID: &str = "f2f0c05d1bb5e4b71ad3c36fd1ba1e27";
+
+#[test]
+fn it_lists_kv_namespaces_from_the_api_base_url() {
+    let api = MockApi::start();
+    api.respond_with_result(
+        "GET",
+        &format!("/accounts/{}/storage/kv/namespaces", ACCOUNT_ID),
+        json!([{ "id": "0f2ac74b498b48028cb68387c421e279", "title": "test-mock-api-CACHE" }]),
+    );
+
+    let fixture = Fixture::new();
+    let mut wrangler_toml = WranglerToml::javascript("test-mock-api");
+    wrangler_toml.account_id = Some(ACCOUNT_ID);
+    fixture.create_wrangler_toml(wrangler_toml);
+    let wrangler_home = TempDir::new().unwrap();
+
+    let mut list = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    list.current_dir(fixture.get_path())
+        .env("WRANGLER_HOME", wrangler_home.path())
+        .env("WRANGLER_API_BASE_URL", api.url())
+        .env("CF_API_TOKEN", "apitokenthisissecretandlong")
+        .arg("kv:namespace")
+        .arg("list");
+    let output = list.output().expect("failed to run wrangler");
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.contains("test-mock-api-CACHE"));
+    let requests = api.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].query, Some("page=1&per_page=100".to_string()));
+}
@@ -15,6 +15,48 @@ function warn(...msg) {
   console.warn("Warning: " + msg.join(" "));
 }
 
+// Turns a webpack error or warning into a diagnostic wrangler can render, with the
+// module and position it points to when webpack knows them.
+function toDiagnostic(e) {
+  if (typeof e === "string") {
+    return e;
+  }
+  const diagnostic = { message: e.message || String(e) };
+  if (e.module && e.module.resource) {
+    diagnostic.file = e.module.resource;
+  }
+  if (e.loc && e.loc.start) {
+    diagnostic.line = e.loc.start.line;
+    diagnostic.column = e.loc.start.column;
+  }
+  return diagnostic;
+}
+
+// JSON for --debug-dir, which keeps functions, regular expressions and plugin
+// instances readable instead of failing on them
+function toDebugJSON(value) {
+  const seen = new WeakSet();
+  return JSON.stringify(
+    value,
+    (key, v) => {
+      if (typeof v === "function") {
+        return "[Function " + (v.name || "anonymous") + "]";
+      }
+      if (v instanceof RegExp) {
+        return v.toString();
+      }
+      if (typeof v === "object" && v !== null) {
+        if (seen.has(v)) {
+          return "[Circular]";
+        }
+        seen.add(v);
+      }
+      return v;
+    },
+    2
+  );
+}
+
 function filterByExtension(ext) {
   return v => v.indexOf("." + ext) !== -1;
 }
@@ -60,6 +102,32 @@ function filterByExtension(ext) {
   }
   config.target = "webworker";
 
+  // node_compat = true: polyfill the Node APIs that npm packages often assume exist,
+  // on top of any the webpack configuration sets up itself
+  if (args["node-compat"] === "1") {
+    const libs = require("node-libs-browser");
+    config.node = Object.assign(
+      { global: true, process: true, Buffer: true, setImmediate: true },
+      config.node
+    );
+    config.resolve = config.resolve || {};
+    config.resolve.alias = Object.assign(
+      {
+        buffer: libs.buffer,
+        events: libs.events,
+        stream: libs.stream,
+        util: libs.util
+      },
+      config.resolve.alias
+    );
+    config.plugins = (config.plugins || []).concat(
+      new webpack.ProvidePlugin({
+        Buffer: ["buffer", "Buffer"],
+        process: libs.process
+      })
+    );
+  }
+
   // The worker runtime will set the name of the script to `worker.js`,
   // regardless of what's specified in the sourcemap.
   // We can tell webpack to name the generated worker by configuring the output.
@@ -89,12 +157,58 @@ function filterByExtension(ext) {
       "because of requirements from the Workers runtime"
     );
   }
+  // minify, keep_names and drop_console from the configuration file
+  if (args["minify"] !== undefined) {
+    config.optimization = Object.assign({}, config.optimization, {
+      minimize: args["minify"] === "1"
+    });
+  }
+  if (args["keep-names"] === "1" || args["drop-console"] === "1") {
+    const TerserPlugin = require("terser-webpack-plugin");
+    const keepNames = args["keep-names"] === "1";
+    config.optimization = Object.assign({}, config.optimization, {
+      minimizer: [
+        new TerserPlugin({
+          terserOptions: {
+            keep_classnames: keepNames,
+            keep_fnames: keepNames,
+            compress: { drop_console: args["drop-console"] === "1" }
+          }
+        })
+      ]
+    });
+  }
+
   config.output.filename = WEBPACK_OUTPUT_FILENAME;
   config.output.sourceMapFilename = WEBPACK_OUTPUT_SOURCEMAPFILENAME;
 
   const compiler = webpack(config);
   const fullConfig = compiler.options;
 
+  // The size of the script before the minimizer runs, so wrangler can report what
+  // minifying saved. The minimizer taps the same hook at the default stage.
+  let unminifiedSize = null;
+  compiler.hooks.compilation.tap("wrangler", compilation => {
+    compilation.hooks.optimizeChunkAssets.tap(
+      { name: "wrangler", stage: -100 },
+      chunks => {
+        unminifiedSize = 0;
+        chunks.forEach(chunk => {
+          chunk.files.filter(filterByExtension("js")).forEach(file => {
+            unminifiedSize += compilation.assets[file].size();
+          });
+        });
+      }
+    );
+  });
+
+  if (args["debug-dir"] !== undefined) {
+    fs.writeFileSync(
+      join(args["debug-dir"], "webpack.config.json"),
+      toDebugJSON(fullConfig)
+    );
+  }
+
   // Override the {FetchCompileWasmTemplatePlugin} and inject our new runtime.
   const [
     fetchCompileWasmTemplatePlugin
@@ -126,11 +240,16 @@ function filterByExtension(ext) {
 
     if (stats.hash !== lastHash) {
       const assets = stats.compilation.assets;
-      const jsonStats = stats.toJson();
       const bundle = {
         wasm: null,
         script: "",
-        errors: jsonStats.errors
+        errors: stats.compilation.errors.map(toDiagnostic),
+        warnings: stats.compilation.warnings.map(toDiagnostic),
+        unminified_size: fullConfig.optimization.minimize ? unminifiedSize : null,
+        // the files bundled into the script, for the SBOM
+        modules: Array.from(stats.compilation.modules)
+          .map(m => m.resource)
+          .filter(resource => typeof resource === "string")
       };
 
       const wasmModuleAsset = Object.keys(assets).find(
@@ -151,6 +270,12 @@ function filterByExtension(ext) {
       }
 
       fs.writeFileSync(args["output-file"], JSON.stringify(bundle));
+      if (args["debug-dir"] !== undefined) {
+        fs.writeFileSync(
+          join(args["debug-dir"], "webpack-stats.json"),
+          JSON.stringify(stats.toJson(), null, 2)
+        );
+      }
     }
     lastHash = stats.hash;
   };